@@ -3,10 +3,10 @@
 use std::io::Cursor;
 
 use criterion::{BenchmarkId, Criterion, Throughput};
-use csv::ReaderBuilder;
 use ndarray::{Array2, Ix2};
-use ndarray_csv::Array2Reader as _;
-use pelt::{OneOrTwoDimensions as _, Pelt, SegmentCostFunction};
+use pelt::{
+    Accumulator, NanPolicy, OneOrTwoDimensions as _, Pelt, SegmentCostFunction, io::CsvOptions,
+};
 
 /// Benchmark the different groups and cases.
 fn benchmark(criterion: &mut Criterion) {
@@ -25,6 +25,7 @@ fn benchmark(criterion: &mut Criterion) {
             let parameter = match segment_cost_function {
                 SegmentCostFunction::L1 => "L1",
                 SegmentCostFunction::L2 => "L2",
+                SegmentCostFunction::Quantile(_) => "Quantile",
             };
 
             // Benchmark
@@ -57,6 +58,7 @@ fn benchmark(criterion: &mut Criterion) {
             let parameter = match segment_cost_function {
                 SegmentCostFunction::L1 => "L1",
                 SegmentCostFunction::L2 => "L2",
+                SegmentCostFunction::Quantile(_) => "Quantile",
             };
 
             // Benchmark these ranges
@@ -65,18 +67,23 @@ fn benchmark(criterion: &mut Criterion) {
 
                 group.bench_with_input(
                     BenchmarkId::new(parameter, size),
-                    &(
-                        signal.view(),
-                        Ix2::precalculate(segment_cost_function, &signal.view()),
-                    ),
-                    |benchmark, (signal, cost)| {
+                    &Ix2::precalculate(
+                        segment_cost_function,
+                        Accumulator::default(),
+                        NanPolicy::default(),
+                        &signal.view(),
+                        None,
+                        None,
+                        None,
+                    )
+                    .expect("Error precalculating"),
+                    |benchmark, cost| {
                         benchmark.iter(|| {
                             // Run the benchmark
                             let mut loss = 0.0;
                             Ix2::loss(
                                 std::hint::black_box(cost),
                                 std::hint::black_box(&mut loss),
-                                std::hint::black_box(signal),
                                 std::hint::black_box(0..size),
                             )
                         });
@@ -92,15 +99,7 @@ fn benchmark(criterion: &mut Criterion) {
 /// Load the signals from a text file.
 #[must_use]
 pub fn load_signals_fixture(file: &'static str) -> Array2<f64> {
-    // Read CSV
-    let mut cursor = Cursor::new(file);
-    let mut reader = ReaderBuilder::new()
-        .has_headers(false)
-        .from_reader(&mut cursor);
-
-    // Convert to array
-    reader
-        .deserialize_array2_dynamic()
+    pelt::io::read_csv_from_reader(Cursor::new(file), &CsvOptions::new())
         .expect("Error deserializing CSV into array")
 }
 
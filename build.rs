@@ -0,0 +1,6 @@
+//! Build script, only needed for linking the Node.js bindings.
+
+fn main() {
+    #[cfg(feature = "node")]
+    napi_build::setup();
+}
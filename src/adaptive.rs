@@ -0,0 +1,99 @@
+//! Adaptive jump, a coarse grid of candidates that's dense where the signal changes.
+
+use ndarray::{ArrayView, Dimension};
+
+use crate::{Error, OneOrTwoDimensions, Penalty, Sample, predict::PredictImpl};
+
+impl PredictImpl {
+    /// Segment with candidates every `jump` samples, and on every sample around the grid points where a local split gains enough.
+    ///
+    /// The local gain of a grid point is the loss of the window of a `jump` on both sides minus the losses of both halves.
+    /// A changepoint must make up for the penalty with its whole segments, so grid points gaining at least half the pruning penalty in their window are refined.
+    pub(crate) fn predict_adaptive<A, D, P>(
+        &self,
+        signal: &ArrayView<A, D>,
+        penalty: &P,
+    ) -> Result<Vec<usize>, Error>
+    where
+        A: Sample,
+        D: OneOrTwoDimensions + Dimension,
+        P: Penalty,
+        D::PrecalculationOutput: Sync,
+    {
+        let len = D::len_or_nrows(signal);
+        let jump = self.pelt.jump;
+        let cost = self.precalculate(signal)?;
+        let loss = |range| {
+            let mut loss = 0.0;
+            D::loss(&cost, &mut loss, range);
+
+            loss
+        };
+
+        let threshold = penalty.pruning_penalty(len) / 2.0;
+        let mut candidates = Vec::new();
+        for point in (jump..len).step_by(jump) {
+            candidates.push(point);
+
+            // Every sample around a grid point with a steep change is a candidate
+            let window = point.saturating_sub(jump)..(point + jump).min(len);
+            let gain = loss(window.clone()) - loss(window.start..point) - loss(point..window.end);
+            if gain >= threshold {
+                candidates.extend((window.start + 1)..window.end);
+            }
+        }
+
+        Self::new(self.pelt.with_candidates_within(&candidates))
+            .predict_with_cost(signal, &cost, penalty)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::num::NonZero;
+
+    use crate::{Pelt, SegmentCostFunction, predict::PredictImpl};
+
+    /// A change between grid points is found at its exact location.
+    #[test]
+    fn predict_adaptive() {
+        let signal = (0..100)
+            .map(|index| if index < 43 { 0.0 } else { 5.0 } + if index % 2 == 0 { 0.1 } else { -0.1 })
+            .collect::<ndarray::Array1<f64>>();
+        let pelt = Pelt::new()
+            .with_segment_cost_function(SegmentCostFunction::L2)
+            .with_jump(NonZero::new(10).expect("Invalid number"));
+
+        assert_eq!(
+            PredictImpl::new(pelt.clone())
+                .predict(&signal.view(), &1.0)
+                .expect("Error predicting"),
+            vec![40, 50, 100]
+        );
+        assert_eq!(
+            PredictImpl::new(pelt)
+                .predict_adaptive(&signal.view(), &1.0)
+                .expect("Error predicting"),
+            vec![43, 100]
+        );
+    }
+
+    /// Configured candidates are kept, the adaptive ones only narrow them down.
+    #[test]
+    fn predict_adaptive_candidates() {
+        let signal = (0..100)
+            .map(|index| if index < 43 { 0.0 } else { 5.0 } + if index % 2 == 0 { 0.1 } else { -0.1 })
+            .collect::<ndarray::Array1<f64>>();
+        let pelt = Pelt::new()
+            .with_segment_cost_function(SegmentCostFunction::L2)
+            .with_jump(NonZero::new(10).expect("Invalid number"))
+            .with_candidates(&[20, 60]);
+
+        assert_eq!(
+            PredictImpl::new(pelt)
+                .predict_adaptive(&signal.view(), &1.0)
+                .expect("Error predicting"),
+            vec![20, 60, 100]
+        );
+    }
+}
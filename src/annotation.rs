@@ -0,0 +1,104 @@
+//! Suggestions of regions to annotate by hand.
+
+use std::ops::Range;
+
+use ndarray::{ArrayView, Dimension};
+
+use crate::{Error, OneOrTwoDimensions, Sample, predict::PredictImpl};
+
+/// Candidate changepoint whose label would help the most.
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct AnnotationSuggestion {
+    /// Candidate changepoint.
+    pub changepoint: usize,
+    /// Samples to look at, from the candidate before to the candidate after.
+    pub region: Range<usize>,
+    /// Increase in loss of removing the candidate, see [`crate::Pelt::rank_changepoints`].
+    pub gain: f64,
+    /// How close the gain is to the penalty, between `0.0` and `1.0`, where `1.0` means the penalty could go either way.
+    pub uncertainty: f64,
+}
+
+/// Fraction of the penalty the candidates are found with.
+const CANDIDATE_PENALTY_FRACTION: f64 = 0.25;
+
+impl PredictImpl {
+    /// Candidates found with a lower penalty, the ones with a gain closest to the penalty first.
+    pub(crate) fn suggest_annotations<A, D>(
+        &mut self,
+        signal: &ArrayView<A, D>,
+        penalty: f64,
+        count: usize,
+    ) -> Result<Vec<AnnotationSuggestion>, Error>
+    where
+        A: Sample,
+        D: OneOrTwoDimensions + Dimension,
+        D::PrecalculationOutput: Sync,
+    {
+        let candidates = self.predict(signal, &(penalty * CANDIDATE_PENALTY_FRACTION))?;
+        let ranking = self.rank_changepoints(signal, &candidates)?;
+
+        let mut suggestions = ranking
+            .into_iter()
+            .map(|(changepoint, gain)| {
+                // Candidates around it, the changepoints always end with the end of the signal
+                let position = candidates.partition_point(|candidate| *candidate < changepoint);
+                let start = position
+                    .checked_sub(1)
+                    .and_then(|before| candidates.get(before))
+                    .copied()
+                    .unwrap_or(0);
+                let end = candidates.get(position + 1).copied().unwrap_or(changepoint);
+
+                // Ratio of the smallest to the largest of the gain and the penalty
+                let uncertainty = if gain > 0.0 {
+                    (gain / penalty).min(penalty / gain)
+                } else {
+                    0.0
+                };
+
+                AnnotationSuggestion {
+                    changepoint,
+                    region: start..end,
+                    gain,
+                    uncertainty,
+                }
+            })
+            .collect::<Vec<_>>();
+        suggestions.sort_by(|left, right| right.uncertainty.total_cmp(&left.uncertainty));
+        suggestions.truncate(count);
+
+        Ok(suggestions)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use ndarray::array;
+
+    use crate::{Pelt, SegmentCostFunction, predict::PredictImpl};
+
+    /// The small change is close to the penalty, the large one isn't.
+    #[test]
+    fn suggest_annotations() {
+        let signal = array![0.0, 0.0, 0.0, 1.0, 1.0, 1.0, 10.0, 10.0, 10.0];
+        let mut predict = PredictImpl::new(
+            Pelt::new()
+                .with_jump(std::num::NonZero::<usize>::MIN)
+                .with_segment_cost_function(SegmentCostFunction::L2),
+        );
+
+        // The gain of the first changepoint is 1.5
+        let suggestions = predict
+            .suggest_annotations(&signal.view(), 2.0, 1)
+            .expect("Error suggesting");
+        assert_eq!(suggestions.len(), 1);
+        assert_eq!(suggestions[0].changepoint, 3);
+        assert_eq!(suggestions[0].region, 0..6);
+        assert!(
+            (suggestions[0].uncertainty - 0.75).abs() < 1e-12,
+            "{suggestions:?}"
+        );
+    }
+}
@@ -0,0 +1,171 @@
+//! Signals stored as Arrow arrays.
+
+use std::borrow::Cow;
+
+use arrow_array::{Array as _, Float64Array, RecordBatch, cast::AsArray as _, types::Float64Type};
+use ndarray::{ArrayView1, ArrayView2};
+
+use crate::{Error, NanPolicy, Penalty, cost::Cost2D, predict::PredictImpl};
+
+/// Values of a column, with the null values as NaN.
+///
+/// Only copies columns containing null values.
+fn column_values(column: &Float64Array) -> Cow<'_, [f64]> {
+    if column.null_count() == 0 {
+        Cow::Borrowed(column.values())
+    } else {
+        Cow::Owned(
+            column
+                .iter()
+                .map(|value| value.unwrap_or(f64::NAN))
+                .collect(),
+        )
+    }
+}
+
+/// Columns of a record batch, which must all be [`Float64Array`]s.
+pub(crate) fn record_batch_columns(batch: &RecordBatch) -> Result<Vec<&Float64Array>, Error> {
+    batch
+        .columns()
+        .iter()
+        .map(|column| {
+            column
+                .as_primitive_opt::<Float64Type>()
+                .ok_or(Error::UnsupportedColumn)
+        })
+        .collect()
+}
+
+impl PredictImpl {
+    /// Segment the columns as a two dimensional signal, every row is a sample.
+    ///
+    /// The cost function is precalculated for each column separately, so the columns aren't copied into a single array.
+    pub(crate) fn predict_arrow<P>(
+        &mut self,
+        columns: &[&Float64Array],
+        penalty: &P,
+    ) -> Result<Vec<usize>, Error>
+    where
+        P: Penalty,
+    {
+        let len = columns.first().map_or(0, |column| column.len());
+        if columns.iter().any(|column| column.len() != len) {
+            return Err(Error::ColumnMismatch);
+        }
+        self.pelt.check_columns(columns.len())?;
+
+        let values = columns
+            .iter()
+            .map(|column| column_values(column))
+            .collect::<Vec<_>>();
+        let views = values
+            .iter()
+            .map(|values| ArrayView1::from(values.as_ref()))
+            .collect::<Vec<_>>();
+
+        // Null values are NaN
+        if self.pelt.nan_policy == NanPolicy::Error
+            && let Some(row) = views
+                .iter()
+                .filter_map(|view| view.iter().position(|value| value.is_nan()))
+                .min()
+        {
+            return Err(Error::InvalidInput { index: row });
+        }
+
+        // Segment a single column directly, since that's faster
+        if let [view] = views.as_slice() {
+            return self.predict(view, penalty);
+        }
+
+        // Building the trees sorts in parallel
+        #[cfg(feature = "rayon")]
+        let cost = crate::Pelt::install(self.pelt.thread_pool.as_deref(), || {
+            Cost2D::precalculate_columns(
+                self.pelt.segment_cost_function,
+                self.pelt.accumulator,
+                self.pelt.nan_policy,
+                &views,
+                self.pelt.column_weights.as_deref(),
+                self.pelt.column_cost_functions.as_deref(),
+            )
+        });
+        #[cfg(not(feature = "rayon"))]
+        let cost = Cost2D::precalculate_columns(
+            self.pelt.segment_cost_function,
+            self.pelt.accumulator,
+            self.pelt.nan_policy,
+            &views,
+            self.pelt.column_weights.as_deref(),
+            self.pelt.column_cost_functions.as_deref(),
+        );
+
+        // Only the amount of rows of the signal is used after precalculating
+        let rows =
+            ArrayView2::<f64>::from_shape((len, 0), &[]).map_err(|_error| Error::ColumnMismatch)?;
+
+        self.predict_with_cost(&rows, &cost, penalty)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+
+    use arrow_array::{ArrayRef, Float64Array, Int32Array, RecordBatch};
+
+    use crate::{Error, NanPolicy, Pelt, SegmentCostFunction};
+
+    /// Columns are segmented the same as a two dimensional array.
+    #[test]
+    fn record_batch() {
+        let signal = ndarray::Array2::from_shape_fn((60, 2), |(row, column)| {
+            let level = if row < 30 { 0.0 } else { 5.0 };
+
+            level + column as f64 + if row % 2 == 0 { 0.1 } else { -0.1 }
+        });
+        let columns = signal
+            .columns()
+            .into_iter()
+            .map(|column| Arc::new(Float64Array::from(column.to_vec())) as ArrayRef)
+            .collect::<Vec<_>>();
+        let batch =
+            RecordBatch::try_from_iter([("a", columns[0].clone()), ("b", columns[1].clone())])
+                .expect("Invalid record batch");
+
+        for segment_cost_function in [SegmentCostFunction::L1, SegmentCostFunction::L2] {
+            let pelt = Pelt::new().with_segment_cost_function(segment_cost_function);
+            assert_eq!(
+                pelt.predict_record_batch(&batch, 1.0)
+                    .expect("Error predicting"),
+                pelt.predict(signal.view(), 1.0).expect("Error predicting"),
+                "{segment_cost_function:?}"
+            );
+        }
+
+        // Null values are NaN
+        let with_null = Float64Array::from(vec![Some(1.0), None, Some(1.0), Some(1.0)]);
+        let pelt = Pelt::new();
+        assert!(matches!(
+            pelt.predict_arrow(&[&with_null], 1.0),
+            Err(Error::InvalidInput { index: 1 })
+        ));
+        assert_eq!(
+            pelt.clone()
+                .with_nan_policy(NanPolicy::Interpolate)
+                .predict_arrow(&[&with_null], 1.0)
+                .expect("Error predicting"),
+            vec![4]
+        );
+
+        let integers = RecordBatch::try_from_iter([(
+            "a",
+            Arc::new(Int32Array::from(vec![1, 2, 3])) as ArrayRef,
+        )])
+        .expect("Invalid record batch");
+        assert!(matches!(
+            pelt.predict_record_batch(&integers, 1.0),
+            Err(Error::UnsupportedColumn)
+        ));
+    }
+}
@@ -0,0 +1,173 @@
+//! Command line interface, segmenting a signal from a file.
+
+use std::{
+    error::Error,
+    io::{BufWriter, Write as _},
+    num::NonZero,
+    path::PathBuf,
+};
+
+use clap::{Parser, ValueEnum};
+use ndarray::Array2;
+use pelt::{
+    Pelt, SegmentCostFunction, Segmentation,
+    io::{CsvOptions, read_csv, read_npy, read_npz, read_parquet},
+};
+
+/// Detect changepoints in a signal with Pruned Exact Linear Time.
+///
+/// Every row of the file is a sample, the format is derived from the extension: `.csv`, `.parquet`, `.npy` or `.npz`.
+#[derive(Parser)]
+#[command(version)]
+struct Arguments {
+    /// File containing the signal.
+    input: PathBuf,
+    /// Penalty added for each changepoint, larger values result in fewer changepoints.
+    #[arg(long)]
+    penalty: f64,
+    /// Segment model.
+    #[arg(long, value_enum, default_value_t = Cost::L1)]
+    cost: Cost,
+    /// Quantile to target with the quantile cost, larger than 0 and smaller than 1.
+    #[arg(long, default_value_t = 0.5)]
+    quantile: f64,
+    /// Step size between candidate changepoints, 1 for an exact solution.
+    #[arg(long, default_value = "5")]
+    jump: NonZero<usize>,
+    /// Minimum number of samples within a segment.
+    #[arg(long, default_value = "2")]
+    min_segment_length: NonZero<usize>,
+    /// Columns to segment, names for Parquet and zero-based indices for CSV, all CSV columns if omitted.
+    #[arg(long, value_delimiter = ',')]
+    columns: Vec<String>,
+    /// Skip the first row of a CSV file, because it contains the names of the columns.
+    #[arg(long)]
+    headers: bool,
+    /// Byte separating the fields of a CSV file.
+    #[arg(long, default_value_t = ',')]
+    delimiter: char,
+    /// Name of the array in a `.npz` file.
+    #[arg(long, default_value = "signal")]
+    array: String,
+    /// Format of the segments written to the standard output.
+    #[arg(long, value_enum, default_value_t = Format::Json)]
+    format: Format,
+}
+
+/// Segment model of the arguments.
+#[derive(Clone, Copy, ValueEnum)]
+enum Cost {
+    /// Least absolute deviation.
+    L1,
+    /// Least squared deviation.
+    L2,
+    /// Pinball deviation around the quantile.
+    Quantile,
+}
+
+/// Output format of the arguments.
+#[derive(Clone, Copy, ValueEnum)]
+enum Format {
+    /// Changepoints, segments with their statistics and the configuration.
+    Json,
+    /// A row with the start, end and cost of every segment.
+    Csv,
+}
+
+/// Read the signal in the format of the extension.
+fn read_signal(arguments: &Arguments) -> Result<Array2<f64>, Box<dyn Error>> {
+    let extension = arguments
+        .input
+        .extension()
+        .and_then(|extension| extension.to_str())
+        .unwrap_or_default();
+
+    Ok(match extension {
+        "csv" => {
+            let delimiter = u8::try_from(arguments.delimiter)
+                .map_err(|_error| "--delimiter must be a single byte")?;
+            let mut options = CsvOptions::new().with_delimiter(delimiter);
+            if arguments.headers {
+                options = options.with_headers();
+            }
+            if !arguments.columns.is_empty() {
+                let columns = arguments
+                    .columns
+                    .iter()
+                    .map(|column| column.parse())
+                    .collect::<Result<Vec<usize>, _>>()
+                    .map_err(|_error| "--columns must be indices for CSV files")?;
+                options = options.with_columns(&columns);
+            }
+
+            read_csv(&arguments.input, &options)?
+        }
+        "parquet" => {
+            if arguments.columns.is_empty() {
+                return Err("--columns is required for Parquet files".into());
+            }
+            let columns = arguments
+                .columns
+                .iter()
+                .map(String::as_str)
+                .collect::<Vec<_>>();
+
+            read_parquet(&arguments.input, &columns)?
+        }
+        "npy" => read_npy(&arguments.input)?,
+        "npz" => read_npz(&arguments.input, &arguments.array)?,
+        _ => {
+            return Err(format!(
+                "unsupported file '{}', must be .csv, .parquet, .npy or .npz",
+                arguments.input.display()
+            )
+            .into());
+        }
+    })
+}
+
+/// Write the segments to the standard output.
+fn write_segmentation(segmentation: &Segmentation, format: Format) -> Result<(), Box<dyn Error>> {
+    let mut output = BufWriter::new(std::io::stdout().lock());
+
+    match format {
+        Format::Json => writeln!(output, "{}", segmentation.to_json())?,
+        Format::Csv => {
+            let mut writer = csv::Writer::from_writer(output);
+            writer.write_record(["start", "end", "cost"])?;
+            for segment in &segmentation.segments {
+                writer.write_record([
+                    segment.start.to_string(),
+                    segment.end.to_string(),
+                    segment.cost.to_string(),
+                ])?;
+            }
+            writer.flush()?;
+
+            return Ok(());
+        }
+    }
+
+    output.flush()?;
+
+    Ok(())
+}
+
+pub fn main() -> Result<(), Box<dyn Error>> {
+    let arguments = Arguments::parse();
+
+    let signal = read_signal(&arguments)?;
+
+    let segment_cost_function = match arguments.cost {
+        Cost::L1 => SegmentCostFunction::L1,
+        Cost::L2 => SegmentCostFunction::L2,
+        Cost::Quantile => SegmentCostFunction::Quantile(arguments.quantile),
+    };
+    let segmentation = Pelt::new()
+        .with_segment_cost_function(segment_cost_function)
+        .with_jump(arguments.jump)
+        .with_minimum_segment_length(arguments.min_segment_length)
+        .segment(&signal, arguments.penalty)?;
+
+    write_segmentation(&segmentation, arguments.format)
+}
@@ -0,0 +1,351 @@
+//! Bayesian online changepoint detection.
+
+use std::{f64::consts::PI, num::NonZero};
+
+use ndarray::{AsArray, Ix1};
+
+use crate::{Error, nan};
+
+/// Conjugate prior of a normal distribution with unknown mean and precision.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct NormalGamma {
+    /// Prior mean.
+    pub mean: f64,
+    /// Amount of pseudo-observations of the mean.
+    pub mean_weight: f64,
+    /// Shape of the gamma distribution of the precision.
+    pub shape: f64,
+    /// Rate of the gamma distribution of the precision.
+    pub rate: f64,
+}
+
+impl NormalGamma {
+    /// Ensure the parameters of the prior are valid.
+    fn validate(&self) -> Result<(), Error> {
+        let positive = |value: f64| value.is_finite() && value > 0.0;
+
+        if self.mean.is_finite()
+            && positive(self.mean_weight)
+            && positive(self.shape)
+            && positive(self.rate)
+        {
+            Ok(())
+        } else {
+            Err(Error::InvalidPrior)
+        }
+    }
+
+    /// Posterior after observing a value.
+    fn update(&self, value: f64) -> Self {
+        let mean_weight = self.mean_weight + 1.0;
+
+        Self {
+            mean: self.mean_weight.mul_add(self.mean, value) / mean_weight,
+            mean_weight,
+            shape: self.shape + 0.5,
+            rate: self.rate + self.mean_weight * (value - self.mean).powi(2) / (2.0 * mean_weight),
+        }
+    }
+
+    /// Log density of the Student-t posterior predictive distribution.
+    fn log_predictive(&self, value: f64) -> f64 {
+        let degrees_of_freedom = 2.0 * self.shape;
+        let scale_squared = self.rate * (self.mean_weight + 1.0) / (self.shape * self.mean_weight);
+
+        let normalization = ln_gamma(self.shape + 0.5)
+            - ln_gamma(self.shape)
+            - (degrees_of_freedom * PI * scale_squared).ln() / 2.0;
+        let tail = ((value - self.mean).powi(2) / (degrees_of_freedom * scale_squared)).ln_1p();
+
+        (self.shape + 0.5).mul_add(-tail, normalization)
+    }
+}
+
+impl Default for NormalGamma {
+    fn default() -> Self {
+        Self {
+            mean: 0.0,
+            mean_weight: 1.0,
+            shape: 1.0,
+            rate: 1.0,
+        }
+    }
+}
+
+/// Bayesian online changepoint detection.
+///
+/// Keeps the posterior probability of each run length, the amount of points since the last changepoint.
+/// Each segment is normally distributed with an unknown mean and variance, drawn from a [`NormalGamma`] prior.
+/// Gives a probability for every point, which PELT can't.
+///
+/// Only supports one-dimensional signals.
+///
+/// # Defaults
+///
+/// - `expected_run_length`: `100`
+/// - `prior`: [`NormalGamma::default`], a mean of `0` and a variance of around `1`
+#[derive(Debug, Clone)]
+pub struct Bocpd {
+    /// Constant probability of a changepoint at every point is the inverse of this.
+    expected_run_length: usize,
+    /// Prior of the distribution of a new segment.
+    prior: NormalGamma,
+    /// Probability of each run length after the last update.
+    run_length_probabilities: Vec<f64>,
+    /// Posterior of the distribution for each run length after the last update.
+    posteriors: Vec<NormalGamma>,
+    /// Amount of points seen since the last reset.
+    position: usize,
+}
+
+impl Bocpd {
+    /// Construct a new online detector with default values.
+    #[must_use]
+    pub fn new() -> Self {
+        Self {
+            expected_run_length: 100,
+            prior: NormalGamma::default(),
+            run_length_probabilities: Vec::new(),
+            posteriors: Vec::new(),
+            position: 0,
+        }
+    }
+
+    /// Set the expected amount of points between changepoints.
+    #[must_use]
+    pub const fn with_expected_run_length(mut self, expected_run_length: NonZero<usize>) -> Self {
+        self.expected_run_length = expected_run_length.get();
+
+        self
+    }
+
+    /// Set the prior of the distribution of a new segment.
+    ///
+    /// Should match the scale of the signal.
+    #[must_use]
+    pub const fn with_prior(mut self, prior: NormalGamma) -> Self {
+        self.prior = prior;
+
+        self
+    }
+
+    /// Forget all previous points.
+    pub fn reset(&mut self) {
+        self.run_length_probabilities.clear();
+        self.posteriors.clear();
+        self.position = 0;
+    }
+
+    /// Add the next point of the signal.
+    ///
+    /// Returns the posterior probability of each run length, the index is the amount of points in the current segment including this one.
+    /// A changepoint is likely right before this point when the probability of a run length of `1` is high.
+    ///
+    /// # Errors
+    ///
+    /// - When the prior is invalid.
+    /// - When the value is NaN or infinite, the detector is left unchanged.
+    pub fn update(&mut self, value: f64) -> Result<&[f64], Error> {
+        self.prior.validate()?;
+
+        // A single NaN would end up in the posterior of every run length
+        if !value.is_finite() {
+            return Err(Error::InvalidInput {
+                index: self.position,
+            });
+        }
+        self.position += 1;
+
+        // Before the first point the run length is always zero
+        if self.posteriors.is_empty() {
+            self.run_length_probabilities.push(1.0);
+            self.posteriors.push(self.prior);
+        }
+
+        let hazard = 1.0 / self.expected_run_length as f64;
+
+        // Predictive log probability of the value for each run length, shifted for numerical stability
+        let log_predictives = self
+            .posteriors
+            .iter()
+            .map(|posterior| posterior.log_predictive(value))
+            .collect::<Vec<_>>();
+        let max_log_predictive = log_predictives
+            .iter()
+            .copied()
+            .fold(f64::NEG_INFINITY, f64::max);
+
+        // Either the run continues or a new segment starts
+        let mut changepoint_probability = 0.0;
+        let mut probabilities = Vec::with_capacity(self.run_length_probabilities.len() + 1);
+        probabilities.push(0.0);
+        for (probability, log_predictive) in
+            self.run_length_probabilities.iter().zip(&log_predictives)
+        {
+            let joint = probability * (log_predictive - max_log_predictive).exp();
+
+            changepoint_probability += joint * hazard;
+            probabilities.push(joint * (1.0 - hazard));
+        }
+        probabilities[0] = changepoint_probability;
+
+        // A new segment hasn't seen any points yet, the others have seen the value
+        let mut posteriors = Vec::with_capacity(self.posteriors.len() + 1);
+        posteriors.push(self.prior);
+        posteriors.extend(
+            self.posteriors
+                .iter()
+                .map(|posterior| posterior.update(value)),
+        );
+
+        // Normalize to a distribution
+        let total = probabilities.iter().sum::<f64>();
+        probabilities
+            .iter_mut()
+            .for_each(|probability| *probability /= total);
+
+        // Remove negligible long runs to keep the state small
+        while probabilities.len() > 1 && probabilities[probabilities.len() - 1] < f64::EPSILON {
+            probabilities.pop();
+            posteriors.pop();
+        }
+
+        self.run_length_probabilities = probabilities;
+        self.posteriors = posteriors;
+
+        Ok(&self.run_length_probabilities)
+    }
+
+    /// Calculate the run length probabilities for every point of a signal, starting without previous points.
+    ///
+    /// # Errors
+    ///
+    /// - When the prior is invalid.
+    /// - When the signal contains a NaN or infinite value.
+    /// - When the signal is empty.
+    pub fn predict<'a>(&self, signal: impl AsArray<'a, f64, Ix1>) -> Result<Vec<Vec<f64>>, Error> {
+        let signal_view = signal.into();
+        nan::check_finite(&signal_view)?;
+
+        if signal_view.is_empty() {
+            return Err(Error::NotEnoughPoints);
+        }
+
+        let mut detector = self.clone();
+        detector.reset();
+
+        signal_view
+            .iter()
+            .map(|value| detector.update(*value).map(<[f64]>::to_vec))
+            .collect()
+    }
+}
+
+impl Default for Bocpd {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Natural logarithm of the gamma function for positive values.
+///
+/// Uses the Lanczos approximation.
+fn ln_gamma(value: f64) -> f64 {
+    /// Lanczos coefficients for `g = 7`.
+    const COEFFICIENTS: [f64; 9] = [
+        0.999_999_999_999_809_9,
+        676.520_368_121_885_1,
+        -1_259.139_216_722_402_8,
+        771.323_428_777_653_1,
+        -176.615_029_162_140_6,
+        12.507_343_278_686_905,
+        -0.138_571_095_265_720_12,
+        9.984_369_578_019_572e-6,
+        1.505_632_735_149_311_6e-7,
+    ];
+
+    let value = value - 1.0;
+    let base = value + 7.5;
+    let series = COEFFICIENTS
+        .iter()
+        .enumerate()
+        .skip(1)
+        .fold(COEFFICIENTS[0], |series, (index, coefficient)| {
+            series + coefficient / (value + index as f64)
+        });
+
+    0.5_f64.mul_add((2.0 * PI).ln(), (value + 0.5).mul_add(base.ln(), -base)) + series.ln()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Compare the log gamma function with known values.
+    #[test]
+    fn ln_gamma_values() {
+        assert!(ln_gamma(1.0).abs() < 1e-12, "Invalid log gamma");
+        assert!(
+            (ln_gamma(5.0) - 24.0_f64.ln()).abs() < 1e-12,
+            "Invalid log gamma"
+        );
+        assert!(
+            (ln_gamma(0.5) - PI.sqrt().ln()).abs() < 1e-12,
+            "Invalid log gamma"
+        );
+    }
+
+    /// Detect a change in mean.
+    #[test]
+    fn step() {
+        let mut detector = Bocpd::new();
+        for index in 1..50 {
+            let probabilities = detector
+                .update(if index % 2 == 0 { 0.1 } else { -0.1 })
+                .expect("Error updating");
+            // The first points are always a new run
+            assert!(
+                index < 5 || probabilities[1] < 0.5,
+                "Unexpected changepoint"
+            );
+        }
+
+        // A new run starts at the change
+        let probabilities = detector.update(10.0).expect("Error updating");
+        assert!(probabilities[1] > 0.5, "Changepoint not detected");
+    }
+
+    /// NaN and infinite values are rejected without changing the detector.
+    #[test]
+    fn non_finite() {
+        let mut detector = Bocpd::new();
+        for index in 0..10 {
+            detector
+                .update(if index % 2 == 0 { 0.1 } else { -0.1 })
+                .expect("Error updating");
+        }
+        let posteriors = detector.posteriors.clone();
+
+        for value in [f64::NAN, f64::INFINITY, f64::NEG_INFINITY] {
+            assert!(matches!(
+                detector.update(value),
+                Err(Error::InvalidInput { index: 10 })
+            ));
+        }
+        assert_eq!(detector.posteriors, posteriors);
+
+        // The probabilities stay valid afterwards
+        let probabilities = detector.update(0.1).expect("Error updating");
+        assert!(
+            probabilities
+                .iter()
+                .all(|probability| probability.is_finite()),
+            "{probabilities:?}"
+        );
+
+        assert!(matches!(
+            Bocpd::new().predict(&[0.0, 1.0, f64::INFINITY]),
+            Err(Error::InvalidInput { index: 2 })
+        ));
+    }
+}
@@ -0,0 +1,316 @@
+//! Bottom-up segmentation.
+
+use std::{cmp::Ordering, collections::BinaryHeap, num::NonZero};
+
+use ndarray::{ArrayView, AsArray, Dimension};
+
+use crate::{Accumulator, Error, NanPolicy, OneOrTwoDimensions, Penalty, SegmentCostFunction, nan};
+
+/// Bottom-up segmentation algorithm.
+///
+/// Starts with a fine grid of segments and keeps merging the two neighboring segments with the smallest gain in loss, until every gain is larger than the penalty.
+/// It's approximate, but a lot cheaper than [`crate::Pelt`] for very long signals.
+///
+/// # Defaults
+///
+/// - `segment_cost_function`: [`SegmentCostFunction::L1`]
+/// - `jump`: `5`
+/// - `minimum_segment_length`: `2`
+#[derive(Debug, Clone)]
+pub struct BottomUp {
+    /// Segment model.
+    segment_cost_function: SegmentCostFunction,
+    /// Size of the segments in the initial grid.
+    jump: usize,
+    /// Minimum allowable number of data points within a segment.
+    minimum_segment_length: usize,
+}
+
+impl BottomUp {
+    /// Construct a new bottom-up instance with default values.
+    #[must_use]
+    pub const fn new() -> Self {
+        Self {
+            segment_cost_function: SegmentCostFunction::L1,
+            jump: 5,
+            minimum_segment_length: 2,
+        }
+    }
+
+    /// Set the segment model, also known as the loss function.
+    ///
+    /// Determines how the cost of each potential segment is calculated.
+    #[must_use]
+    pub const fn with_segment_cost_function(mut self, model: SegmentCostFunction) -> Self {
+        self.segment_cost_function = model;
+
+        self
+    }
+
+    /// Set the size of the segments in the initial grid.
+    ///
+    /// Changepoints can only be found on multiples of `jump`.
+    #[must_use]
+    pub const fn with_jump(mut self, jump: NonZero<usize>) -> Self {
+        self.jump = jump.get();
+
+        self
+    }
+
+    /// Set the minimum allowable number of data points within a segment.
+    ///
+    /// Ensures that segments are not too small.
+    #[must_use]
+    pub const fn with_minimum_segment_length(
+        mut self,
+        minimum_segment_length: NonZero<usize>,
+    ) -> Self {
+        self.minimum_segment_length = minimum_segment_length.get();
+
+        self
+    }
+
+    /// Fit on a data set.
+    ///
+    /// # Errors
+    ///
+    /// - When the input is invalid.
+    /// - When the signal contains a NaN or infinite value.
+    /// - When the signal is shorter than the minimum segment length.
+    pub fn predict<'a, D, P>(
+        &self,
+        signal: impl AsArray<'a, f64, D>,
+        penalty: P,
+    ) -> Result<Vec<usize>, Error>
+    where
+        D: OneOrTwoDimensions + Dimension,
+        P: Penalty,
+    {
+        let signal_view = signal.into();
+        D::check_dimensions(&signal_view)?;
+        nan::check_finite(&signal_view)?;
+
+        // Ensure the cost function parameters are correct
+        self.segment_cost_function.validate()?;
+
+        // Try to lower 2D to 1D to parse as 1D array, since that's faster
+        D::try_as_1d(&signal_view).map_or_else(
+            // Predict as 2D array
+            || self.predict_impl(&signal_view, &penalty),
+            // Predict as 1D array
+            |signal_1d| self.predict_impl(&signal_1d, &penalty),
+        )
+    }
+
+    /// Merge segments until no merge is worth it anymore.
+    fn predict_impl<D, P>(
+        &self,
+        signal: &ArrayView<f64, D>,
+        penalty: &P,
+    ) -> Result<Vec<usize>, Error>
+    where
+        D: OneOrTwoDimensions + Dimension,
+        P: Penalty,
+    {
+        let len = D::len_or_nrows(signal);
+        if len < self.minimum_segment_length {
+            return Err(Error::NotEnoughPoints);
+        }
+
+        // Precalculate the cost function
+        let cost = D::precalculate(
+            self.segment_cost_function,
+            Accumulator::default(),
+            NanPolicy::default(),
+            signal,
+            None,
+            None,
+            None,
+        )?;
+        let loss = |start: usize, end: usize| {
+            let mut loss = 0.0;
+            D::loss(&cost, &mut loss, start..end);
+
+            loss
+        };
+
+        // Build the initial grid, every segment must be long enough
+        let mut segments = Vec::new();
+        let mut start = 0;
+        let step = self.jump.max(self.minimum_segment_length);
+        while len - start >= step + self.minimum_segment_length {
+            segments.push(Segment::new(start, start + step, loss(start, start + step)));
+            start += step;
+        }
+        segments.push(Segment::new(start, len, loss(start, len)));
+
+        // Link the neighbors
+        let last = segments.len() - 1;
+        for (index, segment) in segments.iter_mut().enumerate() {
+            segment.previous = index.checked_sub(1);
+            segment.next = (index < last).then_some(index + 1);
+        }
+
+        // Gain of merging a segment with the next one, minus the penalty that's saved by doing so
+        let merge = |segments: &[Segment], left: usize, right: usize| {
+            let (left_segment, right_segment) = (&segments[left], &segments[right]);
+            let merged_loss = loss(left_segment.start, right_segment.end);
+
+            let saved_penalty = penalty.segment_penalty(left_segment.start..left_segment.end, len)
+                + penalty.segment_penalty(right_segment.start..right_segment.end, len)
+                - penalty.segment_penalty(left_segment.start..right_segment.end, len);
+
+            Merge {
+                net_gain: merged_loss - left_segment.loss - right_segment.loss - saved_penalty,
+                merged_loss,
+                left,
+                left_version: left_segment.version,
+                right,
+                right_version: right_segment.version,
+            }
+        };
+
+        // Queue of all possible merges, cheapest first
+        let mut queue = (0..last)
+            .map(|left| merge(&segments, left, left + 1))
+            .collect::<BinaryHeap<_>>();
+
+        while let Some(candidate) = queue.pop() {
+            // Skip merges with segments that changed since
+            if segments[candidate.left].version != candidate.left_version
+                || segments[candidate.right].version != candidate.right_version
+            {
+                continue;
+            }
+
+            // The cheapest merge isn't worth it, so no merge is
+            if candidate.net_gain >= 0.0 {
+                break;
+            }
+
+            // Merge the right segment into the left one
+            let right = segments[candidate.right].clone();
+            segments[candidate.right].removed = true;
+            segments[candidate.right].version += 1;
+            let left = &mut segments[candidate.left];
+            left.end = right.end;
+            left.loss = candidate.merged_loss;
+            left.next = right.next;
+            left.version += 1;
+            if let Some(next) = right.next {
+                segments[next].previous = Some(candidate.left);
+            }
+
+            // Queue the new merges with the neighbors
+            if let Some(previous) = segments[candidate.left].previous {
+                queue.push(merge(&segments, previous, candidate.left));
+            }
+            if let Some(next) = segments[candidate.left].next {
+                queue.push(merge(&segments, candidate.left, next));
+            }
+        }
+
+        Ok(segments
+            .iter()
+            .filter(|segment| !segment.removed)
+            .map(|segment| segment.end)
+            .collect())
+    }
+}
+
+impl Default for BottomUp {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Segment in the linked list of segments.
+#[derive(Clone)]
+struct Segment {
+    /// First index.
+    start: usize,
+    /// Index after the last.
+    end: usize,
+    /// Loss of the segment.
+    loss: f64,
+    /// Index of the previous segment.
+    previous: Option<usize>,
+    /// Index of the next segment.
+    next: Option<usize>,
+    /// Incremented every time the segment changes, to invalidate queued merges.
+    version: usize,
+    /// Whether the segment is merged into the previous one.
+    removed: bool,
+}
+
+impl Segment {
+    /// Construct a segment without neighbors.
+    const fn new(start: usize, end: usize, loss: f64) -> Self {
+        Self {
+            start,
+            end,
+            loss,
+            previous: None,
+            next: None,
+            version: 0,
+            removed: false,
+        }
+    }
+}
+
+/// Possible merge of two neighboring segments.
+struct Merge {
+    /// Increase in loss minus the saved penalty.
+    net_gain: f64,
+    /// Loss of the merged segment.
+    merged_loss: f64,
+    /// Index of the left segment.
+    left: usize,
+    /// Version of the left segment when this merge was calculated.
+    left_version: usize,
+    /// Index of the right segment.
+    right: usize,
+    /// Version of the right segment when this merge was calculated.
+    right_version: usize,
+}
+
+impl PartialEq for Merge {
+    fn eq(&self, other: &Self) -> bool {
+        self.cmp(other) == Ordering::Equal
+    }
+}
+
+impl Eq for Merge {}
+
+impl PartialOrd for Merge {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Ord for Merge {
+    fn cmp(&self, other: &Self) -> Ordering {
+        // Reversed so the binary heap pops the lowest gain first
+        other.net_gain.total_cmp(&self.net_gain)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Merge everything except the real change.
+    #[test]
+    fn step() {
+        let signal =
+            ndarray::Array1::from_iter((0..40).map(|index| if index < 20 { 0.0 } else { 10.0 }));
+
+        assert_eq!(
+            BottomUp::new()
+                .with_segment_cost_function(SegmentCostFunction::L2)
+                .predict(&signal, 1.0)
+                .expect("Error predicting"),
+            vec![20, 40]
+        );
+    }
+}
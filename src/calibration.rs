@@ -0,0 +1,129 @@
+//! Calibration of the penalty on signals with known changepoints.
+
+use std::num::NonZero;
+
+use ndarray::ArrayView2;
+
+use crate::{ChangepointConvention, Error, Pelt, Sample};
+
+/// Settings with the best F1 score on signals with known changepoints.
+#[derive(Debug, Clone, Copy, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Calibration {
+    /// Best penalty of the candidates.
+    pub penalty: f64,
+    /// Best minimum segment length of the candidates.
+    pub minimum_segment_length: usize,
+    /// Mean F1 score over the signals with these settings.
+    pub f1: f64,
+}
+
+/// F1 score of predicted changepoints compared to the true ones, between `0.0` and `1.0`.
+///
+/// Both must be sorted, a predicted changepoint within `margin` samples of a true one is a match and every changepoint matches at most once.
+/// The last changepoint of both is the end of the signal, the same as the result of [`Pelt::predict`], so it isn't counted.
+/// Without any true or predicted changepoints the score is `1.0`.
+#[must_use]
+pub fn f1_score(predicted: &[usize], truth: &[usize], margin: usize) -> f64 {
+    let predicted = predicted.split_last().map_or(predicted, |(_, rest)| rest);
+    let truth = truth.split_last().map_or(truth, |(_, rest)| rest);
+    if predicted.is_empty() && truth.is_empty() {
+        return 1.0;
+    }
+
+    // Match every true changepoint with the first unmatched prediction within the margin
+    let mut matches = 0;
+    let mut unmatched = predicted.iter().peekable();
+    for true_changepoint in truth {
+        while unmatched
+            .next_if(|changepoint| **changepoint + margin < *true_changepoint)
+            .is_some()
+        {}
+
+        if unmatched
+            .next_if(|changepoint| **changepoint <= true_changepoint + margin)
+            .is_some()
+        {
+            matches += 1;
+        }
+    }
+
+    // Harmonic mean of the precision and the recall
+    2.0 * matches as f64 / (predicted.len() + truth.len()) as f64
+}
+
+/// Find the penalty and minimum segment length with the highest mean F1 score on the labeled signals.
+pub(crate) fn calibrate<A>(
+    pelt: &Pelt,
+    labeled: &[(ArrayView2<A>, &[usize])],
+    penalties: &[f64],
+    minimum_segment_lengths: &[NonZero<usize>],
+    margin: usize,
+) -> Result<Calibration, Error>
+where
+    A: Sample,
+{
+    if labeled.is_empty() {
+        return Err(Error::MissingLabels);
+    }
+    if penalties.is_empty() {
+        return Err(Error::InvalidPenaltyRange);
+    }
+
+    let configured = [NonZero::new(pelt.minimum_segment_length).unwrap_or(NonZero::<usize>::MIN)];
+    let minimum_segment_lengths = if minimum_segment_lengths.is_empty() {
+        configured.as_slice()
+    } else {
+        minimum_segment_lengths
+    };
+
+    let mut best: Option<Calibration> = None;
+    for minimum_segment_length in minimum_segment_lengths {
+        // The true changepoints are in the default convention
+        let candidate = pelt
+            .clone()
+            .with_minimum_segment_length(*minimum_segment_length)
+            .with_changepoint_convention(ChangepointConvention::Exclusive)
+            .with_keep_initial_zero(false)
+            .with_keep_signal_length(true);
+
+        // Sum of the scores of every penalty over all signals
+        let mut f1_sums = vec![0.0; penalties.len()];
+        for (signal, truth) in labeled {
+            let predictions = candidate.predict_many(signal.view(), penalties)?;
+            for (f1_sum, predicted) in f1_sums.iter_mut().zip(&predictions) {
+                *f1_sum += f1_score(predicted, truth, margin);
+            }
+        }
+
+        for (penalty, f1_sum) in penalties.iter().zip(f1_sums) {
+            let f1 = f1_sum / labeled.len() as f64;
+            // Keep the first of equally good candidates
+            if best.is_none_or(|best| f1 > best.f1) {
+                best = Some(Calibration {
+                    penalty: *penalty,
+                    minimum_segment_length: minimum_segment_length.get(),
+                    f1,
+                });
+            }
+        }
+    }
+
+    best.ok_or(Error::InvalidPenaltyRange)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::f1_score;
+
+    /// Matches within the margin, every changepoint only once.
+    #[test]
+    fn f1() {
+        assert!((f1_score(&[100], &[100], 5) - 1.0).abs() < f64::EPSILON);
+        assert!((f1_score(&[50, 100], &[53, 100], 5) - 1.0).abs() < f64::EPSILON);
+        assert!(f1_score(&[50, 100], &[60, 100], 5).abs() < f64::EPSILON);
+        // One match out of two predicted and one true
+        assert!((f1_score(&[50, 52, 100], &[51, 100], 5) - 2.0 / 3.0).abs() < f64::EPSILON);
+        assert!((f1_score(&[20, 50, 80, 100], &[50, 100], 0) - 0.5).abs() < f64::EPSILON);
+    }
+}
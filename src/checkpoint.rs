@@ -0,0 +1,572 @@
+//! Checkpoints to resume long predictions.
+
+use std::{
+    collections::HashSet,
+    io::{self, ErrorKind, Read, Write},
+    ops::Range,
+};
+
+use ndarray::{ArrayView, Dimension};
+use rustc_hash::FxBuildHasher;
+
+use crate::{
+    Error, OneOrTwoDimensions, Penalty, Sample, SegmentCostFunction,
+    predict::{Index, Partition, Partitions, PredictImpl, check_signal_len, from_index, to_index},
+};
+
+/// Magic string at the start of every checkpoint file.
+const MAGIC: &[u8] = b"PELTCKPT";
+
+/// Version of the file format, changed when the layout changes.
+const VERSION: u64 = 3;
+
+/// State of an unfinished prediction.
+///
+/// Contains everything needed to continue solving the remaining breakpoints: the admissible indices and the optimal partitions that can still be used.
+/// Can be written to disk with [`Checkpoint::write`] and read back with [`Checkpoint::read`], so a long prediction survives a restart of the process.
+///
+/// Only valid for the same signal, penalty and [`crate::Pelt`] settings it was created with.
+/// The length of the signal, the penalty, the jump, the minimum segment length, the cost function, the candidates and the forbidden ranges are checked when resuming.
+#[derive(Clone)]
+pub struct Checkpoint {
+    /// Jump of the settings it was created with.
+    jump: usize,
+    /// Minimum segment length of the settings it was created with.
+    minimum_segment_length: usize,
+    /// Cost function of the settings it was created with.
+    segment_cost_function: SegmentCostFunction,
+    /// Candidates of the settings it was created with.
+    candidates: Option<Vec<usize>>,
+    /// Forbidden ranges of the settings it was created with.
+    forbidden_ranges: Vec<Range<usize>>,
+    /// Fingerprint of the penalty it was created with, see [`penalty_fingerprint`].
+    penalty: u64,
+    /// Length of the signal it was created for.
+    signal_len: usize,
+    /// Next breakpoint that isn't solved yet.
+    next_breakpoint: usize,
+    /// Indices that can still be the start of the last segment.
+    admissible: Vec<Index>,
+    /// Optimal partitions that can still be used by the next breakpoints.
+    partitions: Partitions,
+}
+
+impl Checkpoint {
+    /// Next breakpoint that isn't solved yet, can be compared to the length of the signal to show progress.
+    #[must_use]
+    pub const fn next_breakpoint(&self) -> usize {
+        self.next_breakpoint
+    }
+
+    /// Serialize to a writer, such as a file.
+    ///
+    /// # Errors
+    ///
+    /// - When writing fails.
+    pub fn write<W>(&self, mut writer: W) -> io::Result<()>
+    where
+        W: Write,
+    {
+        writer.write_all(MAGIC)?;
+        for value in [
+            VERSION,
+            self.jump as u64,
+            self.minimum_segment_length as u64,
+            self.signal_len as u64,
+            self.next_breakpoint as u64,
+            u64::from(self.segment_cost_function.variant_index()),
+            match self.segment_cost_function {
+                SegmentCostFunction::Quantile(quantile) => quantile.to_bits(),
+                SegmentCostFunction::L1 | SegmentCostFunction::L2 => 0,
+            },
+            self.penalty,
+        ] {
+            write_u64(&mut writer, value)?;
+        }
+
+        // Whether there are candidates, an empty list of candidates is also valid
+        write_u64(&mut writer, u64::from(self.candidates.is_some()))?;
+        write_positions(&mut writer, self.candidates.as_deref().unwrap_or_default())?;
+        write_positions(
+            &mut writer,
+            &self
+                .forbidden_ranges
+                .iter()
+                .flat_map(|range| [range.start, range.end])
+                .collect::<Vec<_>>(),
+        )?;
+
+        write_indices(&mut writer, &self.admissible)?;
+
+        // Sort the partitions, so the same state always results in the same bytes
+        let mut partitions = self.partitions.iter().collect::<Vec<_>>();
+        partitions.sort_unstable_by_key(|(breakpoint, _partition)| **breakpoint);
+
+        write_u64(&mut writer, partitions.len() as u64)?;
+        for (breakpoint, partition) in partitions {
+            write_u64(&mut writer, from_index(*breakpoint) as u64)?;
+            write_u64(&mut writer, from_index(partition.previous) as u64)?;
+            writer.write_all(&partition.loss_and_penalty_sum.to_le_bytes())?;
+        }
+
+        writer.flush()
+    }
+
+    /// Deserialize from a reader, such as a file.
+    ///
+    /// # Errors
+    ///
+    /// - When reading fails.
+    /// - When the data isn't a checkpoint.
+    pub fn read<R>(mut reader: R) -> io::Result<Self>
+    where
+        R: Read,
+    {
+        let mut magic = [0; MAGIC.len()];
+        reader.read_exact(&mut magic)?;
+        if magic != MAGIC {
+            return Err(io::Error::new(ErrorKind::InvalidData, "not a checkpoint"));
+        }
+        if read_u64(&mut reader)? != VERSION {
+            return Err(io::Error::new(
+                ErrorKind::InvalidData,
+                "unsupported checkpoint version",
+            ));
+        }
+
+        let jump = read_usize(&mut reader)?;
+        let minimum_segment_length = read_usize(&mut reader)?;
+        let signal_len = read_usize(&mut reader)?;
+        let next_breakpoint = read_usize(&mut reader)?;
+        let variant_index = read_u64(&mut reader)?;
+        let parameter = f64::from_bits(read_u64(&mut reader)?);
+        let segment_cost_function = match variant_index {
+            0 => SegmentCostFunction::L1,
+            1 => SegmentCostFunction::L2,
+            2 => SegmentCostFunction::Quantile(parameter),
+            _ => {
+                return Err(io::Error::new(
+                    ErrorKind::InvalidData,
+                    "unknown cost function",
+                ));
+            }
+        };
+        let penalty = read_u64(&mut reader)?;
+        let has_candidates = read_u64(&mut reader)? != 0;
+        let candidates = read_positions(&mut reader)?;
+        let candidates = has_candidates.then_some(candidates);
+        let forbidden_ranges = read_positions(&mut reader)?
+            .chunks(2)
+            .map(|bounds| match *bounds {
+                [start, end] => Ok(start..end),
+                _ => Err(io::Error::new(
+                    ErrorKind::InvalidData,
+                    "forbidden range without end",
+                )),
+            })
+            .collect::<io::Result<Vec<_>>>()?;
+        let admissible = read_indices(&mut reader)?;
+
+        let partitions_len = read_usize(&mut reader)?;
+        let mut partitions = Partitions::with_hasher(FxBuildHasher);
+        for _ in 0..partitions_len {
+            let breakpoint = read_index(&mut reader)?;
+            let previous = read_index(&mut reader)?;
+
+            let mut loss_and_penalty_sum = [0; size_of::<f64>()];
+            reader.read_exact(&mut loss_and_penalty_sum)?;
+
+            partitions.insert(
+                breakpoint,
+                Partition {
+                    previous,
+                    loss_and_penalty_sum: f64::from_le_bytes(loss_and_penalty_sum),
+                },
+            );
+        }
+
+        Ok(Self {
+            jump,
+            minimum_segment_length,
+            segment_cost_function,
+            candidates,
+            forbidden_ranges,
+            penalty,
+            signal_len,
+            next_breakpoint,
+            admissible,
+            partitions,
+        })
+    }
+}
+
+impl PredictImpl {
+    /// Run the calculation loop, resuming from a checkpoint and creating a new one every `interval` breakpoints.
+    pub(crate) fn predict_checkpointed<A, D, P, F>(
+        &mut self,
+        signal: &ArrayView<A, D>,
+        penalty: &P,
+        resume: Option<Checkpoint>,
+        interval: usize,
+        on_checkpoint: &mut F,
+    ) -> Result<Vec<usize>, Error>
+    where
+        A: Sample,
+        D: OneOrTwoDimensions + Dimension,
+        P: Penalty,
+        D::PrecalculationOutput: Sync,
+        F: FnMut(&Checkpoint),
+    {
+        // Length as the rows
+        let len = D::len_or_nrows(signal);
+        check_signal_len(len)?;
+
+        let penalty_fingerprint = self.penalty_fingerprint(penalty, len);
+
+        // Continue with the state of the checkpoint, or start from the beginning
+        let (mut partitions, next_breakpoint) = match resume {
+            Some(checkpoint) => {
+                if checkpoint.jump != self.pelt.jump
+                    || checkpoint.minimum_segment_length != self.pelt.minimum_segment_length
+                    || checkpoint.segment_cost_function != self.pelt.segment_cost_function
+                    || checkpoint.candidates != self.pelt.candidates
+                    || checkpoint.forbidden_ranges != self.pelt.forbidden_ranges
+                    || checkpoint.penalty != penalty_fingerprint
+                    || checkpoint.signal_len != len
+                {
+                    return Err(Error::InvalidCheckpoint);
+                }
+
+                self.admissible = checkpoint.admissible;
+
+                (checkpoint.partitions, checkpoint.next_breakpoint)
+            }
+            None => {
+                let mut partitions = Partitions::with_hasher(FxBuildHasher);
+                partitions.insert(0, Partition::default());
+                self.admissible.clear();
+
+                (partitions, 0)
+            }
+        };
+
+        // Precalculate the cost function, which isn't part of the checkpoint because it's derived from the signal
+        let cost = self.precalculate(signal)?;
+
+        let mut solved = 0_usize;
+        let mut breakpoints = self
+            .proposed_indices(len)
+            .skip_while(|breakpoint| *breakpoint < next_breakpoint)
+            .peekable();
+        while let Some(breakpoint) = breakpoints.next() {
+            let partition =
+                self.solve_breakpoint::<D, _>(&partitions, &cost, breakpoint, len, penalty)?;
+            partitions.insert(to_index(breakpoint), partition);
+
+            solved += 1;
+            if let Some(next_breakpoint) = breakpoints.peek()
+                && solved.is_multiple_of(interval)
+            {
+                on_checkpoint(&self.checkpoint(
+                    &mut partitions,
+                    len,
+                    *next_breakpoint,
+                    penalty_fingerprint,
+                ));
+            }
+        }
+
+        // Get the best partition
+        let best_part = partitions
+            .remove(&to_index(len))
+            .ok_or(Error::NoSegmentsFound)?;
+
+        self.changepoints(&partitions, best_part, len)
+    }
+
+    /// Drop the partitions that can't be used anymore and capture the state.
+    fn checkpoint(
+        &self,
+        partitions: &mut Partitions,
+        signal_len: usize,
+        next_breakpoint: usize,
+        penalty: u64,
+    ) -> Checkpoint {
+        // Partitions before the next admission point are only used when they're still admissible
+        let next_admission_point = to_index(
+            (next_breakpoint.saturating_sub(self.pelt.minimum_segment_length) / self.pelt.jump)
+                * self.pelt.jump,
+        );
+        let mut keep = partitions
+            .keys()
+            .copied()
+            .filter(|breakpoint| {
+                *breakpoint >= next_admission_point || self.admissible.contains(breakpoint)
+            })
+            .collect::<HashSet<_, FxBuildHasher>>();
+
+        // The previous changepoints of the kept partitions are needed to reconstruct the result
+        let mut unvisited = keep.iter().copied().collect::<Vec<_>>();
+        while let Some(breakpoint) = unvisited.pop() {
+            if let Some(partition) = partitions.get(&breakpoint)
+                && keep.insert(partition.previous)
+            {
+                unvisited.push(partition.previous);
+            }
+        }
+        partitions.retain(|breakpoint, _partition| keep.contains(breakpoint));
+
+        Checkpoint {
+            jump: self.pelt.jump,
+            minimum_segment_length: self.pelt.minimum_segment_length,
+            segment_cost_function: self.pelt.segment_cost_function,
+            candidates: self.pelt.candidates.clone(),
+            forbidden_ranges: self.pelt.forbidden_ranges.clone(),
+            penalty,
+            signal_len,
+            next_breakpoint,
+            admissible: self.admissible.clone(),
+            partitions: partitions.clone(),
+        }
+    }
+
+    /// Fingerprint of the penalty of the segments starting and ending at every breakpoint, to detect resuming with a different penalty.
+    ///
+    /// Hashed with FNV-1a, so it's the same in every process and on every platform.
+    fn penalty_fingerprint<P>(&self, penalty: &P, signal_len: usize) -> u64
+    where
+        P: Penalty,
+    {
+        let breakpoints = std::iter::once(0).chain(self.proposed_indices(signal_len));
+        let penalties = std::iter::once(penalty.pruning_penalty(signal_len)).chain(
+            breakpoints.flat_map(|breakpoint| {
+                [
+                    penalty.segment_penalty(breakpoint..signal_len, signal_len),
+                    penalty.segment_penalty(0..breakpoint, signal_len),
+                ]
+            }),
+        );
+
+        penalties
+            .flat_map(|penalty| penalty.to_bits().to_le_bytes())
+            .fold(0xcbf2_9ce4_8422_2325, |hash, byte| {
+                (hash ^ u64::from(byte)).wrapping_mul(0x0100_0000_01b3)
+            })
+    }
+}
+
+/// Write a number as little-endian bytes.
+fn write_u64<W>(writer: &mut W, value: u64) -> io::Result<()>
+where
+    W: Write,
+{
+    writer.write_all(&value.to_le_bytes())
+}
+
+/// Write a list of indices prefixed by its length.
+fn write_indices<W>(writer: &mut W, indices: &[Index]) -> io::Result<()>
+where
+    W: Write,
+{
+    write_u64(writer, indices.len() as u64)?;
+    indices
+        .iter()
+        .try_for_each(|index| write_u64(writer, from_index(*index) as u64))
+}
+
+/// Write a list of positions in the signal prefixed by its length.
+fn write_positions<W>(writer: &mut W, positions: &[usize]) -> io::Result<()>
+where
+    W: Write,
+{
+    write_u64(writer, positions.len() as u64)?;
+    positions
+        .iter()
+        .try_for_each(|position| write_u64(writer, *position as u64))
+}
+
+/// Read a number from little-endian bytes.
+fn read_u64<R>(reader: &mut R) -> io::Result<u64>
+where
+    R: Read,
+{
+    let mut bytes = [0; size_of::<u64>()];
+    reader.read_exact(&mut bytes)?;
+
+    Ok(u64::from_le_bytes(bytes))
+}
+
+/// Read a number that must fit in an index.
+fn read_usize<R>(reader: &mut R) -> io::Result<usize>
+where
+    R: Read,
+{
+    usize::try_from(read_u64(reader)?)
+        .map_err(|_| io::Error::new(ErrorKind::InvalidData, "index doesn't fit in memory"))
+}
+
+/// Read a position in the signal as stored by the solver.
+fn read_index<R>(reader: &mut R) -> io::Result<Index>
+where
+    R: Read,
+{
+    Index::try_from(read_u64(reader)?)
+        .map_err(|_| io::Error::new(ErrorKind::InvalidData, "index is too large"))
+}
+
+/// Read a list of positions in the signal prefixed by its length.
+fn read_positions<R>(reader: &mut R) -> io::Result<Vec<usize>>
+where
+    R: Read,
+{
+    let len = read_usize(reader)?;
+
+    (0..len).map(|_| read_usize(reader)).collect()
+}
+
+/// Read a list of indices prefixed by its length.
+fn read_indices<R>(reader: &mut R) -> io::Result<Vec<Index>>
+where
+    R: Read,
+{
+    let len = read_usize(reader)?;
+
+    (0..len).map(|_| read_index(reader)).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use std::num::NonZero;
+
+    use crate::{Pelt, SegmentCostFunction};
+
+    /// Resuming from every checkpoint gives the same result as an uninterrupted run.
+    #[test]
+    fn resume() {
+        let signal = (0..200)
+            .map(|index| match index {
+                0..60 => 0.0,
+                60..140 => 5.0,
+                _ => 1.0,
+            } + if index % 3 == 0 { 0.2 } else { -0.1 })
+            .collect::<Vec<_>>();
+
+        let pelt = Pelt::new()
+            .with_jump(NonZero::new(1).expect("Invalid number"))
+            .with_segment_cost_function(SegmentCostFunction::L2);
+        let expected = pelt.predict(&signal, 5.0).expect("Error predicting");
+
+        // Serialize every checkpoint
+        let mut checkpoints = Vec::new();
+        let changepoints = pelt
+            .predict_checkpointed(
+                &signal,
+                5.0,
+                None,
+                NonZero::new(25).expect("Invalid number"),
+                |checkpoint| {
+                    let mut bytes = Vec::new();
+                    checkpoint.write(&mut bytes).expect("Error writing");
+                    checkpoints.push(bytes);
+                },
+            )
+            .expect("Error predicting");
+        assert_eq!(changepoints, expected);
+        assert_eq!(checkpoints.len(), 7);
+
+        for bytes in checkpoints {
+            let checkpoint = super::Checkpoint::read(bytes.as_slice()).expect("Error reading");
+
+            assert_eq!(
+                pelt.predict_checkpointed(
+                    &signal,
+                    5.0,
+                    Some(checkpoint),
+                    NonZero::new(25).expect("Invalid number"),
+                    |_checkpoint| {}
+                )
+                .expect("Error resuming"),
+                expected
+            );
+        }
+
+        // Different signals can't be resumed
+        let mut bytes = Vec::new();
+        pelt.predict_checkpointed(
+            &signal,
+            5.0,
+            None,
+            NonZero::new(150).expect("Invalid number"),
+            |checkpoint| checkpoint.write(&mut bytes).expect("Error writing"),
+        )
+        .expect("Error predicting");
+        assert!(
+            pelt.predict_checkpointed(
+                &signal[..100],
+                5.0,
+                super::Checkpoint::read(bytes.as_slice()).ok(),
+                NonZero::new(150).expect("Invalid number"),
+                |_checkpoint| {}
+            )
+            .is_err(),
+            "Checkpoint of a different signal should be rejected"
+        );
+    }
+
+    /// A checkpoint only resumes with the same settings and penalty it was created with.
+    #[test]
+    fn resume_settings() {
+        let signal = (0..200)
+            .map(|index| f64::from(index / 70) + if index % 2 == 0 { 0.1 } else { -0.1 })
+            .collect::<Vec<_>>();
+
+        let pelt = Pelt::new()
+            .with_jump(NonZero::new(1).expect("Invalid number"))
+            .with_segment_cost_function(SegmentCostFunction::Quantile(0.3))
+            .with_candidates(&[50, 70, 100, 140, 160])
+            .with_forbidden_ranges(&[100..120, 180..190]);
+        let mut bytes = Vec::new();
+        let expected = pelt
+            .predict_checkpointed(
+                &signal,
+                1.0,
+                None,
+                NonZero::new(3).expect("Invalid number"),
+                |checkpoint| {
+                    if bytes.is_empty() {
+                        checkpoint.write(&mut bytes).expect("Error writing");
+                    }
+                },
+            )
+            .expect("Error predicting");
+        let resume = |pelt: &Pelt, penalty: f64| {
+            pelt.predict_checkpointed(
+                &signal,
+                penalty,
+                Some(super::Checkpoint::read(bytes.as_slice()).expect("Error reading")),
+                NonZero::new(3).expect("Invalid number"),
+                |_checkpoint| {},
+            )
+        };
+
+        assert_eq!(resume(&pelt, 1.0).expect("Error resuming"), expected);
+        for (different, penalty) in [
+            (pelt.clone(), 2.0),
+            (
+                pelt.clone()
+                    .with_segment_cost_function(SegmentCostFunction::Quantile(0.4)),
+                1.0,
+            ),
+            (pelt.clone().with_candidates(&[50, 70, 100, 140]), 1.0),
+            (pelt.with_forbidden_ranges(&[100..110, 180..190]), 1.0),
+        ] {
+            assert!(
+                matches!(
+                    resume(&different, penalty),
+                    Err(crate::Error::InvalidCheckpoint)
+                ),
+                "{different:?} {penalty}"
+            );
+        }
+    }
+}
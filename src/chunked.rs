@@ -0,0 +1,88 @@
+//! Segmentation of signals in overlapping chunks.
+
+use ndarray::{Array, Axis, Dimension, RemoveAxis, Slice};
+
+use crate::{Error, OneOrTwoDimensions, Penalty, Sample, nan, predict::PredictImpl};
+
+impl PredictImpl {
+    /// Segment each chunk together with the end of the previous chunk, and stitch the changepoints.
+    ///
+    /// The overlap between two windows is split in the middle, changepoints before the middle are taken from the first window and after it from the second.
+    pub(crate) fn predict_chunked<A, D, P, I>(
+        &mut self,
+        chunks: I,
+        penalty: &P,
+        overlap: usize,
+    ) -> Result<Vec<usize>, Error>
+    where
+        A: Sample,
+        D: OneOrTwoDimensions + Dimension + RemoveAxis,
+        P: Penalty,
+        D::PrecalculationOutput: Sync,
+        I: IntoIterator<Item = Array<A, D>>,
+    {
+        let mut chunks = chunks.into_iter().peekable();
+
+        let mut changepoints = Vec::new();
+        // End of the previous window, which is still part of the next window
+        let mut tail: Option<Array<A, D>> = None;
+        // Index of the first point of the current window in the whole signal
+        let mut offset = 0;
+
+        while let Some(chunk) = chunks.next() {
+            let chunk = self.pelt.layout(chunk)?;
+
+            // Prepend the end of the previous window
+            let (window, tail_len) = match tail.take() {
+                Some(tail) => {
+                    let tail_len = D::len_or_nrows(&tail.view());
+                    let window = ndarray::concatenate(Axis(0), &[tail.view(), chunk.view()])
+                        .map_err(|_| Error::ColumnMismatch)?;
+
+                    (window, tail_len)
+                }
+                None => (chunk, 0),
+            };
+            let window_view = window.view();
+            let len = D::len_or_nrows(&window_view);
+            nan::check(self.pelt.nan_policy, &window_view, offset)?;
+
+            // Try to lower 2D to 1D to parse as 1D array, since that's faster
+            let window_changepoints = match D::try_as_1d(&window_view) {
+                Some(window_1d) => self.predict(&window_1d, penalty),
+                None => self.predict(&window_view, penalty),
+            }?;
+
+            // Only keep the changepoints between the middles of both overlaps
+            let is_last = chunks.peek().is_none();
+            let next_tail_len = if is_last { 0 } else { overlap.min(len) };
+            let keep = (tail_len / 2)..(len - next_tail_len + next_tail_len / 2);
+            changepoints.extend(
+                window_changepoints
+                    .into_iter()
+                    .filter(|changepoint| *changepoint > 0 && keep.contains(changepoint))
+                    .map(|changepoint| changepoint + offset),
+            );
+
+            // Keep the end of the window for the next one
+            if !is_last {
+                tail = Some(
+                    window
+                        .slice_axis(Axis(0), Slice::from((len - next_tail_len)..))
+                        .to_owned(),
+                );
+                offset += len - next_tail_len;
+            } else {
+                // Add the last item
+                changepoints.push(offset + len);
+            }
+        }
+
+        // No chunks at all
+        if changepoints.is_empty() {
+            return Err(Error::NotEnoughPoints);
+        }
+
+        Ok(changepoints)
+    }
+}
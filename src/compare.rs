@@ -0,0 +1,79 @@
+//! Compare segment cost functions.
+
+use ndarray::{ArrayView, Dimension};
+
+use crate::{
+    Error, InformationCriterion, OneOrTwoDimensions, Sample, SegmentCostFunction,
+    predict::PredictImpl,
+};
+
+/// Segmentation of a signal with a single segment cost function, scored with the BIC.
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct ModelComparison {
+    /// Segment cost function used for the segmentation.
+    pub segment_cost_function: SegmentCostFunction,
+    /// Indices where changes were detected, including the end of the signal.
+    pub changepoints: Vec<usize>,
+    /// Total loss of all segments, without penalties.
+    pub loss: f64,
+    /// Bayesian information criterion of the segmentation, lower is better.
+    ///
+    /// Comparable between segment cost functions because it's calculated from the full likelihood of the segment model.
+    pub bic: f64,
+}
+
+/// Bayesian information criterion of a segmentation, from the full likelihood of the segment model.
+pub(crate) fn bic(
+    segment_cost_function: SegmentCostFunction,
+    loss: f64,
+    segments: usize,
+    rows: usize,
+    columns: usize,
+) -> f64 {
+    // A level per column for each segment, a location for each changepoint and a single scale
+    let parameters = segments * columns + segments.saturating_sub(1) + 1;
+    let values = rows * columns;
+
+    (parameters as f64).mul_add(
+        (values as f64).ln(),
+        segment_cost_function.minus_two_log_likelihood(loss, values),
+    )
+}
+
+impl PredictImpl {
+    /// Segment the signal with the penalty from the information criterion and score it with the BIC.
+    pub(crate) fn compare_model<A, D>(
+        &mut self,
+        signal: &ArrayView<A, D>,
+        information_criterion: InformationCriterion,
+    ) -> Result<ModelComparison, Error>
+    where
+        A: Sample,
+        D: OneOrTwoDimensions + Dimension,
+        D::PrecalculationOutput: Sync,
+    {
+        let segment_cost_function = self.pelt.segment_cost_function;
+        let len = D::len_or_nrows(signal);
+
+        // Segment with a comparable penalty for each cost function
+        let penalty = information_criterion.penalty(segment_cost_function, signal)?;
+        let cost = self.precalculate(signal)?;
+        let changepoints = self.predict_with_cost(signal, &cost, &penalty)?;
+        let loss = Self::segmentation_loss::<D>(&cost, &changepoints);
+        let bic = bic(
+            segment_cost_function,
+            loss,
+            changepoints.len(),
+            len,
+            signal.len() / len.max(1),
+        );
+
+        Ok(ModelComparison {
+            segment_cost_function,
+            changepoints,
+            loss,
+            bic,
+        })
+    }
+}
@@ -0,0 +1,84 @@
+//! Index conventions of the predicted changepoints.
+
+/// Which sample the index of a changepoint points to.
+///
+/// Together with [`crate::Pelt::with_keep_initial_zero`] and [`crate::Pelt::with_keep_signal_length`] the changepoints can match other libraries:
+///
+/// - `ruptures`: [`ChangepointConvention::Exclusive`], with the length of the signal.
+/// - R `changepoint` package (`cpts`): [`ChangepointConvention::Exclusive`], without the length of the signal, since its one-based index of the last sample of a segment is the zero-based index of the first sample of the next.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum ChangepointConvention {
+    /// The changepoint is the index of the first sample of the next segment, the exclusive end of the segment before it.
+    #[default]
+    Exclusive,
+    /// The changepoint is the index of the last sample of the segment before it, the inclusive end.
+    Inclusive,
+}
+
+/// Convert changepoints ending with the length of the signal to the configured convention.
+pub(crate) fn apply(
+    mut changepoints: Vec<usize>,
+    convention: ChangepointConvention,
+    keep_initial_zero: bool,
+    keep_signal_length: bool,
+) -> Vec<usize> {
+    if !keep_signal_length {
+        changepoints.pop();
+    }
+
+    if convention == ChangepointConvention::Inclusive {
+        for changepoint in &mut changepoints {
+            *changepoint = changepoint.saturating_sub(1);
+        }
+    }
+
+    if keep_initial_zero {
+        changepoints.insert(0, 0);
+    }
+
+    changepoints
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{ChangepointConvention, apply};
+
+    /// Every combination of the options.
+    #[test]
+    fn conventions() {
+        let changepoints = vec![50, 80, 100];
+
+        assert_eq!(
+            apply(
+                changepoints.clone(),
+                ChangepointConvention::Exclusive,
+                false,
+                true
+            ),
+            [50, 80, 100]
+        );
+        assert_eq!(
+            apply(
+                changepoints.clone(),
+                ChangepointConvention::Exclusive,
+                true,
+                false
+            ),
+            [0, 50, 80]
+        );
+        assert_eq!(
+            apply(
+                changepoints.clone(),
+                ChangepointConvention::Inclusive,
+                false,
+                true
+            ),
+            [49, 79, 99]
+        );
+        assert_eq!(
+            apply(changepoints, ChangepointConvention::Inclusive, true, false),
+            [0, 49, 79]
+        );
+    }
+}
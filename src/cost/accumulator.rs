@@ -0,0 +1,124 @@
+//! Summation algorithms for the running sums of the cost functions.
+
+/// Summation algorithm for the running sums the cost functions are precalculated with.
+///
+/// The loss of a segment is the difference of two running sums, so rounding errors in the running sums of long signals end up in the losses of short segments at the end.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum Accumulator {
+    /// Add every value to the running sum.
+    ///
+    /// Fastest, but the rounding error grows linearly with the length of the signal.
+    #[default]
+    Naive,
+    /// Compensated summation, tracking the rounding error of every addition.
+    ///
+    /// The rounding error doesn't grow with the length of the signal.
+    Kahan,
+    /// Sum aligned blocks of values pairwise, every running sum is the sum of at most one block per power of two.
+    ///
+    /// The rounding error grows logarithmically with the length of the signal.
+    Pairwise,
+}
+
+impl Accumulator {
+    /// Running sums of the values, the sum of the first `index + 1` values at every index.
+    pub(crate) fn prefix_sums<I>(self, values: I) -> Vec<f64>
+    where
+        I: Iterator<Item = f64>,
+    {
+        match self {
+            Self::Naive => values
+                .scan(0.0, |sum, value| {
+                    *sum += value;
+
+                    Some(*sum)
+                })
+                .collect(),
+            Self::Kahan => values
+                .scan((0.0, 0.0), |(sum, compensation), value: f64| {
+                    // Neumaier's variant, also correct when the value is larger than the sum
+                    let next_sum = *sum + value;
+                    *compensation += if sum.abs() >= value.abs() {
+                        (*sum - next_sum) + value
+                    } else {
+                        (value - next_sum) + *sum
+                    };
+                    *sum = next_sum;
+
+                    Some(*sum + *compensation)
+                })
+                .collect(),
+            Self::Pairwise => pairwise_prefix_sums(values.collect()),
+        }
+    }
+}
+
+/// Running sums from the pairwise sums of aligned blocks with a power of two length.
+fn pairwise_prefix_sums(values: Vec<f64>) -> Vec<f64> {
+    let len = values.len();
+
+    // Every level sums the pairs of the level below, so level `k` contains the sums of the blocks of `2^k` values
+    let mut levels = vec![values];
+    while let Some(level) = levels.last().filter(|level| level.len() > 1) {
+        let next_level = level
+            .chunks_exact(2)
+            .map(|pair| pair.iter().sum())
+            .collect();
+        levels.push(next_level);
+    }
+
+    (1..=len)
+        .map(|end| {
+            // Take the largest blocks first, every block starts where the previous one ended
+            let mut start = 0;
+            let mut sum = 0.0;
+            for (level_index, level) in levels.iter().enumerate().rev() {
+                let block_len = 1 << level_index;
+                if end - start >= block_len {
+                    sum += level.get(start >> level_index).copied().unwrap_or_default();
+                    start += block_len;
+                }
+            }
+
+            sum
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// All accumulators give the running sums, the compensated ones without losing small values.
+    #[test]
+    fn prefix_sums() {
+        let values = [1.0, 2.0, 3.0, 4.0, 5.0];
+        for accumulator in [
+            Accumulator::Naive,
+            Accumulator::Kahan,
+            Accumulator::Pairwise,
+        ] {
+            assert_eq!(
+                accumulator.prefix_sums(values.into_iter()),
+                vec![1.0, 3.0, 6.0, 10.0, 15.0],
+                "{accumulator:?}"
+            );
+        }
+
+        // Every small value is lost when added to the large one
+        let values = std::iter::once(1e16).chain(std::iter::repeat_n(1.0, 1000));
+        assert_eq!(
+            Accumulator::Naive.prefix_sums(values.clone()).last(),
+            Some(&1e16)
+        );
+        assert_eq!(
+            Accumulator::Kahan.prefix_sums(values.clone()).last(),
+            Some(&(1e16 + 1000.0))
+        );
+        assert_eq!(
+            Accumulator::Pairwise.prefix_sums(values).last(),
+            Some(&(1e16 + 1000.0))
+        );
+    }
+}
@@ -0,0 +1,169 @@
+//! Calibration of the threading heuristics on the current machine.
+
+use std::{
+    hint::black_box,
+    sync::{Mutex, PoisonError},
+    time::Instant,
+};
+
+use ndarray::Array1;
+use rayon::iter::{IntoParallelIterator as _, ParallelIterator as _};
+
+use crate::{Accumulator, NanPolicy, SegmentCostFunction, cost::Cost1D};
+
+/// Length of the signal the losses are measured on.
+///
+/// The loss of the trees only grows logarithmically with the segment length, so a short signal is representative.
+const SIGNAL_LEN: usize = 256;
+
+/// Amount of losses measured per repetition.
+const LOSSES: usize = 64;
+
+/// Amount of repetitions of every measurement, the fastest one is used to ignore interruptions.
+const REPETITIONS: usize = 8;
+
+/// Bounds of the calibrated thresholds, in case a measurement is far off.
+const THRESHOLD_RANGE: (usize, usize) = (2, 1 << 16);
+
+/// Measured thresholds for every amount of threads, only calculated on the first use with that amount.
+static THRESHOLDS: Mutex<Vec<(usize, [Thresholds; 2])>> = Mutex::new(Vec::new());
+
+/// Amount of iterations from which spreading over threads pays off.
+#[derive(Clone, Copy)]
+pub(crate) struct Thresholds {
+    /// Minimum amount of iterations to spread over all threads.
+    pub(crate) threading: usize,
+}
+
+/// Thresholds of the cost function for the thread pool of the caller, measured once per amount of threads.
+#[inline]
+pub(crate) fn thresholds(cost: SegmentCostFunction) -> Thresholds {
+    // A custom thread pool can have a different amount of threads than the global one
+    let threads = rayon::current_num_threads();
+
+    let cached = THRESHOLDS
+        .lock()
+        .unwrap_or_else(PoisonError::into_inner)
+        .iter()
+        .find(|(pool_threads, _)| *pool_threads == threads)
+        .map(|(_, thresholds)| *thresholds);
+    let [trees, sums] = cached.unwrap_or_else(|| {
+        // Measure without holding the lock, the measurement runs on the pool which might need the thresholds itself
+        let thresholds = [
+            calibrate(SegmentCostFunction::L1),
+            calibrate(SegmentCostFunction::L2),
+        ];
+        THRESHOLDS
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner)
+            .push((threads, thresholds));
+
+        thresholds
+    });
+
+    match cost {
+        SegmentCostFunction::L1 | SegmentCostFunction::Quantile(_) => trees,
+        SegmentCostFunction::L2 => sums,
+    }
+}
+
+/// Compare the time of a loss to the overhead of the thread pool.
+fn calibrate(cost: SegmentCostFunction) -> Thresholds {
+    let signal = Array1::from_shape_fn(SIGNAL_LEN, |index| (index % 17) as f64);
+    let signal = signal.view();
+    let precalculated = Cost1D::precalculate(
+        cost,
+        Accumulator::default(),
+        NanPolicy::default(),
+        &signal,
+        None,
+        None,
+    );
+
+    // Segments of all lengths ending at the end of the signal, like the admissible indices of a breakpoint
+    let per_loss = fastest(|| {
+        for start in (0..SIGNAL_LEN).step_by(SIGNAL_LEN / LOSSES) {
+            let mut loss = 0.0;
+            precalculated.loss(&mut loss, start..SIGNAL_LEN);
+            black_box(loss);
+        }
+    }) / LOSSES as f64;
+
+    // Starting the threads of the pool the first time isn't part of the overhead
+    let threading_overhead = fastest(|| {
+        (0..LOSSES).into_par_iter().for_each(|index| {
+            black_box(index);
+        });
+    });
+
+    Thresholds {
+        threading: crossover(threading_overhead, per_loss, rayon::current_num_threads()),
+    }
+}
+
+/// Amount of losses from which spreading them over the threads saves more than the overhead, within the bounds.
+///
+/// With `n` losses of `c` seconds on `k` threads it pays off when `n * c / k + overhead < n * c`.
+fn crossover(overhead: f64, per_loss: f64, threads: usize) -> usize {
+    let (min, max) = THRESHOLD_RANGE;
+    let threads = threads as f64;
+
+    // Dividing by zero for a single thread or an immeasurably fast loss gives infinity, which becomes the maximum
+    ((overhead * threads / ((threads - 1.0) * per_loss)).ceil() as usize).clamp(min, max)
+}
+
+/// Fastest duration of the operation in seconds.
+fn fastest<F>(mut operation: F) -> f64
+where
+    F: FnMut(),
+{
+    (0..REPETITIONS)
+        .map(|_| {
+            let start = Instant::now();
+            operation();
+
+            start.elapsed().as_secs_f64()
+        })
+        .fold(f64::INFINITY, f64::min)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Calibrated thresholds are within the bounds.
+    #[test]
+    fn thresholds_in_range() {
+        let (min, max) = THRESHOLD_RANGE;
+
+        for cost in [SegmentCostFunction::L1, SegmentCostFunction::L2] {
+            let thresholds = thresholds(cost);
+
+            assert!(
+                (min..=max).contains(&thresholds.threading),
+                "{}",
+                thresholds.threading
+            );
+        }
+    }
+
+    /// A thread pool gets the thresholds of its own amount of threads, also when another pool calibrated first.
+    #[test]
+    fn thresholds_per_pool() {
+        let (_, max) = THRESHOLD_RANGE;
+        let pool = rayon::ThreadPoolBuilder::new()
+            .num_threads(1)
+            .build()
+            .expect("Error building thread pool");
+
+        // Calibrate on the global pool first
+        thresholds(SegmentCostFunction::L2);
+
+        // Spreading over a single thread never pays off
+        assert_eq!(
+            pool.install(|| thresholds(SegmentCostFunction::L2))
+                .threading,
+            max
+        );
+    }
+}
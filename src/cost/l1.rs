@@ -1,68 +1,136 @@
 //! L1 cost functions.
 
-use std::ops::Range;
+use std::ops::{Range, RangeInclusive};
 
 use ndarray::{ArrayView1, ArrayView2};
 
-use crate::cost::tree::KthSmallestTree;
+#[cfg(feature = "rayon")]
+use crate::cost::par_columns_loss;
+use crate::{
+    Accumulator, Sample,
+    cost::{l2::L2Cost1D, smallest_weight_root, tree::KthSmallestTree},
+};
+
+/// Minimum amount of columns to calculate the loss of a single segment on multiple threads.
+#[cfg(feature = "rayon")]
+const PARALLEL_COLUMNS: usize = 64;
 
 /// Precalculation output.
 pub struct L1Cost1D {
     /// Tree for finding the mean (middle K-th smallest).
     kth_smallest_tree: KthSmallestTree,
+    /// Squared deviations for cheap lower bounds of the loss.
+    squared: L2Cost1D,
+    /// Square root of the smallest positive weight, scales the lower bound.
+    weight_scale: f64,
 }
 
 impl L1Cost1D {
-    /// Build the K-th smallest tree.
+    /// Build the K-th smallest tree, optionally with a weight for each row.
     #[inline]
-    pub(crate) fn precalculate(signal: &ArrayView1<f64>) -> Self {
+    pub(crate) fn precalculate<A>(
+        signal: &ArrayView1<A>,
+        weights: Option<&ArrayView1<f64>>,
+        accumulator: Accumulator,
+    ) -> Self
+    where
+        A: Sample,
+    {
         // Build the tree from the signal
-        let kth_smallest_tree = KthSmallestTree::build(signal);
+        let kth_smallest_tree = KthSmallestTree::build(signal, weights);
+        let squared = L2Cost1D::precalculate(signal, weights, accumulator);
 
-        Self { kth_smallest_tree }
+        Self {
+            kth_smallest_tree,
+            squared,
+            weight_scale: smallest_weight_root(weights),
+        }
     }
 
-    /// Calculate the loss.
+    /// Lower bound of the loss that's cheaper to calculate.
+    ///
+    /// The sum of absolute deviations is at least the square root of the sum of squared deviations, which is the lowest around the mean.
+    /// With weights, each weighted absolute deviation is at least its weighted squared deviation scaled by the smallest weight.
     #[inline]
-    pub(crate) fn loss(&self, total_loss: &mut f64, signal: &ArrayView1<f64>, range: Range<usize>) {
-        // Calculate the median for the segment
-        let median = self.median(range.clone());
-
-        // Take the sub slice of the 2D object
-        signal
-            .slice(ndarray::s!(range))
-            // Calculate the absolute difference for each point with the median
-            .iter()
-            // Sum the values
-            .for_each(|signal| *total_loss += (*signal - median).abs());
+    pub(crate) fn lower_bound(&self, range: Range<usize>) -> f64 {
+        let mut squared_loss = 0.0;
+        self.squared.loss(&mut squared_loss, range);
+
+        // Rounding can result in a slightly negative sum of squares
+        self.weight_scale * squared_loss.max(0.0).sqrt()
     }
 
-    /// Get the median of a range in the signal.
+    /// Calculate the loss.
+    ///
+    /// Half of the points are at most the median and the other half at least, so the absolute differences follow from the sums of both halves without iterating the segment.
     #[inline]
-    fn median(&self, range: Range<usize>) -> f64 {
-        // Find the midpoint of the range
-        let len = range.len();
-
+    pub(crate) fn loss(&self, total_loss: &mut f64, range: Range<usize>) {
         // Convert the range to an inclusive one
         let range_inclusive = range.start..=(range.end - 1);
 
+        if self.kth_smallest_tree.is_weighted() {
+            return self.weighted_loss(total_loss, range_inclusive);
+        }
+
+        // NaN values aren't counted, a segment with only NaN values has nothing to deviate
+        let len = self.kth_smallest_tree.count(range_inclusive.clone());
+        if len == 0 {
+            return;
+        }
+
+        // Amount of points in the lower half, the median itself is in the upper half for odd lengths
+        let lower_len = len / 2;
+
         // Calculate median based one one or two variables if it's even
-        if len.is_multiple_of(2) {
-            // Get the middle value, offset by 1 for the two points
-            let kth = len / 2;
+        let (median, lower_sum) = if len.is_multiple_of(2) {
+            // Get the two middle values and the sum of all values below them
+            let (median1, lower_sum) = self
+                .kth_smallest_tree
+                .kth_with_sum(range_inclusive.clone(), lower_len);
+            let median2 = self
+                .kth_smallest_tree
+                .kth(range_inclusive.clone(), lower_len + 1);
 
             // Get the average of the two median
-            let median1 = self.kth_smallest_tree.kth(range_inclusive.clone(), kth);
-            let median2 = self.kth_smallest_tree.kth(range_inclusive, kth + 1);
-
-            median1.midpoint(median2)
+            (median1.midpoint(median2), lower_sum)
         } else {
-            // Get the middle value
-            let kth = len / 2 + 1;
+            // Get the middle value, the sum includes it
+            let (median, lower_sum_with_median) = self
+                .kth_smallest_tree
+                .kth_with_sum(range_inclusive.clone(), lower_len + 1);
+
+            (median, lower_sum_with_median - median)
+        };
+        let upper_sum = self.kth_smallest_tree.sum(range_inclusive) - lower_sum;
+        let upper_len = len - lower_len;
+
+        // Sum of the distances below and above the median
+        let lower_loss = (lower_len as f64).mul_add(median, -lower_sum);
+        let upper_loss = (upper_len as f64).mul_add(-median, upper_sum);
+        *total_loss += lower_loss + upper_loss;
+    }
 
-            // Get the median
-            self.kth_smallest_tree.kth(range_inclusive, kth)
+    /// Calculate the loss around the weighted median.
+    ///
+    /// The weighted median is the smallest value where the values up to it have at least half of the weight.
+    #[inline]
+    fn weighted_loss(&self, total_loss: &mut f64, range: RangeInclusive<usize>) {
+        // A segment with only NaN values or zero weights has nothing to deviate
+        if self.kth_smallest_tree.count(range.clone()) == 0 {
+            return;
         }
+
+        let weight = self.kth_smallest_tree.weight(range.clone());
+        let (median, lower_weight, lower_sum) = self
+            .kth_smallest_tree
+            .weighted_kth(range.clone(), weight / 2.0);
+        let upper_sum = self.kth_smallest_tree.sum(range) - lower_sum;
+        let upper_weight = weight - lower_weight;
+
+        // Sum of the weighted distances below and above the median
+        let lower_loss = lower_weight.mul_add(median, -lower_sum);
+        let upper_loss = upper_weight.mul_add(-median, upper_sum);
+        *total_loss += lower_loss + upper_loss;
     }
 }
 
@@ -73,30 +141,65 @@ pub struct L1Cost2D {
 }
 
 impl L1Cost2D {
-    /// Precalculate the sum queries.
+    /// Combine columns that are already precalculated.
+    #[inline]
+    pub(crate) const fn from_columns(columns: Vec<L1Cost1D>) -> Self {
+        Self { columns }
+    }
+
+    /// Build the K-th smallest tree for each column, optionally with the same weight for each row of every column.
     #[inline]
-    pub fn precalculate(signal: &ArrayView2<f64>) -> Self {
+    pub fn precalculate<A>(
+        signal: &ArrayView2<A>,
+        weights: Option<&ArrayView1<f64>>,
+        accumulator: Accumulator,
+    ) -> Self
+    where
+        A: Sample,
+    {
         let columns = signal
             .columns()
             .into_iter()
-            .map(|column| L1Cost1D::precalculate(&column))
+            .map(|column| L1Cost1D::precalculate(&column, weights, accumulator))
             .collect();
 
         Self { columns }
     }
 
     /// Calculate the loss.
-    ///
-    /// Calculated using Welford's algorithm.
     #[inline]
-    pub(crate) fn loss(&self, total_loss: &mut f64, signal: &ArrayView2<f64>, range: Range<usize>) {
-        // Calculate total loss
+    pub(crate) fn loss(&self, total_loss: &mut f64, range: Range<usize>) {
+        // Every column needs multiple tree queries, so already worth it for a moderate amount of columns
+        #[cfg(feature = "rayon")]
+        if self.columns.len() >= PARALLEL_COLUMNS {
+            return par_columns_loss(&self.columns, total_loss, |column, loss| {
+                column.loss(loss, range.clone());
+            });
+        }
+
+        self.loss_bounded(total_loss, range, f64::INFINITY);
+    }
+
+    /// Lower bound of the loss that's cheaper to calculate.
+    #[inline]
+    pub(crate) fn lower_bound(&self, range: Range<usize>) -> f64 {
         self.columns
             .iter()
-            .zip(signal.columns())
-            .for_each(|(column, signal_column)| {
-                column.loss(total_loss, &signal_column, range.clone())
-            })
+            .map(|column| column.lower_bound(range.clone()))
+            .sum()
+    }
+
+    /// Calculate the loss, stopping after the column where the total loss exceeds the bound.
+    #[inline]
+    pub(crate) fn loss_bounded(&self, total_loss: &mut f64, range: Range<usize>, bound: f64) {
+        for column in &self.columns {
+            column.loss(total_loss, range.clone());
+
+            // The remaining columns can only increase the loss
+            if *total_loss > bound {
+                break;
+            }
+        }
     }
 }
 
@@ -108,19 +211,37 @@ mod tests {
     #[test]
     fn cost_1d() {
         let array_1d = ndarray::array![10.0, 30.0, 20.0];
-        let cost = L1Cost1D::precalculate(&array_1d.view());
+        let cost = L1Cost1D::precalculate(&array_1d.view(), None, Accumulator::Naive);
         let mut loss = 0.0;
-        cost.loss(&mut loss, &array_1d.view(), 0..3);
+        cost.loss(&mut loss, 0..3);
         assert_eq!(loss, 20.0);
+
+        // Square root of the L2 loss
+        let lower_bound = cost.lower_bound(0..3);
+        assert!(lower_bound <= loss, "{lower_bound}");
+        assert!(
+            (lower_bound - 200.0_f64.sqrt()).abs() < 1e-9,
+            "{lower_bound}"
+        );
+
+        // Weighted median of 30
+        let weights = ndarray::array![1.0, 3.0, 0.5];
+        let cost =
+            L1Cost1D::precalculate(&array_1d.view(), Some(&weights.view()), Accumulator::Naive);
+        let mut loss = 0.0;
+        cost.loss(&mut loss, 0..3);
+        assert_eq!(loss, 25.0);
+        let lower_bound = cost.lower_bound(0..3);
+        assert!(lower_bound <= loss, "{lower_bound}");
     }
 
     /// Check the L1 cost function.
     #[test]
     fn cost_2d() {
         let array_2d = ndarray::array![[10.0], [30.0], [20.0]];
-        let cost = L1Cost2D::precalculate(&array_2d.view());
+        let cost = L1Cost2D::precalculate(&array_2d.view(), None, Accumulator::Naive);
         let mut loss = 0.0;
-        cost.loss(&mut loss, &array_2d.view(), 0..3);
+        cost.loss(&mut loss, 0..3);
         assert_eq!(loss, 20.0);
     }
 }
@@ -4,33 +4,87 @@ use std::ops::Range;
 
 use ndarray::{ArrayView1, ArrayView2};
 
+use crate::{Accumulator, Sample};
+
+#[cfg(feature = "rayon")]
+use crate::cost::par_columns_loss;
+
+/// Minimum amount of columns to calculate the loss of a single segment on multiple threads.
+#[cfg(feature = "rayon")]
+const PARALLEL_COLUMNS: usize = 1024;
+
 /// Precalculation output.
 pub struct L2Cost1D {
     /// Sum query.
     sums: Vec<Sums>,
+    /// Sum of the weights up to and including each index, `None` when every row has a weight of one.
+    ///
+    /// NaN values have no weight.
+    weight_sums: Option<Vec<f64>>,
 }
 
 impl L2Cost1D {
-    /// Precalculate the sum queries.
+    /// Precalculate the sum queries, optionally with a weight for each row.
     #[inline]
-    pub(crate) fn precalculate(signal: &ArrayView1<f64>) -> Self {
-        // Calculate the sum of all previous values
-        let mut sums = vec![Sums::default(); signal.len()];
+    pub(crate) fn precalculate<A>(
+        signal: &ArrayView1<A>,
+        weights: Option<&ArrayView1<f64>>,
+        accumulator: Accumulator,
+    ) -> Self
+    where
+        A: Sample,
+    {
+        // NaN values don't add anything to the sums
+        let weights_and_values = || {
+            signal.iter().enumerate().map(|(index, value)| {
+                let value = value.to_f64();
+                let weight = weights.map_or(1.0, |weights| weights[index]);
+
+                if value.is_nan() {
+                    (0.0, 0.0)
+                } else {
+                    (weight, value)
+                }
+            })
+        };
         // Sum all previous numbers
-        let mut sum_counter = 0.0;
+        let sum =
+            accumulator.prefix_sums(weights_and_values().map(|(weight, value)| weight * value));
         // Sum the squares of all previous numbers
-        let mut sum_squared_counter = 0.0;
-
-        sums.iter_mut()
-            .zip(signal.iter())
-            .for_each(|(sums, signal)| {
-                sum_counter += *signal;
-                sum_squared_counter += signal.powi(2);
-                sums.sum = sum_counter;
-                sums.sum_squared = sum_squared_counter;
-            });
+        let sum_squared = accumulator
+            .prefix_sums(weights_and_values().map(|(weight, value)| weight * value.powi(2)));
+
+        let sums = sum
+            .into_iter()
+            .zip(sum_squared)
+            .map(|(sum, sum_squared)| Sums { sum, sum_squared })
+            .collect();
+
+        // Only sum the weights when needed, otherwise every row has a weight of one
+        let weight_sums = (weights.is_some() || signal.iter().any(|value| value.to_f64().is_nan()))
+            .then(|| accumulator.prefix_sums(weights_and_values().map(|(weight, _value)| weight)));
+
+        Self { sums, weight_sums }
+    }
+
+    /// Append values to the sums precalculated with the naive accumulator, in the same way as precalculating all values at once.
+    ///
+    /// Returns whether the values are appended, which isn't possible when the rows are weighted or a value is NaN.
+    #[inline]
+    pub(crate) fn extend(&mut self, values: &[f64]) -> bool {
+        if self.weight_sums.is_some() || values.iter().any(|value| value.is_nan()) {
+            return false;
+        }
 
-        Self { sums }
+        let mut last = self.sums.last().cloned().unwrap_or_default();
+        self.sums.extend(values.iter().map(|value| {
+            last.sum += value;
+            last.sum_squared += value.powi(2);
+
+            last.clone()
+        }));
+
+        true
     }
 
     /// Calculate the loss.
@@ -38,8 +92,21 @@ impl L2Cost1D {
     /// Calculated using Welford's algorithm.
     #[inline]
     pub(crate) fn loss(&self, total_loss: &mut f64, range: Range<usize>) {
-        // How many rows there are
-        let rows_length = range.end.saturating_sub(range.start) as f64;
+        // Total weight of the rows, how many there are without the NaN values when not weighted
+        let rows_length = self.weight_sums.as_ref().map_or_else(
+            || range.end.saturating_sub(range.start) as f64,
+            |weight_sums| {
+                weight_sums[range.end.saturating_sub(1)]
+                    - weight_sums
+                        .get(range.start.wrapping_sub(1))
+                        .copied()
+                        .unwrap_or_default()
+            },
+        );
+        // A segment with only NaN values or zero weights has nothing to deviate
+        if rows_length <= 0.0 {
+            return;
+        }
 
         // Take the left values or zero if the range is zero
         // We use a wrapping sub for that so when it overflows the get will always return `None`
@@ -67,13 +134,50 @@ pub struct L2Cost2D {
 }
 
 impl L2Cost2D {
+    /// Combine columns that are already precalculated.
+    #[inline]
+    pub(crate) const fn from_columns(columns: Vec<L2Cost1D>) -> Self {
+        Self { columns }
+    }
+
     /// Precalculate the sum queries.
     #[inline]
     pub fn precalculate(signal: &ArrayView2<f64>) -> Self {
+        Self::precalculate_with_accumulator(signal, Accumulator::default())
+    }
+
+    /// Precalculate the sum queries, with the running sums calculated by the accumulator.
+    #[inline]
+    pub fn precalculate_with_accumulator<A>(
+        signal: &ArrayView2<A>,
+        accumulator: Accumulator,
+    ) -> Self
+    where
+        A: Sample,
+    {
         let columns = signal
             .columns()
             .into_iter()
-            .map(|column| L2Cost1D::precalculate(&column))
+            .map(|column| L2Cost1D::precalculate(&column, None, accumulator))
+            .collect();
+
+        Self { columns }
+    }
+
+    /// Precalculate the sum queries with the same weight for each row of every column.
+    #[inline]
+    pub(crate) fn precalculate_weighted<A>(
+        signal: &ArrayView2<A>,
+        weights: &ArrayView1<f64>,
+        accumulator: Accumulator,
+    ) -> Self
+    where
+        A: Sample,
+    {
+        let columns = signal
+            .columns()
+            .into_iter()
+            .map(|column| L2Cost1D::precalculate(&column, Some(weights), accumulator))
             .collect();
 
         Self { columns }
@@ -84,10 +188,28 @@ impl L2Cost2D {
     /// Calculated using Welford's algorithm.
     #[inline]
     pub(crate) fn loss(&self, total_loss: &mut f64, range: Range<usize>) {
-        // Calculate total loss
-        self.columns
-            .iter()
-            .for_each(|column| column.loss(total_loss, range.clone()));
+        // A column is only a couple of lookups, so only worth it for very wide signals
+        #[cfg(feature = "rayon")]
+        if self.columns.len() >= PARALLEL_COLUMNS {
+            return par_columns_loss(&self.columns, total_loss, |column, loss| {
+                column.loss(loss, range.clone());
+            });
+        }
+
+        self.loss_bounded(total_loss, range, f64::INFINITY);
+    }
+
+    /// Calculate the loss, stopping after the column where the total loss exceeds the bound.
+    #[inline]
+    pub(crate) fn loss_bounded(&self, total_loss: &mut f64, range: Range<usize>, bound: f64) {
+        for column in &self.columns {
+            column.loss(total_loss, range.clone());
+
+            // The remaining columns can only increase the loss
+            if *total_loss > bound {
+                break;
+            }
+        }
     }
 }
 
@@ -108,10 +230,29 @@ mod tests {
     #[test]
     fn cost_1d() {
         let array_1d = ndarray::array![10.0, 30.0, 20.0];
-        let cost = L2Cost1D::precalculate(&array_1d.view());
+        let cost = L2Cost1D::precalculate(&array_1d.view(), None, Accumulator::Naive);
         let mut loss = 0.0;
         cost.loss(&mut loss, 0..3);
         assert_eq!(loss, 200.0);
+
+        // NaN values are left out
+        let array_1d = ndarray::array![10.0, f64::NAN, 30.0, 20.0, f64::NAN];
+        let cost = L2Cost1D::precalculate(&array_1d.view(), None, Accumulator::Naive);
+        let mut loss = 0.0;
+        cost.loss(&mut loss, 0..5);
+        assert_eq!(loss, 200.0);
+        let mut loss = 0.0;
+        cost.loss(&mut loss, 4..5);
+        assert_eq!(loss, 0.0);
+
+        // Weighted mean of 25
+        let array_1d = ndarray::array![10.0, 30.0, 20.0];
+        let weights = ndarray::array![1.0, 3.0, 0.0];
+        let cost =
+            L2Cost1D::precalculate(&array_1d.view(), Some(&weights.view()), Accumulator::Naive);
+        let mut loss = 0.0;
+        cost.loss(&mut loss, 0..3);
+        assert_eq!(loss, 300.0);
     }
 
     /// Check the L2 cost function.
@@ -123,4 +264,33 @@ mod tests {
         cost.loss(&mut loss, 0..3);
         assert_eq!(loss, 200.0);
     }
+
+    /// Check the loss of wide signals is the same when the columns are spread over threads.
+    #[test]
+    fn cost_2d_wide() {
+        let array_2d = ndarray::Array2::from_shape_fn((3, 2000), |(row, column)| {
+            (row * column % 7) as f64 * 0.1
+        });
+        let cost = L2Cost2D::precalculate(&array_2d.view());
+
+        let mut loss = 0.0;
+        cost.loss(&mut loss, 0..3);
+        let mut serial_loss = 0.0;
+        cost.loss_bounded(&mut serial_loss, 0..3, f64::INFINITY);
+        assert_eq!(loss, serial_loss);
+    }
+
+    /// Check the L2 cost function stops after exceeding the bound.
+    #[test]
+    fn cost_2d_bounded() {
+        let array_2d = ndarray::array![[10.0, 1.0], [30.0, 2.0], [20.0, 3.0]];
+        let cost = L2Cost2D::precalculate(&array_2d.view());
+        let mut loss = 0.0;
+        cost.loss_bounded(&mut loss, 0..3, 100.0);
+        assert_eq!(loss, 200.0);
+
+        let mut loss = 0.0;
+        cost.loss_bounded(&mut loss, 0..3, f64::INFINITY);
+        assert_eq!(loss, 202.0);
+    }
 }
@@ -1,40 +1,242 @@
 //! Cost functions.
 
+pub(crate) mod accumulator;
+#[cfg(feature = "rayon")]
+mod calibration;
 pub(crate) mod l1;
 pub(crate) mod l2;
+pub(crate) mod quantile;
 mod tree;
 
-use std::ops::Range;
+use std::{cmp::Ordering, ops::Range};
 
 use l2::{L2Cost1D, L2Cost2D};
-use ndarray::{ArrayView1, ArrayView2};
+#[cfg(feature = "arrow")]
+use ndarray::CowArray;
+use ndarray::{Array1, ArrayView1, ArrayView2};
 
-use crate::cost::l1::{L1Cost1D, L1Cost2D};
+use crate::{
+    Accumulator, Error, NanPolicy, Sample,
+    cost::{
+        l1::{L1Cost1D, L1Cost2D},
+        quantile::{QuantileCost1D, QuantileCost2D},
+    },
+    nan,
+};
 
 /// Segment model cost function, also known as the loss function.
-#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+///
+/// Ordered by variant and then by quantile, quantiles are compared with [`f64::total_cmp`] so the order is total.
+#[derive(Debug, Default, Clone, Copy)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum SegmentCostFunction {
     /// Least absolute deviation.
     #[default]
     L1,
     /// Least squared deviation.
     L2,
+    /// Pinball deviation around the quantile, must be larger than `0.0` and smaller than `1.0`.
+    ///
+    /// At `0.0` and `1.0` the loss of a segment is zero for every segment, which never finds a changepoint.
+    ///
+    /// Generalizes [`SegmentCostFunction::L1`] to any quantile, `Quantile(0.5)` targets the median.
+    Quantile(f64),
 }
 
 impl SegmentCostFunction {
+    /// Ensure the parameters of the cost function are valid.
+    #[inline]
+    pub(crate) fn validate(self) -> Result<(), Error> {
+        match self {
+            Self::Quantile(quantile) if !(quantile > 0.0 && quantile < 1.0) => {
+                Err(Error::InvalidQuantile)
+            }
+            _ => Ok(()),
+        }
+    }
+
+    /// Position of the variant in the order of the enum.
+    #[inline]
+    pub(crate) const fn variant_index(self) -> u8 {
+        match self {
+            Self::L1 => 0,
+            Self::L2 => 1,
+            Self::Quantile(_) => 2,
+        }
+    }
+
+    /// Loss of the segment model per unit of -2 log likelihood, scaled by the noise.
+    ///
+    /// Used to convert an information criterion to a penalty.
+    #[inline]
+    pub(crate) const fn loss_per_log_likelihood(self) -> f64 {
+        match self {
+            // Gaussian: -2 log L = RSS / variance
+            Self::L2 => 1.0,
+            // (Asymmetric) Laplace: -2 log L = 2 * loss / scale
+            Self::L1 | Self::Quantile(_) => 0.5,
+        }
+    }
+
+    /// Minus two times the log likelihood of the segment model, with the scale fitted on the total loss of all points.
+    ///
+    /// Gaussian for L2, Laplace for L1 and asymmetric Laplace for the quantile cost.
+    #[inline]
+    pub(crate) fn minus_two_log_likelihood(self, loss: f64, points: usize) -> f64 {
+        let points = points as f64;
+        // Maximum likelihood estimate of the scale
+        let scale = loss / points;
+
+        match self {
+            Self::L2 => points * ((std::f64::consts::TAU * scale).ln() + 1.0),
+            Self::L1 => 2.0 * points * ((2.0 * scale).ln() + 1.0),
+            Self::Quantile(quantile) => {
+                2.0 * points * ((scale / (quantile * (1.0 - quantile))).ln() + 1.0)
+            }
+        }
+    }
+
+    /// Location parameter of the segment model fitted on the values, reorders the values.
+    ///
+    /// The median for L1, the mean for L2 and the quantile for the quantile cost.
+    #[inline]
+    pub(crate) fn location(self, values: &mut [f64]) -> f64 {
+        match self {
+            Self::L1 => quantile_of_sorted(values, 0.5),
+            Self::L2 => values.iter().sum::<f64>() / values.len() as f64,
+            Self::Quantile(quantile) => quantile_of_sorted(values, quantile),
+        }
+    }
+
+    /// Loss of a single value against the location parameter of the segment model.
+    #[inline]
+    pub(crate) fn point_loss(self, value: f64, location: f64) -> f64 {
+        let difference = value - location;
+
+        match self {
+            Self::L1 => difference.abs(),
+            Self::L2 => difference.powi(2),
+            Self::Quantile(quantile) => {
+                if difference >= 0.0 {
+                    quantile * difference
+                } else {
+                    (quantile - 1.0) * difference
+                }
+            }
+        }
+    }
+
     /// Heuristic for determining whether to use a parallel iterator.
+    ///
+    /// The amount of iterations from which it pays off is measured on the first use.
     #[cfg(feature = "rayon")]
     #[inline]
-    pub(crate) const fn should_use_threading(self, iterations: usize) -> bool {
-        match self {
-            // L1 is slow, so with a couple of iterations it already pays of
-            Self::L1 => iterations >= 32,
-            // L2 is quite fast, so it's only worthwhile with many iterations
-            Self::L2 => iterations >= 512,
+    pub(crate) fn should_use_threading(self, iterations: usize) -> bool {
+        iterations >= calibration::thresholds(self).threading
+    }
+}
+
+impl PartialEq for SegmentCostFunction {
+    fn eq(&self, other: &Self) -> bool {
+        self.cmp(other).is_eq()
+    }
+}
+
+impl Eq for SegmentCostFunction {}
+
+impl PartialOrd for SegmentCostFunction {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Ord for SegmentCostFunction {
+    fn cmp(&self, other: &Self) -> Ordering {
+        match (self, other) {
+            (Self::Quantile(quantile), Self::Quantile(other_quantile)) => {
+                quantile.total_cmp(other_quantile)
+            }
+            _ => self.variant_index().cmp(&other.variant_index()),
         }
     }
 }
 
+/// Add the losses of all columns to the total, with the columns spread over threads.
+///
+/// The losses are added in the order of the columns, so the result is the same as adding them on a single thread.
+#[cfg(feature = "rayon")]
+#[inline]
+pub(crate) fn par_columns_loss<C, F>(columns: &[C], total_loss: &mut f64, loss: F)
+where
+    C: Sync,
+    F: Fn(&C, &mut f64) + Sync,
+{
+    use rayon::iter::{IntoParallelRefIterator as _, ParallelIterator as _};
+
+    let losses = columns
+        .par_iter()
+        .map(|column| {
+            let mut column_loss = 0.0;
+            loss(column, &mut column_loss);
+
+            column_loss
+        })
+        .collect::<Vec<_>>();
+
+    for column_loss in losses {
+        *total_loss += column_loss;
+    }
+}
+
+/// Square root of the smallest positive weight, one without weights.
+///
+/// Scales the lower bounds of the absolute deviations, which are based on the squared deviations.
+#[inline]
+fn smallest_weight_root(weights: Option<&ArrayView1<f64>>) -> f64 {
+    weights.map_or(1.0, |weights| {
+        weights
+            .iter()
+            .copied()
+            .filter(|weight| *weight > 0.0)
+            .reduce(f64::min)
+            .unwrap_or_default()
+            .sqrt()
+    })
+}
+
+/// Weight of every row of a column with a weight, the row weights multiplied by the column weight.
+///
+/// Scaling all weights of a column scales its loss by the same factor, without moving the location of the segment model.
+#[inline]
+fn column_row_weights(
+    weights: Option<&ArrayView1<f64>>,
+    column_weight: f64,
+    len: usize,
+) -> Array1<f64> {
+    weights.map_or_else(
+        || Array1::from_elem(len, column_weight),
+        |weights| weights * column_weight,
+    )
+}
+
+/// Sort the values and take the quantile, interpolating linearly between the two closest points.
+#[inline]
+fn quantile_of_sorted(values: &mut [f64], quantile: f64) -> f64 {
+    values.sort_unstable_by(f64::total_cmp);
+
+    // Zero-based fractional position of the quantile
+    let position = values.len().saturating_sub(1) as f64 * quantile;
+    let lower = position.floor();
+    let fraction = position - lower;
+
+    let lower_value = values.get(lower as usize).copied().unwrap_or(f64::NAN);
+    values
+        .get(lower as usize + 1)
+        .map_or(lower_value, |upper_value| {
+            fraction.mul_add(upper_value - lower_value, lower_value)
+        })
+}
+
 /// Precalculation state for each segment cost function.
 #[doc(hidden)]
 pub enum Cost1D {
@@ -42,24 +244,109 @@ pub enum Cost1D {
     L1(L1Cost1D),
     /// L2.
     L2(L2Cost1D),
+    /// Quantile.
+    Quantile(QuantileCost1D),
+    /// Precalculated for the rows from the start only, the ranges are still the rows of the whole signal.
+    Window {
+        /// Precalculated for the rows from the start.
+        cost: Box<Self>,
+        /// First row that's precalculated.
+        start: usize,
+    },
 }
 
 impl Cost1D {
-    /// Construct from signal and cost function.
+    /// Construct from signal and cost function, optionally with a weight for each row and for the column.
     #[inline]
-    pub(crate) fn precalculate(cost: SegmentCostFunction, signal: &ArrayView1<f64>) -> Self {
+    pub(crate) fn precalculate<A>(
+        cost: SegmentCostFunction,
+        accumulator: Accumulator,
+        nan_policy: NanPolicy,
+        signal: &ArrayView1<A>,
+        weights: Option<&ArrayView1<f64>>,
+        column_weight: Option<f64>,
+    ) -> Self
+    where
+        A: Sample,
+    {
+        if let Some(column_weight) = column_weight {
+            let weights = column_row_weights(weights, column_weight, signal.len());
+
+            return Self::precalculate(
+                cost,
+                accumulator,
+                nan_policy,
+                signal,
+                Some(&weights.view()),
+                None,
+            );
+        }
+
+        // Only copy the signal when there's something to interpolate
+        if nan_policy == NanPolicy::Interpolate
+            && signal.iter().any(|value| value.to_f64().is_nan())
+        {
+            let mut interpolated = signal.mapv(Sample::to_f64);
+            nan::interpolate(interpolated.view_mut());
+
+            return Self::precalculate(
+                cost,
+                accumulator,
+                NanPolicy::Ignore,
+                &interpolated.view(),
+                weights,
+                None,
+            );
+        }
+
         match cost {
-            SegmentCostFunction::L1 => Self::L1(L1Cost1D::precalculate(signal)),
-            SegmentCostFunction::L2 => Self::L2(L2Cost1D::precalculate(signal)),
+            SegmentCostFunction::L1 => {
+                Self::L1(L1Cost1D::precalculate(signal, weights, accumulator))
+            }
+            SegmentCostFunction::L2 => {
+                Self::L2(L2Cost1D::precalculate(signal, weights, accumulator))
+            }
+            SegmentCostFunction::Quantile(quantile) => Self::Quantile(
+                QuantileCost1D::precalculate(signal, weights, quantile, accumulator),
+            ),
         }
     }
 
     /// Calculate the loss.
     #[inline]
-    pub(crate) fn loss(&self, total_loss: &mut f64, signal: &ArrayView1<f64>, range: Range<usize>) {
+    pub(crate) fn loss(&self, total_loss: &mut f64, range: Range<usize>) {
         match self {
-            Self::L1(cost) => cost.loss(total_loss, signal, range),
+            Self::L1(cost) => cost.loss(total_loss, range),
             Self::L2(cost) => cost.loss(total_loss, range),
+            Self::Quantile(cost) => cost.loss(total_loss, range),
+            Self::Window { cost, start } => {
+                cost.loss(total_loss, range.start - start..range.end - start);
+            }
+        }
+    }
+
+    /// Lower bound of the loss that's cheaper to calculate, zero when the loss itself is cheap.
+    #[inline]
+    pub(crate) fn lower_bound(&self, range: Range<usize>) -> f64 {
+        match self {
+            Self::L1(cost) => cost.lower_bound(range),
+            Self::L2(_cost) => 0.0,
+            Self::Quantile(cost) => cost.lower_bound(range),
+            Self::Window { cost, start } => {
+                cost.lower_bound(range.start - start..range.end - start)
+            }
+        }
+    }
+
+    /// Append rows to the end of the signal without precalculating the rows before them again.
+    ///
+    /// Returns whether the rows are appended, only [`SegmentCostFunction::L2`] with [`Accumulator::Naive`] can be extended.
+    #[inline]
+    pub(crate) fn extend(&mut self, values: &[f64], accumulator: Accumulator) -> bool {
+        match self {
+            Self::L2(cost) => accumulator == Accumulator::Naive && cost.extend(values),
+            Self::Window { cost, .. } => cost.extend(values, accumulator),
+            Self::L1(_) | Self::Quantile(_) => false,
         }
     }
 }
@@ -71,24 +358,269 @@ pub enum Cost2D {
     L1(L1Cost2D),
     /// L2.
     L2(L2Cost2D),
+    /// Quantile.
+    Quantile(QuantileCost2D),
+    /// Different cost function for every column.
+    Mixed(Vec<Cost1D>),
 }
 
 impl Cost2D {
-    /// Construct from signal and cost function.
+    /// Construct from signal and cost function, optionally with a weight for each row and for each column.
+    ///
+    /// With a cost function for every column the cost function of the signal isn't used.
+    #[inline]
+    pub(crate) fn precalculate<A>(
+        cost: SegmentCostFunction,
+        accumulator: Accumulator,
+        nan_policy: NanPolicy,
+        signal: &ArrayView2<A>,
+        weights: Option<&ArrayView1<f64>>,
+        column_weights: Option<&[f64]>,
+        column_cost_functions: Option<&[SegmentCostFunction]>,
+    ) -> Self
+    where
+        A: Sample,
+    {
+        // Every column interpolates its own NaN values
+        if let Some(column_cost_functions) = column_cost_functions {
+            return Self::Mixed(
+                signal
+                    .columns()
+                    .into_iter()
+                    .zip(column_cost_functions)
+                    .enumerate()
+                    .map(|(index, (column, column_cost))| {
+                        Cost1D::precalculate(
+                            *column_cost,
+                            accumulator,
+                            nan_policy,
+                            &column,
+                            weights,
+                            column_weights
+                                .and_then(|column_weights| column_weights.get(index).copied()),
+                        )
+                    })
+                    .collect(),
+            );
+        }
+
+        // Only copy the signal when there's something to interpolate
+        if nan_policy == NanPolicy::Interpolate
+            && signal.iter().any(|value| value.to_f64().is_nan())
+        {
+            let mut interpolated = signal.mapv(Sample::to_f64);
+            interpolated
+                .columns_mut()
+                .into_iter()
+                .for_each(nan::interpolate);
+
+            return Self::precalculate(
+                cost,
+                accumulator,
+                NanPolicy::Ignore,
+                &interpolated.view(),
+                weights,
+                column_weights,
+                None,
+            );
+        }
+
+        if let Some(column_weights) = column_weights {
+            let row_weights = column_weights
+                .iter()
+                .map(|column_weight| column_row_weights(weights, *column_weight, signal.nrows()))
+                .collect::<Vec<_>>();
+            let weighted_columns = || signal.columns().into_iter().zip(&row_weights);
+
+            return match cost {
+                SegmentCostFunction::L1 => Self::L1(L1Cost2D::from_columns(
+                    weighted_columns()
+                        .map(|(column, weights)| {
+                            L1Cost1D::precalculate(&column, Some(&weights.view()), accumulator)
+                        })
+                        .collect(),
+                )),
+                SegmentCostFunction::L2 => Self::L2(L2Cost2D::from_columns(
+                    weighted_columns()
+                        .map(|(column, weights)| {
+                            L2Cost1D::precalculate(&column, Some(&weights.view()), accumulator)
+                        })
+                        .collect(),
+                )),
+                SegmentCostFunction::Quantile(quantile) => {
+                    Self::Quantile(QuantileCost2D::from_columns(
+                        weighted_columns()
+                            .map(|(column, weights)| {
+                                QuantileCost1D::precalculate(
+                                    &column,
+                                    Some(&weights.view()),
+                                    quantile,
+                                    accumulator,
+                                )
+                            })
+                            .collect(),
+                    ))
+                }
+            };
+        }
+
+        match cost {
+            SegmentCostFunction::L1 => {
+                Self::L1(L1Cost2D::precalculate(signal, weights, accumulator))
+            }
+            SegmentCostFunction::L2 => Self::L2(weights.map_or_else(
+                || L2Cost2D::precalculate_with_accumulator(signal, accumulator),
+                |weights| L2Cost2D::precalculate_weighted(signal, weights, accumulator),
+            )),
+            SegmentCostFunction::Quantile(quantile) => Self::Quantile(
+                QuantileCost2D::precalculate(signal, weights, quantile, accumulator),
+            ),
+        }
+    }
+
+    /// Construct from separately stored columns of the same length and cost function, optionally with a weight for each column.
+    ///
+    /// Only columns containing a NaN value are copied to interpolate them.
+    #[cfg(feature = "arrow")]
     #[inline]
-    pub(crate) fn precalculate(cost: SegmentCostFunction, signal: &ArrayView2<f64>) -> Self {
+    pub(crate) fn precalculate_columns(
+        cost: SegmentCostFunction,
+        accumulator: Accumulator,
+        nan_policy: NanPolicy,
+        columns: &[ArrayView1<f64>],
+        column_weights: Option<&[f64]>,
+        column_cost_functions: Option<&[SegmentCostFunction]>,
+    ) -> Self {
+        if let Some(column_cost_functions) = column_cost_functions {
+            return Self::Mixed(
+                columns
+                    .iter()
+                    .zip(column_cost_functions)
+                    .enumerate()
+                    .map(|(index, (column, column_cost))| {
+                        Cost1D::precalculate(
+                            *column_cost,
+                            accumulator,
+                            nan_policy,
+                            column,
+                            None,
+                            column_weights
+                                .and_then(|column_weights| column_weights.get(index).copied()),
+                        )
+                    })
+                    .collect(),
+            );
+        }
+
+        let columns = columns
+            .iter()
+            .map(|column| {
+                if nan_policy == NanPolicy::Interpolate && column.iter().any(|value| value.is_nan())
+                {
+                    let mut interpolated = column.to_owned();
+                    nan::interpolate(interpolated.view_mut());
+
+                    CowArray::from(interpolated)
+                } else {
+                    CowArray::from(column.view())
+                }
+            })
+            .collect::<Vec<_>>();
+        let row_weights = columns
+            .iter()
+            .enumerate()
+            .map(|(index, column)| {
+                column_weights
+                    .and_then(|column_weights| column_weights.get(index))
+                    .map(|column_weight| column_row_weights(None, *column_weight, column.len()))
+            })
+            .collect::<Vec<_>>();
+        let weighted_columns = || {
+            columns.iter().zip(
+                row_weights
+                    .iter()
+                    .map(|weights| weights.as_ref().map(|weights| weights.view())),
+            )
+        };
+
         match cost {
-            SegmentCostFunction::L1 => Self::L1(L1Cost2D::precalculate(signal)),
-            SegmentCostFunction::L2 => Self::L2(L2Cost2D::precalculate(signal)),
+            SegmentCostFunction::L1 => Self::L1(L1Cost2D::from_columns(
+                weighted_columns()
+                    .map(|(column, weights)| {
+                        L1Cost1D::precalculate(&column.view(), weights.as_ref(), accumulator)
+                    })
+                    .collect(),
+            )),
+            SegmentCostFunction::L2 => Self::L2(L2Cost2D::from_columns(
+                weighted_columns()
+                    .map(|(column, weights)| {
+                        L2Cost1D::precalculate(&column.view(), weights.as_ref(), accumulator)
+                    })
+                    .collect(),
+            )),
+            SegmentCostFunction::Quantile(quantile) => {
+                Self::Quantile(QuantileCost2D::from_columns(
+                    weighted_columns()
+                        .map(|(column, weights)| {
+                            QuantileCost1D::precalculate(
+                                &column.view(),
+                                weights.as_ref(),
+                                quantile,
+                                accumulator,
+                            )
+                        })
+                        .collect(),
+                ))
+            }
         }
     }
 
     /// Calculate the loss.
     #[inline]
-    pub(crate) fn loss(&self, total_loss: &mut f64, signal: &ArrayView2<f64>, range: Range<usize>) {
+    pub(crate) fn loss(&self, total_loss: &mut f64, range: Range<usize>) {
         match self {
-            Self::L1(cost) => cost.loss(total_loss, signal, range),
+            Self::L1(cost) => cost.loss(total_loss, range),
             Self::L2(cost) => cost.loss(total_loss, range),
+            Self::Quantile(cost) => cost.loss(total_loss, range),
+            Self::Mixed(columns) => {
+                for column in columns {
+                    column.loss(total_loss, range.clone());
+                }
+            }
+        }
+    }
+
+    /// Calculate the loss, stopping after the column where the total loss exceeds the bound.
+    #[inline]
+    pub(crate) fn loss_bounded(&self, total_loss: &mut f64, range: Range<usize>, bound: f64) {
+        match self {
+            Self::L1(cost) => cost.loss_bounded(total_loss, range, bound),
+            Self::L2(cost) => cost.loss_bounded(total_loss, range, bound),
+            Self::Quantile(cost) => cost.loss_bounded(total_loss, range, bound),
+            Self::Mixed(columns) => {
+                for column in columns {
+                    column.loss(total_loss, range.clone());
+
+                    // The remaining columns can only increase the loss
+                    if *total_loss > bound {
+                        break;
+                    }
+                }
+            }
+        }
+    }
+
+    /// Lower bound of the loss that's cheaper to calculate, zero when the loss itself is cheap.
+    #[inline]
+    pub(crate) fn lower_bound(&self, range: Range<usize>) -> f64 {
+        match self {
+            Self::L1(cost) => cost.lower_bound(range),
+            Self::L2(_cost) => 0.0,
+            Self::Quantile(cost) => cost.lower_bound(range),
+            Self::Mixed(columns) => columns
+                .iter()
+                .map(|column| column.lower_bound(range.clone()))
+                .sum(),
         }
     }
 }
@@ -0,0 +1,233 @@
+//! Quantile cost functions.
+
+use std::ops::{Range, RangeInclusive};
+
+use ndarray::{ArrayView1, ArrayView2};
+
+#[cfg(feature = "rayon")]
+use crate::cost::par_columns_loss;
+use crate::{
+    Accumulator, Sample,
+    cost::{l2::L2Cost1D, smallest_weight_root, tree::KthSmallestTree},
+};
+
+/// Minimum amount of columns to calculate the loss of a single segment on multiple threads.
+#[cfg(feature = "rayon")]
+const PARALLEL_COLUMNS: usize = 64;
+
+/// Precalculation output.
+pub struct QuantileCost1D {
+    /// Tree for finding the quantile (K-th smallest).
+    kth_smallest_tree: KthSmallestTree,
+    /// Squared deviations for cheap lower bounds of the loss.
+    squared: L2Cost1D,
+    /// Quantile to target, between `0.0` and `1.0`.
+    quantile: f64,
+    /// Square root of the smallest positive weight, scales the lower bound.
+    weight_scale: f64,
+}
+
+impl QuantileCost1D {
+    /// Build the K-th smallest tree, optionally with a weight for each row.
+    #[inline]
+    pub(crate) fn precalculate<A>(
+        signal: &ArrayView1<A>,
+        weights: Option<&ArrayView1<f64>>,
+        quantile: f64,
+        accumulator: Accumulator,
+    ) -> Self
+    where
+        A: Sample,
+    {
+        // Build the tree from the signal
+        let kth_smallest_tree = KthSmallestTree::build(signal, weights);
+        let squared = L2Cost1D::precalculate(signal, weights, accumulator);
+
+        Self {
+            kth_smallest_tree,
+            squared,
+            quantile,
+            weight_scale: smallest_weight_root(weights),
+        }
+    }
+
+    /// Lower bound of the loss that's cheaper to calculate.
+    ///
+    /// Every deviation is weighed at least by the smallest of both weights, and the sum of absolute deviations is at least the square root of the sum of squared deviations.
+    #[inline]
+    pub(crate) fn lower_bound(&self, range: Range<usize>) -> f64 {
+        let mut squared_loss = 0.0;
+        self.squared.loss(&mut squared_loss, range);
+
+        // Rounding can result in a slightly negative sum of squares
+        self.quantile.min(1.0 - self.quantile) * self.weight_scale * squared_loss.max(0.0).sqrt()
+    }
+
+    /// Calculate the loss.
+    ///
+    /// Uses the pinball loss, so a quantile of `0.5` results in half of the L1 loss.
+    /// The points up to the lower neighbour of the quantile are at most the quantile value and the rest at least, so the loss follows from the sums of both parts without iterating the segment.
+    #[inline]
+    pub(crate) fn loss(&self, total_loss: &mut f64, range: Range<usize>) {
+        // Convert the range to an inclusive one
+        let range_inclusive = range.start..=(range.end - 1);
+
+        if self.kth_smallest_tree.is_weighted() {
+            return self.weighted_loss(total_loss, range_inclusive);
+        }
+
+        // NaN values aren't counted, a segment with only NaN values has nothing to deviate
+        let len = self.kth_smallest_tree.count(range_inclusive.clone());
+        if len == 0 {
+            return;
+        }
+
+        // Zero-based fractional position of the quantile in the sorted segment
+        let position = (len - 1) as f64 * self.quantile;
+        let lower = position.floor();
+        let fraction = position - lower;
+
+        // Get the lower value and the sum up to it, offset by 1 because K-th is one-based
+        let lower_len = lower as usize + 1;
+        let (lower_value, lower_sum) = self
+            .kth_smallest_tree
+            .kth_with_sum(range_inclusive.clone(), lower_len);
+
+        // Interpolate with the next value, unless exactly on a point
+        let quantile_value = if fraction <= 0.0 || lower_len >= len {
+            lower_value
+        } else {
+            let upper_value = self
+                .kth_smallest_tree
+                .kth(range_inclusive.clone(), lower_len + 1);
+
+            fraction.mul_add(upper_value - lower_value, lower_value)
+        };
+        let upper_sum = self.kth_smallest_tree.sum(range_inclusive) - lower_sum;
+        let upper_len = len - lower_len;
+
+        // Weigh the points above and below the quantile differently
+        let lower_loss = (lower_len as f64).mul_add(quantile_value, -lower_sum);
+        let upper_loss = (upper_len as f64).mul_add(-quantile_value, upper_sum);
+        *total_loss += (1.0 - self.quantile).mul_add(lower_loss, self.quantile * upper_loss);
+    }
+
+    /// Calculate the loss around the weighted quantile.
+    ///
+    /// The weighted quantile is the smallest value where the values up to it have at least the quantile of the weight.
+    #[inline]
+    fn weighted_loss(&self, total_loss: &mut f64, range: RangeInclusive<usize>) {
+        // A segment with only NaN values or zero weights has nothing to deviate
+        if self.kth_smallest_tree.count(range.clone()) == 0 {
+            return;
+        }
+
+        let weight = self.kth_smallest_tree.weight(range.clone());
+        let (quantile_value, lower_weight, lower_sum) = self
+            .kth_smallest_tree
+            .weighted_kth(range.clone(), weight * self.quantile);
+        let upper_sum = self.kth_smallest_tree.sum(range) - lower_sum;
+        let upper_weight = weight - lower_weight;
+
+        // Weigh the points above and below the quantile differently
+        let lower_loss = lower_weight.mul_add(quantile_value, -lower_sum);
+        let upper_loss = upper_weight.mul_add(-quantile_value, upper_sum);
+        *total_loss += (1.0 - self.quantile).mul_add(lower_loss, self.quantile * upper_loss);
+    }
+}
+
+/// Precalculation output.
+pub struct QuantileCost2D {
+    /// Precalculated per column.
+    columns: Vec<QuantileCost1D>,
+}
+
+impl QuantileCost2D {
+    /// Combine columns that are already precalculated.
+    #[inline]
+    pub(crate) const fn from_columns(columns: Vec<QuantileCost1D>) -> Self {
+        Self { columns }
+    }
+
+    /// Build the K-th smallest tree for each column, optionally with the same weight for each row of every column.
+    #[inline]
+    pub fn precalculate<A>(
+        signal: &ArrayView2<A>,
+        weights: Option<&ArrayView1<f64>>,
+        quantile: f64,
+        accumulator: Accumulator,
+    ) -> Self
+    where
+        A: Sample,
+    {
+        let columns = signal
+            .columns()
+            .into_iter()
+            .map(|column| QuantileCost1D::precalculate(&column, weights, quantile, accumulator))
+            .collect();
+
+        Self { columns }
+    }
+
+    /// Calculate the loss.
+    #[inline]
+    pub(crate) fn loss(&self, total_loss: &mut f64, range: Range<usize>) {
+        // Every column needs multiple tree queries, so already worth it for a moderate amount of columns
+        #[cfg(feature = "rayon")]
+        if self.columns.len() >= PARALLEL_COLUMNS {
+            return par_columns_loss(&self.columns, total_loss, |column, loss| {
+                column.loss(loss, range.clone());
+            });
+        }
+
+        self.loss_bounded(total_loss, range, f64::INFINITY);
+    }
+
+    /// Lower bound of the loss that's cheaper to calculate.
+    #[inline]
+    pub(crate) fn lower_bound(&self, range: Range<usize>) -> f64 {
+        self.columns
+            .iter()
+            .map(|column| column.lower_bound(range.clone()))
+            .sum()
+    }
+
+    /// Calculate the loss, stopping after the column where the total loss exceeds the bound.
+    #[inline]
+    pub(crate) fn loss_bounded(&self, total_loss: &mut f64, range: Range<usize>, bound: f64) {
+        for column in &self.columns {
+            column.loss(total_loss, range.clone());
+
+            // The remaining columns can only increase the loss
+            if *total_loss > bound {
+                break;
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Check the quantile cost function.
+    #[test]
+    fn cost_1d() {
+        let array_1d = ndarray::array![10.0, 30.0, 20.0];
+        let cost = QuantileCost1D::precalculate(&array_1d.view(), None, 0.9, Accumulator::Naive);
+        let mut loss = 0.0;
+        cost.loss(&mut loss, 0..3);
+        assert!((loss - 4.4).abs() < 1e-9, "{loss}");
+    }
+
+    /// Check the quantile cost function.
+    #[test]
+    fn cost_2d() {
+        let array_2d = ndarray::array![[10.0], [30.0], [20.0], [40.0]];
+        let cost = QuantileCost2D::precalculate(&array_2d.view(), None, 0.5, Accumulator::Naive);
+        let mut loss = 0.0;
+        cost.loss(&mut loss, 0..4);
+        // Half of the L1 loss
+        assert_eq!(loss, 20.0);
+    }
+}
@@ -3,6 +3,8 @@
 use std::ops::RangeInclusive;
 
 use ndarray::ArrayView1;
+
+use crate::Sample;
 #[cfg(feature = "rayon")]
 use rayon::slice::ParallelSliceMut as _;
 
@@ -14,6 +16,12 @@ pub struct KthSmallestTree {
     ///
     /// We keep this separate from the nodes as a performance optimization, since it will only be accessed on the left children in the `kth()` implementation.
     counts: Vec<u32>,
+    /// Total map of all node value sums, used for the losses without iterating the segment.
+    ///
+    /// Each value is multiplied by its weight for weighted trees.
+    sums: Vec<f64>,
+    /// Total map of all node weight sums, `None` when every value has the same weight.
+    weights: Option<Vec<f64>>,
     /// Total map of all node siblings.
     siblings: Vec<Node>,
     /// Sorted and unique values.
@@ -23,9 +31,14 @@ pub struct KthSmallestTree {
 }
 
 impl KthSmallestTree {
-    /// Construct the tree from a slice of values.
+    /// Construct the tree from a slice of values, optionally with a weight for each value.
+    ///
+    /// Values with a zero weight aren't part of the tree, just like NaN values.
     #[inline]
-    pub fn build(values: &ArrayView1<f64>) -> Self {
+    pub fn build<A>(values: &ArrayView1<A>, weights: Option<&ArrayView1<f64>>) -> Self
+    where
+        A: Sample,
+    {
         assert!(values.len() < u32::MAX as usize - 1, "Input array too big");
 
         let roots = Vec::with_capacity(values.len());
@@ -34,10 +47,21 @@ impl KthSmallestTree {
         let total_estimate = values.len() * values.len().next_power_of_two().ilog2() as usize + 1;
         let siblings = Vec::with_capacity(total_estimate);
         let counts = Vec::with_capacity(total_estimate);
+        let sums = Vec::with_capacity(total_estimate);
+        let node_weights = weights.map(|_| Vec::with_capacity(total_estimate));
 
         let len = values.len() as u32;
 
-        let mut sorted = values.to_vec();
+        // Weight of each value, NaN values and values without weight aren't part of the tree
+        let weight = |index: usize| weights.map_or(1.0, |weights| weights[index]);
+        let is_included = |index: usize, value: &A| !value.to_f64().is_nan() && weight(index) > 0.0;
+
+        let mut sorted = values
+            .iter()
+            .enumerate()
+            .filter(|(index, value)| is_included(*index, value))
+            .map(|(_index, value)| value.to_f64())
+            .collect::<Vec<_>>();
         // Sort the values
         #[cfg(feature = "rayon")]
         sorted.par_sort_unstable_by(f64::total_cmp);
@@ -51,6 +75,8 @@ impl KthSmallestTree {
             roots,
             siblings,
             counts,
+            sums,
+            weights: node_weights,
             len,
             sorted: sorted.clone(),
         };
@@ -61,28 +87,39 @@ impl KthSmallestTree {
             right_index: 0,
         });
         this.counts.push(0);
+        this.sums.push(0.0);
+        if let Some(node_weights) = &mut this.weights {
+            node_weights.push(0.0);
+        }
         this.roots.push(0);
 
-        // Get each index
-        let indices: Vec<u32> = values
+        // Get each index, `None` for values that aren't included
+        let indices: Vec<Option<u32>> = values
             .iter()
-            .map(|value| {
+            .enumerate()
+            .map(|(index, value)| {
+                if !is_included(index, value) {
+                    return None;
+                }
+
                 // Lookup the index of the value but make it one-based
-                sorted
-                    .binary_search_by(|sorted_value| f64::total_cmp(sorted_value, value))
+                let index = sorted
+                    .binary_search_by(|sorted_value| f64::total_cmp(sorted_value, &value.to_f64()))
                     .unwrap_or_default()
-                    .saturating_add(1) as u32
+                    .saturating_add(1) as u32;
+
+                Some(index)
             })
             .collect();
 
         // Add each value index as a version update
-        for index in indices {
-            // Use one based indexing
-            let root = this.insert(
-                *this.roots.last().expect("Building root failed"),
-                1..=len,
-                index,
-            );
+        for (value_index, index) in indices.into_iter().enumerate() {
+            let previous_root = *this.roots.last().expect("Building root failed");
+
+            // A value that isn't included keeps the previous version, use one based indexing otherwise
+            let root = index.map_or(previous_root, |index| {
+                this.insert(previous_root, 1..=len, index, weight(value_index))
+            });
             this.roots.push(root);
         }
 
@@ -90,12 +127,53 @@ impl KthSmallestTree {
     }
 
     /// Find the K-th element.
-    pub fn kth(&self, range: RangeInclusive<usize>, mut kth: usize) -> f64 {
+    #[inline]
+    pub fn kth(&self, range: RangeInclusive<usize>, kth: usize) -> f64 {
+        self.kth_with_sum(range, kth).0
+    }
+
+    /// Amount of elements in the range, without the NaN values.
+    #[inline]
+    pub fn count(&self, range: RangeInclusive<usize>) -> usize {
+        (self.counts[self.roots[*range.end() + 1] as usize]
+            - self.counts[self.roots[*range.start()] as usize]) as usize
+    }
+
+    /// Sum of the weights of all elements in the range, the amount of elements when not weighted.
+    #[inline]
+    pub fn weight(&self, range: RangeInclusive<usize>) -> f64 {
+        self.weights.as_ref().map_or_else(
+            || self.count(range.clone()) as f64,
+            |weights| {
+                weights[self.roots[*range.end() + 1] as usize]
+                    - weights[self.roots[*range.start()] as usize]
+            },
+        )
+    }
+
+    /// Whether the elements have different weights.
+    #[inline]
+    pub const fn is_weighted(&self) -> bool {
+        self.weights.is_some()
+    }
+
+    /// Sum of all elements in the range, multiplied by their weights for weighted trees.
+    #[inline]
+    pub fn sum(&self, range: RangeInclusive<usize>) -> f64 {
+        self.sums[self.roots[*range.end() + 1] as usize]
+            - self.sums[self.roots[*range.start()] as usize]
+    }
+
+    /// Find the K-th element and the sum of the K smallest elements.
+    pub fn kth_with_sum(&self, range: RangeInclusive<usize>, mut kth: usize) -> (f64, f64) {
         // Get the root node at the end
         let mut current_node = &self.siblings[self.roots[*range.end() + 1] as usize];
         // Get the root node at the start
         let mut previous_node = &self.siblings[self.roots[*range.start()] as usize];
 
+        // Sum of the elements smaller than the current region
+        let mut sum = 0.0;
+
         // Indices range to look for
         let mut start = 1_u32;
         let mut end = self.len;
@@ -120,6 +198,10 @@ impl KthSmallestTree {
                 // start..=mid
                 end = mid;
             } else {
+                // Everything on the left is smaller
+                sum += self.sums[current_node.left_index as usize]
+                    - self.sums[previous_node.left_index as usize];
+
                 current_node = &self.siblings[current_node.right_index as usize];
                 previous_node = &self.siblings[previous_node.right_index as usize];
 
@@ -131,12 +213,86 @@ impl KthSmallestTree {
             }
         }
 
-        // Leaf found
-        self.sorted[start as usize - 1]
+        // Leaf found, all remaining elements have its value
+        let value = self.sorted[start as usize - 1];
+
+        (value, (kth as f64).mul_add(value, sum))
+    }
+
+    /// Find the smallest element where the weight of all elements up to and including it reaches the target weight.
+    ///
+    /// Returns the element, and the weight and weighted sum of all elements that are at most the element.
+    /// Only for weighted trees, and the range must contain an element.
+    pub fn weighted_kth(&self, range: RangeInclusive<usize>, mut target: f64) -> (f64, f64, f64) {
+        let weights = self.weights.as_ref().expect("Tree must be weighted");
+
+        // Get the root node at the end
+        let mut current_index = self.roots[*range.end() + 1] as usize;
+        // Get the root node at the start
+        let mut previous_index = self.roots[*range.start()] as usize;
+
+        // Weight and sum of the elements smaller than the current region
+        let mut weight = 0.0;
+        let mut sum = 0.0;
+
+        // Indices range to look for
+        let mut start = 1_u32;
+        let mut end = self.len;
+
+        // Walk until item found
+        while start != end {
+            let current_node = self.siblings[current_index];
+            let previous_node = self.siblings[previous_index];
+            let (current_left, previous_left) = (
+                current_node.left_index as usize,
+                previous_node.left_index as usize,
+            );
+
+            // The counts are exact, so they decide which sides are empty instead of the weights
+            let left_count = self.counts[current_left] - self.counts[previous_left];
+            let right_count = self.counts[current_node.right_index as usize]
+                - self.counts[previous_node.right_index as usize];
+            let left_weight = weights[current_left] - weights[previous_left];
+
+            let mid = start.midpoint(end);
+
+            // Find the offset point to go left or right
+            if left_count > 0 && (target <= left_weight || right_count == 0) {
+                current_index = current_left;
+                previous_index = previous_left;
+
+                // start..=mid
+                end = mid;
+            } else {
+                // Everything on the left is smaller
+                weight += left_weight;
+                sum += self.sums[current_left] - self.sums[previous_left];
+                target -= left_weight;
+
+                current_index = current_node.right_index as usize;
+                previous_index = previous_node.right_index as usize;
+
+                // mid+1..=end
+                start = mid + 1;
+            }
+        }
+
+        // Leaf found, all remaining elements have its value
+        let value = self.sorted[start as usize - 1];
+        weight += weights[current_index] - weights[previous_index];
+        sum += self.sums[current_index] - self.sums[previous_index];
+
+        (value, weight, sum)
     }
 
     /// Recursive implementation of creating a new version.
-    fn insert(&mut self, current_index: u32, range: RangeInclusive<u32>, update_index: u32) -> u32 {
+    fn insert(
+        &mut self,
+        current_index: u32,
+        range: RangeInclusive<u32>,
+        update_index: u32,
+        weight: f64,
+    ) -> u32 {
         debug_assert!(update_index >= *range.start(), "{update_index} {range:?}");
         debug_assert!(update_index <= *range.end(), "{update_index} {range:?}");
 
@@ -146,12 +302,17 @@ impl KthSmallestTree {
         let mut node = self.siblings[current_index];
         let mut count = self.counts[current_index];
         count += 1;
+        let sum = self.sorted[update_index as usize - 1].mul_add(weight, self.sums[current_index]);
+        let node_weight = self
+            .weights
+            .as_ref()
+            .map(|weights| weights[current_index] + weight);
 
         // If narrowed down to a leaf, push a new node and return it
         if range.start() == range.end() {
             let index = self.siblings.len() as u32;
             self.siblings.push(node);
-            self.counts.push(count);
+            self.push_sums(count, sum, node_weight);
 
             return index;
         }
@@ -162,20 +323,34 @@ impl KthSmallestTree {
         // Update the two branches of a node
         if update_index <= mid {
             // Update the left half
-            node.left_index = self.insert(node.left_index, *range.start()..=mid, update_index);
+            node.left_index =
+                self.insert(node.left_index, *range.start()..=mid, update_index, weight);
         } else {
             // Update the right half
-            node.right_index =
-                self.insert(node.right_index, (mid + 1)..=*range.end(), update_index);
+            node.right_index = self.insert(
+                node.right_index,
+                (mid + 1)..=*range.end(),
+                update_index,
+                weight,
+            );
         };
 
         // Push the node
         let index = self.siblings.len() as u32;
         self.siblings.push(node);
-        self.counts.push(count);
+        self.push_sums(count, sum, node_weight);
 
         index
     }
+
+    /// Push the totals of a new node.
+    fn push_sums(&mut self, count: u32, sum: f64, weight: Option<f64>) {
+        self.counts.push(count);
+        self.sums.push(sum);
+        if let (Some(weights), Some(weight)) = (&mut self.weights, weight) {
+            weights.push(weight);
+        }
+    }
 }
 
 /// Persistent segment tree node.
@@ -195,7 +370,7 @@ mod tests {
     #[test]
     fn kth() {
         let input = ndarray::aview1(&[3.5, 1.2, 4.8, 2.1, 5.0, 1.2]);
-        let tree = KthSmallestTree::build(&input);
+        let tree = KthSmallestTree::build(&input, None);
 
         // Sort the values over the whole range and use that to ensure it works
         let mut sorted = input.to_vec();
@@ -214,5 +389,27 @@ mod tests {
         assert_eq!(tree.kth(2..=4, 1), 2.1);
         assert_eq!(tree.kth(2..=4, 2), 4.8);
         assert_eq!(tree.kth(2..=4, 3), 5.0);
+
+        // Sums of the smallest values
+        assert_eq!(tree.kth_with_sum(2..=4, 2), (4.8, 2.1 + 4.8));
+        assert_eq!(tree.kth_with_sum(0..=5, 2), (1.2, 2.4));
+        assert!((tree.sum(2..=4) - 11.9).abs() < 1e-9, "{}", tree.sum(2..=4));
+    }
+
+    /// Smallest element reaching a weight.
+    #[test]
+    fn weighted_kth() {
+        let input = ndarray::aview1(&[3.0, 1.0, 4.0, 2.0]);
+        let weights = ndarray::aview1(&[1.0, 0.5, 2.0, 0.0]);
+        let tree = KthSmallestTree::build(&input, Some(&weights));
+
+        // The value without weight isn't included
+        assert_eq!(tree.count(0..=3), 3);
+        assert_eq!(tree.weight(0..=3), 3.5);
+
+        assert_eq!(tree.weighted_kth(0..=3, 0.0), (1.0, 0.5, 0.5));
+        assert_eq!(tree.weighted_kth(0..=3, 1.0), (3.0, 1.5, 3.5));
+        assert_eq!(tree.weighted_kth(0..=3, 1.75), (4.0, 3.5, 11.5));
+        assert_eq!(tree.weighted_kth(2..=3, 3.5), (4.0, 2.0, 8.0));
     }
 }
@@ -0,0 +1,96 @@
+//! Changepoints for a range of penalties (CROPS).
+
+use ndarray::{ArrayView, Dimension};
+
+use crate::{Error, OneOrTwoDimensions, Sample, predict::PredictImpl};
+
+/// Optimal segmentation for a single penalty.
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct PenaltySegmentation {
+    /// Penalty the segmentation is calculated with.
+    pub penalty: f64,
+    /// Indices where changes were detected, including the end of the signal.
+    pub changepoints: Vec<usize>,
+    /// Total loss of all segments, without penalties.
+    pub loss: f64,
+}
+
+impl PredictImpl {
+    /// Find all optimal segmentations between two penalties.
+    ///
+    /// Implements "Computationally Efficient Changepoint Detection for a Range of Penalties" by Haynes, Eckley and Fearnhead.
+    pub(crate) fn crops<A, D>(
+        &mut self,
+        signal: &ArrayView<A, D>,
+        penalty_min: f64,
+        penalty_max: f64,
+    ) -> Result<Vec<PenaltySegmentation>, Error>
+    where
+        A: Sample,
+        D: OneOrTwoDimensions + Dimension,
+        D::PrecalculationOutput: Sync,
+    {
+        // Only valid for finite positive ranges
+        if !(0.0..=penalty_max).contains(&penalty_min) || !penalty_max.is_finite() {
+            return Err(Error::InvalidPenaltyRange);
+        }
+
+        // Precalculate the cost function once for all runs
+        let cost = self.precalculate(signal)?;
+
+        // Share the segment losses between all runs
+        self.enable_cache();
+
+        // Run the algorithm for a single penalty
+        let mut segment = |penalty: f64| -> Result<PenaltySegmentation, Error> {
+            let changepoints = self.predict_with_cost(signal, &cost, &penalty)?;
+            let loss = Self::segmentation_loss::<D>(&cost, &changepoints);
+
+            Ok(PenaltySegmentation {
+                penalty,
+                changepoints,
+                loss,
+            })
+        };
+
+        let mut segmentations = vec![segment(penalty_min)?];
+        if penalty_max > penalty_min {
+            segmentations.push(segment(penalty_max)?);
+        }
+
+        // Intervals of segmentations that still need to be searched, by index
+        let mut intervals = vec![(0, segmentations.len() - 1)];
+        while let Some((low, high)) = intervals.pop() {
+            let low_len = segmentations[low].changepoints.len();
+            let high_len = segmentations[high].changepoints.len();
+
+            // No other amount of changepoints possible in between
+            if low_len <= high_len + 1 {
+                continue;
+            }
+
+            // Penalty where both segmentations have the same penalized cost
+            let penalty =
+                (segmentations[high].loss - segmentations[low].loss) / (low_len - high_len) as f64;
+            let middle = segment(penalty)?;
+
+            // When it's one of the two boundaries there's nothing in between
+            let middle_len = middle.changepoints.len();
+            if middle_len >= low_len || middle_len <= high_len {
+                continue;
+            }
+
+            // Search both sides
+            segmentations.push(middle);
+            let middle = segmentations.len() - 1;
+            intervals.push((low, middle));
+            intervals.push((middle, high));
+        }
+
+        // Order by increasing penalty
+        segmentations.sort_by(|left, right| left.penalty.total_cmp(&right.penalty));
+
+        Ok(segmentations)
+    }
+}
@@ -0,0 +1,269 @@
+//! Cumulative sum control chart.
+
+use ndarray::{ArrayView2, AsArray, Dimension};
+
+use crate::{Error, OneOrTwoDimensions, nan};
+
+/// Two-sided cumulative sum detector.
+///
+/// Sums the deviations from the mean of the current segment minus the drift, in both directions.
+/// When either sum exceeds the threshold, the segment ends where that sum last started to grow.
+/// Only keeps a few numbers per column, so it's a lightweight online alternative to [`crate::Pelt`].
+///
+/// # Defaults
+///
+/// - `threshold`: `5.0`
+/// - `drift`: `0.5`
+#[derive(Debug, Clone)]
+pub struct Cusum {
+    /// Sum at which a changepoint is detected.
+    threshold: f64,
+    /// Deviation from the mean that is ignored every point.
+    drift: f64,
+    /// Amount of points seen since the last reset.
+    position: usize,
+    /// Amount of points in the current segment.
+    count: usize,
+    /// Mean of the current segment.
+    mean: f64,
+    /// Sum of the increases with the position where it last was zero.
+    upper: (f64, usize),
+    /// Sum of the decreases with the position where it last was zero.
+    lower: (f64, usize),
+}
+
+impl Cusum {
+    /// Construct a new detector with default values.
+    #[must_use]
+    pub const fn new() -> Self {
+        Self {
+            threshold: 5.0,
+            drift: 0.5,
+            position: 0,
+            count: 0,
+            mean: 0.0,
+            upper: (0.0, 0),
+            lower: (0.0, 0),
+        }
+    }
+
+    /// Set the sum at which a changepoint is detected.
+    ///
+    /// Higher thresholds give less false alarms but detect changes later.
+    #[must_use]
+    pub const fn with_threshold(mut self, threshold: f64) -> Self {
+        self.threshold = threshold;
+
+        self
+    }
+
+    /// Set the deviation from the mean that is ignored every point.
+    ///
+    /// Usually half the size of the smallest change that should be detected.
+    #[must_use]
+    pub const fn with_drift(mut self, drift: f64) -> Self {
+        self.drift = drift;
+
+        self
+    }
+
+    /// Ensure the parameters are valid.
+    fn validate(&self) -> Result<(), Error> {
+        if self.threshold.is_finite()
+            && self.threshold > 0.0
+            && self.drift.is_finite()
+            && self.drift >= 0.0
+        {
+            Ok(())
+        } else {
+            Err(Error::InvalidThreshold)
+        }
+    }
+
+    /// Forget all previous points.
+    pub const fn reset(&mut self) {
+        self.position = 0;
+        self.start_segment();
+    }
+
+    /// Start a new segment at the current position.
+    const fn start_segment(&mut self) {
+        self.count = 0;
+        self.mean = 0.0;
+        self.upper = (0.0, self.position);
+        self.lower = (0.0, self.position);
+    }
+
+    /// Add the next point of the signal.
+    ///
+    /// Returns the position of the first point of the new segment when a changepoint is detected, counted from the first point after the last reset.
+    ///
+    /// # Errors
+    ///
+    /// - When the threshold or drift is invalid.
+    /// - When the value is NaN or infinite, the detector is left unchanged.
+    pub fn update(&mut self, value: f64) -> Result<Option<usize>, Error> {
+        self.validate()?;
+
+        // A single NaN would end up in the mean of the rest of the segment
+        if !value.is_finite() {
+            return Err(Error::InvalidInput {
+                index: self.position,
+            });
+        }
+
+        let changepoint = self.step(value);
+        if changepoint.is_some() {
+            self.start_segment();
+        }
+
+        Ok(changepoint)
+    }
+
+    /// Update the sums without resetting.
+    fn step(&mut self, value: f64) -> Option<usize> {
+        // Deviation from the mean of the previous points of the segment
+        let deviation = if self.count == 0 {
+            0.0
+        } else {
+            value - self.mean
+        };
+
+        // Update the running mean
+        self.count += 1;
+        self.mean += (value - self.mean) / self.count as f64;
+        self.position += 1;
+
+        // Each sum restarts after the last point when it drops below zero
+        let accumulate = |(sum, start): (f64, usize), deviation: f64, position: usize| {
+            let sum = sum + deviation;
+            if sum > 0.0 {
+                (sum, start)
+            } else {
+                (0.0, position)
+            }
+        };
+        self.upper = accumulate(self.upper, deviation - self.drift, self.position);
+        self.lower = accumulate(self.lower, -deviation - self.drift, self.position);
+
+        [self.upper, self.lower]
+            .into_iter()
+            .filter(|(sum, _)| *sum > self.threshold)
+            .map(|(_, start)| start)
+            .min()
+    }
+
+    /// Fit on a data set.
+    ///
+    /// Every column of a 2D signal has its own sums, a changepoint in any column starts a new segment in all of them.
+    ///
+    /// # Errors
+    ///
+    /// - When the threshold or drift is invalid.
+    /// - When the signal contains a NaN or infinite value.
+    /// - When the signal has fewer than two points, like the default minimum segment length of [`crate::Pelt`].
+    pub fn predict<'a, D>(&self, signal: impl AsArray<'a, f64, D>) -> Result<Vec<usize>, Error>
+    where
+        D: OneOrTwoDimensions + Dimension,
+    {
+        let signal_view = signal.into();
+        D::check_dimensions(&signal_view)?;
+        nan::check_finite(&signal_view)?;
+
+        self.validate()?;
+
+        if D::len_or_nrows(&signal_view) < 2 {
+            return Err(Error::NotEnoughPoints);
+        }
+
+        Ok(self.predict_impl(&D::as_2d(&signal_view)?))
+    }
+
+    /// Run a detector for every column.
+    fn predict_impl(&self, signal: &ArrayView2<f64>) -> Vec<usize> {
+        let mut detector = self.clone();
+        detector.reset();
+        let mut detectors = vec![detector; signal.ncols()];
+
+        let mut changepoints = Vec::new();
+        for row in signal.rows() {
+            let changepoint = detectors
+                .iter_mut()
+                .zip(row)
+                .filter_map(|(detector, value)| detector.step(*value))
+                .min();
+
+            if let Some(changepoint) = changepoint {
+                // Can't start before the previous segment
+                if changepoints
+                    .last()
+                    .is_none_or(|previous| changepoint > *previous)
+                    && changepoint > 0
+                {
+                    changepoints.push(changepoint);
+                }
+
+                detectors.iter_mut().for_each(Self::start_segment);
+            }
+        }
+
+        // Add the last item
+        changepoints.push(signal.nrows());
+
+        changepoints
+    }
+}
+
+impl Default for Cusum {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Detect a step online.
+    #[test]
+    fn update() {
+        let mut detector = Cusum::new();
+        for _ in 0..20 {
+            assert_eq!(detector.update(0.0).expect("Error updating"), None);
+        }
+
+        // The change is detected a few points later, but located at the start
+        let changepoint = (0..5)
+            .find_map(|_| detector.update(3.0).expect("Error updating"))
+            .expect("Changepoint not detected");
+        assert_eq!(changepoint, 20);
+    }
+
+    /// NaN and infinite values are rejected without changing the detector.
+    #[test]
+    fn non_finite() {
+        let mut detector = Cusum::new();
+        for _ in 0..20 {
+            detector.update(0.0).expect("Error updating");
+        }
+
+        for value in [f64::NAN, f64::INFINITY, f64::NEG_INFINITY] {
+            assert!(matches!(
+                detector.update(value),
+                Err(Error::InvalidInput { index: 20 })
+            ));
+        }
+        assert_eq!(detector.mean, 0.0);
+
+        // Still detects the change at the right position afterwards
+        let changepoint = (0..5)
+            .find_map(|_| detector.update(3.0).expect("Error updating"))
+            .expect("Changepoint not detected");
+        assert_eq!(changepoint, 20);
+
+        assert!(matches!(
+            Cusum::new().predict(&[0.0, 1.0, f64::NAN, 2.0]),
+            Err(Error::InvalidInput { index: 2 })
+        ));
+    }
+}
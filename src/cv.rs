@@ -0,0 +1,199 @@
+//! Penalty selection with cross-validation.
+
+use ndarray::{ArrayView, ArrayView2, Axis, Dimension, Slice};
+
+use crate::{Error, OneOrTwoDimensions, Sample, SegmentCostFunction, predict::PredictImpl};
+
+impl PredictImpl {
+    /// Select the penalty with the lowest predictive loss from the candidates.
+    ///
+    /// The signal is split in the even and odd points.
+    /// Each half is segmented and the loss of the other half is calculated with the segment models fitted on the first half.
+    pub(crate) fn select_penalty_cv<A, D>(
+        &self,
+        signal: &ArrayView<A, D>,
+        penalties: &[f64],
+    ) -> Result<f64, Error>
+    where
+        A: Sample,
+        D: OneOrTwoDimensions + Dimension,
+        D::PrecalculationOutput: Sync,
+    {
+        // At least one penalty to select
+        if penalties.is_empty() {
+            return Err(Error::InvalidPenaltyRange);
+        }
+
+        // Reject invalid penalties before they're halved
+        if penalties
+            .iter()
+            .any(|penalty| !penalty.is_finite() || *penalty < 0.0)
+        {
+            return Err(Error::InvalidPenalty);
+        }
+
+        // Both halves need a point
+        if D::len_or_nrows(signal) < 2 {
+            return Err(Error::NotEnoughPoints);
+        }
+
+        // Both halves of the signal
+        let even = signal.slice_axis(Axis(0), Slice::new(0, None, 2));
+        let odd = signal.slice_axis(Axis(0), Slice::new(1, None, 2));
+
+        // Each half has half the loss, so half the penalty gives the same trade-off
+        let half_penalties = penalties
+            .iter()
+            .map(|penalty| penalty / 2.0)
+            .collect::<Vec<_>>();
+
+        // Keep the same spacing as the full signal
+        let mut pelt = self.pelt.clone();
+        pelt.jump = (pelt.jump / 2).max(1);
+        pelt.minimum_segment_length = (pelt.minimum_segment_length / 2).max(1);
+        pelt.max_changepoints = None;
+
+        // Segment both halves for all penalties, with the candidates and forbidden ranges at the rows of each half
+        let even_changepoints = Self::new(pelt.reindexed(|row| row.div_ceil(2)))
+            .predict_many(&even, &half_penalties)?;
+        let odd_changepoints =
+            Self::new(pelt.reindexed(|row| row / 2)).predict_many(&odd, &half_penalties)?;
+
+        let even = D::as_2d(&even)?;
+        let odd = D::as_2d(&odd)?;
+
+        penalties
+            .iter()
+            .zip(even_changepoints.iter().zip(&odd_changepoints))
+            .map(|(penalty, (even_changepoints, odd_changepoints))| {
+                // Score each half on the other half
+                let loss = predictive_loss(
+                    self.pelt.segment_cost_function,
+                    &even,
+                    &odd,
+                    even_changepoints,
+                ) + predictive_loss(
+                    self.pelt.segment_cost_function,
+                    &odd,
+                    &even,
+                    odd_changepoints,
+                );
+
+                (*penalty, loss)
+            })
+            .min_by(|(_, left), (_, right)| left.total_cmp(right))
+            .map(|(penalty, _)| penalty)
+            .ok_or(Error::InvalidPenaltyRange)
+    }
+}
+
+/// Loss of the test signal with the segment models fitted on the train signal.
+///
+/// Point `i` of the test signal lies between point `i` and `i + 1` of the train signal, so the segments can be reused.
+fn predictive_loss<A>(
+    segment_cost_function: SegmentCostFunction,
+    train: &ArrayView2<A>,
+    test: &ArrayView2<A>,
+    changepoints: &[usize],
+) -> f64
+where
+    A: Sample,
+{
+    let mut loss = 0.0;
+
+    // Each segment starts at the previous changepoint
+    let mut start = 0;
+    for end in changepoints.iter().copied() {
+        // The test signal can be one point shorter
+        let test_end = end.min(test.nrows());
+
+        for (train_column, test_column) in train.columns().into_iter().zip(test.columns()) {
+            // Fit the segment model on the train segment
+            let mut values = train_column
+                .slice(ndarray::s![start..end])
+                .iter()
+                .map(|value| value.to_f64())
+                .collect::<Vec<_>>();
+            let location = segment_cost_function.location(&mut values);
+
+            // Calculate the loss of the test segment
+            loss += test_column
+                .slice(ndarray::s![start.min(test_end)..test_end])
+                .iter()
+                .map(|value| segment_cost_function.point_loss(value.to_f64(), location))
+                .sum::<f64>();
+        }
+
+        start = end;
+    }
+
+    loss
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Check the predictive loss of a single segment.
+    #[test]
+    fn predictive_loss_l2() {
+        let train = ndarray::array![[1.0], [3.0], [10.0], [10.0]];
+        let test = ndarray::array![[2.0], [5.0], [10.0]];
+
+        // Means are 2 and 10
+        let loss = predictive_loss(
+            SegmentCostFunction::L2,
+            &train.view(),
+            &test.view(),
+            &[2, 4],
+        );
+        assert_eq!(loss, 9.0);
+    }
+
+    /// Signals too short to split in two halves are rejected.
+    #[test]
+    fn select_penalty_cv_short() {
+        let predict = PredictImpl::new(crate::Pelt::new());
+
+        for signal in [ndarray::array![], ndarray::array![1.0]] {
+            assert!(matches!(
+                predict.select_penalty_cv(&signal.view(), &[1.0]),
+                Err(Error::NotEnoughPoints)
+            ));
+        }
+    }
+
+    /// An empty list and negative or non-finite penalties are rejected.
+    #[test]
+    fn select_penalty_cv_invalid_penalties() {
+        let predict = PredictImpl::new(crate::Pelt::new());
+        let signal = ndarray::Array1::from_iter((0..20).map(f64::from));
+
+        assert!(matches!(
+            predict.select_penalty_cv(&signal.view(), &[]),
+            Err(Error::InvalidPenaltyRange)
+        ));
+        for penalty in [-1.0, f64::NAN, f64::INFINITY] {
+            assert!(matches!(
+                predict.select_penalty_cv(&signal.view(), &[1.0, penalty]),
+                Err(Error::InvalidPenalty)
+            ));
+        }
+    }
+
+    /// Forbidden ranges and candidates move to the rows of the even half.
+    #[test]
+    fn reindexed_even() {
+        let pelt = crate::Pelt::new()
+            .with_forbidden_ranges(&[3..8, 20..30])
+            .with_candidates(&[5, 6, 9])
+            .reindexed(|row| row.div_ceil(2));
+
+        // Rows 4 and 6 of the full signal are forbidden, which are rows 2 and 3 of the half
+        assert!(!pelt.is_forbidden(1));
+        assert!(pelt.is_forbidden(2));
+        assert!(pelt.is_forbidden(3));
+        assert!(!pelt.is_forbidden(4));
+        assert_eq!(pelt.candidates, Some(vec![3, 5]));
+    }
+}
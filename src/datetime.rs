@@ -0,0 +1,79 @@
+//! Changepoints mapped to the timestamps of the rows.
+
+use chrono::{DateTime, Utc};
+
+use crate::Error;
+
+/// Changepoint with the timestamp of its row.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct TimestampedChangepoint {
+    /// Row of the signal where the new segment starts.
+    pub index: usize,
+    /// Timestamp of the row where the new segment starts.
+    pub timestamp: DateTime<Utc>,
+}
+
+/// Map the changepoints to the timestamps of their rows.
+///
+/// The end of the signal isn't a row, so it's left out.
+pub(crate) fn timestamped(
+    changepoints: Vec<usize>,
+    timestamps: &[DateTime<Utc>],
+) -> Vec<TimestampedChangepoint> {
+    changepoints
+        .into_iter()
+        .filter_map(|index| {
+            timestamps
+                .get(index)
+                .map(|timestamp| TimestampedChangepoint {
+                    index,
+                    timestamp: *timestamp,
+                })
+        })
+        .collect()
+}
+
+/// Ensure there's one timestamp per row.
+pub(crate) const fn check_len(
+    timestamps: &[DateTime<Utc>],
+    signal_len: usize,
+) -> Result<(), Error> {
+    if timestamps.len() == signal_len {
+        Ok(())
+    } else {
+        Err(Error::InvalidTimestamps)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use chrono::TimeDelta;
+
+    use crate::{Error, Pelt, SegmentCostFunction};
+
+    /// Changepoints have the timestamp of their row.
+    #[test]
+    fn timestamped() {
+        let signal = (0..40)
+            .map(|index| if index < 20 { 0.0 } else { 5.0 })
+            .collect::<Vec<_>>();
+        let start = chrono::DateTime::UNIX_EPOCH;
+        let timestamps = (0..40)
+            .map(|index| start + TimeDelta::minutes(index))
+            .collect::<Vec<_>>();
+
+        let pelt = Pelt::new().with_segment_cost_function(SegmentCostFunction::L2);
+        let changepoints = pelt
+            .predict_with_datetimes(&signal, 1.0, &timestamps)
+            .expect("Error predicting");
+        assert_eq!(changepoints.len(), 1, "{changepoints:?}");
+        assert_eq!(changepoints[0].index, 20);
+        assert_eq!(changepoints[0].timestamp, start + TimeDelta::minutes(20));
+
+        assert!(matches!(
+            pelt.predict_with_datetimes(&signal, 1.0, &timestamps[1..]),
+            Err(Error::InvalidTimestamps)
+        ));
+    }
+}
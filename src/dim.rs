@@ -2,113 +2,424 @@
 
 use std::ops::Range;
 
-use ndarray::{ArrayView, ArrayView1, ArrayView2, Dimension, Ix1, Ix2};
+use ndarray::{
+    ArrayBase, ArrayView, ArrayView1, ArrayView2, ArrayViewD, Axis, Dimension, Ix1, Ix2, IxDyn,
+    RawData,
+};
 
 use crate::{
-    SegmentCostFunction,
+    Accumulator, Error, NanPolicy, SegmentCostFunction,
     cost::{Cost1D, Cost2D},
 };
 
 /// Don't allow other crates to implement this.
 mod sealed {
-    use ndarray::{Ix1, Ix2};
+    use ndarray::{Ix1, Ix2, IxDyn};
 
     /// Don't allow other crates to implement this.
     pub trait Sealed {}
 
     impl Sealed for Ix1 {}
     impl Sealed for Ix2 {}
+    impl Sealed for IxDyn {}
+    impl Sealed for f32 {}
+    impl Sealed for f64 {}
+    impl Sealed for i32 {}
+    impl Sealed for i64 {}
+}
+
+/// Element type of the signal.
+///
+/// Implemented for [`f64`] and [`f32`], and for [`i32`] and [`i64`] for count data.
+/// The cost functions are precalculated in [`f64`] directly from the signal without copying it first.
+/// Sums of integers stay exact as long as they're below 2<sup>53</sup>.
+pub trait Sample: Copy + Send + Sync + sealed::Sealed {
+    /// Convert to double precision.
+    #[doc(hidden)]
+    fn to_f64(self) -> f64;
+}
+
+impl Sample for f64 {
+    #[inline]
+    fn to_f64(self) -> f64 {
+        self
+    }
+}
+
+impl Sample for f32 {
+    #[inline]
+    fn to_f64(self) -> f64 {
+        f64::from(self)
+    }
+}
+
+impl Sample for i32 {
+    #[inline]
+    fn to_f64(self) -> f64 {
+        f64::from(self)
+    }
+}
+
+impl Sample for i64 {
+    #[inline]
+    fn to_f64(self) -> f64 {
+        self as f64
+    }
 }
 
 /// Trait allowing the input array to be both 1 and two dimensional.
+///
+/// Arrays with a dynamic dimension are also accepted, as long as they're one or two dimensional.
 pub trait OneOrTwoDimensions: Dimension + sealed::Sealed {
-    type PrecalculationOutput;
+    type PrecalculationOutput: Send;
+
+    /// Ensure the array has one or two dimensions, only a dynamic dimension can have others.
+    ///
+    /// # Errors
+    ///
+    /// - When the array isn't one or two dimensional.
+    #[doc(hidden)]
+    #[inline]
+    fn check_dimensions<A>(_array: &ArrayView<A, Self>) -> Result<(), Error> {
+        Ok(())
+    }
+
+    /// Put the time along the first axis, only two dimensional arrays have a choice.
+    ///
+    /// # Errors
+    ///
+    /// - When the time axis isn't the first or second axis of a two dimensional array.
+    #[doc(hidden)]
+    #[inline]
+    fn time_major<S>(
+        array: ArrayBase<S, Self>,
+        _time_axis: Axis,
+    ) -> Result<ArrayBase<S, Self>, Error>
+    where
+        S: RawData,
+    {
+        Ok(array)
+    }
 
     /// Amount of rows.
     #[doc(hidden)]
-    fn len_or_nrows(array: &ArrayView<f64, Self>) -> usize;
+    fn len_or_nrows<A>(array: &ArrayView<A, Self>) -> usize;
 
-    /// Precalculate function.
+    /// Precalculate function, optionally with a weight for each row and a weight and cost function for each column.
     #[doc(hidden)]
-    fn precalculate(
+    fn precalculate<A>(
         cost: SegmentCostFunction,
-        signal: &ArrayView<f64, Self>,
-    ) -> Self::PrecalculationOutput;
+        accumulator: Accumulator,
+        nan_policy: NanPolicy,
+        signal: &ArrayView<A, Self>,
+        weights: Option<&ArrayView1<f64>>,
+        column_weights: Option<&[f64]>,
+        column_cost_functions: Option<&[SegmentCostFunction]>,
+    ) -> Result<Self::PrecalculationOutput, Error>
+    where
+        A: Sample;
 
     /// Calculate the loss.
     #[doc(hidden)]
-    fn loss(
+    fn loss(cost: &Self::PrecalculationOutput, total_loss: &mut f64, range: Range<usize>);
+
+    /// Calculate the loss, the calculation may stop early once the total loss exceeds the bound.
+    ///
+    /// When it stops early the total loss is lower than the real loss, but still higher than the bound.
+    #[doc(hidden)]
+    fn loss_bounded(
         cost: &Self::PrecalculationOutput,
         total_loss: &mut f64,
-        signal: &ArrayView<f64, Self>,
         range: Range<usize>,
+        bound: f64,
     );
 
+    /// Lower bound of the loss that's cheaper to calculate.
+    #[doc(hidden)]
+    fn loss_lower_bound(cost: &Self::PrecalculationOutput, range: Range<usize>) -> f64;
+
     /// Convert to 1D if possible.
     #[doc(hidden)]
-    fn try_as_1d<'a>(array: &'a ArrayView<f64, Self>) -> Option<ArrayView1<'a, f64>>;
+    fn try_as_1d<'a, A>(array: &'a ArrayView<A, Self>) -> Option<ArrayView1<'a, A>>;
+
+    /// Convert to 2D, a 1D array becomes a single column.
+    ///
+    /// # Errors
+    ///
+    /// - When the array doesn't have one or two dimensions.
+    #[doc(hidden)]
+    fn as_2d<'a, A>(array: &'a ArrayView<A, Self>) -> Result<ArrayView2<'a, A>, Error>;
 }
 
 impl OneOrTwoDimensions for Ix1 {
     type PrecalculationOutput = Cost1D;
 
     #[inline]
-    fn len_or_nrows(array: &ArrayView1<f64>) -> usize {
+    fn len_or_nrows<A>(array: &ArrayView1<A>) -> usize {
         array.len()
     }
 
     #[inline]
-    fn precalculate(
+    fn precalculate<A>(
         cost: SegmentCostFunction,
-        signal: &ArrayView1<f64>,
-    ) -> Self::PrecalculationOutput {
-        Self::PrecalculationOutput::precalculate(cost, signal)
+        accumulator: Accumulator,
+        nan_policy: NanPolicy,
+        signal: &ArrayView1<A>,
+        weights: Option<&ArrayView1<f64>>,
+        column_weights: Option<&[f64]>,
+        column_cost_functions: Option<&[SegmentCostFunction]>,
+    ) -> Result<Self::PrecalculationOutput, Error>
+    where
+        A: Sample,
+    {
+        Ok(Self::PrecalculationOutput::precalculate(
+            column_cost_functions
+                .and_then(|column_cost_functions| column_cost_functions.first().copied())
+                .unwrap_or(cost),
+            accumulator,
+            nan_policy,
+            signal,
+            weights,
+            column_weights.and_then(|column_weights| column_weights.first().copied()),
+        ))
+    }
+
+    #[inline]
+    fn loss(cost: &Self::PrecalculationOutput, total_loss: &mut f64, range: Range<usize>) {
+        cost.loss(total_loss, range)
     }
 
     #[inline]
-    fn loss(
+    fn loss_bounded(
         cost: &Self::PrecalculationOutput,
         total_loss: &mut f64,
-        signal: &ArrayView1<f64>,
         range: Range<usize>,
+        _bound: f64,
     ) {
-        cost.loss(total_loss, signal, range)
+        // A single column can't stop halfway
+        cost.loss(total_loss, range)
     }
 
     #[inline]
-    fn try_as_1d<'a>(_array: &'a ArrayView1<f64>) -> Option<ArrayView1<'a, f64>> {
+    fn loss_lower_bound(cost: &Self::PrecalculationOutput, range: Range<usize>) -> f64 {
+        cost.lower_bound(range)
+    }
+
+    #[inline]
+    fn try_as_1d<'a, A>(_array: &'a ArrayView1<A>) -> Option<ArrayView1<'a, A>> {
         None
     }
+
+    #[inline]
+    fn as_2d<'a, A>(array: &'a ArrayView1<A>) -> Result<ArrayView2<'a, A>, Error> {
+        Ok(array.view().insert_axis(Axis(1)))
+    }
 }
 
 impl OneOrTwoDimensions for Ix2 {
     type PrecalculationOutput = Cost2D;
 
     #[inline]
-    fn len_or_nrows(array: &ArrayView2<f64>) -> usize {
+    fn time_major<S>(
+        array: ArrayBase<S, Self>,
+        time_axis: Axis,
+    ) -> Result<ArrayBase<S, Self>, Error>
+    where
+        S: RawData,
+    {
+        transpose_to_time_major(array, time_axis)
+    }
+
+    #[inline]
+    fn len_or_nrows<A>(array: &ArrayView2<A>) -> usize {
         array.nrows()
     }
 
     #[inline]
-    fn precalculate(
+    fn precalculate<A>(
         cost: SegmentCostFunction,
-        signal: &ArrayView2<f64>,
-    ) -> Self::PrecalculationOutput {
-        Self::PrecalculationOutput::precalculate(cost, signal)
+        accumulator: Accumulator,
+        nan_policy: NanPolicy,
+        signal: &ArrayView2<A>,
+        weights: Option<&ArrayView1<f64>>,
+        column_weights: Option<&[f64]>,
+        column_cost_functions: Option<&[SegmentCostFunction]>,
+    ) -> Result<Self::PrecalculationOutput, Error>
+    where
+        A: Sample,
+    {
+        Ok(Self::PrecalculationOutput::precalculate(
+            cost,
+            accumulator,
+            nan_policy,
+            signal,
+            weights,
+            column_weights,
+            column_cost_functions,
+        ))
+    }
+
+    #[inline]
+    fn loss(cost: &Self::PrecalculationOutput, total_loss: &mut f64, range: Range<usize>) {
+        cost.loss(total_loss, range)
     }
 
     #[inline]
-    fn loss(
+    fn loss_bounded(
         cost: &Self::PrecalculationOutput,
         total_loss: &mut f64,
-        signal: &ArrayView2<f64>,
         range: Range<usize>,
+        bound: f64,
     ) {
-        cost.loss(total_loss, signal, range)
+        cost.loss_bounded(total_loss, range, bound)
+    }
+
+    #[inline]
+    fn loss_lower_bound(cost: &Self::PrecalculationOutput, range: Range<usize>) -> f64 {
+        cost.lower_bound(range)
     }
 
     #[inline]
-    fn try_as_1d<'a>(array: &'a ArrayView<f64, Self>) -> Option<ArrayView1<'a, f64>> {
+    fn try_as_1d<'a, A>(array: &'a ArrayView2<A>) -> Option<ArrayView1<'a, A>> {
         (array.ncols() == 1).then(|| array.column(0))
     }
+
+    #[inline]
+    fn as_2d<'a, A>(array: &'a ArrayView2<A>) -> Result<ArrayView2<'a, A>, Error> {
+        Ok(array.view())
+    }
+}
+
+/// Arrays with other dimensions are rejected by [`OneOrTwoDimensions::check_dimensions`], which every public function calls first.
+impl OneOrTwoDimensions for IxDyn {
+    type PrecalculationOutput = Cost2D;
+
+    #[inline]
+    fn check_dimensions<A>(array: &ArrayViewD<A>) -> Result<(), Error> {
+        if (1..=2).contains(&array.ndim()) {
+            Ok(())
+        } else {
+            Err(Error::InvalidDimensions)
+        }
+    }
+
+    #[inline]
+    fn time_major<S>(
+        array: ArrayBase<S, Self>,
+        time_axis: Axis,
+    ) -> Result<ArrayBase<S, Self>, Error>
+    where
+        S: RawData,
+    {
+        if array.ndim() == 2 {
+            transpose_to_time_major(array, time_axis)
+        } else {
+            Ok(array)
+        }
+    }
+
+    #[inline]
+    fn len_or_nrows<A>(array: &ArrayViewD<A>) -> usize {
+        array.shape().first().copied().unwrap_or_default()
+    }
+
+    #[inline]
+    fn precalculate<A>(
+        cost: SegmentCostFunction,
+        accumulator: Accumulator,
+        nan_policy: NanPolicy,
+        signal: &ArrayViewD<A>,
+        weights: Option<&ArrayView1<f64>>,
+        column_weights: Option<&[f64]>,
+        column_cost_functions: Option<&[SegmentCostFunction]>,
+    ) -> Result<Self::PrecalculationOutput, Error>
+    where
+        A: Sample,
+    {
+        Ok(Self::PrecalculationOutput::precalculate(
+            cost,
+            accumulator,
+            nan_policy,
+            &Self::as_2d(signal)?,
+            weights,
+            column_weights,
+            column_cost_functions,
+        ))
+    }
+
+    #[inline]
+    fn loss(cost: &Self::PrecalculationOutput, total_loss: &mut f64, range: Range<usize>) {
+        Ix2::loss(cost, total_loss, range);
+    }
+
+    #[inline]
+    fn loss_bounded(
+        cost: &Self::PrecalculationOutput,
+        total_loss: &mut f64,
+        range: Range<usize>,
+        bound: f64,
+    ) {
+        Ix2::loss_bounded(cost, total_loss, range, bound);
+    }
+
+    #[inline]
+    fn loss_lower_bound(cost: &Self::PrecalculationOutput, range: Range<usize>) -> f64 {
+        Ix2::loss_lower_bound(cost, range)
+    }
+
+    #[inline]
+    fn try_as_1d<'a, A>(array: &'a ArrayViewD<A>) -> Option<ArrayView1<'a, A>> {
+        Self::as_2d(array)
+            .ok()
+            .filter(|array_2d| array_2d.ncols() == 1)
+            .map(|array_2d| array_2d.index_axis_move(Axis(1), 0))
+    }
+
+    #[inline]
+    fn as_2d<'a, A>(array: &'a ArrayViewD<A>) -> Result<ArrayView2<'a, A>, Error> {
+        let array = if array.ndim() == 1 {
+            array.view().insert_axis(Axis(1))
+        } else {
+            array.view()
+        };
+
+        array
+            .into_dimensionality()
+            .map_err(|_error| Error::InvalidDimensions)
+    }
+}
+
+/// Swap the axes of a two dimensional array when the time is along the second axis.
+fn transpose_to_time_major<S, D>(
+    array: ArrayBase<S, D>,
+    time_axis: Axis,
+) -> Result<ArrayBase<S, D>, Error>
+where
+    S: RawData,
+    D: Dimension,
+{
+    match time_axis.index() {
+        0 => Ok(array),
+        1 => Ok(array.reversed_axes()),
+        _ => Err(Error::InvalidDimensions),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use ndarray::ArrayD;
+
+    use super::*;
+
+    /// Converting a dynamic array with too many dimensions is an error instead of a panic.
+    #[test]
+    fn as_2d_invalid() {
+        let array = ArrayD::<f64>::zeros(vec![2, 2, 2]);
+
+        assert!(matches!(
+            IxDyn::as_2d(&array.view()),
+            Err(Error::InvalidDimensions)
+        ));
+        assert!(IxDyn::try_as_1d(&array.view()).is_none());
+    }
 }
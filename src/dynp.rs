@@ -0,0 +1,111 @@
+//! Exact amount of changepoints with dynamic programming.
+
+use ndarray::{ArrayView, Dimension};
+
+use crate::{Error, OneOrTwoDimensions, Sample, predict::PredictImpl};
+
+impl PredictImpl {
+    /// Find the optimal segmentation with exactly `n_changepoints` changepoints.
+    ///
+    /// Doesn't need a penalty, but it can't prune so it's quadratic in the amount of proposed indices.
+    pub(crate) fn predict_n_changepoints<A, D>(
+        &self,
+        signal: &ArrayView<A, D>,
+        n_changepoints: usize,
+    ) -> Result<Vec<usize>, Error>
+    where
+        A: Sample,
+        D: OneOrTwoDimensions + Dimension,
+    {
+        let len = D::len_or_nrows(signal);
+
+        // Precalculate the cost function
+        let cost = self.precalculate(signal)?;
+
+        // All positions a segment can start or end at, the first is the start of the signal and the last the end
+        let positions = std::iter::once(0)
+            .chain(self.proposed_indices(len))
+            .filter(|position| *position == len || !self.pelt.is_forbidden(*position))
+            .collect::<Vec<_>>();
+
+        // Amount of segments, one more than the changepoints
+        let segments = n_changepoints + 1;
+
+        // `losses[segment * positions.len() + end]` is the optimal loss of `signal[0..positions[end]]` with `segment` segments
+        let mut losses = vec![f64::INFINITY; (segments + 1) * positions.len()];
+        // Start index of the last segment for backtracking
+        let mut starts = vec![0; (segments + 1) * positions.len()];
+        losses[0] = 0.0;
+
+        for (end, end_position) in positions.iter().enumerate().skip(1) {
+            for (start, start_position) in positions[..end].iter().enumerate() {
+                // Handle invalid case for too short segments
+                if end_position - start_position < self.pelt.minimum_segment_length {
+                    continue;
+                }
+
+                // The loss is the same for every amount of segments, so only calculate it once
+                let mut loss = 0.0;
+                D::loss(&cost, &mut loss, *start_position..*end_position);
+
+                // Extend every amount of segments ending at the start with this segment
+                for segment in 1..=segments {
+                    let previous = losses[(segment - 1) * positions.len() + start];
+                    let index = segment * positions.len() + end;
+
+                    if previous + loss < losses[index] {
+                        losses[index] = previous + loss;
+                        starts[index] = start;
+                    }
+                }
+            }
+        }
+
+        // Signal too short for the amount of changepoints
+        let mut end = positions.len() - 1;
+        if !losses[segments * positions.len() + end].is_finite() {
+            return Err(Error::NotEnoughPoints);
+        }
+
+        // Walk back over the segments
+        let mut indices = Vec::with_capacity(segments);
+        for segment in (1..=segments).rev() {
+            indices.push(positions[end]);
+
+            end = starts[segment * positions.len() + end];
+        }
+        indices.reverse();
+
+        Ok(indices)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::num::NonZero;
+
+    use ndarray::Array1;
+
+    use crate::{Pelt, SegmentCostFunction, predict::PredictImpl};
+
+    /// A forbidden range past the end of the signal doesn't remove the end.
+    #[test]
+    fn forbidden_range_past_end() {
+        let signal = (0..100)
+            .map(|index| if index < 30 { 0.0 } else { 5.0 })
+            .collect::<Array1<f64>>();
+        let predict = PredictImpl::new(
+            Pelt::new()
+                .with_segment_cost_function(SegmentCostFunction::L2)
+                .with_jump(NonZero::<usize>::MIN)
+                .with_forbidden_ranges(std::slice::from_ref(&(30..150))),
+        );
+
+        assert_eq!(
+            predict
+                .predict_n_changepoints(&signal.view(), 1)
+                .expect("Error predicting"),
+            vec![30, 100]
+        );
+    }
+}
@@ -0,0 +1,247 @@
+//! Hierarchical divisive segmentation with the energy statistic.
+
+use std::{num::NonZero, ops::Range};
+
+use ndarray::{ArrayView2, AsArray, Dimension};
+
+use crate::{Error, OneOrTwoDimensions, nan, pairwise::PairwiseSums};
+
+/// E-divisive algorithm.
+///
+/// Recursively splits the segment with the largest energy distance between its two parts.
+/// The energy distance compares whole distributions of multivariate points, so it finds changes in any moment, not only the mean.
+/// A split is only kept when it's significant in a permutation test, so no penalty is needed.
+/// Distances between all pairs of points are precalculated, so memory and time are quadratic in the length of the signal.
+///
+/// # Defaults
+///
+/// - `minimum_segment_length`: `30`
+/// - `exponent`: `1.0`
+/// - `significance`: `0.05`
+/// - `permutations`: `199`
+/// - `seed`: `0`
+#[derive(Debug, Clone)]
+pub struct EDivisive {
+    /// Minimum allowable number of data points within a segment.
+    minimum_segment_length: usize,
+    /// Exponent of the distance between points.
+    exponent: f64,
+    /// Maximum p-value of a split.
+    significance: f64,
+    /// Amount of permutations of the significance test.
+    permutations: usize,
+    /// Seed of the random number generator.
+    seed: u64,
+}
+
+impl EDivisive {
+    /// Construct a new e-divisive instance with default values.
+    #[must_use]
+    pub const fn new() -> Self {
+        Self {
+            minimum_segment_length: 30,
+            exponent: 1.0,
+            significance: 0.05,
+            permutations: 199,
+            seed: 0,
+        }
+    }
+
+    /// Set the minimum allowable number of data points within a segment.
+    ///
+    /// Segments always contain at least two points.
+    #[must_use]
+    pub const fn with_minimum_segment_length(
+        mut self,
+        minimum_segment_length: NonZero<usize>,
+    ) -> Self {
+        self.minimum_segment_length = minimum_segment_length.get();
+
+        self
+    }
+
+    /// Set the exponent of the distance between points.
+    ///
+    /// Must be in the `0.0..=2.0` range, at `2.0` only changes in the mean are found.
+    #[must_use]
+    pub const fn with_exponent(mut self, exponent: f64) -> Self {
+        self.exponent = exponent;
+
+        self
+    }
+
+    /// Set the maximum p-value of a split.
+    #[must_use]
+    pub const fn with_significance(mut self, significance: f64) -> Self {
+        self.significance = significance;
+
+        self
+    }
+
+    /// Set the amount of permutations of the significance test.
+    ///
+    /// More permutations give a more precise p-value, but take longer.
+    #[must_use]
+    pub const fn with_permutations(mut self, permutations: usize) -> Self {
+        self.permutations = permutations;
+
+        self
+    }
+
+    /// Set the seed of the random number generator of the permutations.
+    #[must_use]
+    pub const fn with_seed(mut self, seed: u64) -> Self {
+        self.seed = seed;
+
+        self
+    }
+
+    /// Fit on a data set.
+    ///
+    /// # Errors
+    ///
+    /// - When the exponent is outside of the `0.0..=2.0` range.
+    /// - When the signal contains a NaN or infinite value.
+    /// - When the signal is shorter than the minimum segment length.
+    pub fn predict<'a, D>(&self, signal: impl AsArray<'a, f64, D>) -> Result<Vec<usize>, Error>
+    where
+        D: OneOrTwoDimensions + Dimension,
+    {
+        let signal_view = signal.into();
+        D::check_dimensions(&signal_view)?;
+        nan::check_finite(&signal_view)?;
+
+        // Ensure the exponent is correct
+        if !(self.exponent > 0.0 && self.exponent <= 2.0) {
+            return Err(Error::InvalidExponent);
+        }
+
+        self.predict_impl(&D::as_2d(&signal_view)?)
+    }
+
+    /// Keep splitting while the best split is significant.
+    fn predict_impl(&self, signal: &ArrayView2<f64>) -> Result<Vec<usize>, Error> {
+        let len = signal.nrows();
+        let minimum_segment_length = self.minimum_segment_length.max(2);
+        if len < minimum_segment_length {
+            return Err(Error::NotEnoughPoints);
+        }
+
+        // Distances between all pairs of points
+        let mut distances = vec![0.0; len * len];
+        for (row, left) in signal.rows().into_iter().enumerate() {
+            for (column, right) in signal.rows().into_iter().enumerate().skip(row + 1) {
+                let distance = left
+                    .iter()
+                    .zip(right)
+                    .map(|(left, right)| (left - right).powi(2))
+                    .sum::<f64>()
+                    .sqrt()
+                    .powf(self.exponent);
+
+                distances[row * len + column] = distance;
+                distances[column * len + row] = distance;
+            }
+        }
+        let sums = PairwiseSums::precalculate(len, |row, column| distances[row * len + column]);
+
+        let mut rng = fastrand::Rng::with_seed(self.seed);
+        let mut segments = vec![(0, len)];
+        while let Some((segment, split, statistic)) =
+            best_split(&sums, &segments, minimum_segment_length)
+        {
+            // Count how often shuffling the points within each segment gives a statistic at least as large
+            let mut order = (0..len).collect::<Vec<_>>();
+            let exceeded = (0..self.permutations)
+                .filter(|_| {
+                    for (start, end) in &segments {
+                        rng.shuffle(&mut order[*start..*end]);
+                    }
+                    let permuted_sums = PairwiseSums::precalculate(len, |row, column| {
+                        distances[order[row] * len + order[column]]
+                    });
+
+                    best_split(&permuted_sums, &segments, minimum_segment_length)
+                        .is_some_and(|(_, _, permuted)| permuted >= statistic)
+                })
+                .count();
+
+            let p_value = (exceeded + 1) as f64 / (self.permutations + 1) as f64;
+            if p_value > self.significance {
+                break;
+            }
+
+            let (start, end) = segments[segment];
+            segments[segment] = (start, split);
+            segments.push((split, end));
+        }
+
+        let mut changepoints = segments.into_iter().map(|(_, end)| end).collect::<Vec<_>>();
+        changepoints.sort_unstable();
+
+        Ok(changepoints)
+    }
+}
+
+impl Default for EDivisive {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Find the split with the largest energy statistic over all segments.
+///
+/// Returns the index of the segment, the split and the statistic.
+fn best_split(
+    sums: &PairwiseSums,
+    segments: &[(usize, usize)],
+    minimum_segment_length: usize,
+) -> Option<(usize, usize, f64)> {
+    segments
+        .iter()
+        .enumerate()
+        .flat_map(|(segment, (start, end))| {
+            (start + minimum_segment_length..=end.saturating_sub(minimum_segment_length))
+                .map(move |split| (segment, split, statistic(sums, *start..split, split..*end)))
+        })
+        .max_by(|(_, _, left), (_, _, right)| left.total_cmp(right))
+}
+
+/// Scaled energy distance between two neighboring parts.
+fn statistic(sums: &PairwiseSums, left: Range<usize>, right: Range<usize>) -> f64 {
+    let left_len = left.len() as f64;
+    let right_len = right.len() as f64;
+
+    // Mean distance between the parts and within each part, the diagonal is zero
+    let between = sums.sum(left.clone(), right.clone()) / (left_len * right_len);
+    let within_left = sums.sum(left.clone(), left) / (left_len * (left_len - 1.0));
+    let within_right = sums.sum(right.clone(), right) / (right_len * (right_len - 1.0));
+
+    left_len * right_len / (left_len + right_len)
+        * 2.0_f64.mul_add(between, -within_left - within_right)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Find a change in variance that the mean doesn't show.
+    #[test]
+    fn variance() {
+        let signal =
+            ndarray::Array1::from_iter((0..100).map(|index| match (index < 50, index % 2) {
+                (true, 0) => 0.1,
+                (true, _) => -0.1,
+                (false, 0) => 5.0,
+                (false, _) => -5.0,
+            }));
+
+        assert_eq!(
+            EDivisive::new()
+                .with_minimum_segment_length(NonZero::new(10).expect("Invalid number"))
+                .predict(&signal)
+                .expect("Error predicting"),
+            vec![50, 100]
+        );
+    }
+}
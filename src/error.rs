@@ -9,6 +9,105 @@ pub enum Error {
     /// No segments got calculated.
     #[error("calculation didn't return any segments")]
     NoSegmentsFound,
+    /// Quantile of the cost function isn't strictly between `0.0` and `1.0`.
+    #[error("quantile of segment cost function must be larger than 0 and smaller than 1")]
+    InvalidQuantile,
+    /// Penalty range is empty, negative or not finite.
+    #[error("penalty range must be finite, positive and not empty")]
+    InvalidPenaltyRange,
+    /// Calculated segmentation has more changepoints than allowed.
+    #[error("calculated segmentation has more changepoints than the configured maximum")]
+    TooManyChangepoints,
+    /// Bandwidth of the kernel is negative, zero or not finite.
+    #[error("bandwidth of kernel must be finite and positive")]
+    InvalidKernelBandwidth,
+    /// Parameters of the prior are negative, zero or not finite.
+    #[error("parameters of prior must be finite and positive")]
+    InvalidPrior,
+    /// Threshold is not positive or drift is negative, or either is not finite.
+    #[error("threshold must be finite and positive, and drift finite and not negative")]
+    InvalidThreshold,
+    /// Exponent of the distance is outside of the `0.0..=2.0` range.
+    #[error("exponent of distance must be larger than 0 and at most 2")]
+    InvalidExponent,
+    /// Parts of the signal or the column cost functions have a different amount of columns.
+    #[error(
+        "all parts of the signal and the column cost functions must have the same amount of columns"
+    )]
+    ColumnMismatch,
+    /// Checkpoint was created for a different signal or different settings.
+    #[error("checkpoint doesn't match the signal or the settings")]
+    InvalidCheckpoint,
+    /// Gaps are unsorted, overlapping or outside of the signal.
+    #[error("gaps must be sorted, not overlapping and inside the signal")]
+    InvalidGaps,
+    /// Timestamps don't match the signal length, aren't increasing, or the maximum interval or minimum duration isn't valid.
+    #[error(
+        "timestamps must be finite, increasing and one per row, the maximum interval positive and the minimum duration non-negative"
+    )]
+    InvalidTimestamps,
+    /// Signal has more samples than the solver can index, only with the `u32-indices` feature.
+    #[error("signal is too long for the indices, disable the `u32-indices` feature")]
+    SignalTooLong,
+    /// Signal with a dynamic dimension isn't one or two dimensional.
+    #[error("signal must be one or two dimensional")]
+    InvalidDimensions,
+    /// Signal contains a NaN value with [`crate::NanPolicy::Error`], or a NaN or infinite value for a detector without a NaN policy.
+    #[error("signal contains a NaN or infinite value in row {index}")]
+    InvalidInput {
+        /// First row containing the value.
+        index: usize,
+    },
+    /// Mask doesn't have one value per row.
+    #[error("mask must have one value per row of the signal")]
+    InvalidMask,
+    /// Weights aren't finite, non-negative and one per row.
+    #[error("weights must be finite, non-negative and one per row of the signal")]
+    InvalidWeights,
+    /// Column of an Arrow record batch isn't a `Float64Array`, only with the `arrow` feature.
+    #[error("columns must be 64-bit floats")]
+    UnsupportedColumn,
+    /// Changepoints aren't increasing or don't end at the end of the signal.
+    #[error("changepoints must be increasing and end at the end of the signal")]
+    InvalidChangepoints,
+    /// Changepoint isn't larger than the one before it.
+    #[error("changepoint at position {position} must be larger than the one before it")]
+    UnsortedChangepoints {
+        /// Position of the changepoint in the list.
+        position: usize,
+    },
+    /// Changepoint is zero or beyond the end of the signal.
+    #[error("changepoint {changepoint} must be larger than 0 and at most {signal_len}")]
+    ChangepointOutOfBounds {
+        /// Value of the changepoint.
+        changepoint: usize,
+        /// Length of the signal.
+        signal_len: usize,
+    },
+    /// Segment between two changepoints is shorter than the minimum segment length.
+    #[error("segment {start}..{end} must be at least {minimum_segment_length} samples long")]
+    SegmentTooShort {
+        /// First sample of the segment.
+        start: usize,
+        /// End of the segment, exclusive.
+        end: usize,
+        /// Configured minimum segment length.
+        minimum_segment_length: usize,
+    },
+    /// Fraction of the samples in a subsample or of the runs agreeing on a changepoint is outside of the `0.0..=1.0` range or zero.
+    #[error("fraction must be larger than 0 and at most 1")]
+    InvalidFraction,
+    /// No signals with known changepoints to calibrate on.
+    #[error("calibration needs at least one signal with known changepoints")]
+    MissingLabels,
+    /// Column weights aren't finite, non-negative and one per column.
+    #[error("column weights must be finite, non-negative and one per column of the signal")]
+    InvalidColumnWeights,
+    /// Constant penalty isn't finite and non-negative, or the constant of a segment length penalty isn't finite or its weight isn't finite and non-negative.
+    #[error(
+        "penalty must be finite, and non-negative except for the constant of a segment length penalty"
+    )]
+    InvalidPenalty,
 }
 
 #[cfg(feature = "rayon")]
@@ -19,6 +118,33 @@ impl Error {
         match self {
             Self::NotEnoughPoints => 1,
             Self::NoSegmentsFound => 2,
+            Self::InvalidQuantile => 3,
+            Self::InvalidPenaltyRange => 4,
+            Self::TooManyChangepoints => 5,
+            Self::InvalidKernelBandwidth => 6,
+            Self::InvalidPrior => 7,
+            Self::InvalidThreshold => 8,
+            Self::InvalidExponent => 9,
+            Self::ColumnMismatch => 10,
+            Self::InvalidCheckpoint => 11,
+            Self::InvalidGaps => 12,
+            Self::InvalidTimestamps => 13,
+            Self::SignalTooLong => 14,
+            Self::InvalidDimensions => 15,
+            // The row is lost, only happens before the calculation starts
+            Self::InvalidInput { .. } => 16,
+            Self::InvalidMask => 17,
+            Self::InvalidWeights => 18,
+            Self::UnsupportedColumn => 19,
+            Self::InvalidChangepoints => 20,
+            // Only happen when validating, which doesn't run on threads
+            Self::UnsortedChangepoints { .. } => 21,
+            Self::ChangepointOutOfBounds { .. } => 22,
+            Self::SegmentTooShort { .. } => 23,
+            Self::InvalidFraction => 24,
+            Self::MissingLabels => 25,
+            Self::InvalidColumnWeights => 26,
+            Self::InvalidPenalty => 27,
         }
     }
 
@@ -33,6 +159,38 @@ impl Error {
             0 => Ok(()),
             1 => Err(Self::NotEnoughPoints),
             2 => Err(Self::NoSegmentsFound),
+            3 => Err(Self::InvalidQuantile),
+            4 => Err(Self::InvalidPenaltyRange),
+            5 => Err(Self::TooManyChangepoints),
+            6 => Err(Self::InvalidKernelBandwidth),
+            7 => Err(Self::InvalidPrior),
+            8 => Err(Self::InvalidThreshold),
+            9 => Err(Self::InvalidExponent),
+            10 => Err(Self::ColumnMismatch),
+            11 => Err(Self::InvalidCheckpoint),
+            12 => Err(Self::InvalidGaps),
+            13 => Err(Self::InvalidTimestamps),
+            14 => Err(Self::SignalTooLong),
+            15 => Err(Self::InvalidDimensions),
+            16 => Err(Self::InvalidInput { index: 0 }),
+            17 => Err(Self::InvalidMask),
+            18 => Err(Self::InvalidWeights),
+            19 => Err(Self::UnsupportedColumn),
+            20 => Err(Self::InvalidChangepoints),
+            21 => Err(Self::UnsortedChangepoints { position: 0 }),
+            22 => Err(Self::ChangepointOutOfBounds {
+                changepoint: 0,
+                signal_len: 0,
+            }),
+            23 => Err(Self::SegmentTooShort {
+                start: 0,
+                end: 0,
+                minimum_segment_length: 0,
+            }),
+            24 => Err(Self::InvalidFraction),
+            25 => Err(Self::MissingLabels),
+            26 => Err(Self::InvalidColumnWeights),
+            27 => Err(Self::InvalidPenalty),
             _ => panic!("Unrecognized error number"),
         }
     }
@@ -0,0 +1,174 @@
+//! Segmentation of signals with gaps.
+
+use std::ops::Range;
+
+use ndarray::{ArrayView, Axis, Dimension, Slice};
+
+use crate::{Error, OneOrTwoDimensions, Penalty, Sample, nan, predict::PredictImpl};
+
+impl PredictImpl {
+    /// Segment the parts of the signal between the gaps separately, with the boundaries of the gaps as changepoints.
+    pub(crate) fn predict_with_gaps<A, D, P>(
+        &mut self,
+        signal: &ArrayView<A, D>,
+        penalty: &P,
+        gaps: &[Range<usize>],
+    ) -> Result<Vec<usize>, Error>
+    where
+        A: Sample,
+        D: OneOrTwoDimensions + Dimension,
+        P: Penalty,
+        D::PrecalculationOutput: Sync,
+    {
+        // Length as the rows
+        let len = D::len_or_nrows(signal);
+
+        // Gaps must be sorted, not overlapping and inside the signal
+        if gaps.iter().any(|gap| gap.start > gap.end || gap.end > len)
+            || gaps
+                .iter()
+                .zip(gaps.iter().skip(1))
+                .any(|(previous, next)| previous.end > next.start)
+        {
+            return Err(Error::InvalidGaps);
+        }
+
+        let mut changepoints = Vec::new();
+
+        // Parts with data are between the end of the previous gap and the start of the next one
+        let mut start = 0;
+        for (end, gap_end) in gaps
+            .iter()
+            .map(|gap| (gap.start, Some(gap.end)))
+            .chain(std::iter::once((len, None)))
+        {
+            if end > start {
+                let part_signal = signal.slice_axis(Axis(0), Slice::from(start..end));
+                nan::check(self.pelt.nan_policy, &part_signal, start)?;
+
+                // Parts that are too short can't be segmented, so they are a single segment
+                if end - start < self.pelt.minimum_segment_length {
+                    changepoints.push(end);
+                } else {
+                    changepoints.extend(
+                        self.predict(&part_signal, penalty)?
+                            .into_iter()
+                            .map(|changepoint| changepoint + start),
+                    );
+                }
+            }
+
+            // The gap itself is a segment without data
+            if let Some(gap_end) = gap_end {
+                if gap_end > end {
+                    changepoints.push(gap_end);
+                }
+
+                start = gap_end;
+            }
+        }
+
+        // Gaps at the start of the signal and empty gaps can result in the same boundary twice
+        changepoints.retain(|changepoint| *changepoint > 0);
+        changepoints.dedup();
+
+        if changepoints.is_empty() {
+            return Err(Error::NotEnoughPoints);
+        }
+
+        Ok(changepoints)
+    }
+
+    /// Find the empty gaps between samples that are too far apart in time.
+    pub(crate) fn timestamp_gaps(
+        timestamps: &[f64],
+        signal_len: usize,
+        max_interval: f64,
+    ) -> Result<Vec<Range<usize>>, Error> {
+        // One finite timestamp per row, strictly increasing
+        if timestamps.len() != signal_len
+            || !(max_interval.is_finite() && max_interval > 0.0)
+            || timestamps.iter().any(|timestamp| !timestamp.is_finite())
+            || timestamps
+                .iter()
+                .zip(timestamps.iter().skip(1))
+                .any(|(previous, next)| next <= previous)
+        {
+            return Err(Error::InvalidTimestamps);
+        }
+
+        // No segment may contain both sides of the interval
+        Ok(timestamps
+            .iter()
+            .zip(timestamps.iter().skip(1))
+            .enumerate()
+            .filter(|(_index, (previous, next))| *next - *previous > max_interval)
+            .map(|(index, _timestamps)| (index + 1)..(index + 1))
+            .collect())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{Error, Pelt, SegmentCostFunction};
+
+    /// Gap boundaries are changepoints and the missing data inside isn't used.
+    #[test]
+    fn gaps() {
+        let signal = (0..100)
+            .map(|index| match index {
+                0..30 => 0.0,
+                // Missing data
+                50..60 => f64::NAN,
+                _ => 5.0,
+            } + if index % 2 == 0 { 0.1 } else { -0.1 })
+            .collect::<Vec<_>>();
+
+        let pelt = Pelt::new().with_segment_cost_function(SegmentCostFunction::L2);
+        assert_eq!(
+            pelt.predict_with_gaps(&signal, 1.0, &[50..60, 80..80])
+                .expect("Error predicting"),
+            vec![30, 50, 60, 80, 100]
+        );
+
+        // Gaps at the edges
+        assert_eq!(
+            pelt.predict_with_gaps(&signal[50..], 1.0, &[0..10, 40..50])
+                .expect("Error predicting"),
+            vec![10, 40, 50]
+        );
+
+        assert!(
+            matches!(
+                pelt.predict_with_gaps(&signal, 1.0, &[60..70, 50..55]),
+                Err(Error::InvalidGaps)
+            ),
+            "Unsorted gaps should be rejected"
+        );
+    }
+
+    /// Large intervals between timestamps are boundaries.
+    #[test]
+    fn timestamps() {
+        let signal = vec![0.0; 100];
+        let timestamps = (0..100)
+            .map(|index| if index < 40 { index } else { index + 10 })
+            .map(f64::from)
+            .collect::<Vec<_>>();
+
+        let pelt = Pelt::new().with_segment_cost_function(SegmentCostFunction::L2);
+        assert_eq!(
+            pelt.predict_with_timestamps(&signal, 1.0, &timestamps, 5.0)
+                .expect("Error predicting"),
+            vec![40, 100]
+        );
+
+        assert!(
+            matches!(
+                pelt.predict_with_timestamps(&signal, 1.0, &timestamps[1..], 5.0),
+                Err(Error::InvalidTimestamps)
+            ),
+            "Timestamps of a different length should be rejected"
+        );
+    }
+}
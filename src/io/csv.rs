@@ -0,0 +1,187 @@
+//! CSV files.
+
+use std::{
+    fs::File,
+    io::{Error, ErrorKind, Read, Result},
+    path::Path,
+};
+
+use csv::{ReaderBuilder, StringRecord};
+use ndarray::Array2;
+
+/// Options for reading a CSV file into a signal.
+///
+/// # Defaults
+///
+/// - `headers`: `false`
+/// - `delimiter`: `b','`
+/// - `columns`: all columns
+#[derive(Debug, Clone)]
+pub struct CsvOptions {
+    /// Whether the first row contains the names of the columns.
+    headers: bool,
+    /// Byte separating the fields.
+    delimiter: u8,
+    /// Indices of the columns to read in order, all if `None`.
+    columns: Option<Vec<usize>>,
+}
+
+impl CsvOptions {
+    /// Construct the options with default values.
+    #[must_use]
+    pub const fn new() -> Self {
+        Self {
+            headers: false,
+            delimiter: b',',
+            columns: None,
+        }
+    }
+
+    /// Skip the first row, because it contains the names of the columns.
+    #[must_use]
+    pub const fn with_headers(mut self) -> Self {
+        self.headers = true;
+
+        self
+    }
+
+    /// Set the byte separating the fields, such as `b';'` or `b'\t'`.
+    #[must_use]
+    pub const fn with_delimiter(mut self, delimiter: u8) -> Self {
+        self.delimiter = delimiter;
+
+        self
+    }
+
+    /// Only read the columns with these indices, in this order.
+    #[must_use]
+    pub fn with_columns(mut self, columns: &[usize]) -> Self {
+        self.columns = Some(columns.to_vec());
+
+        self
+    }
+}
+
+impl Default for CsvOptions {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Read a CSV file into a signal, every row is a sample.
+///
+/// Empty fields become NaN, so they can be handled by the [`crate::NanPolicy`].
+///
+/// # Errors
+///
+/// - When the file can't be opened or read.
+/// - When a field isn't a number, or the rows don't have the same amount of columns.
+pub fn read_csv<P>(path: P, options: &CsvOptions) -> Result<Array2<f64>>
+where
+    P: AsRef<Path>,
+{
+    read_csv_from_reader(File::open(path)?, options)
+}
+
+/// Read CSV from any reader into a signal, every row is a sample.
+///
+/// See [`read_csv`].
+///
+/// # Errors
+///
+/// - When reading fails.
+/// - When a field isn't a number, or the rows don't have the same amount of columns.
+pub fn read_csv_from_reader<R>(reader: R, options: &CsvOptions) -> Result<Array2<f64>>
+where
+    R: Read,
+{
+    let mut reader = ReaderBuilder::new()
+        .has_headers(options.headers)
+        .delimiter(options.delimiter)
+        .from_reader(reader);
+
+    let mut samples = Vec::new();
+    let mut columns = None;
+    let mut record = StringRecord::new();
+    while reader.read_record(&mut record)? {
+        let start = samples.len();
+        match &options.columns {
+            Some(indices) => {
+                for index in indices {
+                    let field = record
+                        .get(*index)
+                        .ok_or_else(|| invalid_data(format!("row is missing column {index}")))?;
+                    samples.push(parse_field(field)?);
+                }
+            }
+            None => {
+                for field in &record {
+                    samples.push(parse_field(field)?);
+                }
+            }
+        }
+
+        // Every row must have the same amount of columns as the first
+        let row_columns = samples.len() - start;
+        if *columns.get_or_insert(row_columns) != row_columns {
+            return Err(invalid_data("rows have a different amount of columns"));
+        }
+    }
+
+    let columns = columns.unwrap_or_default();
+    Array2::from_shape_vec((samples.len() / columns.max(1), columns), samples)
+        .map_err(|error| invalid_data(error.to_string()))
+}
+
+/// Parse a field as a sample, NaN if it's empty.
+fn parse_field(field: &str) -> Result<f64> {
+    let field = field.trim();
+    if field.is_empty() {
+        return Ok(f64::NAN);
+    }
+
+    field.parse::<f64>().map_err(invalid_data)
+}
+
+/// Construct an invalid data error.
+fn invalid_data<E>(error: E) -> Error
+where
+    E: Into<Box<dyn std::error::Error + Send + Sync>>,
+{
+    Error::new(ErrorKind::InvalidData, error)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Headers, delimiters and selected columns.
+    #[test]
+    fn csv_options() {
+        let signal = read_csv_from_reader(
+            include_bytes!("../../tests/normal-10.csv").as_slice(),
+            &CsvOptions::new(),
+        )
+        .expect("Error reading CSV");
+        assert_eq!(signal.dim(), (1000, 2));
+
+        let file = b"time;a;b\n0;1.5;2\n1;;3\n".as_slice();
+        let signal = read_csv_from_reader(
+            file,
+            &CsvOptions::new()
+                .with_headers()
+                .with_delimiter(b';')
+                .with_columns(&[2, 1]),
+        )
+        .expect("Error reading CSV");
+        assert_eq!(signal.row(0).to_vec(), vec![2.0, 1.5]);
+        assert_eq!(signal[[1, 0]], 3.0);
+        assert!(signal[[1, 1]].is_nan(), "{signal}");
+
+        // The header isn't a number
+        assert!(
+            read_csv_from_reader(file, &CsvOptions::new().with_delimiter(b';')).is_err(),
+            "Header should be rejected"
+        );
+    }
+}
@@ -0,0 +1,226 @@
+//! Arrow IPC and Feather files.
+
+use std::{
+    fs::File,
+    io::{BufReader, Error, ErrorKind, Read as _, Result, Seek as _, SeekFrom},
+    path::Path,
+};
+
+use arrow_array::{
+    Array, ArrowPrimitiveType, RecordBatch, RecordBatchReader,
+    cast::AsArray as _,
+    types::{
+        Float32Type, Float64Type, Int8Type, Int16Type, Int32Type, Int64Type, UInt8Type, UInt16Type,
+        UInt32Type, UInt64Type,
+    },
+};
+use arrow_ipc::reader::{FileReader, StreamReader};
+use ndarray::Array2;
+
+/// Magic bytes at the start of an IPC file, absent in the streaming format.
+const MAGIC: &[u8; 6] = b"ARROW1";
+
+/// Read columns of an Arrow IPC file into a signal, every row is a sample.
+///
+/// Both the file format, also known as Feather V2, and the streaming format are supported.
+/// The columns are in the order of the names.
+/// Integer and floating point columns are converted to [`f64`], null values become NaN.
+///
+/// # Errors
+///
+/// - When the file can't be opened or isn't a valid Arrow IPC file.
+/// - When a column doesn't exist or isn't numeric.
+pub fn read_ipc<P>(path: P, columns: &[&str]) -> Result<Array2<f64>>
+where
+    P: AsRef<Path>,
+{
+    let mut file = File::open(path)?;
+
+    // Detect the format from the magic bytes
+    let mut magic = [0; MAGIC.len()];
+    let is_file_format = file.read_exact(&mut magic).is_ok() && magic == *MAGIC;
+    file.seek(SeekFrom::Start(0))?;
+
+    let reader: Box<dyn RecordBatchReader> = if is_file_format {
+        Box::new(FileReader::try_new(BufReader::new(file), None).map_err(invalid_data)?)
+    } else {
+        Box::new(StreamReader::try_new(BufReader::new(file), None).map_err(invalid_data)?)
+    };
+
+    // Find the requested columns, in the requested order
+    let schema = reader.schema();
+    let indices = columns
+        .iter()
+        .map(|name| {
+            schema.index_of(name).map_err(|_error| {
+                Error::new(
+                    ErrorKind::InvalidInput,
+                    format!("column `{name}` not found"),
+                )
+            })
+        })
+        .collect::<Result<Vec<_>>>()?;
+
+    let mut samples = Vec::new();
+    let mut rows = 0;
+    for batch in reader {
+        let batch = batch.map_err(invalid_data)?;
+        rows += batch.num_rows();
+        push_batch(&batch, columns, &indices, &mut samples)?;
+    }
+
+    // Without the `std` feature of `ndarray` the shape error isn't an error type
+    Array2::from_shape_vec((rows, columns.len()), samples)
+        .map_err(|error| invalid_data(error.to_string()))
+}
+
+/// Append the rows of the selected columns of a batch to the samples.
+fn push_batch(
+    batch: &RecordBatch,
+    names: &[&str],
+    indices: &[usize],
+    samples: &mut Vec<f64>,
+) -> Result<()> {
+    let start = samples.len();
+    samples.resize(start + batch.num_rows() * indices.len(), f64::NAN);
+
+    for (column_index, (name, index)) in names.iter().zip(indices).enumerate() {
+        let column = batch.column(*index);
+        let values = column_values(column.as_ref())
+            .ok_or_else(|| invalid_data(format!("column `{name}` is not numeric")))?;
+        for (row, value) in values.enumerate() {
+            samples[start + row * indices.len() + column_index] = value;
+        }
+    }
+
+    Ok(())
+}
+
+/// Convert the values of a numeric column to samples, `None` if it isn't numeric.
+#[allow(
+    clippy::cast_precision_loss,
+    reason = "samples are always converted to floating point"
+)]
+fn column_values(column: &dyn Array) -> Option<Box<dyn Iterator<Item = f64> + '_>> {
+    Some(match column.data_type() {
+        data_type if *data_type == Float64Type::DATA_TYPE => {
+            primitive_values::<Float64Type>(column, |value| value)
+        }
+        data_type if *data_type == Float32Type::DATA_TYPE => {
+            primitive_values::<Float32Type>(column, f64::from)
+        }
+        data_type if *data_type == Int8Type::DATA_TYPE => {
+            primitive_values::<Int8Type>(column, f64::from)
+        }
+        data_type if *data_type == Int16Type::DATA_TYPE => {
+            primitive_values::<Int16Type>(column, f64::from)
+        }
+        data_type if *data_type == Int32Type::DATA_TYPE => {
+            primitive_values::<Int32Type>(column, f64::from)
+        }
+        data_type if *data_type == Int64Type::DATA_TYPE => {
+            primitive_values::<Int64Type>(column, |value| value as f64)
+        }
+        data_type if *data_type == UInt8Type::DATA_TYPE => {
+            primitive_values::<UInt8Type>(column, f64::from)
+        }
+        data_type if *data_type == UInt16Type::DATA_TYPE => {
+            primitive_values::<UInt16Type>(column, f64::from)
+        }
+        data_type if *data_type == UInt32Type::DATA_TYPE => {
+            primitive_values::<UInt32Type>(column, f64::from)
+        }
+        data_type if *data_type == UInt64Type::DATA_TYPE => {
+            primitive_values::<UInt64Type>(column, |value| value as f64)
+        }
+        _ => return None,
+    })
+}
+
+/// Convert the values of a primitive column, with the null values as NaN.
+fn primitive_values<T>(
+    column: &dyn Array,
+    convert: fn(T::Native) -> f64,
+) -> Box<dyn Iterator<Item = f64> + '_>
+where
+    T: ArrowPrimitiveType,
+{
+    Box::new(
+        column
+            .as_primitive::<T>()
+            .iter()
+            .map(move |value| value.map_or(f64::NAN, convert)),
+    )
+}
+
+/// Construct an invalid data error.
+fn invalid_data<E>(error: E) -> Error
+where
+    E: Into<Box<dyn std::error::Error + Send + Sync>>,
+{
+    Error::new(ErrorKind::InvalidData, error)
+}
+
+#[cfg(test)]
+mod tests {
+    use arrow_array::{Float64Array, Int32Array};
+    use arrow_ipc::writer::{FileWriter, StreamWriter};
+
+    use std::sync::Arc;
+
+    use super::*;
+
+    /// Requested columns are read in order from both formats, with null values as NaN.
+    #[test]
+    fn ipc_columns() {
+        let batch = RecordBatch::try_from_iter([
+            (
+                "a",
+                Arc::new(Int32Array::from(vec![1, 2, 3])) as Arc<dyn Array>,
+            ),
+            (
+                "b",
+                Arc::new(Float64Array::from(vec![Some(4.0), None, Some(6.0)])),
+            ),
+        ])
+        .expect("Invalid batch");
+
+        let file_path =
+            std::env::temp_dir().join(format!("pelt-{}-signal.arrow", std::process::id()));
+        let mut writer = FileWriter::try_new(
+            File::create(&file_path).expect("Error creating file"),
+            &batch.schema(),
+        )
+        .expect("Error creating writer");
+        writer.write(&batch).expect("Error writing batch");
+        writer.write(&batch).expect("Error writing batch");
+        writer.finish().expect("Error writing file");
+
+        let stream_path =
+            std::env::temp_dir().join(format!("pelt-{}-signal.arrows", std::process::id()));
+        let mut writer = StreamWriter::try_new(
+            File::create(&stream_path).expect("Error creating file"),
+            &batch.schema(),
+        )
+        .expect("Error creating writer");
+        writer.write(&batch).expect("Error writing batch");
+        writer.write(&batch).expect("Error writing batch");
+        writer.finish().expect("Error writing stream");
+
+        for path in [&file_path, &stream_path] {
+            let signal = read_ipc(path, &["b", "a"]).expect("Error reading file");
+            assert_eq!(signal.dim(), (6, 2));
+            assert_eq!(
+                signal.column(1).to_vec(),
+                vec![1.0, 2.0, 3.0, 1.0, 2.0, 3.0]
+            );
+            assert_eq!(signal[[3, 0]], 4.0);
+            assert!(signal[[4, 0]].is_nan(), "{signal}");
+
+            assert!(read_ipc(path, &["c"]).is_err(), "Missing column");
+        }
+
+        std::fs::remove_file(file_path).expect("Error removing file");
+        std::fs::remove_file(stream_path).expect("Error removing file");
+    }
+}
@@ -0,0 +1,19 @@
+//! Loading signals from files.
+
+#[cfg(feature = "csv")]
+mod csv;
+#[cfg(feature = "ipc")]
+mod ipc;
+#[cfg(feature = "npy")]
+mod numpy;
+#[cfg(feature = "parquet")]
+mod parquet;
+
+#[cfg(feature = "csv")]
+pub use self::csv::{CsvOptions, read_csv, read_csv_from_reader};
+#[cfg(feature = "ipc")]
+pub use self::ipc::read_ipc;
+#[cfg(feature = "npy")]
+pub use self::numpy::{read_npy, read_npz};
+#[cfg(feature = "parquet")]
+pub use self::parquet::read_parquet;
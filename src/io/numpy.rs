@@ -0,0 +1,145 @@
+//! NumPy `.npy` and `.npz` files.
+
+use std::{
+    fs::File,
+    io::{Error, ErrorKind, Read as _, Result},
+    path::Path,
+};
+
+use ndarray::Array2;
+use zip::ZipArchive;
+
+use crate::npy;
+
+/// Read a `.npy` file, as written by `numpy.save`, into a signal.
+///
+/// One-dimensional arrays become a single column, two-dimensional arrays are rows of samples in C or Fortran order.
+/// Little-endian `float64`, `float32`, `int32` and `int64` values are converted to [`f64`].
+/// See [`crate::MmapSignal::open_npy`] with the `mmap` feature to read large `float64` files without copying them.
+///
+/// # Errors
+///
+/// - When the file can't be read.
+/// - When the file isn't a supported `.npy` file.
+pub fn read_npy<P>(path: P) -> Result<Array2<f64>>
+where
+    P: AsRef<Path>,
+{
+    npy::decode(&std::fs::read(path)?)
+}
+
+/// Read an array of a `.npz` file, as written by `numpy.savez` or `numpy.savez_compressed`, into a signal.
+///
+/// The name is the keyword the array was saved with, or `arr_0`, `arr_1`, etc. for positional arrays.
+/// See [`read_npy`] for the supported arrays.
+///
+/// # Errors
+///
+/// - When the file can't be read or isn't a valid archive.
+/// - When the archive doesn't contain the array.
+/// - When the array isn't a supported `.npy` file.
+pub fn read_npz<P>(path: P, name: &str) -> Result<Array2<f64>>
+where
+    P: AsRef<Path>,
+{
+    let mut archive = ZipArchive::new(File::open(path)?)
+        .map_err(|error| Error::new(ErrorKind::InvalidData, error))?;
+
+    // Every array is stored as a `.npy` file in the archive
+    let mut file = archive
+        .by_name(&format!("{name}.npy"))
+        .map_err(|error| Error::new(ErrorKind::NotFound, error))?;
+    let mut bytes = Vec::new();
+    file.read_to_end(&mut bytes)?;
+
+    npy::decode(&bytes)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::Write as _;
+
+    use zip::{ZipWriter, write::SimpleFileOptions};
+
+    use super::*;
+
+    /// Encode a `.npy` file with the header padded to 64 bytes, like `numpy.save`.
+    fn encode(descr: &str, fortran_order: bool, shape: &str, data: &[u8]) -> Vec<u8> {
+        let fortran_order = if fortran_order { "True" } else { "False" };
+        let mut header =
+            format!("{{'descr': '{descr}', 'fortran_order': {fortran_order}, 'shape': {shape}, }}");
+        header.push_str(&" ".repeat(63 - (10 + header.len()) % 64));
+        header.push('\n');
+
+        let mut bytes = npy::MAGIC.to_vec();
+        bytes.extend([1, 0]);
+        bytes.extend(
+            u16::try_from(header.len())
+                .expect("Header too long")
+                .to_le_bytes(),
+        );
+        bytes.extend(header.as_bytes());
+        bytes.extend(data);
+
+        bytes
+    }
+
+    /// Arrays are read from `.npy` files and `.npz` archives.
+    #[test]
+    fn npy_and_npz() {
+        let doubles = [1.0_f64, 2.0, 3.0, 4.0, 5.0, 6.0]
+            .iter()
+            .flat_map(|value| value.to_le_bytes())
+            .collect::<Vec<_>>();
+        let integers = [1_i32, 2, 3]
+            .iter()
+            .flat_map(|value| value.to_le_bytes())
+            .collect::<Vec<_>>();
+        let expected = ndarray::array![[1.0, 2.0], [3.0, 4.0], [5.0, 6.0]];
+
+        let npy_path = std::env::temp_dir().join(format!("pelt-{}-signal.npy", std::process::id()));
+        std::fs::write(&npy_path, encode("<f8", false, "(3, 2)", &doubles))
+            .expect("Error writing file");
+        assert_eq!(read_npy(&npy_path).expect("Error reading file"), expected);
+
+        // Columns after each other
+        std::fs::write(&npy_path, encode("<f8", true, "(2, 3)", &doubles))
+            .expect("Error writing file");
+        assert_eq!(
+            read_npy(&npy_path).expect("Error reading file"),
+            expected.t()
+        );
+
+        // Compressed archive with two arrays
+        let npz_path =
+            std::env::temp_dir().join(format!("pelt-{}-signals.npz", std::process::id()));
+        let mut writer = ZipWriter::new(File::create(&npz_path).expect("Error creating file"));
+        for (name, bytes) in [
+            ("signal.npy", encode("<f8", false, "(3, 2)", &doubles)),
+            ("counts.npy", encode("<i4", false, "(3,)", &integers)),
+        ] {
+            writer
+                .start_file(name, SimpleFileOptions::default())
+                .expect("Error writing archive");
+            writer.write_all(&bytes).expect("Error writing archive");
+        }
+        writer.finish().expect("Error writing archive");
+
+        assert_eq!(
+            read_npz(&npz_path, "signal").expect("Error reading archive"),
+            expected
+        );
+        assert_eq!(
+            read_npz(&npz_path, "counts").expect("Error reading archive"),
+            ndarray::array![[1.0], [2.0], [3.0]]
+        );
+        assert!(
+            read_npz(&npz_path, "missing").is_err(),
+            "Missing array should be rejected"
+        );
+
+        for path in [npy_path, npz_path] {
+            std::fs::remove_file(path).expect("Error removing file");
+        }
+    }
+}
@@ -0,0 +1,166 @@
+//! Parquet files.
+
+use std::{
+    fs::File,
+    io::{Error, ErrorKind, Result},
+    path::Path,
+};
+
+use ndarray::Array2;
+use parquet::{
+    file::reader::{FileReader as _, SerializedFileReader},
+    record::Field,
+    schema::types::Type,
+};
+
+/// Read columns of a Parquet file into a signal, every row is a sample.
+///
+/// The columns are in the order of the names and only those columns are decoded.
+/// Integer and floating point columns are converted to [`f64`], null values become NaN.
+///
+/// # Errors
+///
+/// - When the file can't be opened or isn't a valid Parquet file.
+/// - When a column doesn't exist or isn't numeric.
+pub fn read_parquet<P>(path: P, columns: &[&str]) -> Result<Array2<f64>>
+where
+    P: AsRef<Path>,
+{
+    let reader = SerializedFileReader::new(File::open(path)?).map_err(invalid_data)?;
+
+    // Only decode the requested columns, in the requested order
+    let fields = reader.metadata().file_metadata().schema().get_fields();
+    let projected_fields = columns
+        .iter()
+        .map(|name| {
+            fields
+                .iter()
+                .find(|field| field.name() == *name)
+                .cloned()
+                .ok_or_else(|| {
+                    Error::new(
+                        ErrorKind::InvalidInput,
+                        format!("column `{name}` not found"),
+                    )
+                })
+        })
+        .collect::<Result<Vec<_>>>()?;
+    let projection = Type::group_type_builder("schema")
+        .with_fields(projected_fields)
+        .build()
+        .map_err(invalid_data)?;
+
+    let rows =
+        usize::try_from(reader.metadata().file_metadata().num_rows()).map_err(invalid_data)?;
+    let mut samples = Vec::with_capacity(rows * columns.len());
+    for row in reader
+        .get_row_iter(Some(projection))
+        .map_err(invalid_data)?
+    {
+        for (name, field) in row.map_err(invalid_data)?.get_column_iter() {
+            samples.push(field_value(name, field)?);
+        }
+    }
+
+    // Without the `std` feature of `ndarray` the shape error isn't an error type
+    Array2::from_shape_vec(
+        (samples.len() / columns.len().max(1), columns.len()),
+        samples,
+    )
+    .map_err(|error| invalid_data(error.to_string()))
+}
+
+/// Convert a numeric value to a sample.
+#[allow(
+    clippy::cast_precision_loss,
+    reason = "samples are always converted to floating point"
+)]
+fn field_value(name: &str, field: &Field) -> Result<f64> {
+    Ok(match *field {
+        Field::Null => f64::NAN,
+        Field::Byte(value) => value.into(),
+        Field::Short(value) => value.into(),
+        Field::Int(value) => value.into(),
+        Field::Long(value) => value as f64,
+        Field::UByte(value) => value.into(),
+        Field::UShort(value) => value.into(),
+        Field::UInt(value) => value.into(),
+        Field::ULong(value) => value as f64,
+        Field::Float16(value) => value.into(),
+        Field::Float(value) => value.into(),
+        Field::Double(value) => value,
+        _ => {
+            return Err(invalid_data(format!("column `{name}` is not numeric")));
+        }
+    })
+}
+
+/// Construct an invalid data error.
+fn invalid_data<E>(error: E) -> Error
+where
+    E: Into<Box<dyn std::error::Error + Send + Sync>>,
+{
+    Error::new(ErrorKind::InvalidData, error)
+}
+
+#[cfg(test)]
+mod tests {
+    use parquet::{
+        data_type::DoubleType, file::writer::SerializedFileWriter,
+        schema::parser::parse_message_type,
+    };
+
+    use std::sync::Arc;
+
+    use super::*;
+
+    /// Requested columns are read in order, with null values as NaN.
+    #[test]
+    fn parquet_columns() {
+        let path = std::env::temp_dir().join(format!("pelt-{}-signal.parquet", std::process::id()));
+
+        // Write a file with a required and an optional column
+        let schema = Arc::new(
+            parse_message_type("message schema { REQUIRED DOUBLE a; OPTIONAL DOUBLE b; }")
+                .expect("Invalid schema"),
+        );
+        let mut writer = SerializedFileWriter::new(
+            File::create(&path).expect("Error creating file"),
+            schema,
+            Arc::default(),
+        )
+        .expect("Error creating writer");
+        let mut row_group = writer.next_row_group().expect("Error creating row group");
+        let mut column = row_group
+            .next_column()
+            .expect("Error writing column")
+            .expect("Missing column");
+        column
+            .typed::<DoubleType>()
+            .write_batch(&[1.0, 2.0, 3.0], None, None)
+            .expect("Error writing column");
+        column.close().expect("Error writing column");
+        let mut column = row_group
+            .next_column()
+            .expect("Error writing column")
+            .expect("Missing column");
+        column
+            .typed::<DoubleType>()
+            .write_batch(&[4.0, 6.0], Some(&[1, 0, 1]), None)
+            .expect("Error writing column");
+        column.close().expect("Error writing column");
+        row_group.close().expect("Error writing row group");
+        writer.close().expect("Error writing file");
+
+        let signal = read_parquet(&path, &["b", "a"]).expect("Error reading file");
+        assert_eq!(signal.dim(), (3, 2));
+        assert_eq!(signal.column(1).to_vec(), vec![1.0, 2.0, 3.0]);
+        assert_eq!(signal[[0, 0]], 4.0);
+        assert!(signal[[1, 0]].is_nan(), "{signal}");
+        assert_eq!(signal[[2, 0]], 6.0);
+
+        assert!(read_parquet(&path, &["c"]).is_err(), "Missing column");
+
+        std::fs::remove_file(path).expect("Error removing file");
+    }
+}
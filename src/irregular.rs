@@ -0,0 +1,127 @@
+//! Segmentation of irregularly sampled signals.
+
+use ndarray::{ArrayView, ArrayView1, Dimension};
+
+use crate::{
+    Error, OneOrTwoDimensions, Penalty, Sample,
+    predict::{Elapsed, PredictImpl},
+};
+
+impl PredictImpl {
+    /// Segment with the deviations of each row weighted by the time until the next row, and segments lasting at least the minimum duration.
+    ///
+    /// The last row takes the interval before it.
+    pub(crate) fn predict_irregular<A, D, P>(
+        &mut self,
+        signal: &ArrayView<A, D>,
+        times: &[f64],
+        minimum_duration: f64,
+        penalty: &P,
+    ) -> Result<Vec<usize>, Error>
+    where
+        A: Sample,
+        D: OneOrTwoDimensions + Dimension,
+        P: Penalty,
+        D::PrecalculationOutput: Sync,
+    {
+        // One finite time per row, strictly increasing
+        let len = D::len_or_nrows(signal);
+        if times.len() != len
+            || !(minimum_duration.is_finite() && minimum_duration >= 0.0)
+            || times.iter().any(|time| !time.is_finite())
+            || times
+                .iter()
+                .zip(times.iter().skip(1))
+                .any(|(previous, next)| next <= previous)
+        {
+            return Err(Error::InvalidTimestamps);
+        }
+
+        // Time each row covers
+        let durations = times
+            .iter()
+            .zip(times.iter().skip(1))
+            .map(|(previous, next)| next - previous)
+            .collect::<Vec<_>>();
+        let last_duration = durations.last().copied().unwrap_or_default();
+        let durations = durations
+            .into_iter()
+            .chain(std::iter::once(last_duration))
+            .collect::<Vec<_>>();
+
+        // Time since the start at the start of every row and at the end
+        let elapsed_times = std::iter::once(0.0)
+            .chain(durations.iter().scan(0.0, |elapsed, duration| {
+                *elapsed += duration;
+
+                Some(*elapsed)
+            }))
+            .collect();
+
+        let cost = self.precalculate_weighted(signal, Some(&ArrayView1::from(&durations)))?;
+
+        self.elapsed = Some(Elapsed {
+            times: elapsed_times,
+            minimum_duration,
+        });
+        let result = self.predict_with_cost(signal, &cost, penalty);
+        self.elapsed = None;
+
+        result
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{Error, Pelt, SegmentCostFunction};
+
+    /// Segments last the minimum duration, regardless of the amount of samples.
+    #[test]
+    fn irregular() {
+        // Dense sampling in the first half
+        let times = (0..60)
+            .map(|index| {
+                if index < 40 {
+                    index as f64 * 0.25
+                } else {
+                    index as f64 - 30.0
+                }
+            })
+            .collect::<Vec<_>>();
+        let signal = times
+            .iter()
+            .enumerate()
+            .map(|(index, time)| {
+                // Short spike of a couple of dense samples
+                let spike = if (2.0..3.0).contains(time) { 10.0 } else { 0.0 };
+
+                spike + if index % 2 == 0 { 0.1 } else { -0.1 }
+            })
+            .collect::<Vec<_>>();
+
+        let pelt = Pelt::new()
+            .with_segment_cost_function(SegmentCostFunction::L2)
+            .with_jump(std::num::NonZero::new(1).expect("Invalid number"));
+        assert_eq!(
+            pelt.predict_irregular(&signal, 1.0, &times, 0.0)
+                .expect("Error predicting"),
+            vec![8, 12, 60]
+        );
+
+        // The spike is too short to be a segment on its own
+        assert_eq!(
+            pelt.predict_irregular(&signal, 1.0, &times, 2.0)
+                .expect("Error predicting"),
+            vec![8, 16, 60]
+        );
+
+        assert!(matches!(
+            pelt.predict_irregular(&signal, 1.0, &times[1..], 0.0),
+            Err(Error::InvalidTimestamps)
+        ));
+        assert!(matches!(
+            pelt.predict_irregular(&signal, 1.0, &times, -1.0),
+            Err(Error::InvalidTimestamps)
+        ));
+    }
+}
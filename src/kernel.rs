@@ -0,0 +1,290 @@
+//! Kernel changepoint detection.
+
+use std::num::NonZero;
+
+use ndarray::{ArrayView2, AsArray, Dimension};
+
+use crate::{Error, OneOrTwoDimensions, Penalty, nan, pairwise::PairwiseSums};
+
+/// Kernel to compare two points with.
+#[derive(Debug, Default, Clone, Copy, PartialEq, PartialOrd)]
+pub enum Kernel {
+    /// Dot product, detects changes in the mean.
+    #[default]
+    Linear,
+    /// Gaussian radial basis function `exp(-gamma * |x - y|^2)` with the `gamma` bandwidth parameter, detects changes in the distribution.
+    Rbf(f64),
+}
+
+impl Kernel {
+    /// Ensure the parameters of the kernel are valid.
+    fn validate(self) -> Result<(), Error> {
+        match self {
+            Self::Rbf(gamma) if !(gamma.is_finite() && gamma > 0.0) => {
+                Err(Error::InvalidKernelBandwidth)
+            }
+            _ => Ok(()),
+        }
+    }
+
+    /// Similarity between two rows.
+    fn similarity(self, left: &[f64], right: &[f64]) -> f64 {
+        match self {
+            Self::Linear => left
+                .iter()
+                .zip(right)
+                .map(|(left, right)| left * right)
+                .sum(),
+            Self::Rbf(gamma) => {
+                let distance_squared = left
+                    .iter()
+                    .zip(right)
+                    .map(|(left, right)| (left - right).powi(2))
+                    .sum::<f64>();
+
+                (-gamma * distance_squared).exp()
+            }
+        }
+    }
+}
+
+/// Kernel changepoint detection algorithm.
+///
+/// Maps the signal with a kernel and finds the changes in the mean of the mapped signal, with the PELT pruning rule.
+/// The Gram matrix of all pairs of points is precalculated once, so memory and time are quadratic in the length of the signal.
+/// Only suited for signals of a moderate size.
+///
+/// # Defaults
+///
+/// - `kernel`: [`Kernel::Linear`]
+/// - `jump`: `5`
+/// - `minimum_segment_length`: `2`
+#[derive(Debug, Clone)]
+pub struct KernelCpd {
+    /// Kernel to compare points with.
+    kernel: Kernel,
+    /// Subsample, one every `jump` points.
+    jump: usize,
+    /// Minimum allowable number of data points within a segment.
+    minimum_segment_length: usize,
+}
+
+impl KernelCpd {
+    /// Construct a new kernel changepoint detection instance with default values.
+    #[must_use]
+    pub const fn new() -> Self {
+        Self {
+            kernel: Kernel::Linear,
+            jump: 5,
+            minimum_segment_length: 2,
+        }
+    }
+
+    /// Set the kernel to compare points with.
+    #[must_use]
+    pub const fn with_kernel(mut self, kernel: Kernel) -> Self {
+        self.kernel = kernel;
+
+        self
+    }
+
+    /// Set the step size between the proposed changepoints.
+    #[must_use]
+    pub const fn with_jump(mut self, jump: NonZero<usize>) -> Self {
+        self.jump = jump.get();
+
+        self
+    }
+
+    /// Set the minimum allowable number of data points within a segment.
+    ///
+    /// Ensures that segments are not too small.
+    #[must_use]
+    pub const fn with_minimum_segment_length(
+        mut self,
+        minimum_segment_length: NonZero<usize>,
+    ) -> Self {
+        self.minimum_segment_length = minimum_segment_length.get();
+
+        self
+    }
+
+    /// Fit on a data set.
+    ///
+    /// # Errors
+    ///
+    /// - When the kernel parameters are invalid.
+    /// - When the signal contains a NaN or infinite value.
+    /// - When the signal is shorter than the minimum segment length.
+    pub fn predict<'a, D, P>(
+        &self,
+        signal: impl AsArray<'a, f64, D>,
+        penalty: P,
+    ) -> Result<Vec<usize>, Error>
+    where
+        D: OneOrTwoDimensions + Dimension,
+        P: Penalty,
+    {
+        let signal_view = signal.into();
+        D::check_dimensions(&signal_view)?;
+        nan::check_finite(&signal_view)?;
+
+        // Ensure the kernel parameters are correct
+        self.kernel.validate()?;
+
+        self.predict_impl(&D::as_2d(&signal_view)?, &penalty)
+    }
+
+    /// Run PELT on the kernel cost.
+    fn predict_impl<P>(&self, signal: &ArrayView2<f64>, penalty: &P) -> Result<Vec<usize>, Error>
+    where
+        P: Penalty,
+    {
+        let len = signal.nrows();
+        if len < self.minimum_segment_length {
+            return Err(Error::NotEnoughPoints);
+        }
+
+        let gram = GramMatrix::precalculate(self.kernel, signal);
+
+        // Optimal loss including penalties of the signal up to each index, and the start of its last segment
+        let mut losses = vec![f64::INFINITY; len + 1];
+        let mut starts = vec![0; len + 1];
+        losses[0] = 0.0;
+
+        // Proposed ends of segments, always including the end of the signal
+        let mut ends = (self.minimum_segment_length..len)
+            .filter(|index| index % self.jump == 0)
+            .collect::<Vec<_>>();
+        ends.push(len);
+
+        let mut admissible = vec![0];
+        for end in ends {
+            // Loss of every admissible start, without the penalty of the new segment
+            let candidates = admissible
+                .iter()
+                .filter(|start| end - **start >= self.minimum_segment_length)
+                .map(|start| (*start, losses[*start] + gram.loss(*start, end)))
+                .collect::<Vec<_>>();
+
+            let Some((optimal_start, optimal_loss)) = candidates
+                .iter()
+                .map(|(start, loss)| (*start, loss + penalty.segment_penalty(*start..end, len)))
+                .min_by(|(_, left), (_, right)| left.total_cmp(right))
+            else {
+                continue;
+            };
+            losses[end] = optimal_loss;
+            starts[end] = optimal_start;
+
+            // Starts that can never be optimal again are pruned, too short segments might be later
+            let threshold = optimal_loss + penalty.pruning_penalty(len);
+            admissible.retain(|start| {
+                end - start < self.minimum_segment_length
+                    || candidates
+                        .iter()
+                        .any(|(candidate, loss)| candidate == start && *loss <= threshold)
+            });
+            admissible.push(end);
+        }
+
+        if !losses[len].is_finite() {
+            return Err(Error::NoSegmentsFound);
+        }
+
+        // Walk back over the segments
+        let mut changepoints = Vec::new();
+        let mut end = len;
+        while end > 0 {
+            changepoints.push(end);
+
+            end = starts[end];
+        }
+        changepoints.reverse();
+
+        Ok(changepoints)
+    }
+}
+
+impl Default for KernelCpd {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Sum queries over the Gram matrix.
+struct GramMatrix {
+    /// Sums of the kernel of all pairs of points.
+    sums: PairwiseSums,
+    /// Sum of the kernel of each point with itself, before each index.
+    diagonal_sums: Vec<f64>,
+}
+
+impl GramMatrix {
+    /// Calculate the kernel of all pairs of points and sum them.
+    fn precalculate(kernel: Kernel, signal: &ArrayView2<f64>) -> Self {
+        // Contiguous rows so the kernel can work on slices
+        let rows = signal
+            .rows()
+            .into_iter()
+            .map(|row| row.to_vec())
+            .collect::<Vec<_>>();
+
+        let sums = PairwiseSums::precalculate(rows.len(), |row, column| {
+            kernel.similarity(&rows[row], &rows[column])
+        });
+
+        let mut diagonal_sums = Vec::with_capacity(rows.len() + 1);
+        diagonal_sums.push(0.0);
+        for row in &rows {
+            diagonal_sums
+                .push(diagonal_sums[diagonal_sums.len() - 1] + kernel.similarity(row, row));
+        }
+
+        Self {
+            sums,
+            diagonal_sums,
+        }
+    }
+
+    /// Kernel loss of a segment, the spread of the mapped points around their mean.
+    fn loss(&self, start: usize, end: usize) -> f64 {
+        self.diagonal_sums[end]
+            - self.diagonal_sums[start]
+            - self.sums.sum(start..end, start..end) / (end - start) as f64
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// The linear kernel loss is the L2 loss.
+    #[test]
+    fn linear_loss() {
+        let signal = ndarray::array![[1.0], [3.0], [10.0], [10.0]];
+        let gram = GramMatrix::precalculate(Kernel::Linear, &signal.view());
+
+        assert!((gram.loss(0, 2) - 2.0).abs() < 1e-9, "Invalid loss");
+        assert!(gram.loss(2, 4).abs() < 1e-9, "Invalid loss");
+    }
+
+    /// Find a change in variance that the mean doesn't show.
+    #[test]
+    fn rbf_variance() {
+        let signal =
+            ndarray::Array1::from_iter((0..100).map(|index| match (index < 50, index % 2) {
+                (true, _) => 0.0,
+                (false, 0) => 5.0,
+                (false, _) => -5.0,
+            }));
+
+        assert_eq!(
+            KernelCpd::new()
+                .with_kernel(Kernel::Rbf(0.5))
+                .predict(&signal, 1.0)
+                .expect("Error predicting"),
+            vec![50, 100]
+        );
+    }
+}
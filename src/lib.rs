@@ -1,22 +1,111 @@
 //! Changepoint detection with Pruned Exact Linear Time.
 
+pub(crate) mod adaptive;
+pub(crate) mod annotation;
+#[cfg(feature = "arrow")]
+pub(crate) mod arrow;
+pub(crate) mod bocpd;
+pub(crate) mod bottom_up;
+pub(crate) mod calibration;
+pub(crate) mod checkpoint;
+pub(crate) mod chunked;
+pub(crate) mod compare;
+pub(crate) mod convention;
 pub(crate) mod cost;
+pub(crate) mod crops;
+pub(crate) mod cusum;
+pub(crate) mod cv;
+#[cfg(feature = "chrono")]
+pub(crate) mod datetime;
 pub(crate) mod dim;
+pub(crate) mod dynp;
+pub(crate) mod edivisive;
 pub(crate) mod error;
+pub(crate) mod fpop;
+pub(crate) mod gaps;
+#[cfg(any(feature = "csv", feature = "ipc", feature = "npy", feature = "parquet"))]
+pub mod io;
+pub(crate) mod irregular;
+pub(crate) mod kernel;
+pub(crate) mod mask;
+#[cfg(feature = "mmap")]
+pub(crate) mod mmap;
+pub(crate) mod multiscale;
+pub(crate) mod nan;
+#[cfg(feature = "node")]
+mod node;
+#[cfg(any(feature = "mmap", feature = "npy"))]
+pub(crate) mod npy;
+pub(crate) mod online;
+pub(crate) mod pairwise;
+pub(crate) mod path;
+pub(crate) mod penalty;
 pub(crate) mod predict;
+pub(crate) mod predictor;
 #[cfg(feature = "python")]
 mod python;
+pub(crate) mod ranking;
+pub(crate) mod realtime;
+pub(crate) mod refine;
+pub(crate) mod segmentation;
+#[cfg(feature = "serde")]
+pub(crate) mod serialization;
+pub(crate) mod significance;
+#[cfg(feature = "csv")]
+pub(crate) mod source;
+pub(crate) mod stability;
+#[cfg(feature = "async")]
+pub(crate) mod stream;
+#[cfg(feature = "wasm")]
+mod wasm;
+pub(crate) mod wbs;
+pub(crate) mod weights;
+pub(crate) mod window;
 
-use std::num::NonZero;
+#[cfg(feature = "rayon")]
+use std::sync::Arc;
+use std::{mem, num::NonZero, ops::Range};
 
-pub use cost::SegmentCostFunction;
+pub use annotation::AnnotationSuggestion;
+pub use bocpd::{Bocpd, NormalGamma};
+pub use bottom_up::BottomUp;
+pub use calibration::{Calibration, f1_score};
+pub use checkpoint::Checkpoint;
+pub use compare::ModelComparison;
+pub use convention::ChangepointConvention;
+pub use cost::{SegmentCostFunction, accumulator::Accumulator};
+pub use crops::PenaltySegmentation;
+pub use cusum::Cusum;
 // Exposed for benchmarks
 #[doc(hidden)]
 pub use cost::l2::{L2Cost1D, L2Cost2D};
-pub use dim::OneOrTwoDimensions;
+#[cfg(feature = "chrono")]
+pub use datetime::TimestampedChangepoint;
+pub use dim::{OneOrTwoDimensions, Sample};
+pub use edivisive::EDivisive;
 pub use error::Error;
-use ndarray::{AsArray, Dimension};
+pub use fpop::Fpop;
+pub use kernel::{Kernel, KernelCpd};
+#[cfg(feature = "mmap")]
+pub use mmap::MmapSignal;
+pub use multiscale::Multiscale;
+pub use nan::NanPolicy;
+use ndarray::{Array, ArrayBase, ArrayView2, AsArray, Axis, Data, Dimension, Ix2, RemoveAxis};
+pub use online::OnlinePelt;
+pub use path::PenaltyPath;
+pub use penalty::{InformationCriterion, Penalty, SegmentLengthPenalty, WeightedPenalty};
 use predict::PredictImpl;
+pub use predictor::PeltPredictor;
+pub use realtime::RealtimeDetector;
+pub use segmentation::{GoodnessOfFit, Segment, Segmentation, segment_views, segments};
+pub use significance::SignificanceTest;
+#[cfg(feature = "csv")]
+pub use source::CsvSource;
+pub use stability::{JumpConsensus, Subsampling};
+#[cfg(feature = "async")]
+pub use stream::ChangepointStream;
+pub use wbs::Wbs;
+pub use window::Window;
 
 /// PELT algorithm.
 ///
@@ -25,15 +114,126 @@ use predict::PredictImpl;
 /// - `segment_cost_function`: [`SegmentCostFunction::L1`]
 /// - `jump`: `5`
 /// - `minimum_segment_length`: `2`
+/// - `max_changepoints`: unlimited
+/// - `loss_cache_capacity`: unlimited
+/// - `accumulator`: [`Accumulator::Naive`]
+/// - `time_axis`: [`Axis(0)`](Axis), every row is a sample
+/// - `nan_policy`: [`NanPolicy::Error`]
+/// - `threading_threshold`: measured for the cost function on the first use, where the time of the losses exceeds the overhead of the threads
+/// - `thread_pool`: the global [`rayon`] thread pool
 /// - `keep_initial_zero`: `false`
+/// - `keep_signal_length`: `true`
+/// - `changepoint_convention`: [`ChangepointConvention::Exclusive`]
+/// - `forbidden_ranges`: none
+/// - `candidates`: every `jump` samples
+/// - `column_weights`: every column counts equally
+/// - `column_cost_functions`: the `segment_cost_function` for every column
 #[derive(Debug, Clone)]
+#[cfg_attr(
+    feature = "serde",
+    derive(serde::Serialize, serde::Deserialize),
+    serde(default, deny_unknown_fields)
+)]
 pub struct Pelt {
     /// Segment model.
     segment_cost_function: SegmentCostFunction,
     /// Subsample, one every `jump` points.
+    #[cfg_attr(feature = "serde", serde(with = "serialization::non_zero"))]
     jump: usize,
     /// Minimum allowable number of data points within a segment.
+    #[cfg_attr(feature = "serde", serde(with = "serialization::non_zero"))]
     minimum_segment_length: usize,
+    /// Maximum allowable number of changepoints, unlimited if `None`.
+    max_changepoints: Option<usize>,
+    /// Maximum number of segment losses shared between runs, unlimited if `None`.
+    loss_cache_capacity: Option<usize>,
+    /// Summation algorithm for the running sums of the cost function.
+    accumulator: Accumulator,
+    /// Axis of two dimensional signals the samples are along.
+    #[cfg_attr(feature = "serde", serde(with = "serialization::axis"))]
+    time_axis: Axis,
+    /// What to do with NaN values in the signal.
+    nan_policy: NanPolicy,
+    /// Minimum amount of admissible indices to spread over threads, depends on the cost function if `None`.
+    #[cfg(feature = "rayon")]
+    threading_threshold: Option<usize>,
+    /// Thread pool to run in, the global one if `None`.
+    #[cfg(feature = "rayon")]
+    #[cfg_attr(feature = "serde", serde(skip))]
+    thread_pool: Option<SharedThreadPool>,
+    /// Whether the predicted changepoints start with `0`.
+    keep_initial_zero: bool,
+    /// Whether the predicted changepoints end with the length of the signal.
+    keep_signal_length: bool,
+    /// Which sample the index of a predicted changepoint points to.
+    changepoint_convention: ChangepointConvention,
+    /// Ranges no changepoint may be strictly inside of.
+    forbidden_ranges: Vec<Range<usize>>,
+    /// Sorted indices changepoints may be at, every `jump` samples if `None`.
+    candidates: Option<Vec<usize>>,
+    /// Factor of the loss of every column of the signal, the same for all columns if `None`.
+    column_weights: Option<Vec<f64>>,
+    /// Cost function of every column of the signal, the segment cost function for all columns if `None`.
+    column_cost_functions: Option<Vec<SegmentCostFunction>>,
+}
+
+/// Thread pool shared between clones of [`Pelt`].
+///
+/// [`rayon::ThreadPool`] isn't [`RefUnwindSafe`](std::panic::RefUnwindSafe), this keeps [`Pelt`] unwind safe.
+/// A panic in the pool can't leave it in a broken state, the panic is propagated to the caller of `install`.
+#[cfg(feature = "rayon")]
+#[derive(Debug, Clone)]
+struct SharedThreadPool(Arc<rayon::ThreadPool>);
+
+#[cfg(feature = "rayon")]
+impl std::panic::UnwindSafe for SharedThreadPool {}
+
+#[cfg(feature = "rayon")]
+impl std::panic::RefUnwindSafe for SharedThreadPool {}
+
+#[cfg(feature = "rayon")]
+impl std::ops::Deref for SharedThreadPool {
+    type Target = rayon::ThreadPool;
+
+    fn deref(&self) -> &Self::Target {
+        &self.0
+    }
+}
+
+/// Validate the signal and the cost function, then run `$body` on the signal lowered to 1D if possible, since that's faster.
+///
+/// A closure can't be generic over the dimension of the signal, so this is a macro.
+macro_rules! with_signal {
+    // Check for NaN and transpose to time-major
+    ($pelt:expr, $signal:ident, |$predict:ident, $view:ident| $body:expr) => {{
+        let signal_view = $pelt.time_major($signal.into())?;
+        with_signal!($pelt, view signal_view, |$predict, $view| $body)
+    }};
+    // Only transpose to time-major, for methods handling NaN themselves
+    ($pelt:expr, layout $signal:ident, |$predict:ident, $view:ident| $body:expr) => {{
+        let signal_view = $pelt.layout($signal.into())?;
+        with_signal!($pelt, view signal_view, |$predict, $view| $body)
+    }};
+    // Signal that's already time-major
+    ($pelt:expr, view $signal_view:ident, |$predict:ident, $view:ident| $body:expr) => {{
+        // Ensure the cost function parameters are correct
+        $pelt.segment_cost_function.validate()?;
+
+        match D::try_as_1d(&$signal_view) {
+            // Run as 1D array
+            Some(signal_1d) => {
+                let $predict = &mut PredictImpl::new($pelt.clone());
+                let $view = &signal_1d;
+                $body
+            }
+            // Run as 2D array
+            None => {
+                let $predict = &mut PredictImpl::new($pelt.clone());
+                let $view = &$signal_view;
+                $body
+            }
+        }
+    }};
 }
 
 impl Pelt {
@@ -44,6 +244,22 @@ impl Pelt {
             segment_cost_function: SegmentCostFunction::L1,
             jump: 5,
             minimum_segment_length: 2,
+            max_changepoints: None,
+            loss_cache_capacity: None,
+            accumulator: Accumulator::Naive,
+            time_axis: Axis(0),
+            nan_policy: NanPolicy::Error,
+            #[cfg(feature = "rayon")]
+            threading_threshold: None,
+            #[cfg(feature = "rayon")]
+            thread_pool: None,
+            keep_initial_zero: false,
+            keep_signal_length: true,
+            changepoint_convention: ChangepointConvention::Exclusive,
+            forbidden_ranges: Vec::new(),
+            candidates: None,
+            column_weights: None,
+            column_cost_functions: None,
         }
     }
 
@@ -81,30 +297,1437 @@ impl Pelt {
         self
     }
 
+    /// Set the maximum allowable number of changepoints.
+    ///
+    /// Protects against a penalty that's too low, predicting with a penalty results in [`Error::TooManyChangepoints`] when the optimal segmentation has more changepoints.
+    #[must_use]
+    pub const fn with_max_changepoints(mut self, max_changepoints: usize) -> Self {
+        self.max_changepoints = Some(max_changepoints);
+
+        self
+    }
+
+    /// Set the maximum number of segment losses shared between runs.
+    ///
+    /// Methods that run the algorithm multiple times on the same signal, such as [`Pelt::predict_many`], [`Pelt::crops`] and [`Pelt::penalty_path`], cache the loss of every segment so it's only calculated once.
+    /// Each cached loss takes roughly 32 bytes, once the capacity is reached new losses are calculated every time instead of cached.
+    #[must_use]
+    pub const fn with_loss_cache_capacity(mut self, loss_cache_capacity: NonZero<usize>) -> Self {
+        self.loss_cache_capacity = Some(loss_cache_capacity.get());
+
+        self
+    }
+
+    /// Set the summation algorithm for the running sums the segment losses are calculated from.
+    ///
+    /// The compensated algorithms are a bit slower to precalculate, but keep the losses of short segments accurate at the end of very long signals.
+    #[must_use]
+    pub const fn with_accumulator(mut self, accumulator: Accumulator) -> Self {
+        self.accumulator = accumulator;
+
+        self
+    }
+
+    /// Set the axis of two dimensional signals the samples are along.
+    ///
+    /// With [`Axis(1)`](Axis) a signal laid out as channels × time is segmented without transposing it first, every column is a sample.
+    /// One dimensional signals are always along time.
+    /// Predicting a two dimensional signal results in [`Error::InvalidDimensions`] when the axis isn't the first or second.
+    #[must_use]
+    pub const fn with_time_axis(mut self, time_axis: Axis) -> Self {
+        self.time_axis = time_axis;
+
+        self
+    }
+
+    /// Set what to do with NaN values in the signal.
+    ///
+    /// By default a NaN results in [`Error::InvalidInput`] with the first row containing one.
+    #[must_use]
+    pub const fn with_nan_policy(mut self, nan_policy: NanPolicy) -> Self {
+        self.nan_policy = nan_policy;
+
+        self
+    }
+
+    /// Set the minimum amount of admissible indices of a breakpoint before their losses are calculated on multiple threads.
+    ///
+    /// Lower it on machines with many cores, raise it when threads are expensive.
+    #[cfg(feature = "rayon")]
+    #[must_use]
+    pub const fn with_threading_threshold(mut self, threading_threshold: NonZero<usize>) -> Self {
+        self.threading_threshold = Some(threading_threshold.get());
+
+        self
+    }
+
+    /// Set the thread pool all calculations run in, instead of the global one.
+    ///
+    /// Allows limiting the amount of threads, for example in a server handling multiple requests.
+    #[cfg(feature = "rayon")]
+    #[must_use]
+    pub fn with_thread_pool(mut self, thread_pool: Arc<rayon::ThreadPool>) -> Self {
+        self.thread_pool = Some(SharedThreadPool(thread_pool));
+
+        self
+    }
+
+    /// Set whether the predicted changepoints start with `0`, the start of the first segment.
+    #[must_use]
+    pub const fn with_keep_initial_zero(mut self, keep_initial_zero: bool) -> Self {
+        self.keep_initial_zero = keep_initial_zero;
+
+        self
+    }
+
+    /// Set whether the predicted changepoints end with the length of the signal, the end of the last segment.
+    #[must_use]
+    pub const fn with_keep_signal_length(mut self, keep_signal_length: bool) -> Self {
+        self.keep_signal_length = keep_signal_length;
+
+        self
+    }
+
+    /// Set which sample the index of a predicted changepoint points to.
+    ///
+    /// Applies to every method segmenting the signal, such as [`Pelt::predict`], [`Pelt::predict_with_gaps`] and [`Pelt::crops`].
+    /// Methods taking changepoints, such as [`Pelt::score`] and [`Pelt::refine`], expect and return the default convention.
+    /// See [`ChangepointConvention`] for the options matching other libraries.
+    #[must_use]
+    pub const fn with_changepoint_convention(
+        mut self,
+        changepoint_convention: ChangepointConvention,
+    ) -> Self {
+        self.changepoint_convention = changepoint_convention;
+
+        self
+    }
+
+    /// Set the ranges of samples no changepoint may be placed inside of, such as sensor recalibration periods.
+    ///
+    /// A changepoint is forbidden when it's strictly inside a range, the start and end of a range are still allowed, so a range can be a segment of its own.
+    /// The ranges may overlap and don't need to be sorted.
+    #[must_use]
+    pub fn with_forbidden_ranges(mut self, forbidden_ranges: &[Range<usize>]) -> Self {
+        self.forbidden_ranges = forbidden_ranges.to_vec();
+
+        self
+    }
+
+    /// Set the indices changepoints may be at, instead of every `jump` samples.
+    ///
+    /// Allows a non-uniform grid, such as one aligned to the times of known events.
+    /// The candidates don't need to be sorted, duplicates and candidates too close to the start or beyond the end of the signal are ignored.
+    /// The `jump` is ignored, except by [`OnlinePelt`] which doesn't support candidates.
+    #[must_use]
+    pub fn with_candidates(mut self, candidates: &[usize]) -> Self {
+        // The start of the signal is never a changepoint
+        let mut candidates = candidates
+            .iter()
+            .copied()
+            .filter(|candidate| *candidate > 0)
+            .collect::<Vec<_>>();
+        candidates.sort_unstable();
+        candidates.dedup();
+        self.candidates = Some(candidates);
+
+        self
+    }
+
+    /// Set a weight for every column of a two dimensional signal, the loss of each column is multiplied by its weight.
+    ///
+    /// Lets some channels dominate the segmentation, such as the cone resistance over the sleeve friction of a CPT.
+    /// The weights must be finite, non-negative and one per column, a one dimensional signal is a single column.
+    /// The penalty is in the units of the weighted loss.
+    #[must_use]
+    pub fn with_column_weights(mut self, column_weights: &[f64]) -> Self {
+        self.column_weights = Some(column_weights.to_vec());
+
+        self
+    }
+
+    /// Set a segment cost function for every column of a two dimensional signal, the losses of the columns are added.
+    ///
+    /// Allows a cost function fitting every channel, such as [`SegmentCostFunction::L2`] for smooth channels and [`SegmentCostFunction::L1`] for spiky ones.
+    /// There must be one per column, a one dimensional signal is a single column.
+    /// Only the loss of the segments uses them, the statistics of the segments and the information criteria use the segment cost function.
+    #[must_use]
+    pub fn with_column_cost_functions(
+        mut self,
+        column_cost_functions: &[SegmentCostFunction],
+    ) -> Self {
+        self.column_cost_functions = Some(column_cost_functions.to_vec());
+
+        self
+    }
+
+    /// Ensure there's a valid column weight and cost function for every column of the signal.
+    #[inline]
+    pub(crate) fn check_columns(&self, columns: usize) -> Result<(), Error> {
+        if let Some(column_weights) = &self.column_weights
+            && (column_weights.len() != columns
+                || column_weights
+                    .iter()
+                    .any(|weight| !weight.is_finite() || *weight < 0.0))
+        {
+            return Err(Error::InvalidColumnWeights);
+        }
+
+        if let Some(column_cost_functions) = &self.column_cost_functions {
+            if column_cost_functions.len() != columns {
+                return Err(Error::ColumnMismatch);
+            }
+            for column_cost_function in column_cost_functions {
+                column_cost_function.validate()?;
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Whether a changepoint is strictly inside one of the forbidden ranges.
+    #[inline]
+    pub(crate) fn is_forbidden(&self, changepoint: usize) -> bool {
+        self.forbidden_ranges
+            .iter()
+            .any(|range| range.start < changepoint && changepoint < range.end)
+    }
+
+    /// Only the candidates that are also configured candidates, all of them without configured candidates.
+    pub(crate) fn with_candidates_within(&self, candidates: &[usize]) -> Self {
+        let candidates = self.candidates.as_ref().map_or_else(
+            || candidates.to_vec(),
+            |configured| {
+                candidates
+                    .iter()
+                    .copied()
+                    .filter(|candidate| configured.binary_search(candidate).is_ok())
+                    .collect()
+            },
+        );
+
+        self.clone().with_candidates(&candidates)
+    }
+
+    /// Candidates and forbidden ranges for a subset of the rows of the signal, such as every other row.
+    ///
+    /// The map gives the amount of rows of the subset before a row of the full signal, so it must be non-decreasing.
+    /// A changepoint of the subset is forbidden when the row of the full signal it starts at is.
+    pub(crate) fn reindexed(&self, map: impl Fn(usize) -> usize) -> Self {
+        let mut pelt = self.clone();
+        pelt.forbidden_ranges = self
+            .forbidden_ranges
+            .iter()
+            .map(|range| map(range.start + 1).saturating_sub(1)..map(range.end))
+            .collect();
+        if let Some(candidates) = &self.candidates {
+            pelt = pelt.with_candidates(
+                &candidates
+                    .iter()
+                    .map(|candidate| map(*candidate))
+                    .collect::<Vec<_>>(),
+            );
+        }
+
+        pelt
+    }
+
+    /// Convert changepoints in the default convention to the configured one.
+    #[inline]
+    pub(crate) fn convert_changepoints(&self, changepoints: Vec<usize>) -> Vec<usize> {
+        convention::apply(
+            changepoints,
+            self.changepoint_convention,
+            self.keep_initial_zero,
+            self.keep_signal_length,
+        )
+    }
+
+    /// Convert the changepoints of every segmentation in the default convention to the configured one.
+    pub(crate) fn convert_segmentations(
+        &self,
+        mut segmentations: Vec<PenaltySegmentation>,
+    ) -> Vec<PenaltySegmentation> {
+        for segmentation in &mut segmentations {
+            segmentation.changepoints =
+                self.convert_changepoints(mem::take(&mut segmentation.changepoints));
+        }
+
+        segmentations
+    }
+
+    /// Check the dimensions and NaN values of the signal, and put the time along the first axis.
+    #[inline]
+    pub(crate) fn time_major<S, D>(&self, signal: ArrayBase<S, D>) -> Result<ArrayBase<S, D>, Error>
+    where
+        S: Data,
+        S::Elem: Sample,
+        D: OneOrTwoDimensions,
+    {
+        let signal = self.layout(signal)?;
+        nan::check(self.nan_policy, &signal.view(), 0)?;
+
+        Ok(signal)
+    }
+
+    /// Check the dimensions of the signal and put the time along the first axis, without checking for NaN values.
+    #[inline]
+    pub(crate) fn layout<S, D>(&self, signal: ArrayBase<S, D>) -> Result<ArrayBase<S, D>, Error>
+    where
+        S: Data,
+        D: OneOrTwoDimensions,
+    {
+        D::check_dimensions(&signal.view())?;
+
+        let signal = D::time_major(signal, self.time_axis)?;
+        self.check_columns(D::as_2d(&signal.view())?.ncols())?;
+
+        Ok(signal)
+    }
+
+    /// Whether to spread the iterations over threads, with the configured threshold or the heuristic of the cost function.
+    #[cfg(feature = "rayon")]
+    #[inline]
+    pub(crate) fn should_use_threading(&self, iterations: usize) -> bool {
+        self.threading_threshold.map_or_else(
+            || self.segment_cost_function.should_use_threading(iterations),
+            |threading_threshold| iterations >= threading_threshold,
+        )
+    }
+
+    /// Run the operation in the configured thread pool, or in the global one when there is none.
+    #[cfg(feature = "rayon")]
+    #[inline]
+    pub(crate) fn install<R, F>(thread_pool: Option<&rayon::ThreadPool>, operation: F) -> R
+    where
+        F: FnOnce() -> R + Send,
+        R: Send,
+    {
+        match thread_pool {
+            Some(thread_pool) => thread_pool.install(operation),
+            None => operation(),
+        }
+    }
+
     /// Fit on a data set.
     ///
+    /// The penalty can be a constant [`f64`] or anything implementing [`Penalty`].
+    /// The changepoints are sorted and end with the length of the signal, unless configured otherwise with [`Pelt::with_changepoint_convention`].
+    ///
     /// # Errors
     ///
     /// - When the input is invalid.
     /// - When anything went wrong during calculation.
-    pub fn predict<'a, D>(
+    pub fn predict<'a, A, D, P>(
         &self,
-        signal: impl AsArray<'a, f64, D>,
-        penalty: f64,
+        signal: impl AsArray<'a, A, D>,
+        penalty: P,
     ) -> Result<Vec<usize>, Error>
     where
+        A: Sample + 'a,
         D: OneOrTwoDimensions + Dimension,
+        P: Penalty,
         D::PrecalculationOutput: Sync,
     {
-        let signal_view = signal.into();
+        self.predict_changepoints(signal, penalty)
+            .map(|changepoints| self.convert_changepoints(changepoints))
+    }
 
-        // Try to lower 2D to 1D to parse as 1D array, since that's faster
-        D::try_as_1d(&signal_view).map_or_else(
-            // Predict as 2D array
-            || PredictImpl::new(self.clone()).predict(&signal_view, penalty),
-            // Predict as 1D array
-            |signal_1d| PredictImpl::new(self.clone()).predict(&signal_1d, penalty),
-        )
+    /// Fit on a data set, with the changepoints in the default convention.
+    pub(crate) fn predict_changepoints<'a, A, D, P>(
+        &self,
+        signal: impl AsArray<'a, A, D>,
+        penalty: P,
+    ) -> Result<Vec<usize>, Error>
+    where
+        A: Sample + 'a,
+        D: OneOrTwoDimensions + Dimension,
+        P: Penalty,
+        D::PrecalculationOutput: Sync,
+    {
+        with_signal!(self, signal, |predict, signal| {
+            predict.predict(signal, &penalty)
+        })
+    }
+
+    /// Fit on a data set with a coarse `jump` that's refined where the signal changes.
+    ///
+    /// Changepoints are considered every `jump` samples, and on every sample within a `jump` of the grid points where splitting the surrounding window gains at least half the penalty.
+    /// Gives nearly the locations of a `jump` of one at close to the cost of the coarse grid, since only the regions around changes are refined.
+    /// With configured candidates only the adaptive candidates that are also configured are considered.
+    ///
+    /// # Errors
+    ///
+    /// - When the input is invalid.
+    /// - When anything went wrong during calculation.
+    pub fn predict_adaptive<'a, A, D, P>(
+        &self,
+        signal: impl AsArray<'a, A, D>,
+        penalty: P,
+    ) -> Result<Vec<usize>, Error>
+    where
+        A: Sample + 'a,
+        D: OneOrTwoDimensions + Dimension,
+        P: Penalty,
+        D::PrecalculationOutput: Sync,
+    {
+        let changepoints = with_signal!(self, signal, |predict, signal| {
+            predict.predict_adaptive(signal, &penalty)
+        })?;
+
+        Ok(self.convert_changepoints(changepoints))
+    }
+
+    /// Fit on a data set in two stages, first on a decimated signal and then at full resolution around its changepoints.
+    ///
+    /// Meant for very long signals, where a `jump` of one is too slow but the changepoints must be exact.
+    /// The `jump` is ignored and with configured candidates only the refined candidates that are also configured are considered, see [`Multiscale`].
+    ///
+    /// # Errors
+    ///
+    /// - When the input is invalid.
+    /// - When anything went wrong during calculation.
+    pub fn predict_multiscale<'a, A, D, P>(
+        &self,
+        signal: impl AsArray<'a, A, D>,
+        penalty: P,
+        multiscale: &Multiscale,
+    ) -> Result<Vec<usize>, Error>
+    where
+        A: Sample + 'a,
+        D: OneOrTwoDimensions + Dimension,
+        P: Penalty,
+        D::PrecalculationOutput: Sync,
+    {
+        let changepoints = with_signal!(self, signal, |predict, signal| {
+            predict.predict_multiscale(signal, &penalty, multiscale)
+        })?;
+
+        Ok(self.convert_changepoints(changepoints))
+    }
+
+    /// Fit on a data set and calculate the statistics of every segment.
+    ///
+    /// Like [`Pelt::predict`], but the result also contains the optimal objective, the segments with their loss and statistics, and the configuration used.
+    ///
+    /// # Errors
+    ///
+    /// - When the input is invalid.
+    /// - When anything went wrong during calculation.
+    pub fn segment<'a, A, D, P>(
+        &self,
+        signal: impl AsArray<'a, A, D>,
+        penalty: P,
+    ) -> Result<Segmentation, Error>
+    where
+        A: Sample + 'a,
+        D: OneOrTwoDimensions + Dimension,
+        P: Penalty,
+        D::PrecalculationOutput: Sync,
+    {
+        with_signal!(self, signal, |predict, signal| {
+            predict.segment(signal, &penalty)
+        })
+    }
+
+    /// Calculate the statistics of the segments between changepoints, such as from an earlier [`Pelt::predict`].
+    ///
+    /// The changepoints must include the end of the signal, the same as the result of [`Pelt::predict`].
+    /// The loss of every segment is calculated with the configured segment cost function.
+    ///
+    /// # Errors
+    ///
+    /// - When the changepoints aren't increasing or don't end at the end of the signal.
+    /// - When the input is invalid.
+    pub fn segment_statistics<'a, A, D>(
+        &self,
+        signal: impl AsArray<'a, A, D>,
+        changepoints: &[usize],
+    ) -> Result<Vec<Segment>, Error>
+    where
+        A: Sample + 'a,
+        D: OneOrTwoDimensions + Dimension,
+    {
+        with_signal!(self, signal, |predict, signal| {
+            predict.segment_statistics(signal, changepoints)
+        })
+    }
+
+    /// Calculate the total penalized cost of a segmentation, such as a hand-made one.
+    ///
+    /// The changepoints must include the end of the signal, the same as the result of [`Pelt::predict`].
+    /// The cost is the loss of every segment with the configured segment cost function plus its penalty, the same objective [`Pelt::predict`] minimizes.
+    /// Comparing it with [`Segmentation::objective`] shows how far the segmentation is from the optimum.
+    ///
+    /// # Errors
+    ///
+    /// - When the changepoints aren't increasing or don't end at the end of the signal.
+    /// - When the input is invalid.
+    pub fn score<'a, A, D, P>(
+        &self,
+        signal: impl AsArray<'a, A, D>,
+        changepoints: &[usize],
+        penalty: P,
+    ) -> Result<f64, Error>
+    where
+        A: Sample + 'a,
+        D: OneOrTwoDimensions + Dimension,
+        P: Penalty,
+    {
+        with_signal!(self, signal, |predict, signal| {
+            predict.score(signal, changepoints, &penalty)
+        })
+    }
+
+    /// Calculate the p-value of every changepoint with a permutation test of the segments before and after it.
+    ///
+    /// The changepoints must include the end of the signal, the same as the result of [`Pelt::predict`], which has no p-value.
+    /// See [`SignificanceTest`] for how the p-values are calculated.
+    ///
+    /// # Errors
+    ///
+    /// - When the changepoints aren't increasing or don't end at the end of the signal.
+    /// - When the input is invalid.
+    pub fn p_values<'a, A, D>(
+        &self,
+        signal: impl AsArray<'a, A, D>,
+        changepoints: &[usize],
+        test: &SignificanceTest,
+    ) -> Result<Vec<f64>, Error>
+    where
+        A: Sample + 'a,
+        D: OneOrTwoDimensions + Dimension + RemoveAxis,
+    {
+        with_signal!(self, signal, |predict, signal| {
+            predict.p_values(signal, changepoints, test)
+        })
+    }
+
+    /// Fit on many random subsamples of the signal and calculate how often a changepoint is found near every index.
+    ///
+    /// The result has a value between `0.0` and `1.0` for every sample: the fraction of the runs with a changepoint within the tolerance of the [`Subsampling`].
+    /// Plotted along the signal it shows which changepoints are stable and which depend on individual samples.
+    ///
+    /// # Errors
+    ///
+    /// - When the fraction of the subsamples isn't larger than `0.0` and at most `1.0`.
+    /// - When the input is invalid.
+    /// - When anything went wrong during calculation.
+    pub fn changepoint_frequency<'a, A, D, P>(
+        &self,
+        signal: impl AsArray<'a, A, D>,
+        penalty: P,
+        subsampling: &Subsampling,
+    ) -> Result<Vec<f64>, Error>
+    where
+        A: Sample + 'a,
+        D: OneOrTwoDimensions + Dimension + RemoveAxis,
+        P: Penalty,
+        D::PrecalculationOutput: Sync,
+    {
+        // Ensure the parameters are correct
+        subsampling.validate()?;
+
+        with_signal!(self, signal, |predict, signal| {
+            predict.changepoint_frequency(signal, &penalty, subsampling)
+        })
+    }
+
+    /// Fit with several `jump` values and keep the changepoints most runs agree on.
+    ///
+    /// Every changepoint comes with the fraction of the runs agreeing on it, the end of the signal isn't included.
+    /// See [`JumpConsensus`] for how the runs are combined.
+    ///
+    /// # Errors
+    ///
+    /// - When the agreement isn't larger than `0.0` and at most `1.0`.
+    /// - When the input is invalid.
+    /// - When anything went wrong during calculation.
+    pub fn predict_consensus<'a, A, D, P>(
+        &self,
+        signal: impl AsArray<'a, A, D>,
+        penalty: P,
+        consensus: &JumpConsensus,
+    ) -> Result<Vec<(usize, f64)>, Error>
+    where
+        A: Sample + 'a,
+        D: OneOrTwoDimensions + Dimension,
+        P: Penalty,
+        D::PrecalculationOutput: Sync,
+    {
+        // Ensure the parameters are correct
+        consensus.validate()?;
+
+        with_signal!(self, signal, |predict, signal| {
+            predict.predict_consensus(signal, &penalty, consensus)
+        })
+    }
+
+    /// Rank the changepoints by their contribution to the objective, strongest first.
+    ///
+    /// The changepoints must include the end of the signal, the same as the result of [`Pelt::predict`], which isn't ranked.
+    /// The weakest changepoint is removed until none are left, every changepoint comes with its gain when it was removed: the increase in loss of merging the segments before and after it.
+    /// Removing the changepoints in this order approximates raising the penalty, without running the algorithm again.
+    ///
+    /// # Errors
+    ///
+    /// - When the changepoints aren't increasing or don't end at the end of the signal.
+    /// - When the input is invalid.
+    pub fn rank_changepoints<'a, A, D>(
+        &self,
+        signal: impl AsArray<'a, A, D>,
+        changepoints: &[usize],
+    ) -> Result<Vec<(usize, f64)>, Error>
+    where
+        A: Sample + 'a,
+        D: OneOrTwoDimensions + Dimension,
+    {
+        with_signal!(self, signal, |predict, signal| {
+            predict.rank_changepoints(signal, changepoints)
+        })
+    }
+
+    /// Move every changepoint to the position within `radius` samples with the lowest loss, as with a `jump` of one.
+    ///
+    /// Gives exact locations for the result of a fast run with a large `jump`, without solving the whole signal at full resolution.
+    /// The changepoints must include the end of the signal, the same as the result of [`Pelt::predict`], which doesn't move.
+    /// Every changepoint is refined with the segments around it fixed, so the amount of changepoints stays the same.
+    ///
+    /// # Errors
+    ///
+    /// - When the changepoints aren't increasing or don't end at the end of the signal.
+    /// - When the input is invalid.
+    pub fn refine<'a, A, D>(
+        &self,
+        signal: impl AsArray<'a, A, D>,
+        changepoints: &[usize],
+        radius: usize,
+    ) -> Result<Vec<usize>, Error>
+    where
+        A: Sample + 'a,
+        D: OneOrTwoDimensions + Dimension,
+    {
+        with_signal!(self, signal, |predict, signal| {
+            predict.refine(signal, changepoints, radius)
+        })
+    }
+
+    /// Keep only the strongest changepoints, see [`Pelt::rank_changepoints`].
+    ///
+    /// The result is sorted and ends with the end of the signal, the same as the changepoints.
+    ///
+    /// # Errors
+    ///
+    /// - When the changepoints aren't increasing or don't end at the end of the signal.
+    /// - When the input is invalid.
+    pub fn strongest_changepoints<'a, A, D>(
+        &self,
+        signal: impl AsArray<'a, A, D>,
+        changepoints: &[usize],
+        count: usize,
+    ) -> Result<Vec<usize>, Error>
+    where
+        A: Sample + 'a,
+        D: OneOrTwoDimensions + Dimension,
+    {
+        let mut strongest = self
+            .rank_changepoints(signal, changepoints)?
+            .into_iter()
+            .take(count)
+            .map(|(changepoint, _)| changepoint)
+            .collect::<Vec<_>>();
+        strongest.sort_unstable();
+        strongest.extend(changepoints.last());
+
+        Ok(strongest)
+    }
+
+    /// Merge the segments around every changepoint contributing less than `minimum_gain` to the objective, see [`Pelt::rank_changepoints`].
+    ///
+    /// The weakest changepoint is removed as long as its gain is below the minimum, and the gains of its neighbours are updated after every removal.
+    /// Gives a second way to control over-segmentation besides the penalty, in the units of the loss and independent of the amount of segments.
+    /// The result is sorted and ends with the end of the signal, the same as the changepoints.
+    ///
+    /// # Errors
+    ///
+    /// - When the changepoints aren't increasing or don't end at the end of the signal.
+    /// - When the input is invalid.
+    pub fn merge_weak_changepoints<'a, A, D>(
+        &self,
+        signal: impl AsArray<'a, A, D>,
+        changepoints: &[usize],
+        minimum_gain: f64,
+    ) -> Result<Vec<usize>, Error>
+    where
+        A: Sample + 'a,
+        D: OneOrTwoDimensions + Dimension,
+    {
+        // The ranking is the reverse of the order of removal
+        let mut ranking = self.rank_changepoints(signal, changepoints)?;
+        while ranking.pop_if(|(_, gain)| *gain < minimum_gain).is_some() {}
+
+        let mut merged = ranking
+            .into_iter()
+            .map(|(changepoint, _)| changepoint)
+            .collect::<Vec<_>>();
+        merged.sort_unstable();
+        merged.extend(changepoints.last());
+
+        Ok(merged)
+    }
+
+    /// Suggest the candidate changepoints whose label would help the most, such as to guide checking the results by hand.
+    ///
+    /// The candidates are found with a quarter of the penalty and ranked with [`Pelt::rank_changepoints`].
+    /// A candidate with a gain close to the penalty is uncertain: a slightly different penalty would add or remove it, so its label says the most about the right penalty.
+    /// Returns at most `count` suggestions, the most uncertain first.
+    ///
+    /// # Errors
+    ///
+    /// - When the input is invalid.
+    /// - When anything went wrong during calculation.
+    pub fn suggest_annotations<'a, A, D>(
+        &self,
+        signal: impl AsArray<'a, A, D>,
+        penalty: f64,
+        count: usize,
+    ) -> Result<Vec<AnnotationSuggestion>, Error>
+    where
+        A: Sample + 'a,
+        D: OneOrTwoDimensions + Dimension,
+        D::PrecalculationOutput: Sync,
+    {
+        with_signal!(self, signal, |predict, signal| {
+            predict.suggest_annotations(signal, penalty, count)
+        })
+    }
+
+    /// Fit on a data set and remove the changepoints that aren't significant.
+    ///
+    /// Changepoints are removed one at a time, starting with the largest p-value above the significance of the [`SignificanceTest`].
+    /// A low penalty finds many candidates, of which the test keeps the real ones, instead of tuning the penalty by hand.
+    ///
+    /// # Errors
+    ///
+    /// - When the input is invalid.
+    /// - When anything went wrong during calculation.
+    pub fn predict_significant<'a, A, D, P>(
+        &self,
+        signal: impl AsArray<'a, A, D>,
+        penalty: P,
+        test: &SignificanceTest,
+    ) -> Result<Vec<usize>, Error>
+    where
+        A: Sample + 'a,
+        D: OneOrTwoDimensions + Dimension + RemoveAxis,
+        P: Penalty,
+        D::PrecalculationOutput: Sync,
+    {
+        let changepoints = with_signal!(self, signal, |predict, signal| {
+            let changepoints = predict.predict(signal, &penalty)?;
+            predict.prune_insignificant(signal, changepoints, test)
+        })?;
+
+        Ok(self.convert_changepoints(changepoints))
+    }
+
+    /// Fit on the columns of an Arrow record batch, every row is a sample.
+    ///
+    /// The columns are used without copying them into a single array, only columns containing null values are copied to replace them by NaN.
+    /// The null values are handled by the [`NanPolicy`].
+    /// The time axis is ignored, since the columns are always along time.
+    ///
+    /// # Errors
+    ///
+    /// - When a column isn't a [`arrow_array::Float64Array`].
+    /// - When the input is invalid.
+    /// - When anything went wrong during calculation.
+    #[cfg(feature = "arrow")]
+    pub fn predict_record_batch<P>(
+        &self,
+        batch: &arrow_array::RecordBatch,
+        penalty: P,
+    ) -> Result<Vec<usize>, Error>
+    where
+        P: Penalty,
+    {
+        self.predict_arrow(&arrow::record_batch_columns(batch)?, penalty)
+    }
+
+    /// Fit on Arrow columns of the same length, every row is a sample.
+    ///
+    /// See [`Pelt::predict_record_batch`].
+    ///
+    /// # Errors
+    ///
+    /// - When the columns don't have the same length.
+    /// - When the input is invalid.
+    /// - When anything went wrong during calculation.
+    #[cfg(feature = "arrow")]
+    pub fn predict_arrow<P>(
+        &self,
+        columns: &[&arrow_array::Float64Array],
+        penalty: P,
+    ) -> Result<Vec<usize>, Error>
+    where
+        P: Penalty,
+    {
+        // Ensure the cost function parameters are correct
+        self.segment_cost_function.validate()?;
+
+        PredictImpl::new(self.clone())
+            .predict_arrow(columns, &penalty)
+            .map(|changepoints| self.convert_changepoints(changepoints))
+    }
+
+    /// Fit on a single channel signal from a plain slice, without depending on [`ndarray`].
+    ///
+    /// The same as [`Pelt::predict`] with a one dimensional array.
+    ///
+    /// # Errors
+    ///
+    /// - When the input is invalid.
+    /// - When anything went wrong during calculation.
+    pub fn predict_slice<A, P>(&self, signal: &[A], penalty: P) -> Result<Vec<usize>, Error>
+    where
+        A: Sample,
+        P: Penalty,
+    {
+        self.predict(signal, penalty)
+    }
+
+    /// Fit on a multi channel signal from a plain slice with the channels of every sample next to each other, without depending on [`ndarray`].
+    ///
+    /// The slice contains the first sample of every channel, then the second sample of every channel, and so on.
+    /// The same as [`Pelt::predict`] with a two dimensional array of `n_channels` columns.
+    ///
+    /// # Errors
+    ///
+    /// - When the length of the slice isn't a multiple of the amount of channels.
+    /// - When the input is invalid.
+    /// - When anything went wrong during calculation.
+    pub fn predict_interleaved<A, P>(
+        &self,
+        signal: &[A],
+        n_channels: NonZero<usize>,
+        penalty: P,
+    ) -> Result<Vec<usize>, Error>
+    where
+        A: Sample,
+        P: Penalty,
+    {
+        // The last sample can't miss channels
+        if !signal.len().is_multiple_of(n_channels.get()) {
+            return Err(Error::ColumnMismatch);
+        }
+
+        let signal =
+            ArrayView2::from_shape((signal.len() / n_channels.get(), n_channels.get()), signal)
+                .map_err(|_| Error::ColumnMismatch)?;
+
+        // Swapped to the layout of the time axis, `predict` puts the time first again
+        self.predict(Ix2::time_major(signal, self.time_axis)?, penalty)
+    }
+
+    /// Fit on many independent signals, spread over threads.
+    ///
+    /// Each signal is segmented on its own, the same as [`Pelt::predict`], so one failing signal doesn't affect the others.
+    /// Every thread reuses its buffers for the signals it segments.
+    pub fn predict_batch<A, P>(
+        &self,
+        signals: &[ArrayView2<A>],
+        penalty: P,
+    ) -> Vec<Result<Vec<usize>, Error>>
+    where
+        A: Sample,
+        P: Penalty,
+    {
+        // Segment a single signal with the buffers of the thread
+        let predict_one = |predict: &mut PredictImpl, signal: &ArrayView2<A>| {
+            let signal = self.time_major(signal.view())?;
+            // Ensure the cost function parameters are correct
+            self.segment_cost_function.validate()?;
+
+            // Try to lower 2D to 1D to parse as 1D array, since that's faster
+            match Ix2::try_as_1d(&signal) {
+                Some(signal_1d) => predict.predict(&signal_1d, &penalty),
+                None => predict.predict(&signal, &penalty),
+            }
+            .map(|changepoints| self.convert_changepoints(changepoints))
+        };
+
+        #[cfg(feature = "rayon")]
+        return Self::install(self.thread_pool.as_deref(), || {
+            use rayon::iter::{IntoParallelRefIterator as _, ParallelIterator as _};
+
+            signals
+                .par_iter()
+                .map_init(|| PredictImpl::new(self.clone()), predict_one)
+                .collect()
+        });
+
+        #[cfg(not(feature = "rayon"))]
+        {
+            let mut predict = PredictImpl::new(self.clone());
+
+            signals
+                .iter()
+                .map(|signal| predict_one(&mut predict, signal))
+                .collect()
+        }
+    }
+
+    /// Fit on a signal that's too big for memory, by processing it in chunks.
+    ///
+    /// Each chunk is segmented together with the last `overlap` points of the previous window, so changepoints near the chunk boundaries are still found.
+    /// Changepoints in the first half of an overlap are taken from the earlier window, in the second half from the later window.
+    /// Only one window is in memory at a time, so the chunks can be read lazily from disk.
+    /// A penalty that depends on the length of the signal uses the length of the window.
+    ///
+    /// # Errors
+    ///
+    /// - When the input is invalid.
+    /// - When there are no chunks.
+    /// - When the chunks have a different amount of columns.
+    /// - When anything went wrong during calculation.
+    pub fn predict_chunked<A, D, P, I>(
+        &self,
+        chunks: I,
+        penalty: P,
+        overlap: usize,
+    ) -> Result<Vec<usize>, Error>
+    where
+        A: Sample,
+        D: OneOrTwoDimensions + Dimension + RemoveAxis,
+        P: Penalty,
+        D::PrecalculationOutput: Sync,
+        I: IntoIterator<Item = Array<A, D>>,
+    {
+        // Ensure the cost function parameters are correct
+        self.segment_cost_function.validate()?;
+
+        let changepoints =
+            PredictImpl::new(self.clone()).predict_chunked(chunks, &penalty, overlap)?;
+
+        Ok(self.convert_changepoints(changepoints))
+    }
+
+    /// Fit on a data set with known gaps, index ranges without valid data.
+    ///
+    /// The boundaries of every gap are changepoints without a penalty, and no segment crosses a gap, so the values inside the gaps are never used.
+    /// Each gap is returned as its own segment, an empty gap only forces a changepoint.
+    /// The parts between the gaps are segmented separately, a part shorter than the minimum segment length is a single segment.
+    /// A penalty that depends on the length of the signal uses the length of the part.
+    ///
+    /// # Errors
+    ///
+    /// - When the gaps are unsorted, overlapping or outside of the signal.
+    /// - When the input is invalid.
+    /// - When anything went wrong during calculation.
+    pub fn predict_with_gaps<'a, A, D, P>(
+        &self,
+        signal: impl AsArray<'a, A, D>,
+        penalty: P,
+        gaps: &[Range<usize>],
+    ) -> Result<Vec<usize>, Error>
+    where
+        A: Sample + 'a,
+        D: OneOrTwoDimensions + Dimension,
+        P: Penalty,
+        D::PrecalculationOutput: Sync,
+    {
+        let changepoints = with_signal!(self, layout signal, |predict, signal| {
+            predict.predict_with_gaps(signal, &penalty, gaps)
+        })?;
+
+        Ok(self.convert_changepoints(changepoints))
+    }
+
+    /// Fit on a data set with known changepoints, such as maintenance events.
+    ///
+    /// The known changepoints are always in the result and no segment crosses them, only the parts between them are optimized.
+    /// The parts are segmented separately, the same as the parts between the gaps of [`Pelt::predict_with_gaps`].
+    ///
+    /// # Errors
+    ///
+    /// - When the known changepoints aren't increasing, zero or beyond the end of the signal.
+    /// - When the input is invalid.
+    /// - When anything went wrong during calculation.
+    pub fn predict_with_forced<'a, A, D, P>(
+        &self,
+        signal: impl AsArray<'a, A, D>,
+        penalty: P,
+        forced: &[usize],
+    ) -> Result<Vec<usize>, Error>
+    where
+        A: Sample + 'a,
+        D: OneOrTwoDimensions + Dimension,
+        P: Penalty,
+        D::PrecalculationOutput: Sync,
+    {
+        let signal_view = self.layout(signal.into())?;
+        let len = D::len_or_nrows(&signal_view);
+
+        // Known changepoints must be increasing and inside the signal
+        let mut previous = 0;
+        for (position, &changepoint) in forced.iter().enumerate() {
+            if changepoint == 0 || changepoint > len {
+                return Err(Error::ChangepointOutOfBounds {
+                    changepoint,
+                    signal_len: len,
+                });
+            }
+            if changepoint <= previous {
+                return Err(Error::UnsortedChangepoints { position });
+            }
+
+            previous = changepoint;
+        }
+
+        // A known changepoint is an empty gap
+        let gaps = forced
+            .iter()
+            .map(|changepoint| *changepoint..*changepoint)
+            .collect::<Vec<_>>();
+
+        let changepoints = with_signal!(self, view signal_view, |predict, signal| {
+            predict.predict_with_gaps(signal, &penalty, &gaps)
+        })?;
+
+        Ok(self.convert_changepoints(changepoints))
+    }
+
+    /// Fit on a data set with a mask of the valid rows.
+    ///
+    /// The segment losses only use the valid rows and the minimum segment length only counts those, so the masked rows don't need to be imputed.
+    /// Each changepoint is at the first valid row of a segment, the masked rows before it belong to the previous segment.
+    /// A penalty that depends on the length of the signal uses the amount of valid rows.
+    ///
+    /// # Errors
+    ///
+    /// - When the mask doesn't have one value per row.
+    /// - When the input is invalid.
+    /// - When anything went wrong during calculation.
+    pub fn predict_masked<'a, A, D, P>(
+        &self,
+        signal: impl AsArray<'a, A, D>,
+        mask: &[bool],
+        penalty: P,
+    ) -> Result<Vec<usize>, Error>
+    where
+        A: Sample + 'a,
+        D: OneOrTwoDimensions + Dimension + RemoveAxis,
+        P: Penalty,
+        D::PrecalculationOutput: Sync,
+    {
+        // Masked rows are checked for NaN values after leaving them out
+        let signal_view = self.layout(signal.into())?;
+
+        // Ensure the cost function parameters are correct
+        self.segment_cost_function.validate()?;
+
+        let changepoints =
+            PredictImpl::new(self.clone()).predict_masked(&signal_view, mask, &penalty)?;
+
+        Ok(self.convert_changepoints(changepoints))
+    }
+
+    /// Fit on a data set with a non-negative weight for every row.
+    ///
+    /// The deviation of each row in the segment losses is multiplied by its weight, so known low-quality rows can be down-weighted.
+    /// Segments are fitted around the weighted median for [`SegmentCostFunction::L1`], the weighted mean for [`SegmentCostFunction::L2`] and the weighted quantile for [`SegmentCostFunction::Quantile`].
+    /// Rows with a zero weight still count for the minimum segment length.
+    ///
+    /// # Errors
+    ///
+    /// - When the weights aren't finite, non-negative and one per row.
+    /// - When the input is invalid.
+    /// - When anything went wrong during calculation.
+    pub fn predict_weighted<'a, A, D, P>(
+        &self,
+        signal: impl AsArray<'a, A, D>,
+        weights: &[f64],
+        penalty: P,
+    ) -> Result<Vec<usize>, Error>
+    where
+        A: Sample + 'a,
+        D: OneOrTwoDimensions + Dimension,
+        P: Penalty,
+        D::PrecalculationOutput: Sync,
+    {
+        let changepoints = with_signal!(self, signal, |predict, signal| {
+            predict.predict_weighted(signal, weights, &penalty)
+        })?;
+
+        Ok(self.convert_changepoints(changepoints))
+    }
+
+    /// Fit on an irregularly sampled data set with the sampling time of every row.
+    ///
+    /// The deviation of each row in the segment losses is weighted by the time until the next row, the last row takes the interval before it.
+    /// Segments must last at least `minimum_duration` in elapsed time, on top of the minimum segment length in rows.
+    /// See [`Pelt::predict_weighted`] for how the weights change the cost functions.
+    ///
+    /// # Errors
+    ///
+    /// - When the times aren't finite, strictly increasing and one per row.
+    /// - When the minimum duration isn't finite and non-negative.
+    /// - When the input is invalid.
+    /// - When anything went wrong during calculation.
+    pub fn predict_irregular<'a, A, D, P>(
+        &self,
+        signal: impl AsArray<'a, A, D>,
+        penalty: P,
+        times: &[f64],
+        minimum_duration: f64,
+    ) -> Result<Vec<usize>, Error>
+    where
+        A: Sample + 'a,
+        D: OneOrTwoDimensions + Dimension,
+        P: Penalty,
+        D::PrecalculationOutput: Sync,
+    {
+        let changepoints = with_signal!(self, signal, |predict, signal| {
+            predict.predict_irregular(signal, times, minimum_duration, &penalty)
+        })?;
+
+        Ok(self.convert_changepoints(changepoints))
+    }
+
+    /// Fit on a data set with the timestamp of every row, and return the changepoints with their timestamps.
+    ///
+    /// Every changepoint is the first row of a new segment, so the end of the signal isn't included.
+    ///
+    /// # Errors
+    ///
+    /// - When there isn't one timestamp per row.
+    /// - When the input is invalid.
+    /// - When anything went wrong during calculation.
+    #[cfg(feature = "chrono")]
+    pub fn predict_with_datetimes<'a, A, D, P>(
+        &self,
+        signal: impl AsArray<'a, A, D>,
+        penalty: P,
+        timestamps: &[chrono::DateTime<chrono::Utc>],
+    ) -> Result<Vec<TimestampedChangepoint>, Error>
+    where
+        A: Sample + 'a,
+        D: OneOrTwoDimensions + Dimension,
+        P: Penalty,
+        D::PrecalculationOutput: Sync,
+    {
+        let signal_view = signal.into();
+        datetime::check_len(
+            timestamps,
+            D::len_or_nrows(&self.layout(signal_view.view())?),
+        )?;
+
+        let changepoints = self.predict_changepoints(signal_view, penalty)?;
+
+        Ok(datetime::timestamped(changepoints, timestamps))
+    }
+
+    /// Fit on a data set with the sampling time of every row.
+    ///
+    /// When consecutive timestamps are more than `max_interval` apart, the missing samples between them are a gap: there's always a changepoint at the first sample after the gap and no segment crosses it.
+    /// See [`Pelt::predict_with_gaps`] for how the parts between gaps are segmented.
+    ///
+    /// # Errors
+    ///
+    /// - When the timestamps aren't finite, strictly increasing and one per row.
+    /// - When the maximum interval isn't positive.
+    /// - When the input is invalid.
+    /// - When anything went wrong during calculation.
+    pub fn predict_with_timestamps<'a, A, D, P>(
+        &self,
+        signal: impl AsArray<'a, A, D>,
+        penalty: P,
+        timestamps: &[f64],
+        max_interval: f64,
+    ) -> Result<Vec<usize>, Error>
+    where
+        A: Sample + 'a,
+        D: OneOrTwoDimensions + Dimension,
+        P: Penalty,
+        D::PrecalculationOutput: Sync,
+    {
+        let signal_view = signal.into();
+        let len = D::len_or_nrows(&self.layout(signal_view.view())?);
+
+        // Find the gaps in time
+        let gaps = PredictImpl::timestamp_gaps(timestamps, len, max_interval)?;
+
+        // Still in the original layout, `predict_with_gaps` puts the time first itself
+        self.predict_with_gaps(signal_view, penalty, &gaps)
+    }
+
+    /// Fit on a data set, periodically capturing the state so the calculation can be resumed later.
+    ///
+    /// `on_checkpoint` is called every `interval` solved breakpoints with a [`Checkpoint`], which can be written to disk.
+    /// Passing a checkpoint as `resume` continues from its state instead of starting over, the signal, penalty and settings must be the same as when it was created.
+    /// Useful for long exact runs with a jump of `1` on huge signals.
+    ///
+    /// # Errors
+    ///
+    /// - When the input is invalid.
+    /// - When the checkpoint doesn't match the signal or the settings.
+    /// - When anything went wrong during calculation.
+    pub fn predict_checkpointed<'a, A, D, P, F>(
+        &self,
+        signal: impl AsArray<'a, A, D>,
+        penalty: P,
+        resume: Option<Checkpoint>,
+        interval: NonZero<usize>,
+        mut on_checkpoint: F,
+    ) -> Result<Vec<usize>, Error>
+    where
+        A: Sample + 'a,
+        D: OneOrTwoDimensions + Dimension,
+        P: Penalty,
+        D::PrecalculationOutput: Sync,
+        F: FnMut(&Checkpoint),
+    {
+        let changepoints = with_signal!(self, signal, |predict, signal| {
+            predict.predict_checkpointed(
+                signal,
+                &penalty,
+                resume,
+                interval.get(),
+                &mut on_checkpoint,
+            )
+        })?;
+
+        Ok(self.convert_changepoints(changepoints))
+    }
+
+    /// Fit on a data set for each penalty in a list, returning the changepoints in the same order.
+    ///
+    /// The cost function is only precalculated once and segment losses are shared between all runs.
+    /// This is much faster than calling [`Pelt::predict`] for each penalty, at the cost of memory for storing the losses.
+    ///
+    /// # Errors
+    ///
+    /// - When the input is invalid.
+    /// - When anything went wrong during calculation.
+    pub fn predict_many<'a, A, D, P>(
+        &self,
+        signal: impl AsArray<'a, A, D>,
+        penalties: &[P],
+    ) -> Result<Vec<Vec<usize>>, Error>
+    where
+        A: Sample + 'a,
+        D: OneOrTwoDimensions + Dimension,
+        P: Penalty,
+        D::PrecalculationOutput: Sync,
+    {
+        let predictions = with_signal!(self, signal, |predict, signal| {
+            predict.predict_many(signal, penalties)
+        })?;
+
+        Ok(predictions
+            .into_iter()
+            .map(|changepoints| self.convert_changepoints(changepoints))
+            .collect())
+    }
+
+    /// Select the penalty, and optionally the minimum segment length, with the best F1 score on signals with known changepoints.
+    ///
+    /// Every signal is segmented with every candidate and compared to its true changepoints with [`f1_score`], the candidate with the highest mean score is returned.
+    /// The true changepoints must include the end of the signal, the same as the result of [`Pelt::predict`].
+    /// Without minimum segment lengths only the configured one is used.
+    ///
+    /// # Errors
+    ///
+    /// - When there are no signals or no penalties.
+    /// - When the input is invalid.
+    /// - When anything went wrong during calculation.
+    pub fn calibrate_penalty<A>(
+        &self,
+        labeled: &[(ArrayView2<A>, &[usize])],
+        penalties: &[f64],
+        minimum_segment_lengths: &[NonZero<usize>],
+        margin: usize,
+    ) -> Result<Calibration, Error>
+    where
+        A: Sample,
+    {
+        calibration::calibrate(self, labeled, penalties, minimum_segment_lengths, margin)
+    }
+
+    /// Select the best penalty from the candidates with cross-validation.
+    ///
+    /// The signal is split in the even and odd points, each half is segmented with half the penalty and scored by the loss of the other half.
+    /// The penalty with the lowest combined loss is returned.
+    ///
+    /// # Errors
+    ///
+    /// - When there are no penalties.
+    /// - When a penalty is negative or not finite.
+    /// - When the input is invalid.
+    /// - When anything went wrong during calculation.
+    pub fn select_penalty_cv<'a, A, D>(
+        &self,
+        signal: impl AsArray<'a, A, D>,
+        penalties: &[f64],
+    ) -> Result<f64, Error>
+    where
+        A: Sample + 'a,
+        D: OneOrTwoDimensions + Dimension,
+        D::PrecalculationOutput: Sync,
+    {
+        with_signal!(self, signal, |predict, signal| {
+            predict.select_penalty_cv(signal, penalties)
+        })
+    }
+
+    /// Fit on a data set with each segment cost function and score the segmentations.
+    ///
+    /// Each segmentation uses the penalty from the information criterion for that segment cost function.
+    /// The results are in the same order as the segment cost functions, the one with the lowest [`ModelComparison::bic`] fits best.
+    ///
+    /// # Errors
+    ///
+    /// - When the input is invalid.
+    /// - When anything went wrong during calculation.
+    pub fn compare_models<'a, A, D>(
+        &self,
+        signal: impl AsArray<'a, A, D>,
+        segment_cost_functions: &[SegmentCostFunction],
+        information_criterion: InformationCriterion,
+    ) -> Result<Vec<ModelComparison>, Error>
+    where
+        A: Sample + 'a,
+        D: OneOrTwoDimensions + Dimension,
+        D::PrecalculationOutput: Sync,
+    {
+        let signal_view = self.time_major(signal.into())?;
+
+        segment_cost_functions
+            .iter()
+            .map(|segment_cost_function| {
+                let pelt = self
+                    .clone()
+                    .with_segment_cost_function(*segment_cost_function);
+
+                with_signal!(pelt, view signal_view, |predict, signal| {
+                    predict.compare_model(signal, information_criterion)
+                })
+            })
+            .collect()
+    }
+
+    /// Fit on a data set with exactly `n_changepoints` changepoints, without a penalty.
+    ///
+    /// Uses dynamic programming instead of PELT, which can't prune candidates, so it's much slower for long signals.
+    /// Increasing the jump reduces the calculation time quadratically.
+    ///
+    /// # Errors
+    ///
+    /// - When the signal is too short for the amount of changepoints.
+    /// - When the input is invalid.
+    pub fn predict_n_changepoints<'a, A, D>(
+        &self,
+        signal: impl AsArray<'a, A, D>,
+        n_changepoints: usize,
+    ) -> Result<Vec<usize>, Error>
+    where
+        A: Sample + 'a,
+        D: OneOrTwoDimensions + Dimension,
+    {
+        let changepoints = with_signal!(self, signal, |predict, signal| {
+            predict.predict_n_changepoints(signal, n_changepoints)
+        })?;
+
+        Ok(self.convert_changepoints(changepoints))
+    }
+
+    /// Fit on a data set with a penalty derived from an information criterion.
+    ///
+    /// The penalty is calculated from the length of the signal and the noise estimated by the segment model.
+    ///
+    /// # Errors
+    ///
+    /// - When the input is invalid.
+    /// - When anything went wrong during calculation.
+    pub fn predict_auto_penalty<'a, A, D>(
+        &self,
+        signal: impl AsArray<'a, A, D>,
+        information_criterion: InformationCriterion,
+    ) -> Result<Vec<usize>, Error>
+    where
+        A: Sample + 'a,
+        D: OneOrTwoDimensions + Dimension,
+        D::PrecalculationOutput: Sync,
+    {
+        let signal_view = signal.into();
+
+        // Ensure the cost function parameters are correct
+        self.segment_cost_function.validate()?;
+
+        // Derive the penalty from the signal
+        let penalty = information_criterion.penalty(
+            self.segment_cost_function,
+            &self.time_major(signal_view.view())?,
+        )?;
+
+        // Still in the original layout, `predict` puts the time first itself
+        self.predict(signal_view, penalty)
+    }
+
+    /// Fit on a data set for all penalties in a range, using the CROPS algorithm.
+    ///
+    /// Returns every distinct optimal segmentation between `penalty_min` and `penalty_max`, ordered by increasing penalty.
+    /// The cost function is only precalculated once and segment losses are shared between all runs.
+    ///
+    /// # Errors
+    ///
+    /// - When the penalty range is invalid.
+    /// - When the input is invalid.
+    /// - When anything went wrong during calculation.
+    pub fn crops<'a, A, D>(
+        &self,
+        signal: impl AsArray<'a, A, D>,
+        penalty_min: f64,
+        penalty_max: f64,
+    ) -> Result<Vec<PenaltySegmentation>, Error>
+    where
+        A: Sample + 'a,
+        D: OneOrTwoDimensions + Dimension,
+        D::PrecalculationOutput: Sync,
+    {
+        let segmentations = with_signal!(self, signal, |predict, signal| {
+            predict.crops(signal, penalty_min, penalty_max)
+        })?;
+
+        Ok(self.convert_segmentations(segmentations))
+    }
+
+    /// Fit on a data set for each penalty in a list.
+    ///
+    /// Use [`PenaltyPath::elbow`] to get a suggestion for the penalty.
+    /// The cost function is only precalculated once and segment losses are shared between all runs.
+    ///
+    /// # Errors
+    ///
+    /// - When the input is invalid.
+    /// - When anything went wrong during calculation.
+    pub fn penalty_path<'a, A, D>(
+        &self,
+        signal: impl AsArray<'a, A, D>,
+        penalties: &[f64],
+    ) -> Result<PenaltyPath, Error>
+    where
+        A: Sample + 'a,
+        D: OneOrTwoDimensions + Dimension,
+        D::PrecalculationOutput: Sync,
+    {
+        let path = with_signal!(self, signal, |predict, signal| {
+            predict.penalty_path(signal, penalties)
+        })?;
+
+        Ok(PenaltyPath {
+            segmentations: self.convert_segmentations(path.segmentations),
+        })
     }
 }
 
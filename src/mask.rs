@@ -0,0 +1,93 @@
+//! Segmentation of signals with masked samples.
+
+use ndarray::{ArrayView, Axis, Dimension, RemoveAxis};
+
+use crate::{Error, NanPolicy, OneOrTwoDimensions, Penalty, Sample, nan, predict::PredictImpl};
+
+impl PredictImpl {
+    /// Segment only the valid samples, and map the changepoints back to the rows of the signal.
+    ///
+    /// A changepoint is at the first valid sample of a segment, the masked rows before it belong to the previous segment.
+    pub(crate) fn predict_masked<A, D, P>(
+        &mut self,
+        signal: &ArrayView<A, D>,
+        mask: &[bool],
+        penalty: &P,
+    ) -> Result<Vec<usize>, Error>
+    where
+        A: Sample,
+        D: OneOrTwoDimensions + Dimension + RemoveAxis,
+        P: Penalty,
+        D::PrecalculationOutput: Sync,
+    {
+        // Length as the rows
+        let len = D::len_or_nrows(signal);
+        if mask.len() != len {
+            return Err(Error::InvalidMask);
+        }
+
+        let valid_rows = mask
+            .iter()
+            .enumerate()
+            .filter_map(|(row, valid)| valid.then_some(row))
+            .collect::<Vec<_>>();
+
+        // Only the valid rows are copied, so the cost functions and the minimum segment length only see those
+        let valid_signal = signal.select(Axis(0), &valid_rows);
+        let valid_view = valid_signal.view();
+
+        // Masked rows can contain anything
+        if self.pelt.nan_policy == NanPolicy::Error
+            && let Some(row) = nan::first_nan_row(&valid_view)?
+        {
+            return Err(Error::InvalidInput {
+                index: valid_rows[row],
+            });
+        }
+
+        // Try to lower 2D to 1D to parse as 1D array, since that's faster
+        let changepoints = match D::try_as_1d(&valid_view) {
+            Some(valid_1d) => self.predict(&valid_1d, penalty),
+            None => self.predict(&valid_view, penalty),
+        }?;
+
+        // The end of the valid samples is the end of the signal
+        Ok(changepoints
+            .into_iter()
+            .map(|changepoint| valid_rows.get(changepoint).copied().unwrap_or(len))
+            .collect())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{Error, Pelt, SegmentCostFunction};
+
+    /// Masked rows aren't used and the changepoints are rows of the whole signal.
+    #[test]
+    fn masked() {
+        let signal = (0..100)
+            .map(|index| match index {
+                // Spikes that are masked
+                40..45 => 100.0,
+                0..50 => 0.0,
+                _ => 5.0,
+            } + if index % 2 == 0 { 0.1 } else { -0.1 })
+            .collect::<Vec<_>>();
+        let mask = (0..100)
+            .map(|index| !(40..45).contains(&index))
+            .collect::<Vec<_>>();
+
+        let pelt = Pelt::new().with_segment_cost_function(SegmentCostFunction::L2);
+        assert_eq!(
+            pelt.predict_masked(&signal, &mask, 1.0)
+                .expect("Error predicting"),
+            vec![50, 100]
+        );
+
+        assert!(matches!(
+            pelt.predict_masked(&signal, &mask[1..], 1.0),
+            Err(Error::InvalidMask)
+        ));
+    }
+}
@@ -0,0 +1,234 @@
+//! Memory-mapped signals.
+
+use std::{
+    fs::File,
+    io::{Error, ErrorKind, Result},
+    num::NonZero,
+    path::Path,
+};
+
+use memmap2::Mmap;
+use ndarray::ArrayView2;
+
+use crate::npy::{self, invalid_data};
+
+/// Size of a single sample in bytes.
+const SAMPLE_SIZE: usize = size_of::<f64>();
+
+/// Signal of little-endian [`f64`] samples in a memory-mapped file.
+///
+/// The samples are read directly from the file by the operating system when they're needed, so signals bigger than memory can be segmented without copying them into an array first.
+/// Rows are stored after each other, so a file with multiple columns stores the first sample of every column, then the second, etc.
+///
+/// The file must not be changed while it's mapped.
+pub struct MmapSignal {
+    /// Mapped file.
+    map: Mmap,
+    /// Byte index of the first sample in the file.
+    offset: usize,
+    /// Amount of samples in each column.
+    rows: usize,
+    /// Amount of columns.
+    columns: usize,
+}
+
+impl MmapSignal {
+    /// Map a file containing only samples, with the amount of columns known up front.
+    ///
+    /// # Errors
+    ///
+    /// - When the file can't be opened or mapped.
+    /// - When the size of the file isn't a multiple of a full row.
+    pub fn open_raw<P>(path: P, columns: NonZero<usize>) -> Result<Self>
+    where
+        P: AsRef<Path>,
+    {
+        let map = map_file(path.as_ref())?;
+        let columns = columns.get();
+
+        // Every row must be complete
+        let row_size = columns * SAMPLE_SIZE;
+        if !map.len().is_multiple_of(row_size) {
+            return Err(invalid_data("file size is not a multiple of the row size"));
+        }
+        let rows = map.len() / row_size;
+
+        Self::new(map, 0, rows, columns)
+    }
+
+    /// Map a `.npy` file, as written by `numpy.save`.
+    ///
+    /// Only one-dimensional and C-ordered two-dimensional arrays of little-endian `float64` are supported.
+    ///
+    /// # Errors
+    ///
+    /// - When the file can't be opened or mapped.
+    /// - When the file isn't a supported `.npy` file.
+    pub fn open_npy<P>(path: P) -> Result<Self>
+    where
+        P: AsRef<Path>,
+    {
+        let map = map_file(path.as_ref())?;
+        let header = npy::parse_header(&map)?;
+
+        // Only little-endian doubles can be viewed directly
+        if header.descr != "'<f8'" {
+            return Err(invalid_data(
+                "only `.npy` files of little-endian `float64` are supported",
+            ));
+        }
+        // Columns after each other can't be viewed as rows
+        if header.fortran_order && header.columns > 1 {
+            return Err(invalid_data(
+                "only C-ordered two-dimensional `.npy` files are supported",
+            ));
+        }
+        let (offset, rows, columns) = (header.data_offset, header.rows, header.columns);
+
+        // The header could describe more samples than the file contains
+        if map.len() - offset < rows * columns * SAMPLE_SIZE {
+            return Err(invalid_data("file is shorter than the shape in the header"));
+        }
+
+        Self::new(map, offset, rows, columns)
+    }
+
+    /// Amount of samples in each column.
+    #[must_use]
+    pub const fn rows(&self) -> usize {
+        self.rows
+    }
+
+    /// Amount of columns.
+    #[must_use]
+    pub const fn columns(&self) -> usize {
+        self.columns
+    }
+
+    /// View the samples as a two-dimensional array, without copying.
+    ///
+    /// A single column is lowered to a one-dimensional signal when predicting.
+    #[must_use]
+    pub fn view(&self) -> ArrayView2<'_, f64> {
+        ArrayView2::from_shape((self.rows, self.columns), self.samples())
+            .unwrap_or_else(|_| unreachable!("shape is checked when opening"))
+    }
+
+    /// Validate the samples can be viewed as [`f64`] and construct.
+    fn new(map: Mmap, offset: usize, rows: usize, columns: usize) -> Result<Self> {
+        // The bytes are reinterpreted directly
+        if cfg!(target_endian = "big") {
+            return Err(Error::new(
+                ErrorKind::Unsupported,
+                "memory-mapped signals are only supported on little-endian targets",
+            ));
+        }
+
+        let this = Self {
+            map,
+            offset,
+            rows,
+            columns,
+        };
+
+        // Maps are page aligned, so only the offset can misalign the samples
+        bytemuck::try_cast_slice::<u8, f64>(this.sample_bytes())
+            .map_err(|_| invalid_data("samples are not aligned to 8 bytes"))?;
+
+        Ok(this)
+    }
+
+    /// Bytes of all samples.
+    fn sample_bytes(&self) -> &[u8] {
+        &self.map[self.offset..self.offset + self.rows * self.columns * SAMPLE_SIZE]
+    }
+
+    /// All samples.
+    fn samples(&self) -> &[f64] {
+        bytemuck::cast_slice(self.sample_bytes())
+    }
+}
+
+/// Open a file and map it read-only.
+fn map_file(path: &Path) -> Result<Mmap> {
+    let file = File::open(path)?;
+
+    // SAFETY: the file is only read, and the caller must not change it while it's mapped
+    unsafe { Mmap::map(&file) }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::Write as _;
+
+    use crate::{Pelt, SegmentCostFunction};
+
+    use super::*;
+
+    /// Write bytes to a new file in the temporary directory.
+    fn write_temporary(name: &str, bytes: &[u8]) -> std::path::PathBuf {
+        let path = std::env::temp_dir().join(format!("pelt-{}-{name}", std::process::id()));
+        File::create(&path)
+            .and_then(|mut file| file.write_all(bytes))
+            .expect("Error writing file");
+
+        path
+    }
+
+    /// Raw and `.npy` files give the same view and changepoints.
+    #[test]
+    fn raw_and_npy() {
+        let samples = (0..200)
+            .flat_map(|index| {
+                let value = if index < 100 { 0.0_f64 } else { 5.0 };
+                [value, -value]
+            })
+            .collect::<Vec<_>>();
+        let sample_bytes = samples
+            .iter()
+            .flat_map(|sample| sample.to_le_bytes())
+            .collect::<Vec<_>>();
+
+        let raw_path = write_temporary("raw.bin", &sample_bytes);
+
+        // Header padded so the data starts at 128 bytes, like `numpy.save`
+        let mut header = "{'descr': '<f8', 'fortran_order': False, 'shape': (200, 2), }".to_owned();
+        header.push_str(&" ".repeat(128 - 10 - header.len() - 1));
+        header.push('\n');
+        let mut npy_bytes = npy::MAGIC.to_vec();
+        npy_bytes.extend([1, 0]);
+        npy_bytes.extend(
+            u16::try_from(header.len())
+                .expect("Header too long")
+                .to_le_bytes(),
+        );
+        npy_bytes.extend(header.as_bytes());
+        npy_bytes.extend(&sample_bytes);
+        let npy_path = write_temporary("signal.npy", &npy_bytes);
+
+        let raw = MmapSignal::open_raw(&raw_path, NonZero::new(2).expect("Invalid number"))
+            .expect("Error mapping raw file");
+        let npy = MmapSignal::open_npy(&npy_path).expect("Error mapping npy file");
+
+        assert_eq!(raw.view(), npy.view());
+        assert_eq!((npy.rows(), npy.columns()), (200, 2));
+
+        let pelt = Pelt::new().with_segment_cost_function(SegmentCostFunction::L2);
+        assert_eq!(
+            pelt.predict(npy.view(), 10.0).expect("Error predicting"),
+            vec![100, 200]
+        );
+
+        // A row is 16 bytes
+        let truncated_path = write_temporary("truncated.bin", &sample_bytes[..20]);
+        assert!(
+            MmapSignal::open_raw(&truncated_path, NonZero::new(2).expect("Invalid number"))
+                .is_err(),
+            "Incomplete row should be rejected"
+        );
+
+        for path in [raw_path, npy_path, truncated_path] {
+            std::fs::remove_file(path).expect("Error removing file");
+        }
+    }
+}
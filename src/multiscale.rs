@@ -0,0 +1,185 @@
+//! Coarse-to-fine solver, segmenting a decimated signal first and refining at full resolution.
+
+use std::{num::NonZero, ops::Range};
+
+use ndarray::{ArrayView, Axis, Dimension, Slice};
+
+use crate::{Error, OneOrTwoDimensions, Pelt, Penalty, Sample, predict::PredictImpl};
+
+/// Two-stage segmentation for very long signals.
+///
+/// The first stage segments every `factor`-th sample, the second stage only considers the samples within the `radius` of the changepoints of the first stage.
+/// Much faster than a `jump` of one on long signals, but a change that's only visible at full resolution isn't found.
+///
+/// # Defaults
+///
+/// - `factor`: `10`
+/// - `radius`: the `factor`
+#[derive(Debug, Clone, Copy)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Multiscale {
+    /// Decimation of the signal of the first stage.
+    factor: usize,
+    /// Maximum distance in samples of a refined changepoint to a coarse one, the factor if not set.
+    radius: Option<usize>,
+}
+
+impl Multiscale {
+    /// Construct a new multiscale solver with default values.
+    #[must_use]
+    pub const fn new() -> Self {
+        Self {
+            factor: 10,
+            radius: None,
+        }
+    }
+
+    /// Set the decimation of the signal of the first stage.
+    ///
+    /// A factor of one solves the full signal directly.
+    #[must_use]
+    pub const fn with_factor(mut self, factor: NonZero<usize>) -> Self {
+        self.factor = factor.get();
+
+        self
+    }
+
+    /// Set the maximum distance in samples of a changepoint to a changepoint of the first stage.
+    ///
+    /// A change can be anywhere between two samples of the decimated signal, so a radius below the factor can miss the exact location.
+    #[must_use]
+    pub const fn with_radius(mut self, radius: usize) -> Self {
+        self.radius = Some(radius);
+
+        self
+    }
+}
+
+impl Default for Multiscale {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Penalty of the decimated signal, in the units of the full signal.
+///
+/// Every decimated sample stands for `factor` samples, so the loss of a decimated segment is roughly the loss of the full segment divided by the factor.
+struct DecimatedPenalty<'a, P> {
+    /// Penalty of the full signal.
+    penalty: &'a P,
+    /// Decimation of the signal.
+    factor: usize,
+}
+
+impl<P> Penalty for DecimatedPenalty<'_, P>
+where
+    P: Penalty,
+{
+    fn segment_penalty(&self, segment: Range<usize>, signal_len: usize) -> f64 {
+        self.penalty.segment_penalty(
+            segment.start * self.factor..segment.end * self.factor,
+            signal_len * self.factor,
+        ) / self.factor as f64
+    }
+
+    fn pruning_penalty(&self, signal_len: usize) -> f64 {
+        self.penalty.pruning_penalty(signal_len * self.factor) / self.factor as f64
+    }
+}
+
+impl PredictImpl {
+    /// Segment the decimated signal, and segment again at full resolution with only the samples around its changepoints as candidates.
+    pub(crate) fn predict_multiscale<A, D, P>(
+        &mut self,
+        signal: &ArrayView<A, D>,
+        penalty: &P,
+        multiscale: &Multiscale,
+    ) -> Result<Vec<usize>, Error>
+    where
+        A: Sample,
+        D: OneOrTwoDimensions + Dimension,
+        P: Penalty,
+        D::PrecalculationOutput: Sync,
+    {
+        let factor = multiscale.factor;
+        let radius = multiscale.radius.unwrap_or(factor);
+        if factor == 1 {
+            return self.predict(signal, penalty);
+        }
+
+        // Every sample of the coarse stage is a candidate, the constraints on positions are mapped to the decimated rows
+        let decimated = signal.slice_axis(Axis(0), Slice::new(0, None, factor.cast_signed()));
+        let coarse_changepoints = Self::new(Pelt {
+            jump: 1,
+            minimum_segment_length: self.pelt.minimum_segment_length.div_ceil(factor),
+            ..self.pelt.reindexed(|row| row.div_ceil(factor))
+        })
+        .predict(&decimated, &DecimatedPenalty { penalty, factor })?;
+
+        // The end of the decimated signal isn't a changepoint
+        let candidates = coarse_changepoints[..coarse_changepoints.len().saturating_sub(1)]
+            .iter()
+            .flat_map(|changepoint| {
+                let center = changepoint * factor;
+
+                center.saturating_sub(radius)..=center + radius
+            })
+            .collect::<Vec<_>>();
+
+        Self::new(self.pelt.with_candidates_within(&candidates)).predict(signal, penalty)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use ndarray::Array1;
+
+    use super::*;
+    use crate::SegmentCostFunction;
+
+    /// Changes between samples of the decimated signal are found at their exact location.
+    #[test]
+    fn predict_multiscale() {
+        let mut rng = fastrand::Rng::with_seed(1);
+        let signal = (0..1000)
+            .map(|index| {
+                rng.f64()
+                    + match index {
+                        0..333 => 0.0,
+                        333..777 => 5.0,
+                        _ => -5.0,
+                    }
+            })
+            .collect::<Array1<f64>>();
+        let pelt = Pelt::new()
+            .with_segment_cost_function(SegmentCostFunction::L2)
+            .with_jump(NonZero::<usize>::MIN);
+
+        assert_eq!(
+            PredictImpl::new(pelt)
+                .predict_multiscale(&signal.view(), &10.0, &Multiscale::new())
+                .expect("Error predicting"),
+            vec![333, 777, 1000]
+        );
+    }
+
+    /// Configured candidates are kept, the refined ones only narrow them down.
+    #[test]
+    fn predict_multiscale_candidates() {
+        let mut rng = fastrand::Rng::with_seed(1);
+        let signal = (0..1000)
+            .map(|index| rng.f64() + if index < 333 { 0.0 } else { 5.0 })
+            .collect::<Array1<f64>>();
+        let pelt = Pelt::new()
+            .with_segment_cost_function(SegmentCostFunction::L2)
+            .with_jump(NonZero::<usize>::MIN)
+            .with_candidates(&[330, 600]);
+
+        assert_eq!(
+            PredictImpl::new(pelt)
+                .predict_multiscale(&signal.view(), &10.0, &Multiscale::new())
+                .expect("Error predicting"),
+            vec![330, 1000]
+        );
+    }
+}
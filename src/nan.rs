@@ -0,0 +1,134 @@
+//! Handling of NaN values in the signal.
+
+use ndarray::{ArrayView, ArrayViewMut1};
+
+use crate::{Error, OneOrTwoDimensions, Sample};
+
+/// What to do with NaN values in the signal.
+///
+/// A NaN value would otherwise end up in the medians and sums of every segment containing it.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum NanPolicy {
+    /// Fail with [`crate::Error::InvalidInput`] at the first row containing a NaN.
+    #[default]
+    Error,
+    /// Leave the NaN values out of the segment losses.
+    ///
+    /// The minimum segment length still counts every row, a segment with only NaN values has no loss.
+    Ignore,
+    /// Replace the NaN values by interpolating linearly between the closest values before and after it in the same column.
+    ///
+    /// NaN values at the start or end of a column take the closest value, a column with only NaN values is ignored.
+    /// Only copies the signal when it contains a NaN.
+    Interpolate,
+}
+
+/// Fail at the first row containing a NaN with [`NanPolicy::Error`], the other policies are handled by the cost functions.
+///
+/// The offset is added to the row in the error, for parts of a longer signal.
+pub(crate) fn check<A, D>(
+    nan_policy: NanPolicy,
+    signal: &ArrayView<A, D>,
+    offset: usize,
+) -> Result<(), Error>
+where
+    A: Sample,
+    D: OneOrTwoDimensions,
+{
+    if nan_policy != NanPolicy::Error {
+        return Ok(());
+    }
+
+    first_nan_row(signal)?.map_or(Ok(()), |row| {
+        Err(Error::InvalidInput {
+            index: offset + row,
+        })
+    })
+}
+
+/// Fail at the first row containing a NaN or infinite value, for detectors without a [`NanPolicy`].
+pub(crate) fn check_finite<A, D>(signal: &ArrayView<A, D>) -> Result<(), Error>
+where
+    A: Sample,
+    D: OneOrTwoDimensions,
+{
+    D::as_2d(signal)?
+        .rows()
+        .into_iter()
+        .position(|row| row.iter().any(|value| !value.to_f64().is_finite()))
+        .map_or(Ok(()), |index| Err(Error::InvalidInput { index }))
+}
+
+/// Index of the first row containing a NaN.
+pub(crate) fn first_nan_row<A, D>(signal: &ArrayView<A, D>) -> Result<Option<usize>, Error>
+where
+    A: Sample,
+    D: OneOrTwoDimensions,
+{
+    Ok(D::as_2d(signal)?
+        .rows()
+        .into_iter()
+        .position(|row| row.iter().any(|value| value.to_f64().is_nan())))
+}
+
+/// Replace the NaN values by interpolating linearly between the closest values, in place.
+pub(crate) fn interpolate(mut column: ArrayViewMut1<f64>) {
+    // Last value that isn't NaN
+    let mut previous: Option<(usize, f64)> = None;
+
+    for index in 0..column.len() {
+        let value = column[index];
+        if value.is_nan() {
+            continue;
+        }
+
+        match previous {
+            // Fill the gap since the previous value
+            Some((previous_index, previous_value)) => {
+                let gap_len = (index - previous_index) as f64;
+                for gap_index in (previous_index + 1)..index {
+                    let fraction = (gap_index - previous_index) as f64 / gap_len;
+                    column[gap_index] = fraction.mul_add(value - previous_value, previous_value);
+                }
+            }
+            // Fill the start with the first value
+            None => column.slice_mut(ndarray::s![..index]).fill(value),
+        }
+
+        previous = Some((index, value));
+    }
+
+    // Fill the end with the last value, nothing to fill with when there are only NaN values
+    if let Some((previous_index, previous_value)) = previous {
+        column
+            .slice_mut(ndarray::s![(previous_index + 1)..])
+            .fill(previous_value);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use ndarray::Array1;
+
+    use super::*;
+
+    /// Gaps are interpolated linearly and the ends take the closest value.
+    #[test]
+    fn interpolate_gaps() {
+        let mut column = Array1::from(vec![f64::NAN, 1.0, f64::NAN, f64::NAN, 4.0, f64::NAN]);
+        interpolate(column.view_mut());
+        assert_eq!(column.to_vec(), vec![1.0, 1.0, 2.0, 3.0, 4.0, 4.0]);
+
+        // Nothing to interpolate from
+        let mut column = Array1::from(vec![f64::NAN, f64::NAN]);
+        interpolate(column.view_mut());
+        assert!(column.iter().all(|value| value.is_nan()));
+
+        assert_eq!(
+            first_nan_row(&ndarray::aview2(&[[1.0, 2.0], [3.0, f64::NAN]]))
+                .expect("Invalid dimensions"),
+            Some(1)
+        );
+    }
+}
@@ -0,0 +1,115 @@
+//! Node.js bindings.
+#![cfg_attr(
+    test,
+    allow(dead_code, reason = "napi doesn't register the bindings in tests")
+)]
+
+use std::num::NonZero;
+
+use napi::{Error, Result, Status, bindgen_prelude::Float64Array};
+use napi_derive::napi;
+
+use crate::{Pelt, SegmentCostFunction};
+
+/// Convert Rust to JavaScript error.
+impl From<crate::Error> for Error {
+    fn from(err: crate::Error) -> Self {
+        Self::from_reason(err.to_string())
+    }
+}
+
+/// Options of the builder, omitted options take the defaults of [`Pelt`].
+#[napi(object)]
+pub struct PredictOptions {
+    /// Segment model, `"l1"`, `"l2"` or `"quantile"`.
+    pub segment_cost_function: Option<String>,
+    /// Step size between candidate changepoints, `1` for an exact solution.
+    pub jump: Option<u32>,
+    /// Minimum number of samples within a segment.
+    pub minimum_segment_length: Option<u32>,
+    /// Maximum number of changepoints, unlimited if omitted.
+    pub max_changepoints: Option<u32>,
+    /// Quantile to target with the quantile cost, defaults to `0.5`.
+    pub quantile: Option<f64>,
+    /// Amount of channels of which the samples are next to each other, defaults to `1`.
+    pub channels: Option<u32>,
+}
+
+/// Construct an invalid argument error.
+fn invalid_arg(reason: &str) -> Error {
+    Error::new(Status::InvalidArg, reason)
+}
+
+/// Convert an option to a non-zero size.
+fn non_zero(value: u32, reason: &str) -> Result<NonZero<usize>> {
+    NonZero::new(value as usize).ok_or_else(|| invalid_arg(reason))
+}
+
+/// Calculate the changepoints.
+///
+/// Returns the indices where changes in the signal were detected, including the end of the signal.
+///
+/// # Errors
+///
+/// - When an option is outside its valid range.
+/// - When the input is invalid.
+/// - When anything went wrong during calculation.
+#[allow(
+    clippy::needless_pass_by_value,
+    reason = "arguments from JavaScript are always owned"
+)]
+#[napi]
+pub fn predict(
+    signal: Float64Array,
+    penalty: f64,
+    options: Option<PredictOptions>,
+) -> Result<Vec<u32>> {
+    let options = options.unwrap_or(PredictOptions {
+        segment_cost_function: None,
+        jump: None,
+        minimum_segment_length: None,
+        max_changepoints: None,
+        quantile: None,
+        channels: None,
+    });
+    let mut setup = Pelt::new();
+
+    // Map input parameter to enum
+    if let Some(segment_cost_function) = &options.segment_cost_function {
+        setup = setup.with_segment_cost_function(match segment_cost_function.as_str() {
+            "l1" => SegmentCostFunction::L1,
+            "l2" => SegmentCostFunction::L2,
+            "quantile" => SegmentCostFunction::Quantile(options.quantile.unwrap_or(0.5)),
+            // Handle unknown case
+            _ => {
+                return Err(invalid_arg(
+                    "segmentCostFunction must be 'l1', 'l2' or 'quantile'",
+                ));
+            }
+        });
+    }
+
+    // Convert types
+    if let Some(jump) = options.jump {
+        setup = setup.with_jump(non_zero(jump, "jump must be > 0")?);
+    }
+    if let Some(minimum_segment_length) = options.minimum_segment_length {
+        setup = setup.with_minimum_segment_length(non_zero(
+            minimum_segment_length,
+            "minimumSegmentLength must be > 0",
+        )?);
+    }
+    if let Some(max_changepoints) = options.max_changepoints {
+        setup = setup.with_max_changepoints(max_changepoints as usize);
+    }
+    let channels = non_zero(options.channels.unwrap_or(1), "channels must be > 0")?;
+
+    // Do calculation
+    let indices = setup.predict_interleaved(&signal, channels, penalty)?;
+
+    // Typed arrays are limited to 32 bit lengths
+    indices
+        .into_iter()
+        .map(|index| u32::try_from(index).map_err(|_error| invalid_arg("signal is too long")))
+        .collect()
+}
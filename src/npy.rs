@@ -0,0 +1,160 @@
+//! NumPy `.npy` format.
+
+use std::io::{Error, ErrorKind, Result};
+
+/// Magic string at the start of every `.npy` file.
+pub(crate) const MAGIC: &[u8] = b"\x93NUMPY";
+
+/// Parsed header of a one- or two-dimensional `.npy` array.
+pub(crate) struct Header<'a> {
+    /// Byte index of the first value.
+    pub(crate) data_offset: usize,
+    /// Python literal of the data type, such as `'<f8'`.
+    pub(crate) descr: &'a str,
+    /// Whether the columns are stored after each other instead of the rows.
+    pub(crate) fortran_order: bool,
+    /// Amount of rows.
+    pub(crate) rows: usize,
+    /// Amount of columns, one for one-dimensional arrays.
+    pub(crate) columns: usize,
+}
+
+/// Parse the header of a `.npy` file.
+pub(crate) fn parse_header(bytes: &[u8]) -> Result<Header<'_>> {
+    let invalid = || invalid_data("not a valid `.npy` file");
+
+    if !bytes.starts_with(MAGIC) {
+        return Err(invalid());
+    }
+
+    // Version 1 stores the header length in 2 bytes, later versions in 4
+    let major_version = *bytes.get(MAGIC.len()).ok_or_else(invalid)?;
+    let length_start = MAGIC.len() + 2;
+    let (header_start, header_len) = match major_version {
+        1 => {
+            let length = bytes
+                .get(length_start..length_start + 2)
+                .and_then(|length| length.try_into().ok())
+                .ok_or_else(invalid)?;
+
+            (length_start + 2, usize::from(u16::from_le_bytes(length)))
+        }
+        2 | 3 => {
+            let length = bytes
+                .get(length_start..length_start + 4)
+                .and_then(|length| length.try_into().ok())
+                .ok_or_else(invalid)?;
+            let length = u32::from_le_bytes(length);
+
+            (
+                length_start + 4,
+                usize::try_from(length).map_err(|_| invalid())?,
+            )
+        }
+        _ => return Err(invalid_data("unsupported `.npy` version")),
+    };
+    let header = bytes
+        .get(header_start..header_start + header_len)
+        .ok_or_else(invalid)?;
+    let header = std::str::from_utf8(header).map_err(|_| invalid())?;
+
+    let descr = value(header, "descr").ok_or_else(invalid)?;
+    let fortran_order = value(header, "fortran_order").ok_or_else(invalid)? == "True";
+
+    // The shape is a tuple like `(1000,)` or `(1000, 2)`
+    let shape = value(header, "shape")
+        .and_then(|shape| shape.strip_prefix('('))
+        .and_then(|shape| shape.strip_suffix(')'))
+        .ok_or_else(invalid)?
+        .split(',')
+        .map(str::trim)
+        .filter(|dimension| !dimension.is_empty())
+        .map(|dimension| dimension.parse::<usize>().map_err(|_| invalid()))
+        .collect::<Result<Vec<_>>>()?;
+    let (rows, columns) = match *shape.as_slice() {
+        [rows] => (rows, 1),
+        [rows, columns] => (rows, columns),
+        _ => {
+            return Err(invalid_data(
+                "only one- or two-dimensional `.npy` files are supported",
+            ));
+        }
+    };
+
+    Ok(Header {
+        data_offset: header_start + header_len,
+        descr,
+        fortran_order,
+        rows,
+        columns,
+    })
+}
+
+/// Decode a `.npy` file into a signal, every row is a sample.
+///
+/// Little-endian floating point and integer values are converted to [`f64`].
+#[cfg(feature = "npy")]
+pub(crate) fn decode(bytes: &[u8]) -> Result<ndarray::Array2<f64>> {
+    use ndarray::ShapeBuilder as _;
+
+    let header = parse_header(bytes)?;
+
+    // Size of a value and its conversion
+    let (size, convert): (usize, fn(&[u8]) -> f64) = match header.descr {
+        "'<f8'" => (8, |bytes| f64::from_le_bytes(to_array(bytes))),
+        "'<f4'" => (4, |bytes| f32::from_le_bytes(to_array(bytes)).into()),
+        "'<i4'" => (4, |bytes| i32::from_le_bytes(to_array(bytes)).into()),
+        #[allow(
+            clippy::cast_precision_loss,
+            reason = "samples are always converted to floating point"
+        )]
+        "'<i8'" => (8, |bytes| i64::from_le_bytes(to_array(bytes)) as f64),
+        _ => {
+            return Err(invalid_data(
+                "only `.npy` files of little-endian `float64`, `float32`, `int32` or `int64` are supported",
+            ));
+        }
+    };
+
+    // The header could describe more values than the file contains
+    let len = header.rows * header.columns;
+    let values = bytes
+        .get(header.data_offset..)
+        .and_then(|data| data.get(..len * size))
+        .ok_or_else(|| invalid_data("file is shorter than the shape in the header"))?
+        .chunks_exact(size)
+        .map(convert)
+        .collect::<Vec<_>>();
+
+    let shape = (header.rows, header.columns).set_f(header.fortran_order);
+    ndarray::Array2::from_shape_vec(shape, values)
+        .map_err(|_| invalid_data("shape doesn't match the values"))
+}
+
+/// Convert a chunk of bytes to a fixed size array, the chunks always have the size of the value.
+#[cfg(feature = "npy")]
+fn to_array<const N: usize>(bytes: &[u8]) -> [u8; N] {
+    bytes
+        .try_into()
+        .unwrap_or_else(|_| unreachable!("chunks have the size of the value"))
+}
+
+/// Find the value of a key in the Python dictionary literal of a `.npy` header.
+fn value<'a>(header: &'a str, key: &str) -> Option<&'a str> {
+    let (_, rest) = header.split_once(&format!("'{key}'"))?;
+    let rest = rest.trim_start().strip_prefix(':')?.trim_start();
+
+    // Tuples contain commas, so they end at the closing parenthesis
+    let end = if rest.starts_with('(') {
+        rest.find(')')? + 1
+    } else {
+        rest.find([',', '}'])?
+    };
+
+    Some(rest[..end].trim_end())
+}
+
+/// Construct an error for a file with invalid contents.
+pub(crate) fn invalid_data(message: &str) -> Error {
+    Error::new(ErrorKind::InvalidData, message)
+}
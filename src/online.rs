@@ -0,0 +1,294 @@
+//! Incremental PELT on a growing signal.
+
+use ndarray::{ArrayView1, Ix1};
+use rustc_hash::FxBuildHasher;
+
+use crate::{
+    Error, Pelt,
+    cost::Cost1D,
+    nan,
+    predict::{Partition, Partitions, PredictImpl, check_signal_len, from_index, to_index},
+};
+
+/// PELT on a signal that grows over time.
+///
+/// New samples are pushed in batches, and only the breakpoints after the previous batch are solved.
+/// The optimal partitions and admissible indices of the previous batches are kept, so the result is the same as running [`Pelt::predict`] on the whole signal.
+/// Samples before the earliest admissible index can't be part of a segment anymore, so they're dropped and the cost function is only precalculated for the samples after it.
+///
+/// Only supports one-dimensional signals and a constant penalty, since a penalty that depends on the length of the signal would change the previous partitions.
+pub struct OnlinePelt {
+    /// Solver with the admissible indices after the last solved breakpoint.
+    predict: PredictImpl,
+    /// Penalty added for every segment.
+    penalty: f64,
+    /// Samples pushed so far from the start of the window.
+    window: Vec<f64>,
+    /// Index of the first sample of the window, all samples before it are dropped.
+    window_start: usize,
+    /// Cost function of the window, `None` before the first samples are pushed.
+    cost: Option<Cost1D>,
+    /// Optimal partition up to each solved breakpoint.
+    partitions: Partitions,
+    /// Next breakpoint that isn't solved yet.
+    next_breakpoint: usize,
+}
+
+impl OnlinePelt {
+    /// Start with an empty signal.
+    #[must_use]
+    pub fn new(pelt: Pelt, penalty: f64) -> Self {
+        let predict = PredictImpl::new(pelt);
+
+        // The partition of an empty signal is empty
+        let mut partitions = Partitions::with_hasher(FxBuildHasher);
+        partitions.insert(0, Partition::default());
+
+        // The first proposed index
+        let next_breakpoint = predict.proposed_indices(usize::MAX).next().unwrap_or(0);
+
+        Self {
+            predict,
+            penalty,
+            window: Vec::new(),
+            window_start: 0,
+            cost: None,
+            partitions,
+            next_breakpoint,
+        }
+    }
+
+    /// Amount of samples pushed so far.
+    #[must_use]
+    pub const fn len(&self) -> usize {
+        self.window_start + self.window.len()
+    }
+
+    /// Whether no samples are pushed yet.
+    #[must_use]
+    pub const fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Append samples to the signal and find the changepoints of the whole signal.
+    ///
+    /// # Errors
+    ///
+    /// - When fewer samples than the minimum segment length are pushed so far, the samples are still kept.
+    /// - When the input is invalid.
+    /// - When anything went wrong during calculation.
+    pub fn push(&mut self, samples: &[f64]) -> Result<Vec<usize>, Error> {
+        // Ensure the cost function parameters are correct
+        self.predict.pelt.segment_cost_function.validate()?;
+        self.predict.pelt.check_columns(1)?;
+
+        // Check before the samples are added, so the detector can still be used afterwards
+        let len = self.len() + samples.len();
+        check_signal_len(len)?;
+        nan::check(
+            self.predict.pelt.nan_policy,
+            &ArrayView1::from(samples),
+            self.len(),
+        )?;
+
+        self.extend_window(samples);
+        if len < self.predict.pelt.minimum_segment_length {
+            return Err(Error::NotEnoughPoints);
+        }
+
+        let cost = match self.cost.take() {
+            Some(cost) => cost,
+            None => Cost1D::Window {
+                cost: Box::new(self.predict.precalculate(&ArrayView1::from(&self.window))?),
+                start: self.window_start,
+            },
+        };
+        let cost = &*self.cost.insert(cost);
+
+        // Solve the new breakpoints, they don't depend on the samples after them
+        while self.next_breakpoint <= len {
+            let partition = self.predict.solve_breakpoint::<Ix1, _>(
+                &self.partitions,
+                cost,
+                self.next_breakpoint,
+                len,
+                &self.penalty,
+            )?;
+            self.partitions
+                .insert(to_index(self.next_breakpoint), partition);
+
+            self.next_breakpoint += self.predict.pelt.jump;
+        }
+
+        // The end of the signal is only solved on a copy when it's not a proposed index, since the next samples will continue from the previous one
+        let best_part = match self.partitions.get(&to_index(len)) {
+            Some(partition) => *partition,
+            None => self.predict.clone().solve_breakpoint::<Ix1, _>(
+                &self.partitions,
+                cost,
+                len,
+                len,
+                &self.penalty,
+            )?,
+        };
+
+        self.predict
+            .changepoints(&self.partitions, best_part, len)
+            .map(|changepoints| self.predict.pelt.convert_changepoints(changepoints))
+    }
+
+    /// Append the samples to the window and the cost function, dropping the samples before the earliest admissible index.
+    ///
+    /// The cost function is extended when possible, otherwise it's cleared to be precalculated again for the samples in the window.
+    fn extend_window(&mut self, samples: &[f64]) {
+        // New admissible indices come after the current ones, so no segment starts before the earliest one anymore
+        let earliest = self
+            .predict
+            .admissible
+            .first()
+            .map_or(self.window_start, |admissible| from_index(*admissible));
+        let droppable = earliest - self.window_start;
+
+        // Only precalculate again for dropping when at least half of the window can go, so every sample is precalculated a constant amount of times
+        let accumulator = self.predict.pelt.accumulator;
+        let extended = droppable * 2 < self.window.len()
+            && self
+                .cost
+                .as_mut()
+                .is_some_and(|cost| cost.extend(samples, accumulator));
+        self.window.extend_from_slice(samples);
+
+        if !extended {
+            self.window.drain(..droppable);
+            self.window_start = earliest;
+            self.cost = None;
+        }
+    }
+}
+
+impl Clone for OnlinePelt {
+    /// The cost function isn't cloned, it's precalculated again for the window on the next push.
+    fn clone(&self) -> Self {
+        Self {
+            predict: self.predict.clone(),
+            penalty: self.penalty,
+            window: self.window.clone(),
+            window_start: self.window_start,
+            cost: None,
+            partitions: self.partitions.clone(),
+            next_breakpoint: self.next_breakpoint,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::num::NonZero;
+
+    use super::*;
+
+    /// Pushing in batches gives the same result as the whole signal at once.
+    #[test]
+    fn batches() {
+        let signal = (0..100)
+            .map(|index| match index {
+                0..30 => 0.0,
+                30..70 => 5.0,
+                _ => 1.0,
+            } + if index % 2 == 0 { 0.1 } else { -0.1 })
+            .collect::<Vec<_>>();
+
+        let pelt = Pelt::new()
+            .with_jump(NonZero::new(5).expect("Invalid number"))
+            .with_segment_cost_function(crate::SegmentCostFunction::L2);
+
+        let mut online = OnlinePelt::new(pelt.clone(), 1.0);
+        for batch in signal.chunks(13) {
+            let changepoints = online.push(batch).expect("Error predicting");
+
+            assert_eq!(
+                changepoints,
+                pelt.predict(&signal[..online.len()], 1.0)
+                    .expect("Error predicting")
+            );
+        }
+        assert_eq!(
+            online.push(&[]).expect("Error predicting"),
+            vec![30, 70, 100]
+        );
+    }
+
+    /// NaN values fail the same as the whole signal at once, and the samples before them are kept.
+    #[test]
+    fn nan() {
+        let mut signal = (0..60)
+            .map(|index| if index < 30 { 0.0 } else { 5.0 } + if index % 2 == 0 { 0.1 } else { -0.1 })
+            .collect::<Vec<_>>();
+        signal[10] = f64::NAN;
+
+        let pelt = Pelt::new()
+            .with_jump(NonZero::new(5).expect("Invalid number"))
+            .with_segment_cost_function(crate::SegmentCostFunction::L2);
+        assert!(matches!(
+            pelt.predict(&signal, 1.0),
+            Err(Error::InvalidInput { index: 10 })
+        ));
+
+        let mut online = OnlinePelt::new(pelt, 1.0);
+        online.push(&signal[..5]).expect("Error predicting");
+        assert!(matches!(
+            online.push(&signal[5..]),
+            Err(Error::InvalidInput { index: 10 })
+        ));
+        assert_eq!(online.len(), 5);
+    }
+
+    /// The changepoints follow the configured convention, the same as the whole signal at once.
+    #[test]
+    fn changepoint_convention() {
+        let signal = (0..60)
+            .map(|index| if index < 30 { 0.0 } else { 5.0 } + if index % 2 == 0 { 0.1 } else { -0.1 })
+            .collect::<Vec<_>>();
+
+        let pelt = Pelt::new()
+            .with_jump(NonZero::new(5).expect("Invalid number"))
+            .with_segment_cost_function(crate::SegmentCostFunction::L2)
+            .with_keep_initial_zero(true)
+            .with_keep_signal_length(false);
+
+        let mut online = OnlinePelt::new(pelt.clone(), 1.0);
+        assert_eq!(
+            online.push(&signal).expect("Error predicting"),
+            pelt.predict(&signal, 1.0).expect("Error predicting")
+        );
+        assert_eq!(online.push(&[]).expect("Error predicting"), vec![0, 30]);
+    }
+
+    /// Samples before the last changes are dropped, also when the cost function can't be extended.
+    #[test]
+    fn window() {
+        let signal = (0..300)
+            .map(|index| (index / 100 * 5) as f64 + if index % 2 == 0 { 0.1 } else { -0.1 })
+            .collect::<Vec<_>>();
+
+        for segment_cost_function in [
+            crate::SegmentCostFunction::L1,
+            crate::SegmentCostFunction::L2,
+            crate::SegmentCostFunction::Quantile(0.3),
+        ] {
+            let pelt = Pelt::new().with_segment_cost_function(segment_cost_function);
+
+            let mut online = OnlinePelt::new(pelt.clone(), 1.0);
+            for batch in signal.chunks(7) {
+                let changepoints = online.push(batch).expect("Error predicting");
+
+                assert_eq!(
+                    changepoints,
+                    pelt.predict(&signal[..online.len()], 1.0)
+                        .expect("Error predicting")
+                );
+            }
+            assert!(online.window.len() <= 100, "{}", online.window.len());
+        }
+    }
+}
@@ -0,0 +1,59 @@
+//! Sum queries over a matrix of all pairs of points.
+
+use std::ops::Range;
+
+/// Two-dimensional prefix sums of a square matrix.
+pub(crate) struct PairwiseSums {
+    /// Amount of rows and columns of the summed matrix, one more than the amount of points.
+    size: usize,
+    /// `sums[row * size + column]` is the sum of all pairs before the row and column.
+    sums: Vec<f64>,
+}
+
+impl PairwiseSums {
+    /// Calculate the value of all pairs of points and sum them.
+    ///
+    /// Memory and time are quadratic in the amount of points.
+    pub(crate) fn precalculate<F>(len: usize, pair: F) -> Self
+    where
+        F: Fn(usize, usize) -> f64,
+    {
+        let size = len + 1;
+
+        let mut sums = vec![0.0; size * size];
+        for row in 0..len {
+            for column in 0..len {
+                sums[(row + 1) * size + column + 1] = pair(row, column)
+                    + sums[row * size + column + 1]
+                    + sums[(row + 1) * size + column]
+                    - sums[row * size + column];
+            }
+        }
+
+        Self { size, sums }
+    }
+
+    /// Sum of the values of all pairs in a block of the matrix.
+    pub(crate) fn sum(&self, rows: Range<usize>, columns: Range<usize>) -> f64 {
+        self.sums[rows.end * self.size + columns.end]
+            - self.sums[rows.start * self.size + columns.end]
+            - self.sums[rows.end * self.size + columns.start]
+            + self.sums[rows.start * self.size + columns.start]
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Sum blocks of a small matrix.
+    #[test]
+    fn block_sums() {
+        let sums = PairwiseSums::precalculate(3, |row, column| (row * 3 + column) as f64);
+
+        assert_eq!(sums.sum(0..3, 0..3), 36.0);
+        assert_eq!(sums.sum(1..2, 0..3), 12.0);
+        assert_eq!(sums.sum(1..3, 2..3), 13.0);
+        assert_eq!(sums.sum(2..2, 0..3), 0.0);
+    }
+}
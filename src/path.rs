@@ -0,0 +1,164 @@
+//! Sweep over a list of penalties.
+
+use ndarray::{ArrayView, Dimension};
+
+use crate::{
+    Error, OneOrTwoDimensions, Penalty, PenaltySegmentation, Sample, predict::PredictImpl,
+};
+
+/// Segmentations for a list of penalties.
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct PenaltyPath {
+    /// Segmentation for each penalty, in the same order as the penalties.
+    pub segmentations: Vec<PenaltySegmentation>,
+}
+
+impl PenaltyPath {
+    /// Suggest the penalty at the elbow of the curve of the amount of changepoints against the loss.
+    ///
+    /// The elbow is the point furthest away from the line between the segmentations with the least and the most changepoints.
+    /// Returns `None` when there are less than three different amounts of changepoints.
+    #[must_use]
+    pub fn elbow(&self) -> Option<&PenaltySegmentation> {
+        // Both ends of the curve, only differences in the amount of changepoints matter so the convention doesn't
+        let first = self
+            .segmentations
+            .iter()
+            .min_by_key(|segmentation| segmentation.changepoints.len())?;
+        let last = self
+            .segmentations
+            .iter()
+            .max_by_key(|segmentation| segmentation.changepoints.len())?;
+
+        // Normalize both axis so the units don't matter
+        let count_range = (last.changepoints.len() - first.changepoints.len()) as f64;
+        let loss_range = first.loss - last.loss;
+        if count_range < 2.0 || loss_range <= 0.0 {
+            return None;
+        }
+
+        // Normalized distance to the line between the ends, the line is `x + y = 1`
+        let distance = |segmentation: &PenaltySegmentation| {
+            let count = (segmentation.changepoints.len() - first.changepoints.len()) as f64;
+            let loss = (segmentation.loss - last.loss) / loss_range;
+
+            1.0 - count / count_range - loss
+        };
+
+        self.segmentations
+            .iter()
+            .max_by(|left, right| distance(left).total_cmp(&distance(right)))
+            // Only an elbow if it's below the line
+            .filter(|segmentation| distance(segmentation) > 0.0)
+    }
+}
+
+impl PenaltySegmentation {
+    /// Amount of changepoints, without the end of the signal.
+    ///
+    /// Only for the default changepoint convention, see [`crate::Pelt::with_changepoint_convention`].
+    #[must_use]
+    pub const fn changepoint_count(&self) -> usize {
+        self.changepoints.len().saturating_sub(1)
+    }
+}
+
+impl PredictImpl {
+    /// Find the changepoints for each penalty.
+    pub(crate) fn predict_many<A, D, P>(
+        &mut self,
+        signal: &ArrayView<A, D>,
+        penalties: &[P],
+    ) -> Result<Vec<Vec<usize>>, Error>
+    where
+        A: Sample,
+        D: OneOrTwoDimensions + Dimension,
+        P: Penalty,
+        D::PrecalculationOutput: Sync,
+    {
+        // Precalculate the cost function once for all runs
+        let cost = self.precalculate(signal)?;
+
+        // Share the segment losses between all runs
+        self.enable_cache();
+
+        penalties
+            .iter()
+            .map(|penalty| self.predict_with_cost(signal, &cost, penalty))
+            .collect()
+    }
+
+    /// Find the segmentation for each penalty.
+    pub(crate) fn penalty_path<A, D>(
+        &mut self,
+        signal: &ArrayView<A, D>,
+        penalties: &[f64],
+    ) -> Result<PenaltyPath, Error>
+    where
+        A: Sample,
+        D: OneOrTwoDimensions + Dimension,
+        D::PrecalculationOutput: Sync,
+    {
+        // Precalculate the cost function once for all runs
+        let cost = self.precalculate(signal)?;
+
+        // Share the segment losses between all runs
+        self.enable_cache();
+
+        let segmentations = penalties
+            .iter()
+            .map(|penalty| {
+                let changepoints = self.predict_with_cost(signal, &cost, penalty)?;
+                let loss = Self::segmentation_loss::<D>(&cost, &changepoints);
+
+                Ok(PenaltySegmentation {
+                    penalty: *penalty,
+                    changepoints,
+                    loss,
+                })
+            })
+            .collect::<Result<_, Error>>()?;
+
+        Ok(PenaltyPath { segmentations })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Construct a segmentation with an amount of changepoints.
+    fn segmentation(penalty: f64, changepoint_count: usize, loss: f64) -> PenaltySegmentation {
+        PenaltySegmentation {
+            penalty,
+            changepoints: (0..=changepoint_count).collect(),
+            loss,
+        }
+    }
+
+    /// Find the elbow of a curve.
+    #[test]
+    fn elbow() {
+        let path = PenaltyPath {
+            segmentations: vec![
+                segmentation(1.0, 10, 5.0),
+                segmentation(2.0, 6, 8.0),
+                segmentation(5.0, 2, 10.0),
+                segmentation(10.0, 1, 50.0),
+                segmentation(20.0, 0, 100.0),
+            ],
+        };
+        assert_eq!(path.elbow().map(|elbow| elbow.penalty), Some(5.0));
+
+        // A straight line has no elbow
+        let path = PenaltyPath {
+            segmentations: vec![
+                segmentation(1.0, 2, 0.0),
+                segmentation(2.0, 1, 1.0),
+                segmentation(3.0, 0, 2.0),
+            ],
+        };
+        assert_eq!(path.elbow(), None);
+    }
+}
@@ -0,0 +1,319 @@
+//! Penalties and automatic penalty selection.
+
+use std::{f64::consts::SQRT_2, ops::Range};
+
+use ndarray::{ArrayView, Axis, Dimension, Zip};
+
+use crate::{Accumulator, Error, NanPolicy, OneOrTwoDimensions, Sample, SegmentCostFunction};
+
+/// Penalty added for each segment, larger values result in fewer changepoints.
+///
+/// Implemented for [`f64`] for a constant penalty.
+pub trait Penalty: Sync {
+    /// Penalty of a segment of the signal.
+    fn segment_penalty(&self, segment: Range<usize>, signal_len: usize) -> f64;
+
+    /// Margin used for pruning candidate changepoints.
+    ///
+    /// Candidates with a cost above the optimal cost plus this margin are discarded.
+    /// For an exact solution this must be an upper bound of [`Penalty::segment_penalty`] plus the largest increase of it when the start of a segment moves forward.
+    fn pruning_penalty(&self, signal_len: usize) -> f64;
+}
+
+impl Penalty for f64 {
+    #[inline]
+    fn segment_penalty(&self, _segment: Range<usize>, _signal_len: usize) -> f64 {
+        *self
+    }
+
+    #[inline]
+    fn pruning_penalty(&self, _signal_len: usize) -> f64 {
+        *self
+    }
+}
+
+/// Penalty depending on the logarithm of the relative length of each segment.
+///
+/// Each segment gets `constant + log_length_weight * ln(signal_len / segment_len)`, so short segments are penalized more.
+#[derive(Debug, Clone, Copy, PartialEq, PartialOrd)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct SegmentLengthPenalty {
+    /// Constant penalty for each segment.
+    constant: f64,
+    /// Weight of the logarithm of the relative segment length.
+    log_length_weight: f64,
+}
+
+impl SegmentLengthPenalty {
+    /// Construct a new penalty from the constant part and the weight of the segment length.
+    ///
+    /// # Errors
+    ///
+    /// - When the constant isn't finite.
+    /// - When the weight is negative or not finite.
+    pub fn new(constant: f64, log_length_weight: f64) -> Result<Self, Error> {
+        if !constant.is_finite() || !log_length_weight.is_finite() || log_length_weight < 0.0 {
+            return Err(Error::InvalidPenalty);
+        }
+
+        Ok(Self {
+            constant,
+            log_length_weight,
+        })
+    }
+}
+
+impl Penalty for SegmentLengthPenalty {
+    #[inline]
+    fn segment_penalty(&self, segment: Range<usize>, signal_len: usize) -> f64 {
+        let inverse_relative_len = signal_len as f64 / segment.len() as f64;
+
+        self.log_length_weight
+            .mul_add(inverse_relative_len.ln(), self.constant)
+    }
+
+    #[inline]
+    fn pruning_penalty(&self, signal_len: usize) -> f64 {
+        // Highest penalty is for a single point, with a margin of `ln(2)` for moving the start of a segment forward
+        self.log_length_weight
+            .mul_add((2.0 * signal_len as f64).ln(), self.constant)
+    }
+}
+
+/// Penalty with a multiplier for every position of a changepoint, to bias the segmentation with prior knowledge.
+///
+/// Each segment gets the penalty of the inner penalty times the weight at its start, so a weight below `1.0` makes a changepoint at that index more likely and a weight above `1.0` less likely.
+/// Indices without a weight have a weight of `1.0`.
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct WeightedPenalty<P> {
+    /// Penalty before weighting.
+    penalty: P,
+    /// Multiplier of the penalty of the segment starting at each index.
+    weights: Vec<f64>,
+    /// Largest weight, for the pruning penalty.
+    max_weight: f64,
+}
+
+impl<P> WeightedPenalty<P>
+where
+    P: Penalty,
+{
+    /// Construct a new penalty from the penalty before weighting and a weight for every index of the signal.
+    ///
+    /// # Errors
+    ///
+    /// - When a weight is negative or not finite.
+    pub fn new(penalty: P, weights: Vec<f64>) -> Result<Self, Error> {
+        if weights
+            .iter()
+            .any(|weight| !weight.is_finite() || *weight < 0.0)
+        {
+            return Err(Error::InvalidWeights);
+        }
+
+        // Indices without a weight have a weight of one
+        let max_weight = weights.iter().copied().fold(1.0, f64::max);
+
+        Ok(Self {
+            penalty,
+            weights,
+            max_weight,
+        })
+    }
+}
+
+impl<P> Penalty for WeightedPenalty<P>
+where
+    P: Penalty,
+{
+    #[inline]
+    fn segment_penalty(&self, segment: Range<usize>, signal_len: usize) -> f64 {
+        let weight = self.weights.get(segment.start).copied().unwrap_or(1.0);
+
+        weight * self.penalty.segment_penalty(segment, signal_len)
+    }
+
+    #[inline]
+    fn pruning_penalty(&self, signal_len: usize) -> f64 {
+        // The weighted penalty and its increase when the start moves are at most the largest weight times the inner margin
+        self.max_weight * self.penalty.pruning_penalty(signal_len)
+    }
+}
+
+/// Information criterion used to derive the penalty from the signal.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum InformationCriterion {
+    /// Bayesian information criterion, penalizes each parameter with `ln(n)`.
+    #[default]
+    Bic,
+    /// Akaike information criterion, penalizes each parameter with `2`.
+    ///
+    /// Doesn't grow with the signal length, so it results in more changepoints than [`InformationCriterion::Bic`] for long signals.
+    Aic,
+    /// Bayesian information criterion with an extra parameter per changepoint, that also penalizes short segments.
+    ///
+    /// Each segment gets `ln(n / n_i)` times the noise scale on top of the constant, where `n_i` is the length of the segment.
+    /// This is a heuristic of this crate and not the modified BIC of Zhang and Siegmund, whose segment length term has the opposite sign.
+    ModifiedBic,
+}
+
+impl InformationCriterion {
+    /// Calculate the penalty for adding a single segment to the signal.
+    ///
+    /// The noise is estimated from the first differences of the signal, so it's robust against the changes themselves.
+    #[inline]
+    pub(crate) fn penalty<A, D>(
+        self,
+        segment_cost_function: SegmentCostFunction,
+        signal: &ArrayView<A, D>,
+    ) -> Result<SegmentLengthPenalty, Error>
+    where
+        A: Sample,
+        D: OneOrTwoDimensions + Dimension,
+    {
+        let len = D::len_or_nrows(signal);
+
+        // Not enough points to estimate the noise from
+        if len < 3 {
+            return Ok(SegmentLengthPenalty {
+                constant: 0.0,
+                log_length_weight: 0.0,
+            });
+        }
+
+        // Each changepoint adds a location and a level per column
+        let columns = signal.len() / len;
+        let parameters = (columns + 1) as f64;
+
+        // Convert from units of -2 log likelihood to the loss
+        let scale = segment_cost_function.loss_per_log_likelihood()
+            * Self::noise_scale(segment_cost_function, signal, len)?;
+
+        let (constant, log_length_weight) = match self {
+            Self::Bic => (scale * parameters * (len as f64).ln(), 0.0),
+            Self::Aic => (scale * parameters * 2.0, 0.0),
+            // One parameter more than BIC, plus the segment length term in units of the loss
+            Self::ModifiedBic => (scale * (parameters + 1.0) * (len as f64).ln(), scale),
+        };
+
+        Ok(SegmentLengthPenalty {
+            constant,
+            log_length_weight,
+        })
+    }
+
+    /// Estimate the scale of the noise per point with the maximum likelihood estimator of the segment model.
+    #[inline]
+    fn noise_scale<A, D>(
+        segment_cost_function: SegmentCostFunction,
+        signal: &ArrayView<A, D>,
+        len: usize,
+    ) -> Result<f64, Error>
+    where
+        A: Sample,
+        D: OneOrTwoDimensions + Dimension,
+    {
+        // Differences between consecutive points, normalized so the noise keeps the same scale
+        let differences = Zip::from(signal.slice_axis(Axis(0), (1..).into()))
+            .and(signal.slice_axis(Axis(0), (..-1).into()))
+            .map_collect(|next, previous| (next.to_f64() - previous.to_f64()) / SQRT_2);
+        let differences = differences.view();
+
+        // Calculate the loss over all differences
+        let cost = D::precalculate(
+            segment_cost_function,
+            Accumulator::default(),
+            NanPolicy::default(),
+            &differences,
+            None,
+            None,
+            None,
+        )?;
+        let mut loss = 0.0;
+        D::loss(&cost, &mut loss, 0..(len - 1));
+
+        Ok(loss / (len - 1) as f64)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Check the BIC penalty for alternating noise.
+    #[test]
+    fn bic() {
+        let signal = ndarray::Array1::from_iter((0..100).map(|index| f64::from(index % 2)));
+
+        // The differences are all 1 / sqrt(2) around a mean of almost zero
+        let penalty = InformationCriterion::Bic
+            .penalty(SegmentCostFunction::L2, &signal.view())
+            .expect("Error calculating penalty")
+            .segment_penalty(0..10, 100);
+        let expected = 2.0 * 100.0_f64.ln() * 0.5;
+        assert!((penalty - expected).abs() < 1e-2, "{penalty} {expected}");
+    }
+
+    /// Check the AIC penalty for alternating noise.
+    #[test]
+    fn aic() {
+        let signal = ndarray::Array1::from_iter((0..100).map(|index| f64::from(index % 2)));
+
+        // The differences are all 1 / sqrt(2) around a mean of almost zero
+        let penalty = InformationCriterion::Aic
+            .penalty(SegmentCostFunction::L2, &signal.view())
+            .expect("Error calculating penalty")
+            .segment_penalty(0..10, 100);
+        let expected = 2.0 * 2.0 * 0.5;
+        assert!((penalty - expected).abs() < 1e-2, "{penalty} {expected}");
+    }
+
+    /// Check the segment length heuristic of the modified BIC penalty for alternating noise.
+    #[test]
+    fn modified_bic() {
+        let signal = ndarray::Array1::from_iter((0..100).map(|index| f64::from(index % 2)));
+
+        // Short segments are penalized more
+        let penalty = InformationCriterion::ModifiedBic
+            .penalty(SegmentCostFunction::L2, &signal.view())
+            .expect("Error calculating penalty");
+        let expected = 0.5 * 3.0_f64.mul_add(100.0_f64.ln(), 10.0_f64.ln());
+        let short = penalty.segment_penalty(0..10, 100);
+        assert!((short - expected).abs() < 1e-2, "{short} {expected}");
+        assert!(short < penalty.pruning_penalty(100), "{short}");
+        assert!(short > penalty.segment_penalty(0..50, 100), "{short}");
+    }
+
+    /// A low weight makes a changepoint there cheaper, indices without a weight are unchanged.
+    #[test]
+    fn weighted() {
+        let penalty = WeightedPenalty::new(10.0, vec![1.0, 0.5, 2.0]).expect("Invalid weights");
+
+        assert!((penalty.segment_penalty(1..5, 10) - 5.0).abs() < f64::EPSILON);
+        assert!((penalty.segment_penalty(5..10, 10) - 10.0).abs() < f64::EPSILON);
+        assert!((penalty.pruning_penalty(10) - 20.0).abs() < f64::EPSILON);
+        assert!(matches!(
+            WeightedPenalty::new(10.0, vec![-1.0]),
+            Err(Error::InvalidWeights)
+        ));
+    }
+
+    /// A negative or non-finite segment length weight or a non-finite constant is rejected.
+    #[test]
+    fn segment_length_invalid() {
+        assert!(SegmentLengthPenalty::new(10.0, 1.0).is_ok());
+        for (constant, log_length_weight) in [
+            (10.0, -1.0),
+            (10.0, f64::NAN),
+            (10.0, f64::INFINITY),
+            (f64::NAN, 1.0),
+        ] {
+            assert!(matches!(
+                SegmentLengthPenalty::new(constant, log_length_weight),
+                Err(Error::InvalidPenalty)
+            ));
+        }
+    }
+}
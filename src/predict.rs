@@ -1,21 +1,120 @@
 //! Predict implementation.
 
-use std::collections::HashMap;
+use std::{collections::HashMap, ops::Range};
 
-use ndarray::{ArrayView, Dimension};
+use ndarray::{ArrayView, ArrayView1, Dimension};
 use rustc_hash::FxBuildHasher;
-use smallvec::SmallVec;
 
-use crate::{Error, OneOrTwoDimensions, Pelt};
+use crate::{Error, OneOrTwoDimensions, Pelt, Penalty, Sample};
+
+/// Losses of segments calculated in previous runs, keyed by the start and end of the segment.
+#[derive(Clone)]
+pub(crate) struct LossCache {
+    /// Cached losses.
+    losses: HashMap<(usize, usize), f64, FxBuildHasher>,
+    /// Maximum amount of cached losses, unlimited if `None`.
+    capacity: Option<usize>,
+}
+
+impl LossCache {
+    /// Construct an empty cache.
+    pub(crate) fn new(capacity: Option<usize>) -> Self {
+        Self {
+            losses: HashMap::default(),
+            capacity,
+        }
+    }
+
+    /// Get the loss of a segment when it's cached.
+    #[inline]
+    pub(crate) fn get(&self, start: usize, end: usize) -> Option<f64> {
+        self.losses.get(&(start, end)).copied()
+    }
+
+    /// Whether no more losses can be added.
+    #[inline]
+    fn is_full(&self) -> bool {
+        self.capacity
+            .is_some_and(|capacity| self.losses.len() >= capacity)
+    }
+
+    /// Add losses until the capacity is reached, the losses that don't fit are dropped.
+    fn extend(&mut self, losses: Vec<((usize, usize), f64)>) {
+        let remaining = self.capacity.map_or(usize::MAX, |capacity| {
+            capacity.saturating_sub(self.losses.len())
+        });
+
+        self.losses.extend(losses.into_iter().take(remaining));
+    }
+}
+
+/// Position in the signal as stored by the solver.
+///
+/// Half the size with the `u32-indices` feature, limiting the signal to [`u32::MAX`] samples.
+#[cfg(feature = "u32-indices")]
+pub(crate) type Index = u32;
+/// Position in the signal as stored by the solver.
+#[cfg(not(feature = "u32-indices"))]
+pub(crate) type Index = usize;
+
+/// Convert a position in the signal to a stored index.
+///
+/// The length of the signal must be checked with [`check_signal_len`] first.
+#[inline]
+#[allow(clippy::cast_possible_truncation, reason = "the length is checked")]
+pub(crate) const fn to_index(position: usize) -> Index {
+    position as Index
+}
+
+/// Convert a stored index back to a position in the signal.
+#[inline]
+#[allow(
+    clippy::unnecessary_cast,
+    reason = "only the same type without the `u32-indices` feature"
+)]
+pub(crate) const fn from_index(index: Index) -> usize {
+    index as usize
+}
+
+/// Ensure every position in the signal can be stored as an index.
+#[inline]
+pub(crate) fn check_signal_len(signal_len: usize) -> Result<(), Error> {
+    Index::try_from(signal_len)
+        .map(|_index| ())
+        .map_err(|_error| Error::SignalTooLong)
+}
+
+/// Optimal partition of the signal up to each solved breakpoint.
+///
+/// Every partition points to the breakpoint of the partition before it, so partitions with the same start share it instead of copying it.
+pub(crate) type Partitions = HashMap<Index, Partition, FxBuildHasher>;
 
 /// Implementation of predict with state.
+#[derive(Clone)]
 pub struct PredictImpl {
     /// Pelt data.
-    pelt: Pelt,
+    pub(crate) pelt: Pelt,
     /// List of indices we can accept
-    admissible: Vec<usize>,
+    pub(crate) admissible: Vec<Index>,
     /// All subproblems.
     subproblems: Vec<Partition>,
+    /// Losses shared between runs on the same signal, disabled if `None`.
+    cache: Option<LossCache>,
+    /// Partitions of the previous run, kept to reuse the allocation.
+    partitions: Partitions,
+    /// Elapsed time of irregularly sampled signals, the minimum segment length only counts rows if `None`.
+    pub(crate) elapsed: Option<Elapsed>,
+    /// Next point that can be admitted for irregularly sampled signals, all points before it are admitted or skipped.
+    admitted_until: usize,
+}
+
+/// Elapsed time of an irregularly sampled signal.
+#[derive(Clone)]
+pub(crate) struct Elapsed {
+    /// Time since the start of the signal at the start of each row, and at the end of the signal.
+    pub(crate) times: Vec<f64>,
+    /// Minimum time each segment must last.
+    pub(crate) minimum_duration: f64,
 }
 
 impl PredictImpl {
@@ -31,138 +130,507 @@ impl PredictImpl {
             pelt,
             admissible,
             subproblems,
+            cache: None,
+            partitions: Partitions::default(),
+            elapsed: None,
+            admitted_until: 0,
         }
     }
 
+    /// Share the segment losses between all following runs, which must be on the same signal.
+    ///
+    /// Only worth it when the same segments are evaluated multiple times, such as for multiple penalties.
+    pub(crate) fn enable_cache(&mut self) {
+        self.cache = Some(LossCache::new(self.pelt.loss_cache_capacity));
+    }
+
     /// Run the calculation loop.
-    pub(crate) fn predict<D>(
+    pub(crate) fn predict<A, D, P>(
+        &mut self,
+        signal: &ArrayView<A, D>,
+        penalty: &P,
+    ) -> Result<Vec<usize>, Error>
+    where
+        A: Sample,
+        D: OneOrTwoDimensions + Dimension,
+        P: Penalty,
+        D::PrecalculationOutput: Sync,
+    {
+        // Precalculate the cost function
+        let cost = self.precalculate(signal)?;
+
+        self.predict_with_cost(signal, &cost, penalty)
+    }
+
+    /// Precalculate the cost function for the signal.
+    #[inline]
+    pub(crate) fn precalculate<A, D>(
+        &self,
+        signal: &ArrayView<A, D>,
+    ) -> Result<D::PrecalculationOutput, Error>
+    where
+        A: Sample,
+        D: OneOrTwoDimensions + Dimension,
+    {
+        self.precalculate_weighted(signal, None)
+    }
+
+    /// Precalculate the cost function for the signal, optionally with a weight for each row.
+    #[inline]
+    pub(crate) fn precalculate_weighted<A, D>(
+        &self,
+        signal: &ArrayView<A, D>,
+        weights: Option<&ArrayView1<f64>>,
+    ) -> Result<D::PrecalculationOutput, Error>
+    where
+        A: Sample,
+        D: OneOrTwoDimensions + Dimension,
+    {
+        // Building the trees sorts in parallel
+        #[cfg(feature = "rayon")]
+        return Pelt::install(self.pelt.thread_pool.as_deref(), || {
+            D::precalculate(
+                self.pelt.segment_cost_function,
+                self.pelt.accumulator,
+                self.pelt.nan_policy,
+                signal,
+                weights,
+                self.pelt.column_weights.as_deref(),
+                self.pelt.column_cost_functions.as_deref(),
+            )
+        });
+
+        #[cfg(not(feature = "rayon"))]
+        D::precalculate(
+            self.pelt.segment_cost_function,
+            self.pelt.accumulator,
+            self.pelt.nan_policy,
+            signal,
+            weights,
+            self.pelt.column_weights.as_deref(),
+            self.pelt.column_cost_functions.as_deref(),
+        )
+    }
+
+    /// Run the calculation loop with an already precalculated cost function.
+    ///
+    /// Allows reusing the precalculation and the segment losses for multiple runs on the same signal.
+    pub(crate) fn predict_with_cost<A, D, P>(
         &mut self,
-        signal: &ArrayView<f64, D>,
-        penalty: f64,
+        signal: &ArrayView<A, D>,
+        cost: &D::PrecalculationOutput,
+        penalty: &P,
     ) -> Result<Vec<usize>, Error>
     where
+        A: Sample,
         D: OneOrTwoDimensions + Dimension,
+        P: Penalty,
         D::PrecalculationOutput: Sync,
     {
         // Length as the rows
         let len = D::len_or_nrows(signal);
+        check_signal_len(len)?;
 
         // `partitions[t]` stores the optimal partition of `signal[0..t]`
+        // Reuse the allocation of a previous run
+        let mut partitions = std::mem::take(&mut self.partitions);
+        partitions.clear();
         // Pre-allocate at least the number of partitions, it will still grow somewhat
-        let mut partitions =
-            HashMap::with_capacity_and_hasher(signal.len() / self.pelt.jump, FxBuildHasher);
+        partitions.reserve(signal.len() / self.pelt.jump);
         partitions.insert(0, Partition::default());
 
-        // Precalculate the cost function
-        let cost = D::precalculate(self.pelt.segment_cost_function, signal);
+        // Reset state from a previous run
+        self.admissible.clear();
+        self.admitted_until = 0;
+
+        let result = self.solve_all(&mut partitions, cost, signal, penalty);
+
+        // Keep the allocation for the next run, also when it failed
+        self.partitions = partitions;
+
+        result
+    }
+
+    /// Solve every proposed breakpoint and reconstruct the changepoints from the partitions.
+    fn solve_all<A, D, P>(
+        &mut self,
+        partitions: &mut Partitions,
+        cost: &D::PrecalculationOutput,
+        signal: &ArrayView<A, D>,
+        penalty: &P,
+    ) -> Result<Vec<usize>, Error>
+    where
+        A: Sample,
+        D: OneOrTwoDimensions + Dimension,
+        P: Penalty,
+        D::PrecalculationOutput: Sync,
+    {
+        // Length as the rows
+        let len = D::len_or_nrows(signal);
 
         // Find the initial changepoint indices
         for breakpoint in self.proposed_indices(len) {
+            let partition =
+                self.solve_breakpoint::<D, _>(partitions, cost, breakpoint, len, penalty)?;
+
+            // Assign optimal partition to the map
+            partitions.insert(to_index(breakpoint), partition);
+        }
+
+        // Get the best partition
+        let best_part = partitions
+            .remove(&to_index(len))
+            .ok_or(Error::NoSegmentsFound)?;
+
+        self.changepoints(partitions, best_part, len)
+    }
+
+    /// Find the optimal partition ending at the breakpoint and prune the admissible indices.
+    ///
+    /// All breakpoints before it must already be solved in the partitions.
+    pub(crate) fn solve_breakpoint<D, P>(
+        &mut self,
+        partitions: &Partitions,
+        cost: &D::PrecalculationOutput,
+        breakpoint: usize,
+        signal_len: usize,
+        penalty: &P,
+    ) -> Result<Partition, Error>
+    where
+        D: OneOrTwoDimensions + Dimension,
+        P: Penalty,
+        D::PrecalculationOutput: Sync,
+    {
+        if self.elapsed.is_some() || self.pelt.candidates.is_some() {
+            self.admit_until(partitions, breakpoint);
+        } else {
             // Add points from 0 to the current breakpoint as admissible
-            let new_admission_point = (breakpoint.saturating_sub(self.pelt.minimum_segment_length)
-                / self.pelt.jump)
-                * self.pelt.jump;
-            self.admissible.push(new_admission_point);
-
-            // Reset subproblems
-            self.subproblems.clear();
-
-            // Split admissible into sub problems based on a heuristic
-            // The heuristic determines whether the overhead of starting the threads is worth it
-            #[cfg(feature = "rayon")]
-            if self
-                .pelt
-                .segment_cost_function
-                .should_use_threading(self.admissible.len())
+            let new_admission_point = to_index(
+                (breakpoint.saturating_sub(self.pelt.minimum_segment_length) / self.pelt.jump)
+                    * self.pelt.jump,
+            );
+
+            // Only admit points that are a partition themselves and allowed as a changepoint, and don't admit the same point twice
+            if partitions.contains_key(&new_admission_point)
+                && self.admissible.last() != Some(&new_admission_point)
+                && !self.pelt.is_forbidden(from_index(new_admission_point))
             {
-                // Use all available threads
-                self.par_split_into_subproblems(&partitions, &cost, breakpoint, signal, penalty)?;
-            } else {
-                // Keep using a single thread
-                self.split_into_subproblems(&partitions, &cost, breakpoint, signal, penalty)?;
+                self.admissible.push(new_admission_point);
             }
+        }
 
-            // Split admissible into sub problems
-            #[cfg(not(feature = "rayon"))]
-            self.split_into_subproblems(&partitions, &cost, breakpoint, signal, penalty)?;
+        // Reset subproblems
+        self.subproblems.clear();
 
-            // Find the optimal partition with the lowest loss
-            let min_subproblem = self
-                .subproblems
-                .iter()
-                .min_by(|left, right| {
-                    left.loss_and_penalty_sum()
-                        .total_cmp(&right.loss_and_penalty_sum())
-                })
-                .ok_or(Error::NotEnoughPoints)?;
+        // Calculate the losses that haven't been calculated in previous runs
+        let mut cache = self.cache.take();
+        if let Some(cache) = &mut cache {
+            self.fill_cache::<D>(cache, cost, breakpoint);
+        }
 
-            // Assign optimal partition to the map
-            partitions.insert(breakpoint, min_subproblem.clone());
+        // Split admissible into sub problems based on a heuristic
+        // The heuristic determines whether the overhead of starting the threads is worth it
+        #[cfg(feature = "rayon")]
+        let thread_pool = self.pelt.thread_pool.clone();
+        #[cfg(feature = "rayon")]
+        let result = if self.pelt.should_use_threading(self.admissible.len()) {
+            // Use all available threads
+            Pelt::install(thread_pool.as_deref(), || {
+                self.par_split_into_subproblems::<D, _>(
+                    partitions,
+                    cost,
+                    breakpoint,
+                    signal_len,
+                    penalty,
+                    cache.as_ref(),
+                )
+            })
+        } else {
+            // Keep using a single thread
+            self.split_into_subproblems::<D, _>(
+                partitions,
+                cost,
+                breakpoint,
+                signal_len,
+                penalty,
+                cache.as_ref(),
+            )
+        };
 
-            // Threshold loss to filter each partition
-            let loss_current_part = min_subproblem.loss_and_penalty_sum() + penalty;
+        // Split admissible into sub problems
+        #[cfg(not(feature = "rayon"))]
+        let result = self.split_into_subproblems::<D, _>(
+            partitions,
+            cost,
+            breakpoint,
+            signal_len,
+            penalty,
+            cache.as_ref(),
+        );
 
-            // We apply a zip to the subproblems manually
-            self.admissible.resize(self.subproblems.len(), 0);
+        // Keep the cache for the next breakpoints
+        self.cache = cache;
+        result?;
+
+        // Find the optimal partition with the lowest loss
+        let min_subproblem = self
+            .subproblems
+            .iter()
+            .min_by(|left, right| {
+                left.loss_and_penalty_sum()
+                    .total_cmp(&right.loss_and_penalty_sum())
+            })
+            .copied()
+            .ok_or(Error::NotEnoughPoints)?;
+
+        // Threshold loss to filter each partition
+        let loss_current_part =
+            min_subproblem.loss_and_penalty_sum() + penalty.pruning_penalty(signal_len);
+
+        // We apply a zip to the subproblems manually
+        self.admissible.resize(self.subproblems.len(), 0);
+
+        // A forbidden breakpoint can't be the changepoint that beats the pruned candidates
+        if self.pelt.is_forbidden(breakpoint) {
+            return Ok(min_subproblem);
+        }
 
-            // Filter the admissible array
-            let mut index = 0;
-            self.admissible.retain(|_admissible| {
-                // Drain and zip the subproblems
-                let subproblem = &self.subproblems[index];
-                index += 1;
+        // Filter the admissible array
+        let mut index = 0;
+        self.admissible.retain(|_admissible| {
+            // Drain and zip the subproblems
+            let subproblem = &self.subproblems[index];
+            index += 1;
 
-                subproblem.loss_and_penalty_sum() < loss_current_part
-            });
-        }
+            subproblem.loss_and_penalty_sum() < loss_current_part
+        });
 
-        // Get the best partition
-        let best_part = partitions.remove(&len).ok_or(Error::NoSegmentsFound)?;
+        Ok(min_subproblem)
+    }
 
-        // Extract the indices
-        let mut indices = best_part.ranges;
+    /// Reconstruct the sorted changepoints by following the previous changepoints from the best partition of the whole signal.
+    ///
+    /// The partitions must contain every previous changepoint on the path from the best partition.
+    pub(crate) fn changepoints(
+        &self,
+        partitions: &Partitions,
+        best_part: Partition,
+        signal_len: usize,
+    ) -> Result<Vec<usize>, Error> {
+        // Walk back from the end of the signal
+        let mut indices = vec![signal_len];
+        let mut previous = best_part.previous;
+        while previous > 0 {
+            indices.push(from_index(previous));
+
+            previous = partitions
+                .get(&previous)
+                .ok_or(Error::NoSegmentsFound)?
+                .previous;
+        }
+
+        // Don't allow more changepoints than configured, the end of the signal isn't a changepoint
+        if self
+            .pelt
+            .max_changepoints
+            .is_some_and(|max_changepoints| indices.len() > max_changepoints + 1)
+        {
+            return Err(Error::TooManyChangepoints);
+        }
 
         // Sort indices
-        indices.sort_unstable();
+        indices.reverse();
 
-        Ok(indices.to_vec())
+        Ok(indices)
+    }
+
+    /// Calculate the total loss of all segments, without penalties.
+    pub(crate) fn segmentation_loss<D>(
+        cost: &D::PrecalculationOutput,
+        changepoints: &[usize],
+    ) -> f64
+    where
+        D: OneOrTwoDimensions + Dimension,
+    {
+        let mut loss = 0.0;
+
+        // Each segment starts at the previous changepoint
+        let mut start = 0;
+        for end in changepoints.iter().copied() {
+            D::loss(cost, &mut loss, start..end);
+
+            start = end;
+        }
+
+        loss
+    }
+
+    /// Calculate the losses of all admissible segments ending at the breakpoint that aren't cached yet.
+    fn fill_cache<D>(
+        &self,
+        cache: &mut LossCache,
+        cost: &D::PrecalculationOutput,
+        breakpoint: usize,
+    ) where
+        D: OneOrTwoDimensions + Dimension,
+        D::PrecalculationOutput: Sync,
+    {
+        // Nothing can be added anymore
+        if cache.is_full() {
+            return;
+        }
+
+        // Calculate the loss if it's not cached and a valid segment
+        let missing = |admissible_start: &Index| {
+            let admissible_start = from_index(*admissible_start);
+            let key = (admissible_start, breakpoint);
+            let valid =
+                breakpoint.saturating_sub(admissible_start) >= self.pelt.minimum_segment_length;
+
+            (valid && cache.get(admissible_start, breakpoint).is_none()).then(|| {
+                let mut loss = 0.0;
+                D::loss(cost, &mut loss, admissible_start..breakpoint);
+
+                (key, loss)
+            })
+        };
+
+        // Use the same heuristic as splitting into subproblems
+        #[cfg(feature = "rayon")]
+        let losses: Vec<_> = if self.pelt.should_use_threading(self.admissible.len()) {
+            use rayon::iter::{IntoParallelRefIterator as _, ParallelIterator as _};
+
+            Pelt::install(self.pelt.thread_pool.as_deref(), || {
+                self.admissible.par_iter().filter_map(missing).collect()
+            })
+        } else {
+            self.admissible.iter().filter_map(missing).collect()
+        };
+        #[cfg(not(feature = "rayon"))]
+        let losses: Vec<_> = self.admissible.iter().filter_map(missing).collect();
+
+        cache.extend(losses);
+    }
+
+    /// Admit the points from which a segment to the breakpoint has the minimum length and lasts at least the minimum duration.
+    ///
+    /// With irregular sampling or candidate breakpoints the latest start can move multiple jumps between breakpoints, so every point since the previous breakpoint is admitted.
+    fn admit_until(&mut self, partitions: &Partitions, breakpoint: usize) {
+        // Latest start with the minimum length
+        let Some(mut latest) = breakpoint.checked_sub(self.pelt.minimum_segment_length) else {
+            return;
+        };
+        // Latest start lasting the minimum duration
+        if let Some(elapsed) = &self.elapsed {
+            let end_time = elapsed.times[breakpoint];
+            let Some(latest_by_duration) = elapsed.times[..=breakpoint]
+                .partition_point(|time| end_time - time >= elapsed.minimum_duration)
+                .checked_sub(1)
+            else {
+                return;
+            };
+            latest = latest.min(latest_by_duration);
+        }
+
+        // Only admit points that are a partition themselves and allowed as a changepoint
+        let admit = |point: &usize| {
+            partitions.contains_key(&to_index(*point)) && !self.pelt.is_forbidden(*point)
+        };
+        let admitted_until = self.admitted_until;
+        let next_admitted = match &self.pelt.candidates {
+            Some(candidates) => {
+                // The start of the signal is always a partition, the candidates are sorted
+                let first = candidates.partition_point(|candidate| *candidate < admitted_until);
+                self.admissible.extend(
+                    (admitted_until == 0)
+                        .then_some(0)
+                        .into_iter()
+                        .chain(
+                            candidates[first..]
+                                .iter()
+                                .copied()
+                                .take_while(|candidate| *candidate <= latest),
+                        )
+                        .filter(admit)
+                        .map(to_index),
+                );
+
+                latest + 1
+            }
+            None => {
+                let latest = (latest / self.pelt.jump) * self.pelt.jump;
+                self.admissible.extend(
+                    (admitted_until..=latest)
+                        .step_by(self.pelt.jump)
+                        .filter(admit)
+                        .map(to_index),
+                );
+
+                latest + self.pelt.jump
+            }
+        };
+        self.admitted_until = self.admitted_until.max(next_admitted);
     }
 
     /// Calculate the proposed changepoint indices.
     #[inline]
-    fn proposed_indices(&self, signal_len: usize) -> impl Iterator<Item = usize> + use<> {
-        // Skip the minimum length to the next jump
-        let start = self
-            .pelt
-            .minimum_segment_length
-            // If it's zero nothing will be skipped
-            .saturating_sub(1)
-            // Also skip to the next jump position
-            .next_multiple_of(self.pelt.jump);
-
-        (start..signal_len)
-            // Take a index every "jump" items
-            .step_by(self.pelt.jump)
+    pub(crate) fn proposed_indices(
+        &self,
+        signal_len: usize,
+    ) -> impl Iterator<Item = usize> + use<> {
+        // The first segment must also last the minimum duration
+        let minimum_len = self.elapsed.as_ref().map_or(0, |elapsed| {
+            elapsed
+                .times
+                .partition_point(|time| *time < elapsed.minimum_duration)
+        });
+
+        // Skip the minimum length
+        let start = self.pelt.minimum_segment_length.max(minimum_len);
+
+        // Candidates given instead of the grid
+        let candidates = self.pelt.candidates.clone();
+        let grid = candidates.is_none().then(|| {
+            // Skip to the next jump position
+            (start.next_multiple_of(self.pelt.jump)..signal_len)
+                // Take a index every "jump" items
+                .step_by(self.pelt.jump)
+        });
+
+        grid.into_iter()
+            .flatten()
+            .chain(
+                candidates
+                    .into_iter()
+                    .flatten()
+                    .filter(move |candidate| (start..signal_len).contains(candidate)),
+            )
             // Add the last item
             .chain(std::iter::once(signal_len))
     }
 
     /// Split admissible into sub problems based on the breakpoint.
     #[inline]
-    fn split_into_subproblems<D>(
+    fn split_into_subproblems<D, P>(
         &mut self,
-        partitions: &HashMap<usize, Partition, FxBuildHasher>,
+        partitions: &Partitions,
         cost: &D::PrecalculationOutput,
         breakpoint: usize,
-        signal: &ArrayView<f64, D>,
-        penalty: f64,
+        signal_len: usize,
+        penalty: &P,
+        cache: Option<&LossCache>,
     ) -> Result<(), Error>
     where
         D: OneOrTwoDimensions + Dimension,
+        P: Penalty,
     {
         // We store the result but calculate everything even if it fails, so we can use extend
         let mut result = Ok(());
 
+        // Lowest loss and penalty sum of the subproblems so far, candidates that can't get below it plus the pruning penalty are pruned anyway
+        let mut best = f64::INFINITY;
+        let pruning_penalty = penalty.pruning_penalty(signal_len);
+
         let iter = self.admissible.iter().map(|admissible_start| {
             // Handle case where there's no partitions yet, shouldn't happen
             let Some(partition) = partitions.get(admissible_start) else {
@@ -173,10 +641,11 @@ impl PredictImpl {
                 // We have to return something
                 return Partition::default();
             };
+            let admissible_start = from_index(*admissible_start);
 
             // Handle invalid case for too short segments
             if branches::unlikely(
-                breakpoint.saturating_sub(*admissible_start) < self.pelt.minimum_segment_length,
+                breakpoint.saturating_sub(admissible_start) < self.pelt.minimum_segment_length,
             ) {
                 // Store the error
                 result = Err(Error::NotEnoughPoints);
@@ -185,13 +654,31 @@ impl PredictImpl {
                 return Partition::default();
             }
 
-            // Calculate loss function for the admissible range
-            let mut loss = 0.0;
-            D::loss(cost, &mut loss, signal, *admissible_start..breakpoint);
+            // Calculate loss function for the admissible range, or take it from a previous run
+            let loss = cache
+                .and_then(|cache| cache.get(admissible_start, breakpoint))
+                .unwrap_or_else(|| {
+                    // Stop calculating once the candidate would be pruned
+                    let bound = best + pruning_penalty
+                        - partition.loss_and_penalty_sum()
+                        - penalty.segment_penalty(admissible_start..breakpoint, signal_len);
+
+                    // Skip the exact loss when even a cheap lower bound of it is too high, the candidate is pruned anyway
+                    let lower_bound = D::loss_lower_bound(cost, admissible_start..breakpoint);
+                    if lower_bound > bound {
+                        return lower_bound;
+                    }
+
+                    let mut loss = 0.0;
+                    D::loss_bounded(cost, &mut loss, admissible_start..breakpoint, bound);
+
+                    loss
+                });
 
             // Update with the right partition
-            let mut new_partition = partition.clone();
-            new_partition.push(breakpoint, loss, penalty);
+            let mut new_partition = *partition;
+            new_partition.push(admissible_start..breakpoint, loss, penalty, signal_len);
+            best = best.min(new_partition.loss_and_penalty_sum());
 
             new_partition
         });
@@ -203,16 +690,18 @@ impl PredictImpl {
     /// Split admissible into sub problems based on the breakpoint, spread across threads.
     #[cfg(feature = "rayon")]
     #[inline]
-    fn par_split_into_subproblems<D>(
+    fn par_split_into_subproblems<D, P>(
         &mut self,
-        partitions: &HashMap<usize, Partition, FxBuildHasher>,
+        partitions: &Partitions,
         cost: &D::PrecalculationOutput,
         breakpoint: usize,
-        signal: &ArrayView<f64, D>,
-        penalty: f64,
+        signal_len: usize,
+        penalty: &P,
+        cache: Option<&LossCache>,
     ) -> Result<(), Error>
     where
         D: OneOrTwoDimensions + Dimension,
+        P: Penalty,
         D::PrecalculationOutput: Sync,
     {
         use rayon::iter::{
@@ -235,10 +724,11 @@ impl PredictImpl {
                 // We have to return something
                 return Partition::default();
             };
+            let admissible_start = from_index(*admissible_start);
 
             // Handle invalid case for too short segments
             if branches::unlikely(
-                breakpoint.saturating_sub(*admissible_start) < self.pelt.minimum_segment_length,
+                breakpoint.saturating_sub(admissible_start) < self.pelt.minimum_segment_length,
             ) {
                 // Store the error
                 error.store(Error::NotEnoughPoints.into_error_u8(), Ordering::Relaxed);
@@ -247,13 +737,19 @@ impl PredictImpl {
                 return Partition::default();
             }
 
-            // Calculate loss function for the admissible range
-            let mut loss = 0.0;
-            D::loss(cost, &mut loss, signal, *admissible_start..breakpoint);
+            // Calculate loss function for the admissible range, or take it from a previous run
+            let loss = cache
+                .and_then(|cache| cache.get(admissible_start, breakpoint))
+                .unwrap_or_else(|| {
+                    let mut loss = 0.0;
+                    D::loss(cost, &mut loss, admissible_start..breakpoint);
+
+                    loss
+                });
 
             // Update with the right partition
-            let mut new_partition = partition.clone();
-            new_partition.push(breakpoint, loss, penalty);
+            let mut new_partition = *partition;
+            new_partition.push(admissible_start..breakpoint, loss, penalty, signal_len);
 
             new_partition
         });
@@ -264,20 +760,30 @@ impl PredictImpl {
     }
 }
 
-/// A single partition.
-#[derive(Clone)]
-struct Partition {
-    /// End of ranges it applies to.
-    ranges: SmallVec<usize, 8>,
+/// Optimal partition of the signal up to a breakpoint.
+///
+/// Only the start of the last segment is stored, the partition before it is stored at that index.
+/// This avoids copying all changepoints for every subproblem, they're reconstructed once at the end.
+#[derive(Clone, Copy, Default)]
+// Without padding after the index the partition takes 12 instead of 16 bytes
+#[cfg_attr(feature = "u32-indices", repr(C, packed(4)))]
+pub(crate) struct Partition {
+    /// Start of the last segment, zero for the empty partition.
+    pub(crate) previous: Index,
     /// Sum of all loss and penalty values.
-    loss_and_penalty_sum: f64,
+    pub(crate) loss_and_penalty_sum: f64,
 }
 
 impl Partition {
-    /// Push a new value.
+    /// Push a new segment.
     #[inline]
-    pub fn push(&mut self, range: usize, loss: f64, penalty: f64) {
-        self.ranges.push(range);
+    pub fn push<P>(&mut self, segment: Range<usize>, loss: f64, penalty: &P, signal_len: usize)
+    where
+        P: Penalty,
+    {
+        let penalty = penalty.segment_penalty(segment.clone(), signal_len);
+
+        self.previous = to_index(segment.start);
 
         self.loss_and_penalty_sum = self.loss_and_penalty_sum + loss + penalty;
     }
@@ -289,16 +795,6 @@ impl Partition {
     }
 }
 
-impl Default for Partition {
-    #[inline]
-    fn default() -> Self {
-        Self {
-            ranges: SmallVec::new(),
-            loss_and_penalty_sum: 0.0,
-        }
-    }
-}
-
 #[cfg(test)]
 mod tests {
     use std::num::NonZero;
@@ -329,5 +825,140 @@ mod tests {
             .collect::<Vec<_>>(),
             vec![10, 15, 20]
         );
+
+        assert_eq!(
+            PredictImpl::new(
+                Pelt::new()
+                    .with_jump(NonZero::new(1).expect("Invalid number"))
+                    .with_minimum_segment_length(NonZero::new(2).expect("Invalid number"))
+            )
+            .proposed_indices(5)
+            .collect::<Vec<_>>(),
+            vec![2, 3, 4, 5]
+        );
+    }
+
+    /// Compact indices remove the padding from the stored partitions.
+    #[cfg(feature = "u32-indices")]
+    #[test]
+    fn compact_partition() {
+        assert_eq!(
+            size_of::<Partition>(),
+            12,
+            "Partition should be an index and a float without padding"
+        );
+    }
+
+    /// Threading in a small thread pool from the first admissible index gives the same results.
+    #[cfg(feature = "rayon")]
+    #[test]
+    fn thread_pool() {
+        let signal = (0..300)
+            .map(|index| f64::from(index / 100) + if index % 2 == 0 { 0.1 } else { -0.1 })
+            .collect::<Vec<_>>();
+
+        let pelt = Pelt::new().with_jump(NonZero::new(1).expect("Invalid number"));
+        let thread_pool = rayon::ThreadPoolBuilder::new()
+            .num_threads(2)
+            .build()
+            .expect("Error building thread pool");
+        let threaded = pelt
+            .clone()
+            .with_threading_threshold(NonZero::new(1).expect("Invalid number"))
+            .with_thread_pool(std::sync::Arc::new(thread_pool));
+
+        assert_eq!(
+            threaded.predict(&signal, 1.0).expect("Error predicting"),
+            pelt.predict(&signal, 1.0).expect("Error predicting")
+        );
+    }
+
+    /// Setting a thread pool keeps the auto traits of the settings.
+    #[cfg(feature = "rayon")]
+    #[test]
+    fn thread_pool_auto_traits() {
+        const fn assert_auto_traits<T>()
+        where
+            T: Send + Sync + Unpin + std::panic::UnwindSafe + std::panic::RefUnwindSafe,
+        {
+        }
+
+        assert_auto_traits::<Pelt>();
+    }
+
+    /// A full loss cache gives the same results as an unlimited one.
+    #[test]
+    fn loss_cache_capacity() {
+        let signal = (0..100)
+            .map(|index| f64::from(index / 30) + if index % 2 == 0 { 0.1 } else { -0.1 })
+            .collect::<Vec<_>>();
+        let penalties = [0.1, 1.0, 10.0];
+
+        let pelt = Pelt::new().with_segment_cost_function(crate::SegmentCostFunction::L2);
+        let limited = pelt
+            .clone()
+            .with_loss_cache_capacity(NonZero::new(10).expect("Invalid number"));
+
+        assert_eq!(
+            limited
+                .predict_many(&signal, &penalties)
+                .expect("Error predicting"),
+            pelt.predict_many(&signal, &penalties)
+                .expect("Error predicting")
+        );
+
+        // The cache stops growing at the capacity
+        let mut predict = PredictImpl::new(limited);
+        predict.enable_cache();
+        predict
+            .predict(&ndarray::aview1(&signal), &1.0)
+            .expect("Error predicting");
+        assert_eq!(predict.cache.map(|cache| cache.losses.len()), Some(10));
+    }
+
+    /// Candidates on every index match a jump of one, other candidates restrict the changepoints.
+    #[test]
+    fn candidates() {
+        let signal = (0..100)
+            .map(|index| f64::from(index / 30) + if index % 2 == 0 { 0.1 } else { -0.1 })
+            .collect::<Vec<_>>();
+        let pelt = Pelt::new().with_segment_cost_function(crate::SegmentCostFunction::L2);
+
+        assert_eq!(
+            pelt.clone()
+                .with_candidates(&(0..100).collect::<Vec<_>>())
+                .predict(&signal, 1.0)
+                .expect("Error predicting"),
+            pelt.clone()
+                .with_jump(NonZero::<usize>::MIN)
+                .predict(&signal, 1.0)
+                .expect("Error predicting")
+        );
+        assert_eq!(
+            pelt.with_candidates(&[61, 29, 45, 29, 0, 500])
+                .predict(&signal, 1.0)
+                .expect("Error predicting"),
+            vec![29, 61, 100]
+        );
+    }
+
+    /// The best allowed changepoint is found instead of the forbidden one, also with a changepoint limit.
+    #[test]
+    fn forbidden_ranges() {
+        let signal = [0.0, 0.0, 0.0, 0.0, 5.0, 5.0, 5.0, 5.0];
+        let pelt = Pelt::new()
+            .with_jump(NonZero::<usize>::MIN)
+            .with_segment_cost_function(crate::SegmentCostFunction::L2)
+            .with_forbidden_ranges(&[3..6, 4..6]);
+
+        assert_eq!(
+            pelt.predict(&signal, 5.0).expect("Error predicting"),
+            vec![3, 8]
+        );
+        assert_eq!(
+            pelt.predict_n_changepoints(&signal, 1)
+                .expect("Error predicting"),
+            vec![3, 8]
+        );
     }
 }
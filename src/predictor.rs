@@ -0,0 +1,84 @@
+//! Reusable predictor for many signals.
+
+use ndarray::{AsArray, Dimension};
+
+use crate::{Error, OneOrTwoDimensions, Pelt, Penalty, Sample, predict::PredictImpl};
+
+/// PELT that keeps its buffers alive between calls.
+///
+/// [`Pelt::predict`] allocates the partitions, the admissible indices and the subproblems for every call.
+/// This keeps them, so segmenting many short signals one after the other only allocates when a signal is longer than all previous ones.
+/// The result is the same as [`Pelt::predict`].
+#[derive(Clone)]
+pub struct PeltPredictor {
+    /// Solver with the buffers of the previous calls.
+    predict: PredictImpl,
+}
+
+impl PeltPredictor {
+    /// Create the predictor with empty buffers.
+    #[must_use]
+    pub fn new(pelt: Pelt) -> Self {
+        Self {
+            predict: PredictImpl::new(pelt),
+        }
+    }
+
+    /// Fit on a data set, reusing the buffers of the previous calls.
+    ///
+    /// The penalty can be a constant [`f64`] or anything implementing [`Penalty`].
+    ///
+    /// # Errors
+    ///
+    /// - When the input is invalid.
+    /// - When anything went wrong during calculation.
+    pub fn predict<'a, A, D, P>(
+        &mut self,
+        signal: impl AsArray<'a, A, D>,
+        penalty: P,
+    ) -> Result<Vec<usize>, Error>
+    where
+        A: Sample + 'a,
+        D: OneOrTwoDimensions + Dimension,
+        P: Penalty,
+        D::PrecalculationOutput: Sync,
+    {
+        let signal_view = self.predict.pelt.time_major(signal.into())?;
+
+        // Ensure the cost function parameters are correct
+        self.predict.pelt.segment_cost_function.validate()?;
+
+        // Try to lower 2D to 1D to parse as 1D array, since that's faster
+        match D::try_as_1d(&signal_view) {
+            // Predict as 1D array
+            Some(signal_1d) => self.predict.predict(&signal_1d, &penalty),
+            // Predict as 2D array
+            None => self.predict.predict(&signal_view, &penalty),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::SegmentCostFunction;
+
+    use super::*;
+
+    /// Reusing the buffers for signals of different lengths gives the same results as fresh runs.
+    #[test]
+    fn reuse() {
+        let pelt = Pelt::new().with_segment_cost_function(SegmentCostFunction::L2);
+        let mut predictor = PeltPredictor::new(pelt.clone());
+
+        for len in [100, 40, 200, 60] {
+            let signal = (0..len)
+                .map(|index| f64::from(index * 3 / len) + if index % 2 == 0 { 0.1 } else { -0.1 })
+                .collect::<Vec<_>>();
+
+            assert_eq!(
+                predictor.predict(&signal, 1.0).expect("Error predicting"),
+                pelt.predict(&signal, 1.0).expect("Error predicting")
+            );
+        }
+    }
+}
@@ -1,8 +1,16 @@
 //! Python bindings.
 
-use pyo3::{PyErr, exceptions::PyRuntimeError};
+use std::num::NonZero;
 
-use crate::Error;
+use ndarray::{ArrayView, ArrayViewD, Dimension, Ix1, Ix2};
+use numpy::PyArray1;
+use pyo3::{
+    Bound, FromPyObject, IntoPyObject as _, PyAny, PyErr, PyResult, Python,
+    exceptions::{PyRuntimeError, PyValueError},
+    types::{PyAnyMethods as _, PyTypeMethods as _},
+};
+
+use crate::{Error, InformationCriterion, OneOrTwoDimensions, Pelt, SegmentCostFunction};
 
 /// Convert Rust to Python error.
 impl From<Error> for PyErr {
@@ -11,30 +19,196 @@ impl From<Error> for PyErr {
     }
 }
 
+/// Penalty argument, either a number or the name of an information criterion.
+#[derive(FromPyObject)]
+enum Penalty {
+    /// Fixed penalty value.
+    Value(f64),
+    /// Name of the information criterion.
+    Criterion(String),
+}
+
+impl Penalty {
+    /// Run the prediction with the penalty.
+    fn predict<D>(&self, setup: &Pelt, signal: ArrayView<f64, D>) -> PyResult<Vec<usize>>
+    where
+        D: OneOrTwoDimensions + Dimension,
+        D::PrecalculationOutput: Sync,
+    {
+        let indices = match self {
+            Self::Value(penalty) => setup.predict(signal, *penalty)?,
+            Self::Criterion(criterion) => {
+                // Map input parameter to enum
+                let information_criterion = match criterion.as_str() {
+                    "bic" => InformationCriterion::Bic,
+                    "aic" => InformationCriterion::Aic,
+                    "mbic" => InformationCriterion::ModifiedBic,
+                    // Handle unknown case
+                    _ => {
+                        return Err(PyValueError::new_err(
+                            "penalty must be a number, 'bic', 'aic' or 'mbic'",
+                        ));
+                    }
+                };
+
+                setup.predict_auto_penalty(signal, information_criterion)?
+            }
+        };
+
+        Ok(indices)
+    }
+}
+
+/// Construct the setup from the arguments of the bindings.
+fn setup(
+    segment_cost_function: &str,
+    jump: usize,
+    minimum_segment_length: usize,
+    max_changepoints: Option<usize>,
+    quantile: f64,
+) -> PyResult<Pelt> {
+    // Map input parameter to enum
+    let segment_cost_function = match segment_cost_function {
+        "l1" => SegmentCostFunction::L1,
+        "l2" => SegmentCostFunction::L2,
+        "quantile" => SegmentCostFunction::Quantile(quantile),
+        // Handle unknown case
+        _ => {
+            return Err(PyValueError::new_err(
+                "segment_cost_function must be 'l1', 'l2' or 'quantile'",
+            ));
+        }
+    };
+
+    // Convert types
+    let jump = NonZero::new(jump).ok_or_else(|| PyValueError::new_err("jump must be > 0"))?;
+    let minimum_segment_length = NonZero::new(minimum_segment_length)
+        .ok_or_else(|| PyValueError::new_err("minimum_segment_length must be > 0"))?;
+
+    let mut setup = Pelt::new()
+        .with_segment_cost_function(segment_cost_function)
+        .with_jump(jump)
+        .with_minimum_segment_length(minimum_segment_length);
+    if let Some(max_changepoints) = max_changepoints {
+        setup = setup.with_max_changepoints(max_changepoints);
+    }
+
+    Ok(setup)
+}
+
+/// Run the prediction on a 1D or 2D signal.
+fn predict_dyn(setup: &Pelt, signal: ArrayViewD<f64>, penalty: &Penalty) -> PyResult<Vec<usize>> {
+    // Try to coerce the input into a dimension we can use
+    match signal.ndim() {
+        1 => penalty.predict(
+            setup,
+            signal
+                .into_dimensionality::<Ix1>()
+                .map_err(|_| PyValueError::new_err("dimension mismatch"))?,
+        ),
+        2 => penalty.predict(
+            setup,
+            signal
+                .into_dimensionality::<Ix2>()
+                .map_err(|_| PyValueError::new_err("dimension mismatch"))?,
+        ),
+        _ => Err(PyValueError::new_err(
+            "signal array dimensions must be 1 or 2",
+        )),
+    }
+}
+
+/// Run the prediction on many 1D or 2D signals, spread over threads.
+fn predict_many(
+    setup: &Pelt,
+    signals: &[ArrayViewD<f64>],
+    penalty: &Penalty,
+) -> PyResult<Vec<Vec<usize>>> {
+    #[cfg(feature = "rayon")]
+    {
+        use rayon::iter::{IntoParallelRefIterator as _, ParallelIterator as _};
+
+        signals
+            .par_iter()
+            .map(|signal| predict_dyn(setup, signal.view(), penalty))
+            .collect()
+    }
+
+    #[cfg(not(feature = "rayon"))]
+    signals
+        .iter()
+        .map(|signal| predict_dyn(setup, signal.view(), penalty))
+        .collect()
+}
+
+/// Index of a pandas object when it's a `DatetimeIndex`, without importing pandas.
+fn datetime_index<'py>(signal: &Bound<'py, PyAny>) -> PyResult<Option<Bound<'py, PyAny>>> {
+    let Ok(index) = signal.getattr("index") else {
+        return Ok(None);
+    };
+    let is_datetime_index = index.get_type().name()? == "DatetimeIndex";
+
+    Ok(is_datetime_index.then_some(index))
+}
+
+/// Convert the changepoints to Python, with the timestamps from the `DatetimeIndex` of the signal when it has one.
+fn changepoints<'py>(
+    py: Python<'py>,
+    indices: Vec<usize>,
+    datetime_index: Option<&Bound<'py, PyAny>>,
+) -> PyResult<Bound<'py, PyAny>> {
+    // The end of the signal has no timestamp
+    let positions = indices
+        .split_last()
+        .map_or_else(Vec::new, |(_end, positions)| positions.to_vec());
+    let indices = PyArray1::from_vec(py, indices);
+
+    match datetime_index {
+        Some(datetime_index) => {
+            let timestamps = datetime_index.call_method1("take", (positions,))?;
+
+            Ok((indices, timestamps).into_pyobject(py)?.into_any())
+        }
+        None => Ok(indices.into_any()),
+    }
+}
+
 #[pyo3::pymodule]
 mod pelt {
-    use std::num::NonZero;
-
-    use numpy::{Ix1, Ix2, PyArray1, PyArrayLikeDyn};
-    use pyo3::{exceptions::PyValueError, prelude::*};
+    use ndarray::ArrayD;
+    use numpy::{AllowTypeChange, PyArray1, PyArrayLikeDyn, PyReadonlyArrayDyn, ToPyArray as _};
+    use pyo3::{
+        exceptions::{PyRuntimeError, PyTypeError, PyValueError},
+        prelude::*,
+        types::PyDict,
+    };
 
-    use crate::{Pelt, SegmentCostFunction};
+    use super::Penalty;
 
     /// Calculate the changepoints.
     ///
+    /// The GIL is released during the calculation, so other Python threads can run.
+    ///
     /// Arguments
     /// ---------
-    /// signal : :py:class:`numpy.typing.NDArray[numpy.float64] <numpy.typing.NDArray>`
-    ///     1D or 2D input signal array. Can only contain numbers. ``None`` values are not accepted.
-    /// penalty : float
+    /// signal : :py:class:`numpy.typing.ArrayLike`
+    ///     1D or 2D input signal, such as a NumPy array or a pandas ``Series`` or ``DataFrame``.
+    ///     Every row is a sample and is converted to floating point. ``None`` values are not accepted.
+    /// penalty : float or str
     ///     Penalty value for each changepoint added. Larger values result in fewer
     ///     changepoints detected.
+    ///     Can also be the name of an information criterion to derive the penalty from the signal:
+    ///     
+    ///     * ``"bic"`` - Bayesian information criterion
+    ///     * ``"aic"`` - Akaike information criterion
+    ///     * ``"mbic"`` - Modified Bayesian information criterion, also penalizes short segments
     /// segment_cost_function : str, optional
     ///     Determines how the cost of each potential segment is calculated.
     ///     Must be one of:
     ///     
     ///     * ``"l1"`` - L1 cost function (least absolute deviation)
     ///     * ``"l2"`` - L2 cost function (least squared deviation)
+    ///     * ``"quantile"`` - Quantile cost function (pinball deviation around ``quantile``)
     ///     
     ///     Defaults to ``"l1"``.
     /// jump : int, optional
@@ -45,12 +219,21 @@ mod pelt {
     /// minimum_segment_length : int, optional
     ///     Minimum number of allowable number of data points within a segment.
     ///     Must be positive. Defaults to 2.
+    /// max_changepoints : int, optional
+    ///     Maximum number of allowable changepoints, raises an error when more are found.
+    ///     Defaults to ``None``, which means unlimited.
+    /// quantile : float, optional
+    ///     Quantile to target when ``segment_cost_function`` is ``"quantile"``.
+    ///     Must be larger than 0 and smaller than 1. Defaults to 0.5.
     ///
     /// Returns
     /// -------
     /// :py:class:`numpy.typing.NDArray[numpy.uint64] <numpy.typing.NDArray>`
     ///     1D array of zero-based indices where changes in the signal were detected.
     ///
+    ///     When the signal has a pandas ``DatetimeIndex``, a tuple of the indices and a ``DatetimeIndex`` of the
+    ///     timestamps of the changepoints, without the end of the signal.
+    ///
     /// Raises
     /// ------
     /// ValueError
@@ -63,60 +246,359 @@ mod pelt {
     /// >>> changepoints = predict(signal, penalty=20.0, segment_cost_function="l1", jump=10, minimum_segment_length=2)
     /// >>> print(changepoints)
     ///
-    #[pyfunction(signature = (signal, penalty, segment_cost_function = "l1", jump = 10, minimum_segment_length = 2))]
+    // Keyword arguments are idiomatic in Python
+    #[allow(clippy::too_many_arguments)]
+    #[pyfunction(signature = (signal, penalty, segment_cost_function = "l1", jump = 10, minimum_segment_length = 2, max_changepoints = None, quantile = 0.5))]
     fn predict<'py>(
         py: Python<'py>,
-        signal: PyArrayLikeDyn<'py, f64>,
-        penalty: f64,
+        signal: &Bound<'py, PyAny>,
+        penalty: Penalty,
         segment_cost_function: &str,
         jump: usize,
         minimum_segment_length: usize,
-    ) -> PyResult<Bound<'py, PyArray1<usize>>> {
-        // Map input parameter to enum
-        let segment_cost_function = match segment_cost_function {
-            "l1" => SegmentCostFunction::L1,
-            "l2" => SegmentCostFunction::L2,
-            // Handle unknown case
-            _ => {
-                return Err(PyValueError::new_err(
-                    "segment_cost_function must be 'l1' or 'l2'",
-                ));
+        max_changepoints: Option<usize>,
+        quantile: f64,
+    ) -> PyResult<Bound<'py, PyAny>> {
+        let setup = super::setup(
+            segment_cost_function,
+            jump,
+            minimum_segment_length,
+            max_changepoints,
+            quantile,
+        )?;
+        let array = signal.extract::<PyArrayLikeDyn<'py, f64, AllowTypeChange>>()?;
+        let view = array.as_array();
+
+        // Other Python threads can run during the calculation
+        let indices = py.detach(|| super::predict_dyn(&setup, view, &penalty))?;
+
+        super::changepoints(py, indices, super::datetime_index(signal)?.as_ref())
+    }
+
+    /// Calculate the changepoints of many signals, spread over threads.
+    ///
+    /// Arguments
+    /// ---------
+    /// signals : list of :py:class:`numpy.typing.ArrayLike`
+    ///     1D or 2D input signals, each is segmented on its own.
+    /// penalty : float or str
+    ///     Penalty value for each changepoint added, or the name of an information criterion, see ``predict``.
+    ///
+    /// The other arguments are the same as for ``predict``.
+    ///
+    /// Returns
+    /// -------
+    /// list of :py:class:`numpy.typing.NDArray[numpy.uint64] <numpy.typing.NDArray>`
+    ///     1D array of zero-based indices where changes were detected for each signal, in the same order.
+    ///
+    /// Raises
+    /// ------
+    /// ValueError
+    ///     If an array has invalid dimensions or if any of the parameters are
+    ///     outside their valid ranges.
+    ///
+    /// Examples
+    /// --------
+    /// >>> from pelt import predict_batch
+    /// >>> changepoints = predict_batch([signal_a, signal_b], penalty=20.0)
+    ///
+    // Keyword arguments are idiomatic in Python
+    #[allow(clippy::too_many_arguments)]
+    #[pyfunction(signature = (signals, penalty, segment_cost_function = "l1", jump = 10, minimum_segment_length = 2, max_changepoints = None, quantile = 0.5))]
+    fn predict_batch<'py>(
+        py: Python<'py>,
+        signals: Vec<PyArrayLikeDyn<'py, f64, AllowTypeChange>>,
+        penalty: Penalty,
+        segment_cost_function: &str,
+        jump: usize,
+        minimum_segment_length: usize,
+        max_changepoints: Option<usize>,
+        quantile: f64,
+    ) -> PyResult<Vec<Bound<'py, PyArray1<usize>>>> {
+        let setup = super::setup(
+            segment_cost_function,
+            jump,
+            minimum_segment_length,
+            max_changepoints,
+            quantile,
+        )?;
+        let views = signals
+            .iter()
+            .map(|signal| signal.as_array())
+            .collect::<Vec<_>>();
+
+        // Other Python threads can run during the calculation
+        let indices = py.detach(|| super::predict_many(&setup, &views, &penalty))?;
+
+        Ok(indices
+            .into_iter()
+            .map(|indices| PyArray1::from_vec(py, indices))
+            .collect())
+    }
+
+    /// Changepoint detection estimator, compatible with ``ruptures.Pelt`` and scikit-learn.
+    ///
+    /// Arguments
+    /// ---------
+    /// model : str, optional
+    ///     Segment cost function, ``"l1"``, ``"l2"`` or ``"quantile"``. Defaults to ``"l2"``.
+    /// min_size : int, optional
+    ///     Minimum number of data points within a segment. Must be positive. Defaults to 2.
+    /// jump : int, optional
+    ///     Step size between candidate changepoint positions. Must be > 0. Defaults to 5.
+    /// max_changepoints : int, optional
+    ///     Maximum number of allowable changepoints, raises an error when more are found.
+    ///     Defaults to ``None``, which means unlimited.
+    /// quantile : float, optional
+    ///     Quantile to target when ``model`` is ``"quantile"``. Defaults to 0.5.
+    ///
+    /// Examples
+    /// --------
+    /// >>> from pelt import Pelt
+    /// >>> changepoints = Pelt(model="l1", jump=1).fit(signal).predict(pen=20.0)
+    /// >>> print(changepoints)
+    ///
+    #[pyclass(name = "Pelt", module = "pelt")]
+    struct Estimator {
+        /// Segment cost function.
+        model: String,
+        /// Minimum number of data points within a segment.
+        min_size: usize,
+        /// Step size between candidate changepoint positions.
+        jump: usize,
+        /// Maximum number of allowable changepoints, unlimited if `None`.
+        max_changepoints: Option<usize>,
+        /// Quantile to target with the quantile cost.
+        quantile: f64,
+        /// Signal of the last fit, `None` before fitting.
+        signal: Option<ArrayD<f64>>,
+        /// Pandas `DatetimeIndex` of the signal of the last fit, if it has one.
+        datetime_index: Option<Py<PyAny>>,
+    }
+
+    impl Estimator {
+        /// Set a parameter by name.
+        fn set_param(&mut self, key: &str, value: &Bound<'_, PyAny>) -> PyResult<()> {
+            match key {
+                "model" => self.model = value.extract()?,
+                "min_size" => self.min_size = value.extract()?,
+                "jump" => self.jump = value.extract()?,
+                "max_changepoints" => self.max_changepoints = value.extract()?,
+                "quantile" => self.quantile = value.extract()?,
+                key => {
+                    return Err(PyTypeError::new_err(format!(
+                        "invalid parameter '{key}' for estimator Pelt"
+                    )));
+                }
             }
-        };
 
-        // Convert types
-        let jump = NonZero::new(jump).ok_or_else(|| PyValueError::new_err("jump must be > 0"))?;
-        let minimum_segment_length = NonZero::new(minimum_segment_length)
-            .ok_or_else(|| PyValueError::new_err("minimum_segment_length must be > 0"))?;
-
-        // Do calculation
-        let setup = Pelt::new()
-            .with_segment_cost_function(segment_cost_function)
-            .with_jump(jump)
-            .with_minimum_segment_length(minimum_segment_length);
-
-        // Try to coerce the input into a dimension we can use
-        let signal = signal.as_array();
-        let indices = match signal.ndim() {
-            1 => setup.predict(
-                signal
-                    .into_dimensionality::<Ix1>()
-                    .map_err(|_| PyValueError::new_err("dimension mismatch"))?,
-                penalty,
-            )?,
-            2 => setup.predict(
-                signal
-                    .into_dimensionality::<Ix2>()
-                    .map_err(|_| PyValueError::new_err("dimension mismatch"))?,
-                penalty,
-            )?,
-            _ => {
+            Ok(())
+        }
+
+        /// Construct the setup from the parameters.
+        fn setup(&self) -> PyResult<crate::Pelt> {
+            super::setup(
+                &self.model,
+                self.jump,
+                self.min_size,
+                self.max_changepoints,
+                self.quantile,
+            )
+        }
+    }
+
+    #[pymethods]
+    impl Estimator {
+        /// Construct the estimator, failing on invalid parameters.
+        #[new]
+        #[pyo3(signature = (model = "l2", min_size = 2, jump = 5, max_changepoints = None, quantile = 0.5))]
+        fn new(
+            model: &str,
+            min_size: usize,
+            jump: usize,
+            max_changepoints: Option<usize>,
+            quantile: f64,
+        ) -> PyResult<Self> {
+            let estimator = Self {
+                model: model.to_owned(),
+                min_size,
+                jump,
+                max_changepoints,
+                quantile,
+                signal: None,
+                datetime_index: None,
+            };
+
+            // Fail early on invalid parameters
+            estimator.setup()?;
+
+            Ok(estimator)
+        }
+
+        /// Store the signal to segment.
+        ///
+        /// Arguments
+        /// ---------
+        /// signal : :py:class:`numpy.typing.ArrayLike`
+        ///     1D or 2D input signal, such as a NumPy array or a pandas ``Series`` or ``DataFrame``.
+        ///     Every row is a sample and is converted to floating point.
+        ///
+        /// Returns
+        /// -------
+        /// Pelt
+        ///     The estimator itself.
+        fn fit<'py>(
+            mut slf: PyRefMut<'py, Self>,
+            signal: &Bound<'py, PyAny>,
+        ) -> PyResult<PyRefMut<'py, Self>> {
+            let array = signal.extract::<PyArrayLikeDyn<'py, f64, AllowTypeChange>>()?;
+            let array = array.as_array();
+            if !matches!(array.ndim(), 1 | 2) {
                 return Err(PyValueError::new_err(
                     "signal array dimensions must be 1 or 2",
                 ));
             }
-        };
+            slf.signal = Some(array.to_owned());
+            slf.datetime_index = super::datetime_index(signal)?.map(Bound::unbind);
+
+            Ok(slf)
+        }
+
+        /// Calculate the changepoints of the fitted signal.
+        ///
+        /// Arguments
+        /// ---------
+        /// pen : float or str
+        ///     Penalty value for each changepoint added, or the name of an information criterion
+        ///     ``"bic"``, ``"aic"`` or ``"mbic"``.
+        ///
+        /// Returns
+        /// -------
+        /// :py:class:`numpy.typing.NDArray[numpy.uint64] <numpy.typing.NDArray>`
+        ///     1D array of indices where changes were detected, including the end of the signal.
+        ///
+        ///     When the signal has a pandas ``DatetimeIndex``, a tuple of the indices and a ``DatetimeIndex`` of the
+        ///     timestamps of the changepoints, without the end of the signal.
+        fn predict<'py>(&self, py: Python<'py>, pen: Penalty) -> PyResult<Bound<'py, PyAny>> {
+            let signal = self
+                .signal
+                .as_ref()
+                .ok_or_else(|| PyRuntimeError::new_err("call fit before predict"))?;
+            let setup = self.setup()?;
+            let view = signal.view();
+
+            // Other Python threads can run during the calculation
+            let indices = py.detach(|| super::predict_dyn(&setup, view, &pen))?;
+
+            super::changepoints(
+                py,
+                indices,
+                self.datetime_index
+                    .as_ref()
+                    .map(|datetime_index| datetime_index.bind(py)),
+            )
+        }
+
+        /// Fit on the signal and calculate its changepoints.
+        ///
+        /// See ``fit`` and ``predict``.
+        fn fit_predict<'py>(
+            slf: PyRefMut<'py, Self>,
+            py: Python<'py>,
+            signal: &Bound<'py, PyAny>,
+            pen: Penalty,
+        ) -> PyResult<Bound<'py, PyAny>> {
+            Self::fit(slf, signal)?.predict(py, pen)
+        }
+
+        /// Parameters of the estimator, as passed to the constructor.
+        #[pyo3(signature = (deep = true))]
+        fn get_params<'py>(&self, py: Python<'py>, deep: bool) -> PyResult<Bound<'py, PyDict>> {
+            // There are no nested estimators
+            let _ = deep;
+
+            let params = PyDict::new(py);
+            params.set_item("model", &self.model)?;
+            params.set_item("min_size", self.min_size)?;
+            params.set_item("jump", self.jump)?;
+            params.set_item("max_changepoints", self.max_changepoints)?;
+            params.set_item("quantile", self.quantile)?;
+
+            Ok(params)
+        }
+
+        /// Set parameters of the estimator by name.
+        ///
+        /// Returns
+        /// -------
+        /// Pelt
+        ///     The estimator itself.
+        #[pyo3(signature = (**params))]
+        fn set_params<'py>(
+            mut slf: PyRefMut<'py, Self>,
+            params: Option<&Bound<'py, PyDict>>,
+        ) -> PyResult<PyRefMut<'py, Self>> {
+            let Some(params) = params else {
+                return Ok(slf);
+            };
+
+            for (key, value) in params {
+                slf.set_param(&key.extract::<String>()?, &value)?;
+            }
+
+            // Fail early on invalid parameters
+            slf.setup()?;
+
+            Ok(slf)
+        }
+
+        /// Parameters and fitted signal, for pickling.
+        fn __getstate__<'py>(&self, py: Python<'py>) -> PyResult<Bound<'py, PyDict>> {
+            let state = self.get_params(py, true)?;
+            state.set_item(
+                "signal",
+                self.signal.as_ref().map(|signal| signal.to_pyarray(py)),
+            )?;
+            state.set_item("datetime_index", &self.datetime_index)?;
+
+            Ok(state)
+        }
+
+        /// Restore the parameters and fitted signal after unpickling.
+        fn __setstate__(&mut self, state: &Bound<'_, PyDict>) -> PyResult<()> {
+            for (key, value) in state {
+                match key.extract::<String>()?.as_str() {
+                    "signal" => {
+                        self.signal = value
+                            .extract::<Option<PyReadonlyArrayDyn<'_, f64>>>()?
+                            .map(|signal| signal.as_array().to_owned());
+                    }
+                    "datetime_index" => {
+                        self.datetime_index = (!value.is_none()).then(|| value.unbind());
+                    }
+                    key => self.set_param(key, &value)?,
+                }
+            }
+
+            // Fail early on invalid parameters
+            self.setup()?;
+
+            Ok(())
+        }
 
-        Ok(PyArray1::from_vec(py, indices))
+        /// Constructor call with the parameters, like scikit-learn estimators.
+        fn __repr__(&self) -> String {
+            format!(
+                "Pelt(model='{}', min_size={}, jump={}, max_changepoints={}, quantile={:?})",
+                self.model,
+                self.min_size,
+                self.jump,
+                self.max_changepoints.map_or_else(
+                    || "None".to_owned(),
+                    |max_changepoints| max_changepoints.to_string()
+                ),
+                self.quantile,
+            )
+        }
     }
 }
@@ -0,0 +1,93 @@
+//! Ranking of changepoints by their contribution to the objective.
+
+use ndarray::{ArrayView, Dimension};
+
+use crate::{Error, OneOrTwoDimensions, Sample, predict::PredictImpl, segmentation};
+
+impl PredictImpl {
+    /// Rank the changepoints by removing the weakest one until none are left, strongest first.
+    ///
+    /// The weakest changepoint has the smallest gain, the increase in loss of merging the segments before and after it.
+    /// After a removal the gains of the changepoints next to the merged segment are calculated again.
+    pub(crate) fn rank_changepoints<A, D>(
+        &self,
+        signal: &ArrayView<A, D>,
+        changepoints: &[usize],
+    ) -> Result<Vec<(usize, f64)>, Error>
+    where
+        A: Sample,
+        D: OneOrTwoDimensions + Dimension,
+    {
+        segmentation::check_changepoints(changepoints, D::len_or_nrows(signal))?;
+
+        let cost = self.precalculate(signal)?;
+        let loss = |range| {
+            let mut loss = 0.0;
+            D::loss(&cost, &mut loss, range);
+
+            loss
+        };
+        // Gain of the changepoint at the position, the end of the signal is never removed
+        let gain = |remaining: &[usize], position: usize| {
+            let start = position
+                .checked_sub(1)
+                .map_or(0, |before| remaining[before]);
+            let (changepoint, end) = (remaining[position], remaining[position + 1]);
+
+            loss(start..end) - loss(start..changepoint) - loss(changepoint..end)
+        };
+
+        let mut remaining = changepoints.to_vec();
+        let mut gains = (0..remaining.len() - 1)
+            .map(|position| gain(&remaining, position))
+            .collect::<Vec<_>>();
+
+        let mut ranking = Vec::with_capacity(gains.len());
+        while let Some((position, weakest)) = gains
+            .iter()
+            .copied()
+            .enumerate()
+            .min_by(|(_, left), (_, right)| left.total_cmp(right))
+        {
+            ranking.push((remaining.remove(position), weakest));
+            gains.remove(position);
+
+            // Only the changepoints around the merged segment have a different gain
+            for neighbour in position.saturating_sub(1)..(position + 1).min(gains.len()) {
+                gains[neighbour] = gain(&remaining, neighbour);
+            }
+        }
+
+        ranking.reverse();
+
+        Ok(ranking)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use ndarray::array;
+
+    use crate::{Pelt, SegmentCostFunction, predict::PredictImpl};
+
+    /// The largest change ranks first.
+    #[test]
+    fn rank_changepoints() {
+        let signal = array![0.0, 0.0, 0.0, 1.0, 1.0, 1.0, 10.0, 10.0, 10.0];
+        let predict =
+            PredictImpl::new(Pelt::new().with_segment_cost_function(SegmentCostFunction::L2));
+
+        let ranking = predict
+            .rank_changepoints(&signal.view(), &[3, 6, 9])
+            .expect("Error ranking");
+        assert_eq!(
+            ranking
+                .iter()
+                .map(|(changepoint, _)| *changepoint)
+                .collect::<Vec<_>>(),
+            vec![6, 3]
+        );
+        // Merging the first two segments costs 6 times a squared deviation of 0.5
+        assert!((ranking[1].1 - 1.5).abs() < 1e-12, "{ranking:?}");
+    }
+}
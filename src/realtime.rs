@@ -0,0 +1,257 @@
+//! Real-time detection on a bounded window.
+
+use std::{collections::VecDeque, num::NonZero};
+
+use crate::{Error, Pelt};
+
+/// Function called with the index of every confirmed changepoint.
+type Callback = Box<dyn FnMut(usize) + Send>;
+
+/// Real-time changepoint detector.
+///
+/// Keeps the last samples in a ring buffer and runs [`Pelt::predict`] on it every `cadence` samples.
+/// A changepoint is confirmed once it's at least `confirmation_delay` samples old, so later samples had the chance to move it.
+/// Every changepoint is confirmed once, with its index counted from the first pushed sample.
+///
+/// # Defaults
+///
+/// - `capacity`: `1000`
+/// - `cadence`: `10`
+/// - `confirmation_delay`: `50`
+/// - `max_latency`: unlimited
+pub struct RealtimeDetector {
+    /// Detection algorithm.
+    pelt: Pelt,
+    /// Penalty added for every segment.
+    penalty: f64,
+    /// Last samples.
+    buffer: VecDeque<f64>,
+    /// Maximum amount of samples in the buffer.
+    capacity: usize,
+    /// Amount of samples between detection runs.
+    cadence: usize,
+    /// Minimum age of a changepoint before it's confirmed.
+    confirmation_delay: usize,
+    /// Amount of samples pushed since the start.
+    position: usize,
+    /// Maximum age of a changepoint when it's confirmed, unlimited if `None`.
+    max_latency: Option<usize>,
+    /// Index of the last confirmed changepoint.
+    last_confirmed: Option<usize>,
+    /// Called for every confirmed changepoint.
+    callback: Option<Callback>,
+}
+
+impl RealtimeDetector {
+    /// Construct a new real-time detector with default values.
+    #[must_use]
+    pub fn new(pelt: Pelt, penalty: f64) -> Self {
+        Self {
+            pelt,
+            penalty,
+            buffer: VecDeque::new(),
+            capacity: 1000,
+            cadence: 10,
+            confirmation_delay: 50,
+            max_latency: None,
+            position: 0,
+            last_confirmed: None,
+            callback: None,
+        }
+    }
+
+    /// Set the maximum amount of samples in the ring buffer.
+    ///
+    /// Changepoints are only detected within the buffer, so it should be a lot longer than the confirmation delay.
+    #[must_use]
+    pub const fn with_capacity(mut self, capacity: NonZero<usize>) -> Self {
+        self.capacity = capacity.get();
+
+        self
+    }
+
+    /// Set the amount of samples between detection runs.
+    #[must_use]
+    pub const fn with_cadence(mut self, cadence: NonZero<usize>) -> Self {
+        self.cadence = cadence.get();
+
+        self
+    }
+
+    /// Set the minimum amount of samples after a changepoint before it's confirmed.
+    #[must_use]
+    pub const fn with_confirmation_delay(mut self, confirmation_delay: usize) -> Self {
+        self.confirmation_delay = confirmation_delay;
+
+        self
+    }
+
+    /// Guarantee every changepoint is confirmed at most `max_latency` samples after it occurs.
+    ///
+    /// The cadence and confirmation delay are shortened when needed, so every changepoint is seen by a detection run before the latency is exceeded.
+    /// Changepoints that are only found after the latency is exceeded are never confirmed, trading accuracy for a bounded latency.
+    #[must_use]
+    pub const fn with_max_latency(mut self, max_latency: NonZero<usize>) -> Self {
+        self.max_latency = Some(max_latency.get());
+
+        self
+    }
+
+    /// Set a function that's called with the index of every confirmed changepoint.
+    #[must_use]
+    pub fn with_callback<F>(mut self, callback: F) -> Self
+    where
+        F: FnMut(usize) + Send + 'static,
+    {
+        self.callback = Some(Box::new(callback));
+
+        self
+    }
+
+    /// Add the next sample.
+    ///
+    /// Returns the changepoints confirmed by this sample, which is empty when no detection ran.
+    ///
+    /// # Errors
+    ///
+    /// - When the input is invalid.
+    /// - When anything went wrong during calculation.
+    pub fn push(&mut self, value: f64) -> Result<Vec<usize>, Error> {
+        // Drop the oldest sample when full
+        if self.buffer.len() == self.capacity {
+            self.buffer.pop_front();
+        }
+        self.buffer.push_back(value);
+        self.position += 1;
+
+        // Run often enough to see every changepoint between the confirmation delay and the maximum latency
+        let (cadence, confirmation_delay) = match self.max_latency {
+            Some(max_latency) => {
+                let cadence = self.cadence.min(max_latency);
+
+                (
+                    cadence,
+                    self.confirmation_delay.min(max_latency + 1 - cadence),
+                )
+            }
+            None => (self.cadence, self.confirmation_delay),
+        };
+
+        // Only detect on the cadence, once there's enough samples for a segment
+        if !self.position.is_multiple_of(cadence)
+            || self.buffer.len() < self.pelt.minimum_segment_length
+        {
+            return Ok(Vec::new());
+        }
+
+        let changepoints = self
+            .pelt
+            .predict_changepoints(&*self.buffer.make_contiguous(), self.penalty)?;
+
+        // Index of the first sample in the buffer
+        let offset = self.position - self.buffer.len();
+
+        // The last changepoint is the end of the buffer, and the start of the buffer isn't a real changepoint
+        let confirmed = changepoints[..changepoints.len().saturating_sub(1)]
+            .iter()
+            .map(|changepoint| changepoint + offset)
+            .filter(|changepoint| {
+                *changepoint > offset
+                    && *changepoint + confirmation_delay <= self.position
+                    && self
+                        .max_latency
+                        .is_none_or(|max_latency| *changepoint + max_latency >= self.position)
+                    && self.last_confirmed.is_none_or(|last| *changepoint > last)
+            })
+            .collect::<Vec<_>>();
+
+        if let Some(last) = confirmed.last() {
+            self.last_confirmed = Some(*last);
+        }
+        if let Some(callback) = &mut self.callback {
+            confirmed
+                .iter()
+                .for_each(|changepoint| callback(*changepoint));
+        }
+
+        Ok(confirmed)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::{Arc, Mutex};
+
+    use super::*;
+
+    /// Confirm a step once, after the delay.
+    #[test]
+    fn step() {
+        let called = Arc::new(Mutex::new(Vec::new()));
+        let called_clone = Arc::clone(&called);
+
+        let mut detector = RealtimeDetector::new(
+            Pelt::new().with_segment_cost_function(crate::SegmentCostFunction::L2),
+            1.0,
+        )
+        .with_capacity(NonZero::new(100).expect("Invalid number"))
+        .with_confirmation_delay(20)
+        .with_callback(move |changepoint| {
+            called_clone
+                .lock()
+                .expect("Poisoned lock")
+                .push(changepoint);
+        });
+
+        let mut confirmed = Vec::new();
+        for index in 0..300 {
+            let value = if (150..300).contains(&index) {
+                5.0
+            } else {
+                0.0
+            };
+            let changepoints = detector.push(value).expect("Error detecting");
+
+            // Never confirmed before the delay
+            assert!(
+                changepoints
+                    .iter()
+                    .all(|changepoint| changepoint + 20 <= index + 1),
+                "Confirmed too early"
+            );
+            confirmed.extend(changepoints);
+        }
+
+        assert_eq!(confirmed, vec![150]);
+        assert_eq!(*called.lock().expect("Poisoned lock"), vec![150]);
+    }
+
+    /// Confirm a step before the maximum latency, even with a longer delay.
+    #[test]
+    fn max_latency() {
+        let mut detector = RealtimeDetector::new(
+            Pelt::new().with_segment_cost_function(crate::SegmentCostFunction::L2),
+            1.0,
+        )
+        .with_capacity(NonZero::new(100).expect("Invalid number"))
+        .with_confirmation_delay(50)
+        .with_max_latency(NonZero::new(15).expect("Invalid number"));
+
+        let mut confirmed = Vec::new();
+        for index in 0..300 {
+            let value = if index < 150 { 0.0 } else { 5.0 };
+            let changepoints = detector.push(value).expect("Error detecting");
+
+            // Never confirmed after the latency
+            assert!(
+                changepoints
+                    .iter()
+                    .all(|changepoint| changepoint + 15 > index),
+                "Confirmed too late"
+            );
+            confirmed.extend(changepoints);
+        }
+
+        assert_eq!(confirmed, vec![150]);
+    }
+}
@@ -0,0 +1,87 @@
+//! Refinement of approximate changepoints at full resolution.
+
+use ndarray::{ArrayView, Dimension};
+
+use crate::{Error, OneOrTwoDimensions, Sample, predict::PredictImpl, segmentation};
+
+impl PredictImpl {
+    /// Move every changepoint to the position within the radius with the lowest loss of the segments around it.
+    ///
+    /// The changepoints are refined from the start to the end, each one between the refined changepoint before it and the original one after it.
+    /// Positions making a segment shorter than the minimum segment length or inside a forbidden range are skipped, a changepoint only moves when the loss decreases.
+    pub(crate) fn refine<A, D>(
+        &self,
+        signal: &ArrayView<A, D>,
+        changepoints: &[usize],
+        radius: usize,
+    ) -> Result<Vec<usize>, Error>
+    where
+        A: Sample,
+        D: OneOrTwoDimensions + Dimension,
+    {
+        segmentation::check_changepoints(changepoints, D::len_or_nrows(signal))?;
+
+        let cost = self.precalculate(signal)?;
+        let loss = |range| {
+            let mut loss = 0.0;
+            D::loss(&cost, &mut loss, range);
+
+            loss
+        };
+
+        let minimum_segment_length = self.pelt.minimum_segment_length;
+        let mut refined = changepoints.to_vec();
+        // The end of the signal never moves
+        for position in 0..refined.len() - 1 {
+            let start = position.checked_sub(1).map_or(0, |before| refined[before]);
+            let (changepoint, end) = (refined[position], refined[position + 1]);
+            let split_loss = |split| loss(start..split) + loss(split..end);
+
+            let nearby = changepoint
+                .saturating_sub(radius)
+                .max(start + minimum_segment_length)
+                ..=(changepoint + radius).min(end.saturating_sub(minimum_segment_length));
+            let (best, _) = nearby
+                .filter(|split| !self.pelt.is_forbidden(*split))
+                .map(|split| (split, split_loss(split)))
+                .fold((changepoint, split_loss(changepoint)), |best, candidate| {
+                    if candidate.1 < best.1 {
+                        candidate
+                    } else {
+                        best
+                    }
+                });
+            refined[position] = best;
+        }
+
+        Ok(refined)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use ndarray::array;
+
+    use crate::{Pelt, SegmentCostFunction, predict::PredictImpl};
+
+    /// Changepoints move to the exact changes, but not further than the radius.
+    #[test]
+    fn refine() {
+        let signal = array![0.0, 0.0, 0.0, 1.0, 1.0, 1.0, 1.0, 10.0, 10.0, 10.0];
+        let predict =
+            PredictImpl::new(Pelt::new().with_segment_cost_function(SegmentCostFunction::L2));
+
+        assert_eq!(
+            predict
+                .refine(&signal.view(), &[2, 5, 10], 1)
+                .expect("Error refining"),
+            vec![3, 6, 10]
+        );
+        assert_eq!(
+            predict
+                .refine(&signal.view(), &[2, 5, 10], 2)
+                .expect("Error refining"),
+            vec![3, 7, 10]
+        );
+    }
+}
@@ -0,0 +1,662 @@
+//! Segmentation with statistics for every segment.
+
+use std::ops::Range;
+
+use ndarray::{Array2, ArrayView, ArrayView1, ArrayView2, AsArray, Axis, Dimension, Slice};
+
+use crate::{
+    Error, OneOrTwoDimensions, Pelt, Penalty, Sample, SegmentCostFunction, compare::bic,
+    predict::PredictImpl,
+};
+
+/// Single segment of a segmentation, with a statistic for each column of the signal.
+///
+/// NaN values in the signal are left out of the statistics, a column with only NaN values in the segment has NaN statistics.
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Segment {
+    /// Index of the first sample in the segment.
+    pub start: usize,
+    /// Index after the last sample in the segment.
+    pub end: usize,
+    /// Loss of the segment for the segment cost function.
+    pub cost: f64,
+    /// Location parameter of the segment model for each column, the level of the piecewise constant model fitted by the segment cost function.
+    ///
+    /// The median for [`crate::SegmentCostFunction::L1`], the mean for [`crate::SegmentCostFunction::L2`] and the quantile for [`crate::SegmentCostFunction::Quantile`].
+    pub location: Vec<f64>,
+    /// Mean of each column.
+    pub mean: Vec<f64>,
+    /// Median of each column.
+    pub median: Vec<f64>,
+    /// Population standard deviation of each column.
+    pub standard_deviation: Vec<f64>,
+    /// Minimum of each column.
+    pub min: Vec<f64>,
+    /// Maximum of each column.
+    pub max: Vec<f64>,
+}
+
+impl Segment {
+    /// Indices of the samples in the segment.
+    #[must_use]
+    pub const fn range(&self) -> Range<usize> {
+        self.start..self.end
+    }
+
+    /// Amount of samples in the segment.
+    #[allow(clippy::len_without_is_empty, reason = "segments are never empty")]
+    #[must_use]
+    pub const fn len(&self) -> usize {
+        self.end - self.start
+    }
+}
+
+/// How well the piecewise model of a segmentation explains the signal.
+///
+/// NaN values in the signal are left out, the sums are over all columns.
+#[derive(Debug, Clone, Copy, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct GoodnessOfFit {
+    /// Coefficient of determination of the segment means, the fraction of the total sum of squares explained by the segments.
+    pub r_squared: f64,
+    /// Fraction of the variance of the signal explained by the fitted piecewise model, see [`Segmentation::fitted`].
+    ///
+    /// The same as [`GoodnessOfFit::r_squared`] for [`crate::SegmentCostFunction::L2`], lower for the other segment cost functions when their residuals aren't centered.
+    pub explained_variance: f64,
+    /// Bayesian information criterion of the segmentation, lower is better.
+    ///
+    /// Calculated the same as [`crate::ModelComparison::bic`].
+    pub bic: f64,
+}
+
+/// Changepoints of a signal with the segments between them and the configuration used.
+#[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Segmentation {
+    /// Indices where changes were detected, including the end of the signal.
+    pub changepoints: Vec<usize>,
+    /// Segments ending at each changepoint.
+    pub segments: Vec<Segment>,
+    /// Total loss of all segments, without penalties.
+    pub loss: f64,
+    /// Optimal value of the objective, the total loss plus the penalty of every segment.
+    pub objective: f64,
+    /// Reduction of the loss by each changepoint, for every changepoint except the end of the signal.
+    ///
+    /// The loss of the segments before and after the changepoint merged, minus the loss of both segments.
+    /// Larger gains are more important changepoints.
+    pub gains: Vec<f64>,
+    /// How well the segments explain the signal.
+    pub goodness_of_fit: GoodnessOfFit,
+    /// Configuration the signal is segmented with.
+    pub configuration: Pelt,
+}
+
+impl Segmentation {
+    /// Range of the segment containing the sample at the index.
+    ///
+    /// Searches the changepoints in logarithmic time, `None` when the index is beyond the end of the signal.
+    #[must_use]
+    pub fn segment_containing(&self, index: usize) -> Option<Range<usize>> {
+        let segment = self
+            .changepoints
+            .partition_point(|changepoint| *changepoint <= index);
+
+        self.segments.get(segment).map(Segment::range)
+    }
+
+    /// Check the changepoints are a valid segmentation of a signal with the configuration, such as changepoints from another source.
+    ///
+    /// # Errors
+    ///
+    /// - [`Error::ChangepointOutOfBounds`] when a changepoint is zero or beyond the end of the signal.
+    /// - [`Error::UnsortedChangepoints`] when a changepoint isn't larger than the one before it.
+    /// - [`Error::SegmentTooShort`] when a segment is shorter than the minimum segment length of the configuration.
+    /// - [`Error::TooManyChangepoints`] when there are more changepoints than the maximum of the configuration.
+    /// - [`Error::InvalidChangepoints`] when the changepoints don't end at the end of the signal or don't match the segments.
+    pub fn validate(&self, signal_len: usize, pelt: &Pelt) -> Result<(), Error> {
+        let mut start = 0;
+        for (position, &end) in self.changepoints.iter().enumerate() {
+            if end == 0 || end > signal_len {
+                return Err(Error::ChangepointOutOfBounds {
+                    changepoint: end,
+                    signal_len,
+                });
+            }
+            if end <= start {
+                return Err(Error::UnsortedChangepoints { position });
+            }
+            if end - start < pelt.minimum_segment_length {
+                return Err(Error::SegmentTooShort {
+                    start,
+                    end,
+                    minimum_segment_length: pelt.minimum_segment_length,
+                });
+            }
+
+            start = end;
+        }
+
+        // The end of the signal isn't a changepoint
+        if pelt
+            .max_changepoints
+            .is_some_and(|max_changepoints| self.changepoints.len() > max_changepoints + 1)
+        {
+            return Err(Error::TooManyChangepoints);
+        }
+
+        if self.changepoints.last() != Some(&signal_len)
+            || !segments(&self.changepoints).eq(self.segments.iter().map(Segment::range))
+        {
+            return Err(Error::InvalidChangepoints);
+        }
+
+        Ok(())
+    }
+
+    /// Index of the segment of every sample, such as for coloring a plot or grouping the samples.
+    #[must_use]
+    pub fn labels(&self) -> Vec<usize> {
+        self.segments
+            .iter()
+            .enumerate()
+            .flat_map(|(label, segment)| std::iter::repeat_n(label, segment.len()))
+            .collect()
+    }
+
+    /// Values of the piecewise model, the location of the segment of every sample.
+    ///
+    /// Every row is a sample with a value for each column of the signal, subtracting it from the signal gives the residuals of the model.
+    #[must_use]
+    pub fn fitted(&self) -> Array2<f64> {
+        let len = self.segments.last().map_or(0, |segment| segment.end);
+        let columns = self
+            .segments
+            .first()
+            .map_or(0, |segment| segment.location.len());
+
+        let mut fitted = Array2::zeros((len, columns));
+        for segment in &self.segments {
+            fitted
+                .slice_mut(ndarray::s![segment.range(), ..])
+                .assign(&ArrayView1::from(&segment.location));
+        }
+
+        fitted
+    }
+
+    /// Export the segmentation as JSON.
+    ///
+    /// The schema is, with a value for each column of the signal in the statistics:
+    ///
+    /// ```json
+    /// {
+    ///   "changepoints": [50, 100],
+    ///   "segments": [
+    ///     {
+    ///       "start": 0,
+    ///       "end": 50,
+    ///       "cost": 12.5,
+    ///       "location": [0.1],
+    ///       "mean": [0.12],
+    ///       "median": [0.1],
+    ///       "standard_deviation": [0.98],
+    ///       "min": [-2.4],
+    ///       "max": [2.7]
+    ///     },
+    ///     ...
+    ///   ],
+    ///   "loss": 25.0,
+    ///   "objective": 45.0,
+    ///   "gains": [104.5],
+    ///   "goodness_of_fit": {
+    ///     "r_squared": 0.9,
+    ///     "explained_variance": 0.9,
+    ///     "bic": 120.0
+    ///   },
+    ///   "configuration": {
+    ///     "segment_cost_function": "L1",
+    ///     "jump": 5,
+    ///     "minimum_segment_length": 2,
+    ///     "max_changepoints": null,
+    ///     "loss_cache_capacity": null,
+    ///     "accumulator": "Naive",
+    ///     "time_axis": 0,
+    ///     "nan_policy": "Error",
+    ///     "threading_threshold": null,
+    ///     "keep_initial_zero": false,
+    ///     "keep_signal_length": true,
+    ///     "changepoint_convention": "Exclusive",
+    ///     "forbidden_ranges": [{ "start": 10, "end": 20 }],
+    ///     "candidates": null
+    ///   }
+    /// }
+    /// ```
+    ///
+    /// NaN statistics are `null`, `threading_threshold` is only present with the `rayon` feature.
+    /// The configuration can be read back with `serde`, so the segmentation can be reproduced.
+    #[cfg(feature = "json")]
+    #[must_use]
+    pub fn to_json(&self) -> String {
+        // Only fails for maps with non-string keys, which aren't used
+        serde_json::to_string(self).expect("Serializing a segmentation can't fail")
+    }
+}
+
+/// Ranges of the segments ending at each changepoint, such as the result of [`Pelt::predict`].
+///
+/// The first segment starts at zero, every next segment at the end of the previous one.
+pub fn segments(changepoints: &[usize]) -> impl Iterator<Item = Range<usize>> + '_ {
+    let starts = std::iter::once(0).chain(changepoints.iter().copied());
+
+    starts.zip(changepoints).map(|(start, end)| start..*end)
+}
+
+/// Views of the samples of the segments ending at each changepoint, such as the result of [`Pelt::predict`].
+///
+/// The signal is sliced along the first axis, so every row must be a sample.
+///
+/// # Panics
+///
+/// - When a changepoint is beyond the end of the signal or smaller than the one before it.
+pub fn segment_views<'a, 'b, A, D>(
+    signal: impl AsArray<'a, A, D>,
+    changepoints: &'b [usize],
+) -> impl Iterator<Item = ArrayView<'a, A, D>> + 'b
+where
+    'a: 'b,
+    A: 'a,
+    D: Dimension + 'a,
+{
+    let signal = signal.into();
+
+    segments(changepoints)
+        .map(move |range| signal.clone().slice_axis_move(Axis(0), Slice::from(range)))
+}
+
+/// Fraction of the sum of squares and of the variance explained by the segments, without the information criterion.
+fn goodness_of_fit<A>(signal: &ArrayView2<A>, segments: &[Segment]) -> GoodnessOfFit
+where
+    A: Sample,
+{
+    let mut total_squares = 0.0;
+    let mut total_variance = 0.0;
+    let mut residual_squares = 0.0;
+    let mut residual_variance = 0.0;
+
+    for (column_index, column) in signal.columns().into_iter().enumerate() {
+        let values = column.iter().map(|value| value.to_f64());
+        let (count, sum, sum_squares) = moments(values.clone());
+        let column_squares = sum.mul_add(-sum / count, sum_squares);
+        total_squares += column_squares;
+        total_variance += column_squares / count;
+
+        // Residuals of the segment means and of the locations of the segment model
+        let mut residual_count = 0.0;
+        let mut residual_sum = 0.0;
+        let mut residual_sum_squares = 0.0;
+        for segment in segments {
+            let mean = segment.mean[column_index];
+            let location = segment.location[column_index];
+            let segment_values = values.clone().skip(segment.start).take(segment.len());
+            residual_squares += segment_values
+                .clone()
+                .filter(|value| !value.is_nan())
+                .map(|value| (value - mean).powi(2))
+                .sum::<f64>();
+
+            let (count, sum, sum_squares) = moments(segment_values.map(|value| value - location));
+            residual_count += count;
+            residual_sum += sum;
+            residual_sum_squares += sum_squares;
+        }
+        residual_variance += residual_sum
+            .mul_add(-residual_sum / residual_count, residual_sum_squares)
+            / residual_count;
+    }
+
+    GoodnessOfFit {
+        r_squared: 1.0 - residual_squares / total_squares,
+        explained_variance: 1.0 - residual_variance / total_variance,
+        bic: f64::NAN,
+    }
+}
+
+/// Count, sum and sum of squares of the values that aren't NaN.
+fn moments<I>(values: I) -> (f64, f64, f64)
+where
+    I: Iterator<Item = f64>,
+{
+    values.filter(|value| !value.is_nan()).fold(
+        (0.0, 0.0, 0.0),
+        |(count, sum, sum_squares), value| {
+            (count + 1.0, sum + value, value.mul_add(value, sum_squares))
+        },
+    )
+}
+
+/// Ensure the changepoints are increasing and end at the end of the signal.
+pub(crate) fn check_changepoints(changepoints: &[usize], signal_len: usize) -> Result<(), Error> {
+    let is_increasing = changepoints
+        .iter()
+        .zip(changepoints.iter().skip(1))
+        .all(|(changepoint, next)| changepoint < next);
+    if !is_increasing
+        || changepoints.first().is_none_or(|first| *first == 0)
+        || changepoints.last() != Some(&signal_len)
+    {
+        return Err(Error::InvalidChangepoints);
+    }
+
+    Ok(())
+}
+
+impl PredictImpl {
+    /// Segment the signal and calculate the statistics of every segment.
+    pub(crate) fn segment<A, D, P>(
+        &mut self,
+        signal: &ArrayView<A, D>,
+        penalty: &P,
+    ) -> Result<Segmentation, Error>
+    where
+        A: Sample,
+        D: OneOrTwoDimensions + Dimension,
+        P: Penalty,
+        D::PrecalculationOutput: Sync,
+    {
+        let cost = self.precalculate(signal)?;
+        let changepoints = self.predict_with_cost(signal, &cost, penalty)?;
+        let segments = self.segments(signal, &cost, &changepoints)?;
+
+        let signal_len = D::len_or_nrows(signal);
+        let loss = segments.iter().map(|segment| segment.cost).sum();
+        let objective = segments
+            .iter()
+            .map(|segment| {
+                segment.cost + penalty.segment_penalty(segment.start..segment.end, signal_len)
+            })
+            .sum();
+
+        // Loss of merging each pair of adjacent segments
+        let gains = segments
+            .iter()
+            .zip(segments.iter().skip(1))
+            .map(|(before, after)| {
+                let mut merged = 0.0;
+                D::loss(&cost, &mut merged, before.start..after.end);
+
+                merged - before.cost - after.cost
+            })
+            .collect();
+
+        let goodness_of_fit = GoodnessOfFit {
+            bic: bic(
+                self.pelt.segment_cost_function,
+                loss,
+                segments.len(),
+                signal_len,
+                D::as_2d(signal)?.ncols(),
+            ),
+            ..goodness_of_fit(&D::as_2d(signal)?, &segments)
+        };
+
+        Ok(Segmentation {
+            changepoints,
+            segments,
+            loss,
+            objective,
+            gains,
+            goodness_of_fit,
+            configuration: self.pelt.clone(),
+        })
+    }
+
+    /// Calculate the statistics of the segments between the changepoints.
+    pub(crate) fn segment_statistics<A, D>(
+        &self,
+        signal: &ArrayView<A, D>,
+        changepoints: &[usize],
+    ) -> Result<Vec<Segment>, Error>
+    where
+        A: Sample,
+        D: OneOrTwoDimensions + Dimension,
+    {
+        check_changepoints(changepoints, D::len_or_nrows(signal))?;
+
+        let cost = self.precalculate(signal)?;
+
+        self.segments(signal, &cost, changepoints)
+    }
+
+    /// Total loss plus the penalty of every segment between the changepoints.
+    pub(crate) fn score<A, D, P>(
+        &self,
+        signal: &ArrayView<A, D>,
+        changepoints: &[usize],
+        penalty: &P,
+    ) -> Result<f64, Error>
+    where
+        A: Sample,
+        D: OneOrTwoDimensions + Dimension,
+        P: Penalty,
+    {
+        let signal_len = D::len_or_nrows(signal);
+        check_changepoints(changepoints, signal_len)?;
+
+        let cost = self.precalculate(signal)?;
+
+        let mut objective = 0.0;
+        for range in segments(changepoints) {
+            objective += penalty.segment_penalty(range.clone(), signal_len);
+            D::loss(&cost, &mut objective, range);
+        }
+
+        Ok(objective)
+    }
+
+    /// Statistics of the segments ending at each changepoint.
+    fn segments<A, D>(
+        &self,
+        signal: &ArrayView<A, D>,
+        cost: &D::PrecalculationOutput,
+        changepoints: &[usize],
+    ) -> Result<Vec<Segment>, Error>
+    where
+        A: Sample,
+        D: OneOrTwoDimensions + Dimension,
+    {
+        let signal = D::as_2d(signal)?;
+        let segment_cost_function = self.pelt.segment_cost_function;
+        let mut values = Vec::new();
+
+        // Each segment starts at the previous changepoint
+        let mut start = 0;
+        let segments = changepoints
+            .iter()
+            .map(|&end| {
+                let mut cost_of_segment = 0.0;
+                D::loss(cost, &mut cost_of_segment, start..end);
+
+                let mut segment = Segment {
+                    start,
+                    end,
+                    cost: cost_of_segment,
+                    location: Vec::with_capacity(signal.ncols()),
+                    mean: Vec::with_capacity(signal.ncols()),
+                    median: Vec::with_capacity(signal.ncols()),
+                    standard_deviation: Vec::with_capacity(signal.ncols()),
+                    min: Vec::with_capacity(signal.ncols()),
+                    max: Vec::with_capacity(signal.ncols()),
+                };
+                for column in signal.columns() {
+                    values.clear();
+                    values.extend(
+                        column
+                            .slice(ndarray::s![start..end])
+                            .iter()
+                            .map(|value| value.to_f64())
+                            .filter(|value| !value.is_nan()),
+                    );
+
+                    let count = values.len() as f64;
+                    let mean = values.iter().sum::<f64>() / count;
+                    let variance = values
+                        .iter()
+                        .map(|value| (value - mean).powi(2))
+                        .sum::<f64>()
+                        / count;
+                    segment.mean.push(mean);
+                    segment.standard_deviation.push(variance.sqrt());
+                    segment
+                        .location
+                        .push(segment_cost_function.location(&mut values));
+                    segment
+                        .median
+                        .push(SegmentCostFunction::L1.location(&mut values));
+                    // Taking the median sorted the values
+                    segment
+                        .min
+                        .push(values.first().copied().unwrap_or(f64::NAN));
+                    segment.max.push(values.last().copied().unwrap_or(f64::NAN));
+                }
+
+                start = end;
+
+                segment
+            })
+            .collect();
+
+        Ok(segments)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use ndarray::array;
+
+    use super::*;
+
+    /// Statistics of every segment, leaving out NaN values.
+    #[test]
+    fn segment_statistics() {
+        let signal = array![1.0, f64::NAN, 1.0, 3.0, 10.0, 12.0];
+        let segmentation = PredictImpl::new(
+            Pelt::new()
+                .with_jump(std::num::NonZero::<usize>::MIN)
+                .with_segment_cost_function(crate::SegmentCostFunction::L2)
+                .with_nan_policy(crate::NanPolicy::Ignore),
+        )
+        .segment(&signal.view(), &5.0)
+        .expect("Error segmenting");
+
+        assert_eq!(segmentation.changepoints, vec![4, 6]);
+        let first = &segmentation.segments[0];
+        assert_eq!((first.start, first.end), (0, 4));
+        assert!((first.mean[0] - 5.0 / 3.0).abs() < 1e-12, "{first:?}");
+        assert!((first.cost - 8.0 / 3.0).abs() < 1e-12, "{first:?}");
+        assert_eq!(segmentation.segments[1].location, vec![11.0]);
+        assert_eq!(segmentation.segments[1].standard_deviation, vec![1.0]);
+        assert_eq!(segmentation.segments[1].len(), 2);
+        // Sum of squares 109.2 of which 8/3 + 2 is left
+        let goodness_of_fit = segmentation.goodness_of_fit;
+        assert!(
+            (goodness_of_fit.r_squared - (1.0 - (8.0 / 3.0 + 2.0) / 109.2)).abs() < 1e-9,
+            "{goodness_of_fit:?}"
+        );
+        assert!(
+            (goodness_of_fit.explained_variance - goodness_of_fit.r_squared).abs() < 1e-9,
+            "{goodness_of_fit:?}"
+        );
+        assert!(goodness_of_fit.bic.is_finite(), "{goodness_of_fit:?}");
+        assert_eq!(
+            segmentation.fitted().column(0).to_vec(),
+            vec![5.0 / 3.0, 5.0 / 3.0, 5.0 / 3.0, 5.0 / 3.0, 11.0, 11.0]
+        );
+        let score = PredictImpl::new(segmentation.configuration.clone())
+            .score(&signal.view(), &segmentation.changepoints, &5.0)
+            .expect("Error scoring");
+        assert!(
+            (score - segmentation.objective).abs() < 1e-12,
+            "{score} {segmentation:?}"
+        );
+        assert_eq!(
+            segments(&segmentation.changepoints).collect::<Vec<_>>(),
+            segmentation
+                .segments
+                .iter()
+                .map(Segment::range)
+                .collect::<Vec<_>>()
+        );
+        assert_eq!(
+            segment_views(&signal, &segmentation.changepoints)
+                .map(|view| view.len())
+                .collect::<Vec<_>>(),
+            vec![4, 2]
+        );
+        assert_eq!(segmentation.segment_containing(3), Some(0..4));
+        assert_eq!(segmentation.segment_containing(4), Some(4..6));
+        assert_eq!(segmentation.segment_containing(6), None);
+        assert_eq!(segmentation.labels(), vec![0, 0, 0, 0, 1, 1]);
+        assert!(
+            segmentation
+                .validate(6, &segmentation.configuration)
+                .is_ok()
+        );
+        assert!(matches!(
+            segmentation.validate(5, &segmentation.configuration),
+            Err(Error::ChangepointOutOfBounds {
+                changepoint: 6,
+                signal_len: 5
+            })
+        ));
+        assert!(matches!(
+            segmentation.validate(
+                6,
+                &Pelt::new().with_minimum_segment_length(
+                    std::num::NonZero::new(3).expect("Invalid number")
+                )
+            ),
+            Err(Error::SegmentTooShort {
+                start: 4,
+                end: 6,
+                minimum_segment_length: 3
+            })
+        ));
+        let mut unsorted = segmentation.clone();
+        unsorted.changepoints = vec![4, 4, 6];
+        assert!(matches!(
+            unsorted.validate(6, &segmentation.configuration),
+            Err(Error::UnsortedChangepoints { position: 1 })
+        ));
+        assert_eq!(first.median, vec![1.0]);
+        assert_eq!((first.min[0], first.max[0]), (1.0, 3.0));
+        assert_eq!(segmentation.gains.len(), 1);
+        assert!(
+            (segmentation.gains[0] - (109.2 - 8.0 / 3.0 - 2.0)).abs() < 1e-9,
+            "{segmentation:?}"
+        );
+        assert!(
+            (segmentation.objective - segmentation.loss - 10.0).abs() < 1e-12,
+            "{segmentation:?}"
+        );
+    }
+
+    /// The JSON export contains the segments and the configuration.
+    #[cfg(feature = "json")]
+    #[test]
+    fn json() {
+        let segmentation = Pelt::new()
+            .segment(&[0.0, 0.0, 0.0, 0.0, 5.0, 5.0, 5.0, 5.0, 5.0, 5.0], 1.0)
+            .expect("Error segmenting");
+        let json = segmentation.to_json();
+
+        let value: serde_json::Value = serde_json::from_str(&json).expect("Invalid JSON");
+        assert_eq!(value["changepoints"], serde_json::json!([5, 10]));
+        assert_eq!(value["segments"][1]["start"], 5);
+        assert_eq!(value["configuration"]["segment_cost_function"], "L1");
+
+        let deserialized: Segmentation = serde_json::from_str(&json).expect("Error deserializing");
+        assert_eq!(deserialized.segments, segmentation.segments);
+    }
+}
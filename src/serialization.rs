@@ -0,0 +1,106 @@
+//! Serialization of fields without a [`serde`] implementation of their own.
+
+/// [`ndarray::Axis`] as its index.
+pub(crate) mod axis {
+    use ndarray::Axis;
+    use serde::{Deserialize as _, Deserializer, Serializer};
+
+    /// Serialize the index of the axis.
+    #[allow(
+        clippy::trivially_copy_pass_by_ref,
+        reason = "signature required by serde"
+    )]
+    pub(crate) fn serialize<S>(axis: &Axis, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        serializer.serialize_u64(axis.index() as u64)
+    }
+
+    /// Deserialize the axis from its index.
+    pub(crate) fn deserialize<'de, D>(deserializer: D) -> Result<Axis, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        usize::deserialize(deserializer).map(Axis)
+    }
+}
+
+/// Non-zero values stored as plain [`usize`], rejecting zero when deserializing.
+pub(crate) mod non_zero {
+    use std::num::NonZero;
+
+    use serde::{Deserialize as _, Deserializer, Serializer};
+
+    /// Serialize the value.
+    #[allow(
+        clippy::trivially_copy_pass_by_ref,
+        reason = "signature required by serde"
+    )]
+    pub(crate) fn serialize<S>(value: &usize, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        serializer.serialize_u64(*value as u64)
+    }
+
+    /// Deserialize the value, failing when it's zero.
+    pub(crate) fn deserialize<'de, D>(deserializer: D) -> Result<usize, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        NonZero::<usize>::deserialize(deserializer).map(NonZero::get)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::num::NonZero;
+
+    use ndarray::Axis;
+
+    use crate::{NanPolicy, Pelt, PenaltySegmentation, SegmentCostFunction};
+
+    /// Configurations and results survive a round trip, missing fields take the defaults.
+    #[test]
+    fn round_trip() {
+        let pelt = Pelt::new()
+            .with_segment_cost_function(SegmentCostFunction::Quantile(0.25))
+            .with_jump(NonZero::new(3).expect("Invalid jump"))
+            .with_time_axis(Axis(1))
+            .with_nan_policy(NanPolicy::Interpolate);
+        let json = serde_json::to_string(&pelt).expect("Error serializing");
+        let deserialized: Pelt = serde_json::from_str(&json).expect("Error deserializing");
+        assert_eq!(format!("{deserialized:?}"), format!("{pelt:?}"));
+
+        let pelt: Pelt =
+            serde_json::from_str(r#"{"segment_cost_function":"L2"}"#).expect("Error deserializing");
+        assert_eq!(
+            format!("{pelt:?}"),
+            format!(
+                "{:?}",
+                Pelt::new().with_segment_cost_function(SegmentCostFunction::L2)
+            )
+        );
+
+        assert!(
+            serde_json::from_str::<Pelt>(r#"{"jump":0}"#).is_err(),
+            "Zero jump should be rejected"
+        );
+        assert!(
+            serde_json::from_str::<Pelt>(r#"{"jupm":5}"#).is_err(),
+            "Unknown field should be rejected"
+        );
+
+        let segmentation = PenaltySegmentation {
+            penalty: 10.0,
+            changepoints: vec![50, 100],
+            loss: 3.5,
+        };
+        let json = serde_json::to_string(&segmentation).expect("Error serializing");
+        assert_eq!(
+            serde_json::from_str::<PenaltySegmentation>(&json).expect("Error deserializing"),
+            segmentation
+        );
+    }
+}
@@ -0,0 +1,227 @@
+//! Permutation tests of the significance of changepoints.
+
+use ndarray::{ArrayView, Axis, Dimension, RemoveAxis};
+
+use crate::{Error, OneOrTwoDimensions, Sample, predict::PredictImpl, segmentation};
+
+/// Permutation test of the changepoints between adjacent segments.
+///
+/// The statistic of a changepoint is its gain, the loss of merging the segments before and after it minus their separate losses.
+/// The samples of both segments are shuffled, the p-value is the fraction of shuffles with a gain at least as large at the same changepoint.
+/// Works for every segment cost function, since it makes no assumptions about the distribution of the signal.
+///
+/// # Defaults
+///
+/// - `significance`: `0.05`
+/// - `permutations`: `199`
+/// - `seed`: `0`
+#[derive(Debug, Clone)]
+pub struct SignificanceTest {
+    /// Maximum p-value of a changepoint that's kept.
+    significance: f64,
+    /// Amount of permutations of each test.
+    permutations: usize,
+    /// Seed of the random number generator.
+    seed: u64,
+}
+
+impl SignificanceTest {
+    /// Construct a new significance test with default values.
+    #[must_use]
+    pub const fn new() -> Self {
+        Self {
+            significance: 0.05,
+            permutations: 199,
+            seed: 0,
+        }
+    }
+
+    /// Set the maximum p-value of a changepoint that's kept.
+    #[must_use]
+    pub const fn with_significance(mut self, significance: f64) -> Self {
+        self.significance = significance;
+
+        self
+    }
+
+    /// Set the amount of permutations of each test.
+    ///
+    /// More permutations give a more precise p-value, but take longer.
+    /// The smallest possible p-value is `1 / (permutations + 1)`.
+    #[must_use]
+    pub const fn with_permutations(mut self, permutations: usize) -> Self {
+        self.permutations = permutations;
+
+        self
+    }
+
+    /// Set the seed of the random number generator of the permutations.
+    #[must_use]
+    pub const fn with_seed(mut self, seed: u64) -> Self {
+        self.seed = seed;
+
+        self
+    }
+}
+
+impl Default for SignificanceTest {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl PredictImpl {
+    /// P-value of every changepoint except the end of the signal.
+    pub(crate) fn p_values<A, D>(
+        &self,
+        signal: &ArrayView<A, D>,
+        changepoints: &[usize],
+        test: &SignificanceTest,
+    ) -> Result<Vec<f64>, Error>
+    where
+        A: Sample,
+        D: OneOrTwoDimensions + Dimension + RemoveAxis,
+    {
+        segmentation::check_changepoints(changepoints, D::len_or_nrows(signal))?;
+
+        let mut rng = fastrand::Rng::with_seed(test.seed);
+        let starts = std::iter::once(0).chain(changepoints.iter().copied());
+
+        starts
+            .zip(changepoints.windows(2))
+            .map(|(start, window)| self.p_value(signal, start, window, test, &mut rng))
+            .collect()
+    }
+
+    /// Remove the changepoints that aren't significant.
+    ///
+    /// The changepoint with the largest p-value is removed first, after which the changepoints next to it are tested again with the merged segment.
+    pub(crate) fn prune_insignificant<A, D>(
+        &self,
+        signal: &ArrayView<A, D>,
+        mut changepoints: Vec<usize>,
+        test: &SignificanceTest,
+    ) -> Result<Vec<usize>, Error>
+    where
+        A: Sample,
+        D: OneOrTwoDimensions + Dimension + RemoveAxis,
+    {
+        let mut p_values = self.p_values(signal, &changepoints, test)?;
+        let mut rng = fastrand::Rng::with_seed(test.seed);
+
+        while let Some((position, _)) = p_values
+            .iter()
+            .enumerate()
+            .filter(|(_, p_value)| **p_value > test.significance)
+            .max_by(|(_, left), (_, right)| left.total_cmp(right))
+        {
+            changepoints.remove(position);
+            p_values.remove(position);
+
+            // Only the changepoints around the merged segment have a different test
+            for neighbour in position.saturating_sub(1)..(position + 1).min(p_values.len()) {
+                let start = neighbour
+                    .checked_sub(1)
+                    .map_or(0, |before| changepoints[before]);
+                p_values[neighbour] = self.p_value(
+                    signal,
+                    start,
+                    &changepoints[neighbour..neighbour + 2],
+                    test,
+                    &mut rng,
+                )?;
+            }
+        }
+
+        Ok(changepoints)
+    }
+
+    /// P-value of the changepoint between the segment from the start to the first end and the segment to the second end.
+    fn p_value<A, D>(
+        &self,
+        signal: &ArrayView<A, D>,
+        start: usize,
+        ends: &[usize],
+        test: &SignificanceTest,
+        rng: &mut fastrand::Rng,
+    ) -> Result<f64, Error>
+    where
+        A: Sample,
+        D: OneOrTwoDimensions + Dimension + RemoveAxis,
+    {
+        let &[changepoint, end] = ends else {
+            return Ok(1.0);
+        };
+        let split = changepoint - start;
+        let len = end - start;
+
+        let mut order = (start..end).collect::<Vec<_>>();
+        let statistic = self.gain(&signal.select(Axis(0), &order).view(), split, len)?;
+
+        // Count how often shuffling the samples of both segments gives a gain at least as large
+        let mut exceeded = 0;
+        for _ in 0..test.permutations {
+            rng.shuffle(&mut order);
+
+            if self.gain(&signal.select(Axis(0), &order).view(), split, len)? >= statistic {
+                exceeded += 1;
+            }
+        }
+
+        Ok((exceeded + 1) as f64 / (test.permutations + 1) as f64)
+    }
+
+    /// Loss of the whole signal minus the losses of the parts before and after the split.
+    fn gain<A, D>(&self, signal: &ArrayView<A, D>, split: usize, len: usize) -> Result<f64, Error>
+    where
+        A: Sample,
+        D: OneOrTwoDimensions + Dimension,
+    {
+        let cost = self.precalculate(signal)?;
+
+        let mut merged = 0.0;
+        D::loss(&cost, &mut merged, 0..len);
+        let mut before = 0.0;
+        D::loss(&cost, &mut before, 0..split);
+        let mut after = 0.0;
+        D::loss(&cost, &mut after, split..len);
+
+        Ok(merged - before - after)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use ndarray::Array1;
+
+    use super::*;
+    use crate::Pelt;
+
+    /// A real change is significant, a changepoint in noise isn't.
+    #[test]
+    fn p_values() {
+        let mut rng = fastrand::Rng::with_seed(1);
+        let signal = (0..200)
+            .map(|index| rng.f64() + if index >= 100 { 5.0 } else { 0.0 })
+            .collect::<Array1<f64>>();
+        let predict = PredictImpl::new(Pelt::new());
+        let test = SignificanceTest::new();
+
+        let p_values = predict
+            .p_values(&signal.view(), &[50, 100, 200], &test)
+            .expect("Error testing");
+        assert!(p_values[0] > 0.05, "{p_values:?}");
+        assert!((p_values[1] - 1.0 / 200.0).abs() < 1e-12, "{p_values:?}");
+
+        assert_eq!(
+            predict
+                .prune_insignificant(&signal.view(), vec![50, 100, 150, 200], &test)
+                .expect("Error pruning"),
+            vec![100, 200]
+        );
+        assert!(matches!(
+            predict.p_values(&signal.view(), &[100, 50, 200], &test),
+            Err(Error::InvalidChangepoints)
+        ));
+    }
+}
@@ -0,0 +1,194 @@
+//! Streaming signal sources.
+
+use std::{fs::File, io, num::NonZero, path::Path};
+
+use csv::{ReaderBuilder, StringRecord};
+
+use crate::OnlinePelt;
+
+/// Incremental reader of a single column of a CSV file.
+///
+/// Rows are parsed one by one when iterating, so the whole file is never in memory.
+/// Can feed an [`OnlinePelt`] in batches with [`CsvSource::feed`], or any other detector by iterating the samples.
+///
+/// # Defaults
+///
+/// - `column`: `0`
+/// - `headers`: `false`
+pub struct CsvSource<R> {
+    /// Reader of the rows.
+    reader: csv::Reader<R>,
+    /// Index of the column containing the samples.
+    column: usize,
+    /// Buffer for the current row, reused between rows.
+    record: StringRecord,
+    /// Whether the first row still has to be skipped.
+    skip_header: bool,
+}
+
+impl CsvSource<File> {
+    /// Open a CSV file without headers.
+    ///
+    /// # Errors
+    ///
+    /// - When the file can't be opened.
+    pub fn open<P>(path: P) -> io::Result<Self>
+    where
+        P: AsRef<Path>,
+    {
+        Ok(Self::from_reader(File::open(path)?))
+    }
+}
+
+impl<R> CsvSource<R>
+where
+    R: io::Read,
+{
+    /// Read CSV without headers from any reader.
+    #[must_use]
+    pub fn from_reader(reader: R) -> Self {
+        Self {
+            reader: ReaderBuilder::new().has_headers(false).from_reader(reader),
+            column: 0,
+            record: StringRecord::new(),
+            skip_header: false,
+        }
+    }
+
+    /// Set the index of the column containing the samples.
+    #[must_use]
+    pub const fn with_column(mut self, column: usize) -> Self {
+        self.column = column;
+
+        self
+    }
+
+    /// Skip the first row, because it contains the names of the columns.
+    ///
+    /// Must be set before reading any samples.
+    #[must_use]
+    pub const fn with_headers(mut self) -> Self {
+        self.skip_header = true;
+
+        self
+    }
+
+    /// Read at most `batch_size` samples into the buffer, returning how many were read.
+    ///
+    /// Returns `0` at the end of the file.
+    ///
+    /// # Errors
+    ///
+    /// - When reading or parsing a row fails.
+    pub fn read_batch(
+        &mut self,
+        buffer: &mut Vec<f64>,
+        batch_size: NonZero<usize>,
+    ) -> io::Result<usize> {
+        let start = buffer.len();
+
+        for sample in self.by_ref().take(batch_size.get()) {
+            buffer.push(sample?);
+        }
+
+        Ok(buffer.len() - start)
+    }
+
+    /// Push all remaining samples into the online detector in batches, returning the changepoints of the whole signal.
+    ///
+    /// Only one batch is in memory besides the samples kept by the detector.
+    ///
+    /// # Errors
+    ///
+    /// - When reading or parsing a row fails.
+    /// - When detection fails, with the [`crate::Error`] as the inner error.
+    pub fn feed(
+        &mut self,
+        online: &mut OnlinePelt,
+        batch_size: NonZero<usize>,
+    ) -> io::Result<Vec<usize>> {
+        let mut batch = Vec::with_capacity(batch_size.get());
+        let mut changepoints = Vec::new();
+
+        while self.read_batch(&mut batch, batch_size)? > 0 {
+            changepoints = online.push(&batch).map_err(io::Error::other)?;
+
+            batch.clear();
+        }
+
+        Ok(changepoints)
+    }
+}
+
+impl<R> Iterator for CsvSource<R>
+where
+    R: io::Read,
+{
+    type Item = io::Result<f64>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        loop {
+            match self.reader.read_record(&mut self.record) {
+                Ok(true) => {}
+                Ok(false) => return None,
+                Err(error) => return Some(Err(error.into())),
+            }
+
+            // Ignore the names of the columns
+            if !std::mem::take(&mut self.skip_header) {
+                break;
+            }
+        }
+
+        // Parse the configured column
+        let sample = self
+            .record
+            .get(self.column)
+            .ok_or_else(|| io::Error::new(io::ErrorKind::InvalidData, "row is missing the column"))
+            .and_then(|field| {
+                field
+                    .trim()
+                    .parse::<f64>()
+                    .map_err(|error| io::Error::new(io::ErrorKind::InvalidData, error))
+            });
+
+        Some(sample)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{Pelt, SegmentCostFunction};
+
+    use super::*;
+
+    /// Feeding from CSV in batches gives the same result as the whole signal.
+    #[test]
+    fn feed() {
+        let file = include_str!("../tests/signals-small.csv");
+        let pelt = Pelt::new().with_segment_cost_function(SegmentCostFunction::L2);
+
+        let signal = CsvSource::from_reader(file.as_bytes())
+            .collect::<io::Result<Vec<_>>>()
+            .expect("Error reading CSV");
+
+        let mut online = OnlinePelt::new(pelt.clone(), 10.0);
+        let changepoints = CsvSource::from_reader(file.as_bytes())
+            .feed(&mut online, NonZero::new(64).expect("Invalid number"))
+            .expect("Error feeding CSV");
+
+        assert_eq!(online.len(), signal.len());
+        assert_eq!(
+            changepoints,
+            pelt.predict(&signal, 10.0).expect("Error predicting")
+        );
+
+        // Headers and other columns
+        let samples = CsvSource::from_reader(b"name,value\na,1.5\nb,2.5\n".as_slice())
+            .with_headers()
+            .with_column(1)
+            .collect::<io::Result<Vec<_>>>()
+            .expect("Error reading CSV");
+        assert_eq!(samples, vec![1.5, 2.5]);
+    }
+}
@@ -0,0 +1,388 @@
+//! Stability of changepoints over repeated runs on subsamples of the signal or with different settings.
+
+use std::num::NonZero;
+
+use ndarray::{ArrayView, Axis, Dimension, RemoveAxis, Slice};
+
+use crate::{Error, OneOrTwoDimensions, Pelt, Penalty, Sample, predict::PredictImpl};
+
+/// Repeated runs on random subsamples of the signal.
+///
+/// Every run segments a random subset of the samples, in their original order.
+/// A changepoint that's found in most runs is stable, one that's only found in a few depends on individual samples.
+///
+/// # Defaults
+///
+/// - `runs`: `100`
+/// - `fraction`: `0.8`
+/// - `tolerance`: `5`
+/// - `seed`: `0`
+#[derive(Debug, Clone)]
+pub struct Subsampling {
+    /// Amount of runs.
+    runs: usize,
+    /// Fraction of the samples in every subsample.
+    fraction: f64,
+    /// Maximum distance in samples of a changepoint to count as nearby.
+    tolerance: usize,
+    /// Seed of the random number generator.
+    seed: u64,
+}
+
+impl Subsampling {
+    /// Construct a new subsampling with default values.
+    #[must_use]
+    pub const fn new() -> Self {
+        Self {
+            runs: 100,
+            fraction: 0.8,
+            tolerance: 5,
+            seed: 0,
+        }
+    }
+
+    /// Set the amount of runs.
+    ///
+    /// More runs give a smoother frequency, but take longer.
+    #[must_use]
+    pub const fn with_runs(mut self, runs: NonZero<usize>) -> Self {
+        self.runs = runs.get();
+
+        self
+    }
+
+    /// Set the fraction of the samples in every subsample.
+    ///
+    /// Must be larger than `0.0` and at most `1.0`.
+    #[must_use]
+    pub const fn with_fraction(mut self, fraction: f64) -> Self {
+        self.fraction = fraction;
+
+        self
+    }
+
+    /// Set the maximum distance in samples of a changepoint to an index to count as found at that index.
+    ///
+    /// Removing samples shifts the changepoints a bit, so a tolerance of zero underestimates the frequency.
+    #[must_use]
+    pub const fn with_tolerance(mut self, tolerance: usize) -> Self {
+        self.tolerance = tolerance;
+
+        self
+    }
+
+    /// Set the seed of the random number generator of the subsamples.
+    #[must_use]
+    pub const fn with_seed(mut self, seed: u64) -> Self {
+        self.seed = seed;
+
+        self
+    }
+
+    /// Ensure the fraction is correct.
+    pub(crate) fn validate(&self) -> Result<(), Error> {
+        if self.fraction > 0.0 && self.fraction <= 1.0 {
+            Ok(())
+        } else {
+            Err(Error::InvalidFraction)
+        }
+    }
+}
+
+impl Default for Subsampling {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Consensus of runs with different `jump` values.
+///
+/// A large `jump` only considers changepoints on a coarse grid, so a changepoint between grid points moves to a nearby one or disappears.
+/// Running with several `jump` values, and optionally with the grid shifted by offsets, and keeping the changepoints most runs agree on removes these artifacts.
+///
+/// # Defaults
+///
+/// - `jumps`: `[2, 5, 10]`
+/// - `offsets`: `1`, only the grid starting at the start of the signal
+/// - `tolerance`: `5`
+/// - `agreement`: `0.5`
+#[derive(Debug, Clone)]
+pub struct JumpConsensus {
+    /// Jump of every run, the jump of the configuration if empty.
+    jumps: Vec<usize>,
+    /// Amount of shifted grids for every jump.
+    offsets: usize,
+    /// Maximum distance in samples between changepoints of different runs to count as the same.
+    tolerance: usize,
+    /// Minimum fraction of the runs agreeing on a changepoint.
+    agreement: f64,
+}
+
+impl JumpConsensus {
+    /// Construct a new consensus with default values.
+    #[must_use]
+    pub fn new() -> Self {
+        Self {
+            jumps: vec![2, 5, 10],
+            offsets: 1,
+            tolerance: 5,
+            agreement: 0.5,
+        }
+    }
+
+    /// Set the `jump` value of every run.
+    ///
+    /// Without any, only the `jump` of the configuration is used.
+    #[must_use]
+    pub fn with_jumps(mut self, jumps: &[NonZero<usize>]) -> Self {
+        self.jumps = jumps.iter().map(|jump| jump.get()).collect();
+
+        self
+    }
+
+    /// Set the amount of grids for every `jump`, each shifted by an equal part of the `jump`.
+    ///
+    /// The samples before the offset are left out of a run, they belong to the first segment.
+    #[must_use]
+    pub const fn with_offsets(mut self, offsets: NonZero<usize>) -> Self {
+        self.offsets = offsets.get();
+
+        self
+    }
+
+    /// Set the maximum distance in samples between changepoints of different runs to count as the same changepoint.
+    #[must_use]
+    pub const fn with_tolerance(mut self, tolerance: usize) -> Self {
+        self.tolerance = tolerance;
+
+        self
+    }
+
+    /// Set the minimum fraction of the runs that must agree on a changepoint to keep it.
+    ///
+    /// Must be larger than `0.0` and at most `1.0`.
+    #[must_use]
+    pub const fn with_agreement(mut self, agreement: f64) -> Self {
+        self.agreement = agreement;
+
+        self
+    }
+
+    /// Ensure the agreement is correct.
+    pub(crate) fn validate(&self) -> Result<(), Error> {
+        if self.agreement > 0.0 && self.agreement <= 1.0 {
+            Ok(())
+        } else {
+            Err(Error::InvalidFraction)
+        }
+    }
+}
+
+impl Default for JumpConsensus {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl PredictImpl {
+    /// Fraction of the runs on subsamples with a changepoint near every index of the signal.
+    pub(crate) fn changepoint_frequency<A, D, P>(
+        &self,
+        signal: &ArrayView<A, D>,
+        penalty: &P,
+        subsampling: &Subsampling,
+    ) -> Result<Vec<f64>, Error>
+    where
+        A: Sample,
+        D: OneOrTwoDimensions + Dimension + RemoveAxis,
+        P: Penalty,
+        D::PrecalculationOutput: Sync,
+    {
+        let len = D::len_or_nrows(signal);
+        if len == 0 {
+            return Err(Error::NotEnoughPoints);
+        }
+        #[expect(
+            clippy::cast_possible_truncation,
+            clippy::cast_sign_loss,
+            reason = "the fraction is between 0 and 1"
+        )]
+        let subsample_len = ((len as f64 * subsampling.fraction).round() as usize).max(1);
+
+        let mut rng = fastrand::Rng::with_seed(subsampling.seed);
+        let mut rows = (0..len).collect::<Vec<_>>();
+        let mut counts = vec![0_usize; len];
+        // Run in which every index was last counted, so nearby changepoints only count once per run
+        let mut counted = vec![usize::MAX; len];
+        for run in 0..subsampling.runs {
+            rng.shuffle(&mut rows);
+            let mut subsample_rows = rows[..subsample_len].to_vec();
+            subsample_rows.sort_unstable();
+
+            // The candidates and forbidden ranges are at the rows of the full signal
+            let subsample = signal.select(Axis(0), &subsample_rows);
+            let changepoints = Self::new(self.pelt.reindexed(|row| {
+                subsample_rows.partition_point(|subsample_row| *subsample_row < row)
+            }))
+            .predict(&subsample.view(), penalty)?;
+
+            // The end of the subsample isn't a changepoint
+            for changepoint in &changepoints[..changepoints.len().saturating_sub(1)] {
+                let index = subsample_rows[*changepoint];
+                let nearby = index.saturating_sub(subsampling.tolerance)
+                    ..(index + subsampling.tolerance + 1).min(len);
+                for near in nearby {
+                    if counted[near] != run {
+                        counted[near] = run;
+                        counts[near] += 1;
+                    }
+                }
+            }
+        }
+
+        Ok(counts
+            .into_iter()
+            .map(|count| count as f64 / subsampling.runs as f64)
+            .collect())
+    }
+
+    /// Changepoints most runs with the different jumps and offsets agree on, with the fraction of the runs agreeing.
+    ///
+    /// The changepoints of all runs are grouped when they're within the tolerance of each other, every group with enough agreement becomes the median of its changepoints.
+    pub(crate) fn predict_consensus<A, D, P>(
+        &self,
+        signal: &ArrayView<A, D>,
+        penalty: &P,
+        consensus: &JumpConsensus,
+    ) -> Result<Vec<(usize, f64)>, Error>
+    where
+        A: Sample,
+        D: OneOrTwoDimensions + Dimension,
+        P: Penalty,
+        D::PrecalculationOutput: Sync,
+    {
+        let len = D::len_or_nrows(signal);
+        let jumps = if consensus.jumps.is_empty() {
+            std::slice::from_ref(&self.pelt.jump)
+        } else {
+            consensus.jumps.as_slice()
+        };
+
+        // Changepoints of every run, with the run they're from
+        let mut detections = Vec::new();
+        let mut runs = 0;
+        for jump in jumps {
+            let mut predict = Self::new(Pelt {
+                jump: *jump,
+                ..self.pelt.clone()
+            });
+
+            for offset_index in 0..consensus.offsets {
+                let offset = offset_index * jump / consensus.offsets;
+                if offset >= len {
+                    continue;
+                }
+
+                let shifted = signal.slice_axis(Axis(0), Slice::from(offset..));
+                let changepoints = predict.predict(&shifted, penalty)?;
+
+                // The end of the signal isn't a changepoint
+                detections.extend(
+                    changepoints[..changepoints.len().saturating_sub(1)]
+                        .iter()
+                        .map(|changepoint| (changepoint + offset, runs)),
+                );
+                runs += 1;
+            }
+        }
+        detections.sort_unstable();
+
+        Ok(detections
+            .chunk_by(|before, after| after.0 - before.0 <= consensus.tolerance)
+            .filter_map(|group| {
+                let mut group_runs = group.iter().map(|(_, run)| *run).collect::<Vec<_>>();
+                group_runs.sort_unstable();
+                group_runs.dedup();
+                let agreement = group_runs.len() as f64 / runs as f64;
+
+                (agreement >= consensus.agreement).then(|| (group[group.len() / 2].0, agreement))
+            })
+            .collect())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use ndarray::Array1;
+
+    use super::*;
+    use crate::Pelt;
+
+    /// A real change is found in every run, nothing is found far from it.
+    #[test]
+    fn changepoint_frequency() {
+        let mut rng = fastrand::Rng::with_seed(1);
+        let signal = (0..200)
+            .map(|index| rng.f64() + if index >= 100 { 5.0 } else { 0.0 })
+            .collect::<Array1<f64>>();
+
+        let frequency = PredictImpl::new(Pelt::new().with_jump(NonZero::<usize>::MIN))
+            .changepoint_frequency(
+                &signal.view(),
+                &10.0,
+                &Subsampling::new().with_runs(NonZero::new(20).expect("Invalid number")),
+            )
+            .expect("Error subsampling");
+
+        assert_eq!(frequency.len(), 200);
+        assert!((frequency[100] - 1.0).abs() < 1e-12, "{frequency:?}");
+        assert!(frequency[50].abs() < 1e-12, "{frequency:?}");
+        assert!(matches!(
+            Subsampling::new().with_fraction(0.0).validate(),
+            Err(Error::InvalidFraction)
+        ));
+        assert!(matches!(
+            PredictImpl::new(Pelt::new()).changepoint_frequency(
+                &Array1::<f64>::zeros(0).view(),
+                &10.0,
+                &Subsampling::new()
+            ),
+            Err(Error::NotEnoughPoints)
+        ));
+
+        // A forbidden change is never found
+        let frequency = PredictImpl::new(
+            Pelt::new()
+                .with_jump(NonZero::<usize>::MIN)
+                .with_forbidden_ranges(&[90..110, 150..160]),
+        )
+        .changepoint_frequency(
+            &signal.view(),
+            &10.0,
+            &Subsampling::new().with_runs(NonZero::new(20).expect("Invalid number")),
+        )
+        .expect("Error subsampling");
+        assert!(frequency[100].abs() < 1e-12, "{frequency:?}");
+    }
+
+    /// Every jump and offset agrees on a real change, even when it's between grid points.
+    #[test]
+    fn predict_consensus() {
+        let mut rng = fastrand::Rng::with_seed(1);
+        let signal = (0..200)
+            .map(|index| rng.f64() + if index >= 103 { 5.0 } else { 0.0 })
+            .collect::<Array1<f64>>();
+
+        let consensus = PredictImpl::new(Pelt::new())
+            .predict_consensus(
+                &signal.view(),
+                &10.0,
+                &JumpConsensus::new().with_offsets(NonZero::new(2).expect("Invalid number")),
+            )
+            .expect("Error predicting");
+
+        assert_eq!(consensus.len(), 1, "{consensus:?}");
+        assert!(consensus[0].0.abs_diff(103) <= 5, "{consensus:?}");
+        assert!((consensus[0].1 - 1.0).abs() < 1e-12, "{consensus:?}");
+    }
+}
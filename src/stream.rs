@@ -0,0 +1,101 @@
+//! Asynchronous stream of changepoints.
+
+use std::{
+    collections::VecDeque,
+    pin::Pin,
+    task::{Context, Poll},
+};
+
+use futures_core::Stream;
+
+use crate::{Error, RealtimeDetector};
+
+/// Stream of confirmed changepoints, detected on a stream of samples.
+///
+/// Created with [`RealtimeDetector::into_stream`].
+/// Yields the index of every confirmed changepoint, counted from the first sample, and ends when the samples end.
+pub struct ChangepointStream<S> {
+    /// Stream of samples.
+    samples: S,
+    /// Detector the samples are pushed into.
+    detector: RealtimeDetector,
+    /// Confirmed changepoints that haven't been yielded yet.
+    pending: VecDeque<usize>,
+}
+
+impl RealtimeDetector {
+    /// Detect changepoints on a stream of samples.
+    ///
+    /// A stream of chunks can be flattened into a stream of samples first.
+    pub const fn into_stream<S>(self, samples: S) -> ChangepointStream<S>
+    where
+        S: Stream<Item = f64> + Unpin,
+    {
+        ChangepointStream {
+            samples,
+            detector: self,
+            pending: VecDeque::new(),
+        }
+    }
+}
+
+impl<S> Stream for ChangepointStream<S>
+where
+    S: Stream<Item = f64> + Unpin,
+{
+    type Item = Result<usize, Error>;
+
+    fn poll_next(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        loop {
+            // Yield the changepoints of previous samples first
+            if let Some(changepoint) = self.pending.pop_front() {
+                return Poll::Ready(Some(Ok(changepoint)));
+            }
+
+            // Push samples until a changepoint is confirmed
+            let this = &mut *self;
+            match Pin::new(&mut this.samples).poll_next(cx) {
+                Poll::Ready(Some(sample)) => match this.detector.push(sample) {
+                    Ok(changepoints) => this.pending.extend(changepoints),
+                    Err(error) => return Poll::Ready(Some(Err(error))),
+                },
+                Poll::Ready(None) => return Poll::Ready(None),
+                Poll::Pending => return Poll::Pending,
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::num::NonZero;
+
+    use futures::{StreamExt as _, executor::block_on, stream};
+
+    use crate::{Pelt, SegmentCostFunction};
+
+    use super::*;
+
+    /// Yield a step from a stream.
+    #[test]
+    fn step() {
+        let samples = stream::iter((0..300).map(|index| if index < 150 { 0.0 } else { 5.0 }));
+
+        let changepoints = RealtimeDetector::new(
+            Pelt::new().with_segment_cost_function(SegmentCostFunction::L2),
+            1.0,
+        )
+        .with_capacity(NonZero::new(100).expect("Invalid number"))
+        .with_confirmation_delay(20)
+        .into_stream(samples)
+        .collect::<Vec<_>>();
+
+        assert_eq!(
+            block_on(changepoints)
+                .into_iter()
+                .collect::<Result<Vec<_>, _>>()
+                .expect("Error detecting"),
+            vec![150]
+        );
+    }
+}
@@ -0,0 +1,80 @@
+//! WebAssembly bindings.
+
+use std::num::NonZero;
+
+use wasm_bindgen::{JsError, prelude::wasm_bindgen};
+
+use crate::{Pelt, SegmentCostFunction};
+
+/// Calculate the changepoints.
+///
+/// The signal is a `Float64Array`, with `channels` the samples of every channel are next to each other.
+/// Omitted options take the defaults of [`Pelt`], the segment cost function is `"l1"`, `"l2"` or `"quantile"`.
+///
+/// Returns a `Uint32Array` of the indices where changes in the signal were detected, including the end of the signal.
+///
+/// # Errors
+///
+/// - When an option is outside its valid range.
+/// - When the input is invalid.
+/// - When anything went wrong during calculation.
+// Optional arguments are idiomatic in JavaScript
+#[allow(clippy::too_many_arguments)]
+#[allow(
+    clippy::needless_pass_by_value,
+    reason = "strings from JavaScript are always owned"
+)]
+#[wasm_bindgen]
+pub fn predict(
+    signal: &[f64],
+    penalty: f64,
+    segment_cost_function: Option<String>,
+    jump: Option<usize>,
+    minimum_segment_length: Option<usize>,
+    max_changepoints: Option<usize>,
+    quantile: Option<f64>,
+    channels: Option<usize>,
+) -> Result<Vec<u32>, JsError> {
+    let mut setup = Pelt::new();
+
+    // Map input parameter to enum
+    if let Some(segment_cost_function) = segment_cost_function {
+        setup = setup.with_segment_cost_function(match segment_cost_function.as_str() {
+            "l1" => SegmentCostFunction::L1,
+            "l2" => SegmentCostFunction::L2,
+            "quantile" => SegmentCostFunction::Quantile(quantile.unwrap_or(0.5)),
+            // Handle unknown case
+            _ => {
+                return Err(JsError::new(
+                    "segment_cost_function must be 'l1', 'l2' or 'quantile'",
+                ));
+            }
+        });
+    }
+
+    // Convert types
+    if let Some(jump) = jump {
+        setup =
+            setup.with_jump(NonZero::new(jump).ok_or_else(|| JsError::new("jump must be > 0"))?);
+    }
+    if let Some(minimum_segment_length) = minimum_segment_length {
+        setup = setup.with_minimum_segment_length(
+            NonZero::new(minimum_segment_length)
+                .ok_or_else(|| JsError::new("minimum_segment_length must be > 0"))?,
+        );
+    }
+    if let Some(max_changepoints) = max_changepoints {
+        setup = setup.with_max_changepoints(max_changepoints);
+    }
+    let channels =
+        NonZero::new(channels.unwrap_or(1)).ok_or_else(|| JsError::new("channels must be > 0"))?;
+
+    // Do calculation
+    let indices = setup.predict_interleaved(signal, channels, penalty)?;
+
+    // Always fits in 32 bit memory, but the bindings also compile for other targets
+    indices
+        .into_iter()
+        .map(|index| u32::try_from(index).map_err(|_error| JsError::new("signal is too long")))
+        .collect()
+}
@@ -0,0 +1,259 @@
+//! Wild binary segmentation.
+
+use std::{num::NonZero, ops::Range};
+
+use ndarray::{ArrayView, AsArray, Dimension};
+
+use crate::{Accumulator, Error, NanPolicy, OneOrTwoDimensions, Penalty, SegmentCostFunction, nan};
+
+/// Wild binary segmentation algorithm.
+///
+/// Draws random intervals from the signal and finds the best split in each of them.
+/// Segments are recursively split at the best split of the intervals they contain, as long as the gain is larger than the penalty.
+/// Short segments are easier to find in a small interval, so this handles frequent changes better than a single global penalty with [`crate::Pelt`].
+///
+/// # Defaults
+///
+/// - `segment_cost_function`: [`SegmentCostFunction::L1`]
+/// - `jump`: `5`
+/// - `minimum_segment_length`: `2`
+/// - `intervals`: `1000`
+/// - `seed`: `0`
+#[derive(Debug, Clone)]
+pub struct Wbs {
+    /// Segment model.
+    segment_cost_function: SegmentCostFunction,
+    /// Subsample, one every `jump` points.
+    jump: usize,
+    /// Minimum allowable number of data points within a segment.
+    minimum_segment_length: usize,
+    /// Amount of random intervals.
+    intervals: usize,
+    /// Seed of the random number generator.
+    seed: u64,
+}
+
+impl Wbs {
+    /// Construct a new wild binary segmentation instance with default values.
+    #[must_use]
+    pub const fn new() -> Self {
+        Self {
+            segment_cost_function: SegmentCostFunction::L1,
+            jump: 5,
+            minimum_segment_length: 2,
+            intervals: 1000,
+            seed: 0,
+        }
+    }
+
+    /// Set the segment model, also known as the loss function.
+    ///
+    /// Determines how the cost of each potential segment is calculated.
+    #[must_use]
+    pub const fn with_segment_cost_function(mut self, model: SegmentCostFunction) -> Self {
+        self.segment_cost_function = model;
+
+        self
+    }
+
+    /// Set the step size between the proposed changepoints.
+    #[must_use]
+    pub const fn with_jump(mut self, jump: NonZero<usize>) -> Self {
+        self.jump = jump.get();
+
+        self
+    }
+
+    /// Set the minimum allowable number of data points within a segment.
+    ///
+    /// Ensures that segments are not too small.
+    #[must_use]
+    pub const fn with_minimum_segment_length(
+        mut self,
+        minimum_segment_length: NonZero<usize>,
+    ) -> Self {
+        self.minimum_segment_length = minimum_segment_length.get();
+
+        self
+    }
+
+    /// Set the amount of random intervals.
+    ///
+    /// More intervals find more short segments, but take longer.
+    #[must_use]
+    pub const fn with_intervals(mut self, intervals: usize) -> Self {
+        self.intervals = intervals;
+
+        self
+    }
+
+    /// Set the seed of the random number generator.
+    ///
+    /// The same seed always gives the same changepoints.
+    #[must_use]
+    pub const fn with_seed(mut self, seed: u64) -> Self {
+        self.seed = seed;
+
+        self
+    }
+
+    /// Fit on a data set.
+    ///
+    /// # Errors
+    ///
+    /// - When the input is invalid.
+    /// - When the signal contains a NaN or infinite value.
+    /// - When the signal is shorter than the minimum segment length.
+    pub fn predict<'a, D, P>(
+        &self,
+        signal: impl AsArray<'a, f64, D>,
+        penalty: P,
+    ) -> Result<Vec<usize>, Error>
+    where
+        D: OneOrTwoDimensions + Dimension,
+        P: Penalty,
+    {
+        let signal_view = signal.into();
+        D::check_dimensions(&signal_view)?;
+        nan::check_finite(&signal_view)?;
+
+        // Ensure the cost function parameters are correct
+        self.segment_cost_function.validate()?;
+
+        // Try to lower 2D to 1D to parse as 1D array, since that's faster
+        D::try_as_1d(&signal_view).map_or_else(
+            // Predict as 2D array
+            || self.predict_impl(&signal_view, &penalty),
+            // Predict as 1D array
+            |signal_1d| self.predict_impl(&signal_1d, &penalty),
+        )
+    }
+
+    /// Split the signal recursively at the best split of the random intervals.
+    fn predict_impl<D, P>(
+        &self,
+        signal: &ArrayView<f64, D>,
+        penalty: &P,
+    ) -> Result<Vec<usize>, Error>
+    where
+        D: OneOrTwoDimensions + Dimension,
+        P: Penalty,
+    {
+        let len = D::len_or_nrows(signal);
+        if len < self.minimum_segment_length {
+            return Err(Error::NotEnoughPoints);
+        }
+
+        // Precalculate the cost function
+        let cost = D::precalculate(
+            self.segment_cost_function,
+            Accumulator::default(),
+            NanPolicy::default(),
+            signal,
+            None,
+            None,
+            None,
+        )?;
+        let loss = |segment: Range<usize>| {
+            let mut loss = 0.0;
+            D::loss(&cost, &mut loss, segment);
+
+            loss
+        };
+
+        // Best split of an interval with its gain in loss
+        let best_split = |interval: Range<usize>| {
+            let total_loss = loss(interval.clone());
+
+            (interval.start + self.minimum_segment_length..)
+                .filter(|index| index % self.jump == 0)
+                .take_while(|index| *index + self.minimum_segment_length <= interval.end)
+                .map(|index| {
+                    let gain = total_loss - loss(interval.start..index) - loss(index..interval.end);
+
+                    (index, gain)
+                })
+                .max_by(|(_, left), (_, right)| left.total_cmp(right))
+        };
+
+        // Draw the random intervals and find their best splits once, they don't change while splitting
+        let mut rng = fastrand::Rng::with_seed(self.seed);
+        let candidates = (0..self.intervals)
+            .filter_map(|_| {
+                let start = rng.usize(0..len);
+                let end = rng.usize(start + 1..=len);
+
+                best_split(start..end).map(|(index, gain)| (start..end, index, gain))
+            })
+            .collect::<Vec<_>>();
+
+        // Segments left to split
+        let mut changepoints = vec![len];
+        let mut stack = vec![(0, len)];
+        while let Some((start, end)) = stack.pop() {
+            let segment = start..end;
+
+            // The segment itself is always a candidate, on top of the random intervals inside it
+            let Some((index, gain)) = best_split(segment.clone())
+                .into_iter()
+                .chain(
+                    candidates
+                        .iter()
+                        .filter(|(interval, _, _)| interval.start >= start && interval.end <= end)
+                        .map(|(_, index, gain)| (*index, *gain)),
+                )
+                .max_by(|(_, left), (_, right)| left.total_cmp(right))
+            else {
+                continue;
+            };
+
+            // Only split when it's worth the added penalty
+            let added_penalty = penalty.segment_penalty(start..index, len)
+                + penalty.segment_penalty(index..end, len)
+                - penalty.segment_penalty(segment, len);
+            if gain <= added_penalty {
+                continue;
+            }
+
+            changepoints.push(index);
+            stack.push((start, index));
+            stack.push((index, end));
+        }
+
+        changepoints.sort_unstable();
+
+        Ok(changepoints)
+    }
+}
+
+impl Default for Wbs {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Find a short segment that global binary segmentation would miss.
+    #[test]
+    fn short_segment() {
+        let signal = ndarray::Array1::from_iter((0..200).map(|index| {
+            if (100..110).contains(&index) {
+                10.0
+            } else {
+                0.0
+            }
+        }));
+
+        assert_eq!(
+            Wbs::new()
+                .with_segment_cost_function(SegmentCostFunction::L2)
+                .with_jump(NonZero::new(1).expect("Invalid number"))
+                .predict(&signal, 1.0)
+                .expect("Error predicting"),
+            vec![100, 110, 200]
+        );
+    }
+}
@@ -0,0 +1,84 @@
+//! Segmentation of signals with a weight for each sample.
+
+use ndarray::{ArrayView, ArrayView1, Dimension};
+
+use crate::{Error, OneOrTwoDimensions, Penalty, Sample, predict::PredictImpl};
+
+impl PredictImpl {
+    /// Segment with the deviations of each row multiplied by its weight.
+    ///
+    /// The cost functions are fitted around the weighted median, mean or quantile.
+    pub(crate) fn predict_weighted<A, D, P>(
+        &mut self,
+        signal: &ArrayView<A, D>,
+        weights: &[f64],
+        penalty: &P,
+    ) -> Result<Vec<usize>, Error>
+    where
+        A: Sample,
+        D: OneOrTwoDimensions + Dimension,
+        P: Penalty,
+        D::PrecalculationOutput: Sync,
+    {
+        // Length as the rows
+        let len = D::len_or_nrows(signal);
+        if weights.len() != len
+            || weights
+                .iter()
+                .any(|weight| !weight.is_finite() || *weight < 0.0)
+        {
+            return Err(Error::InvalidWeights);
+        }
+
+        let cost = self.precalculate_weighted(signal, Some(&ArrayView1::from(weights)))?;
+
+        self.predict_with_cost(signal, &cost, penalty)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{Error, Pelt, SegmentCostFunction};
+
+    /// Samples without weight don't affect the segmentation.
+    #[test]
+    fn weighted() {
+        let signal = (0..100)
+            .map(|index| match index {
+                // Spikes without weight
+                40..45 => 100.0,
+                0..50 => 0.0,
+                _ => 5.0,
+            } + if index % 2 == 0 { 0.1 } else { -0.1 })
+            .collect::<Vec<_>>();
+        let weights = (0..100)
+            .map(|index| if (40..45).contains(&index) { 0.0 } else { 1.0 })
+            .collect::<Vec<_>>();
+
+        for segment_cost_function in [
+            SegmentCostFunction::L1,
+            SegmentCostFunction::L2,
+            SegmentCostFunction::Quantile(0.5),
+        ] {
+            let pelt = Pelt::new().with_segment_cost_function(segment_cost_function);
+            assert_eq!(
+                pelt.predict_weighted(&signal, &weights, 1.0)
+                    .expect("Error predicting"),
+                vec![50, 100],
+                "{segment_cost_function:?}"
+            );
+        }
+
+        let pelt = Pelt::new();
+        assert!(matches!(
+            pelt.predict_weighted(&signal, &weights[1..], 1.0),
+            Err(Error::InvalidWeights)
+        ));
+        let mut negative = weights;
+        negative[0] = -1.0;
+        assert!(matches!(
+            pelt.predict_weighted(&signal, &negative, 1.0),
+            Err(Error::InvalidWeights)
+        ));
+    }
+}
@@ -0,0 +1,199 @@
+//! Sliding window detection.
+
+use std::num::NonZero;
+
+use ndarray::{ArrayView, AsArray, Dimension};
+
+use crate::{Accumulator, Error, NanPolicy, OneOrTwoDimensions, Penalty, SegmentCostFunction, nan};
+
+/// Sliding window algorithm.
+///
+/// Slides two adjacent windows over the signal and scores how much the loss decreases by splitting them.
+/// Changepoints are the peaks of the score that are above the penalty.
+/// Only looks at a window around each point, so it's a streaming-friendly complement to [`crate::Pelt`].
+///
+/// # Defaults
+///
+/// - `segment_cost_function`: [`SegmentCostFunction::L1`]
+/// - `width`: `100`
+/// - `jump`: `5`
+#[derive(Debug, Clone)]
+pub struct Window {
+    /// Segment model.
+    segment_cost_function: SegmentCostFunction,
+    /// Total width of both windows.
+    width: usize,
+    /// Subsample, one every `jump` points.
+    jump: usize,
+}
+
+impl Window {
+    /// Construct a new sliding window instance with default values.
+    #[must_use]
+    pub const fn new() -> Self {
+        Self {
+            segment_cost_function: SegmentCostFunction::L1,
+            width: 100,
+            jump: 5,
+        }
+    }
+
+    /// Set the segment model, also known as the loss function.
+    ///
+    /// Determines how the cost of each potential segment is calculated.
+    #[must_use]
+    pub const fn with_segment_cost_function(mut self, model: SegmentCostFunction) -> Self {
+        self.segment_cost_function = model;
+
+        self
+    }
+
+    /// Set the total width of both windows.
+    ///
+    /// Changepoints closer than half the width to each other or the edges of the signal can't be found.
+    #[must_use]
+    pub const fn with_width(mut self, width: NonZero<usize>) -> Self {
+        self.width = width.get();
+
+        self
+    }
+
+    /// Set the step size between the scored points.
+    #[must_use]
+    pub const fn with_jump(mut self, jump: NonZero<usize>) -> Self {
+        self.jump = jump.get();
+
+        self
+    }
+
+    /// Fit on a data set.
+    ///
+    /// # Errors
+    ///
+    /// - When the input is invalid.
+    /// - When the signal contains a NaN or infinite value.
+    /// - When the signal is shorter than the window.
+    pub fn predict<'a, D, P>(
+        &self,
+        signal: impl AsArray<'a, f64, D>,
+        penalty: P,
+    ) -> Result<Vec<usize>, Error>
+    where
+        D: OneOrTwoDimensions + Dimension,
+        P: Penalty,
+    {
+        let signal_view = signal.into();
+        D::check_dimensions(&signal_view)?;
+        nan::check_finite(&signal_view)?;
+
+        // Ensure the cost function parameters are correct
+        self.segment_cost_function.validate()?;
+
+        // Try to lower 2D to 1D to parse as 1D array, since that's faster
+        D::try_as_1d(&signal_view).map_or_else(
+            // Predict as 2D array
+            || self.predict_impl(&signal_view, &penalty),
+            // Predict as 1D array
+            |signal_1d| self.predict_impl(&signal_1d, &penalty),
+        )
+    }
+
+    /// Score all points and find the peaks.
+    fn predict_impl<D, P>(
+        &self,
+        signal: &ArrayView<f64, D>,
+        penalty: &P,
+    ) -> Result<Vec<usize>, Error>
+    where
+        D: OneOrTwoDimensions + Dimension,
+        P: Penalty,
+    {
+        let len = D::len_or_nrows(signal);
+
+        // Each window must contain at least a single point
+        let half_width = (self.width / 2).max(1);
+        if len < 2 * half_width {
+            return Err(Error::NotEnoughPoints);
+        }
+
+        // Precalculate the cost function
+        let cost = D::precalculate(
+            self.segment_cost_function,
+            Accumulator::default(),
+            NanPolicy::default(),
+            signal,
+            None,
+            None,
+            None,
+        )?;
+        let loss = |start: usize, end: usize| {
+            let mut loss = 0.0;
+            D::loss(&cost, &mut loss, start..end);
+
+            loss
+        };
+
+        // Score each point by the decrease of loss minus the added penalty
+        let scores = (half_width..=(len - half_width))
+            .step_by(self.jump)
+            .map(|index| {
+                let (start, end) = (index - half_width, index + half_width);
+
+                let gain = loss(start, end) - loss(start, index) - loss(index, end);
+                let added_penalty = penalty.segment_penalty(start..index, len)
+                    + penalty.segment_penalty(index..end, len)
+                    - penalty.segment_penalty(start..end, len);
+
+                (index, gain - added_penalty)
+            })
+            .collect::<Vec<_>>();
+
+        // A peak must be the highest score within half a window, on a plateau the first one is taken
+        let neighbors = half_width.div_ceil(self.jump);
+        let mut changepoints = scores
+            .iter()
+            .enumerate()
+            .filter(|(position, (_, score))| {
+                let before = &scores[position.saturating_sub(neighbors)..*position];
+                let after = &scores[(position + 1)..(position + neighbors + 1).min(scores.len())];
+
+                *score > 0.0
+                    && before.iter().all(|(_, other)| other < score)
+                    && after.iter().all(|(_, other)| other <= score)
+            })
+            .map(|(_, (index, _))| *index)
+            .collect::<Vec<_>>();
+
+        // Add the last item
+        changepoints.push(len);
+
+        Ok(changepoints)
+    }
+}
+
+impl Default for Window {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Find a single step.
+    #[test]
+    fn step() {
+        let signal =
+            ndarray::Array1::from_iter((0..200).map(|index| if index < 120 { 0.0 } else { 10.0 }));
+
+        assert_eq!(
+            Window::new()
+                .with_segment_cost_function(SegmentCostFunction::L2)
+                .with_width(NonZero::new(40).expect("Invalid number"))
+                .predict(&signal, 1.0)
+                .expect("Error predicting"),
+            vec![120, 200]
+        );
+    }
+}
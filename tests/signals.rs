@@ -4,7 +4,11 @@ mod common;
 
 use std::num::NonZero;
 
-use pelt::{Pelt, SegmentCostFunction};
+use pelt::{
+    Bocpd, BottomUp, ChangepointConvention, Cusum, EDivisive, Error, Fpop, InformationCriterion,
+    JumpConsensus, Kernel, KernelCpd, Multiscale, NanPolicy, NormalGamma, OnlinePelt, Pelt,
+    SegmentCostFunction, SignificanceTest, Subsampling, Wbs, WeightedPenalty, Window,
+};
 
 /// Ensure the main algorithm is correct.
 #[test]
@@ -44,6 +48,293 @@ fn pelt_large() {
     );
 }
 
+/// Ensure single-precision signals give the same changepoints.
+#[test]
+fn pelt_small_f32() {
+    let pelt = Pelt::new()
+        .with_jump(NonZero::new(5).expect("Invalid number"))
+        .with_minimum_segment_length(NonZero::new(2).expect("Invalid number"))
+        .with_segment_cost_function(SegmentCostFunction::L1);
+
+    // Test prediction
+    #[expect(clippy::cast_possible_truncation, reason = "the fixture fits in f32")]
+    let signal = common::load_signals_fixture(include_str!("../tests/signals-small.csv"))
+        .mapv(|value| value as f32);
+    assert_eq!(
+        pelt.predict(signal.view(), 10.0).expect("Error predicting"),
+        vec![100, 200]
+    );
+}
+
+/// Ensure arrays with a dynamic dimension are accepted when they're one or two dimensional.
+#[test]
+fn pelt_dynamic_dimensions() {
+    let pelt = Pelt::new()
+        .with_jump(NonZero::new(5).expect("Invalid number"))
+        .with_minimum_segment_length(NonZero::new(2).expect("Invalid number"))
+        .with_segment_cost_function(SegmentCostFunction::L1);
+
+    let signal = common::load_signals_fixture(include_str!("../tests/signals-small.csv"));
+    assert_eq!(
+        pelt.predict(signal.view().into_dyn(), 10.0)
+            .expect("Error predicting"),
+        vec![100, 200]
+    );
+    assert_eq!(
+        pelt.predict(signal.column(0).into_dyn(), 10.0)
+            .expect("Error predicting"),
+        vec![100, 200]
+    );
+
+    // Three dimensions are rejected
+    assert!(matches!(
+        pelt.predict(signal.view().insert_axis(ndarray::Axis(2)).into_dyn(), 10.0),
+        Err(Error::InvalidDimensions)
+    ));
+}
+
+/// Ensure a signal laid out as channels × time gives the same changepoints as time × channels.
+#[test]
+fn pelt_time_axis() {
+    let pelt = Pelt::new()
+        .with_jump(NonZero::new(5).expect("Invalid number"))
+        .with_minimum_segment_length(NonZero::new(2).expect("Invalid number"))
+        .with_segment_cost_function(SegmentCostFunction::L1);
+
+    // Second channel is the first one reversed
+    let signal = common::load_signals_fixture(include_str!("../tests/signals-small.csv"));
+    let signal = ndarray::concatenate(
+        ndarray::Axis(1),
+        &[signal.view(), signal.slice(ndarray::s![..;-1, ..])],
+    )
+    .expect("Error concatenating");
+    let expected = pelt.predict(signal.view(), 10.0).expect("Error predicting");
+
+    // Transposed in memory, not only the view
+    let channels_first = signal.t().as_standard_layout().into_owned();
+    let pelt = pelt.with_time_axis(ndarray::Axis(1));
+    assert_eq!(
+        pelt.predict(channels_first.view(), 10.0)
+            .expect("Error predicting"),
+        expected
+    );
+
+    // A single channel is still lowered to one dimension
+    assert_eq!(
+        pelt.predict(channels_first.slice(ndarray::s![..1, ..]), 10.0)
+            .expect("Error predicting"),
+        vec![100, 200]
+    );
+
+    // The interleaved layout is always time first
+    let interleaved = signal.iter().copied().collect::<Vec<_>>();
+    assert_eq!(
+        pelt.predict_interleaved(&interleaved, NonZero::new(2).expect("Invalid number"), 10.0)
+            .expect("Error predicting"),
+        expected
+    );
+
+    // Only two axes
+    assert!(matches!(
+        pelt.with_time_axis(ndarray::Axis(2))
+            .predict(channels_first.view(), 10.0),
+        Err(Error::InvalidDimensions)
+    ));
+}
+
+/// Ensure NaN values are rejected, ignored or interpolated.
+#[test]
+fn pelt_nan_policies() {
+    let pelt = Pelt::new()
+        .with_jump(NonZero::new(5).expect("Invalid number"))
+        .with_minimum_segment_length(NonZero::new(2).expect("Invalid number"))
+        .with_segment_cost_function(SegmentCostFunction::L1);
+
+    let mut signal = common::load_signals_fixture(include_str!("../tests/signals-small.csv"));
+    for row in [0, 37, 38, 150, 199] {
+        signal[[row, 0]] = f64::NAN;
+    }
+
+    assert!(matches!(
+        pelt.predict(signal.view(), 10.0),
+        Err(Error::InvalidInput { index: 0 })
+    ));
+    for nan_policy in [NanPolicy::Ignore, NanPolicy::Interpolate] {
+        for segment_cost_function in [SegmentCostFunction::L1, SegmentCostFunction::L2] {
+            assert_eq!(
+                pelt.clone()
+                    .with_nan_policy(nan_policy)
+                    .with_segment_cost_function(segment_cost_function)
+                    .predict(signal.view(), 10.0)
+                    .expect("Error predicting"),
+                vec![100, 200],
+                "{nan_policy:?} {segment_cost_function:?}"
+            );
+        }
+    }
+}
+
+/// Ensure count data doesn't need to be converted to floats first.
+#[test]
+fn pelt_counts() {
+    let pelt = Pelt::new()
+        .with_jump(NonZero::new(5).expect("Invalid number"))
+        .with_minimum_segment_length(NonZero::new(2).expect("Invalid number"))
+        .with_segment_cost_function(SegmentCostFunction::L2);
+
+    // Counts jumping between a low and a high rate
+    let counts = (0..300_i32)
+        .map(|index| if (100..200).contains(&index) { 20 } else { 5 } + index % 3)
+        .collect::<Vec<_>>();
+    let floats = counts.iter().copied().map(f64::from).collect::<Vec<_>>();
+
+    assert_eq!(
+        pelt.predict(&counts, 10.0).expect("Error predicting"),
+        vec![100, 200, 300]
+    );
+    assert_eq!(
+        pelt.predict(
+            &counts.iter().copied().map(i64::from).collect::<Vec<_>>(),
+            10.0
+        )
+        .expect("Error predicting"),
+        pelt.predict(&floats, 10.0).expect("Error predicting")
+    );
+}
+
+/// Ensure the other ways of fitting accept count data and give the same results as floats.
+#[test]
+fn pelt_counts_variants() {
+    let pelt = Pelt::new()
+        .with_jump(NonZero::new(5).expect("Invalid number"))
+        .with_minimum_segment_length(NonZero::new(2).expect("Invalid number"))
+        .with_segment_cost_function(SegmentCostFunction::L2);
+
+    let counts = (0..300_i32)
+        .map(|index| if (100..200).contains(&index) { 20 } else { 5 } + index % 3)
+        .collect::<Vec<_>>();
+    let floats = counts.iter().copied().map(f64::from).collect::<Vec<_>>();
+
+    assert_eq!(
+        pelt.predict_with_gaps(&counts, 10.0, &[50..60, 250..250])
+            .expect("Error predicting"),
+        pelt.predict_with_gaps(&floats, 10.0, &[50..60, 250..250])
+            .expect("Error predicting")
+    );
+    assert_eq!(
+        pelt.predict_with_forced(&counts, 10.0, &[150])
+            .expect("Error predicting"),
+        pelt.predict_with_forced(&floats, 10.0, &[150])
+            .expect("Error predicting")
+    );
+    assert_eq!(
+        pelt.predict_many(&counts, &[1.0, 10.0])
+            .expect("Error predicting"),
+        pelt.predict_many(&floats, &[1.0, 10.0])
+            .expect("Error predicting")
+    );
+    assert_eq!(
+        pelt.predict_n_changepoints(&counts, 2)
+            .expect("Error predicting"),
+        pelt.predict_n_changepoints(&floats, 2)
+            .expect("Error predicting")
+    );
+    assert_eq!(
+        pelt.predict_auto_penalty(&counts, InformationCriterion::Bic)
+            .expect("Error predicting"),
+        pelt.predict_auto_penalty(&floats, InformationCriterion::Bic)
+            .expect("Error predicting")
+    );
+    assert_eq!(
+        pelt.crops(&counts, 1.0, 100.0).expect("Error predicting"),
+        pelt.crops(&floats, 1.0, 100.0).expect("Error predicting")
+    );
+    assert!(
+        (pelt
+            .select_penalty_cv(&counts, &[1.0, 10.0, 100.0])
+            .expect("Error selecting")
+            - pelt
+                .select_penalty_cv(&floats, &[1.0, 10.0, 100.0])
+                .expect("Error selecting"))
+        .abs()
+            < f64::EPSILON
+    );
+}
+
+/// Ensure plain slices give the same changepoints as arrays.
+#[test]
+fn pelt_small_slices() {
+    let pelt = Pelt::new()
+        .with_jump(NonZero::new(5).expect("Invalid number"))
+        .with_minimum_segment_length(NonZero::new(2).expect("Invalid number"))
+        .with_segment_cost_function(SegmentCostFunction::L1);
+
+    let signal = common::load_signals_fixture(include_str!("../tests/signals-small.csv"))
+        .column(0)
+        .to_vec();
+    assert_eq!(
+        pelt.predict_slice(&signal, 10.0).expect("Error predicting"),
+        vec![100, 200]
+    );
+
+    // Second channel is the first one reversed
+    let interleaved = signal
+        .iter()
+        .zip(signal.iter().rev())
+        .flat_map(|(first, second)| [*first, *second])
+        .collect::<Vec<_>>();
+    let channels = NonZero::new(2).expect("Invalid number");
+    assert_eq!(
+        pelt.predict_interleaved(&interleaved, channels, 10.0)
+            .expect("Error predicting"),
+        pelt.predict(
+            ndarray::Array2::from_shape_vec((signal.len(), 2), interleaved.clone())
+                .expect("Invalid shape")
+                .view(),
+            10.0
+        )
+        .expect("Error predicting")
+    );
+
+    // Last sample misses a channel
+    assert!(matches!(
+        pelt.predict_interleaved(&interleaved[1..], channels, 10.0),
+        Err(Error::ColumnMismatch)
+    ));
+}
+
+/// Ensure the quantile cost function finds the same changepoints.
+#[test]
+fn pelt_small_quantile() {
+    let pelt = Pelt::new()
+        .with_jump(NonZero::new(5).expect("Invalid number"))
+        .with_minimum_segment_length(NonZero::new(2).expect("Invalid number"))
+        .with_segment_cost_function(SegmentCostFunction::Quantile(0.9));
+
+    // Test prediction
+    assert_eq!(
+        pelt.predict(
+            common::load_signals_fixture(include_str!("../tests/signals-small.csv")).view(),
+            2.0
+        )
+        .expect("Error predicting"),
+        vec![100, 200]
+    );
+
+    // Quantile must be in range, the endpoints never find a changepoint
+    for quantile in [1.5, 0.0, 1.0, f64::NAN] {
+        assert!(matches!(
+            pelt.clone()
+                .with_segment_cost_function(SegmentCostFunction::Quantile(quantile))
+                .predict(
+                    common::load_signals_fixture(include_str!("../tests/signals-small.csv")).view(),
+                    10.0
+                ),
+            Err(Error::InvalidQuantile)
+        ));
+    }
+}
+
 /// `ruptures.datasets.pw_normal(1000, 10)`.
 #[test]
 fn pelt_10_changepoints_normal_l1() {
@@ -84,3 +375,1220 @@ fn pelt_10_changepoints_normal_l2() {
         ]
     );
 }
+
+/// Ensure the information criteria find the changepoints without tuning.
+#[test]
+fn pelt_auto_penalty() {
+    let pelt = Pelt::new()
+        .with_jump(NonZero::new(5).expect("Invalid number"))
+        .with_minimum_segment_length(NonZero::new(2).expect("Invalid number"));
+
+    for (segment_cost_function, information_criterion) in [
+        (SegmentCostFunction::L1, InformationCriterion::Bic),
+        (SegmentCostFunction::L2, InformationCriterion::Bic),
+        (
+            SegmentCostFunction::Quantile(0.5),
+            InformationCriterion::Bic,
+        ),
+        (SegmentCostFunction::L1, InformationCriterion::ModifiedBic),
+        (SegmentCostFunction::L2, InformationCriterion::ModifiedBic),
+    ] {
+        assert_eq!(
+            pelt.clone()
+                .with_segment_cost_function(segment_cost_function)
+                .predict_auto_penalty(
+                    common::load_signals_fixture(include_str!("../tests/signals-small.csv")).view(),
+                    information_criterion
+                )
+                .expect("Error predicting"),
+            vec![100, 200],
+            "{segment_cost_function:?} {information_criterion:?}"
+        );
+    }
+
+    // AIC penalizes less, so it finds more changepoints
+    let aic = pelt
+        .predict_auto_penalty(
+            common::load_signals_fixture(include_str!("../tests/signals-small.csv")).view(),
+            InformationCriterion::Aic,
+        )
+        .expect("Error predicting");
+    assert!(aic.contains(&100) && aic.contains(&200), "{aic:?}");
+}
+
+/// Ensure a jump smaller than the minimum segment length finds the changepoints.
+///
+/// Admitting start points without a partition used to fail with [`Error::NotEnoughPoints`].
+#[test]
+fn pelt_jump_smaller_than_minimum_segment_length() {
+    // Step from 0 to 5 at index 30 with some alternating noise
+    let signal = ndarray::Array1::from_shape_fn(60, |index| {
+        let noise = if index % 2 == 0 { 0.1 } else { -0.1 };
+        if index < 30 { noise } else { 5.0 + noise }
+    });
+
+    for (jump, minimum_segment_length) in [(1, 1), (1, 2), (1, 3), (2, 5)] {
+        assert_eq!(
+            Pelt::new()
+                .with_jump(NonZero::new(jump).expect("Invalid number"))
+                .with_minimum_segment_length(
+                    NonZero::new(minimum_segment_length).expect("Invalid number")
+                )
+                .with_segment_cost_function(SegmentCostFunction::L2)
+                .predict(signal.view(), 10.0)
+                .expect("Error predicting"),
+            vec![30, 60],
+            "jump {jump} minimum segment length {minimum_segment_length}"
+        );
+    }
+}
+
+/// Ensure CROPS returns all segmentations in a penalty range.
+#[test]
+fn pelt_crops() {
+    let data = common::load_signals_fixture(include_str!("../tests/normal-10.csv"));
+
+    let pelt = Pelt::new()
+        .with_jump(NonZero::new(1).expect("Invalid number"))
+        .with_minimum_segment_length(NonZero::new(2).expect("Invalid number"))
+        .with_segment_cost_function(SegmentCostFunction::L2);
+
+    let segmentations = pelt
+        .crops(data.view(), 5.0, 500.0)
+        .expect("Error predicting");
+    assert!(segmentations.len() > 2, "{segmentations:?}");
+
+    // Each segmentation must be the same as a single run
+    for segmentation in &segmentations {
+        assert_eq!(
+            pelt.predict(data.view(), segmentation.penalty)
+                .expect("Error predicting"),
+            segmentation.changepoints
+        );
+    }
+
+    // Higher penalties result in less changepoints
+    assert!(
+        segmentations.windows(2).all(|window| {
+            let [lower, higher] = window else {
+                return false;
+            };
+
+            lower.changepoints.len() > higher.changepoints.len() && lower.loss <= higher.loss
+        }),
+        "{segmentations:?}"
+    );
+
+    // Invalid range
+    assert!(matches!(
+        pelt.crops(data.view(), 10.0, 5.0),
+        Err(Error::InvalidPenaltyRange)
+    ));
+}
+
+/// Ensure the penalty path suggests a penalty.
+#[test]
+fn pelt_penalty_path() {
+    let pelt = Pelt::new()
+        .with_jump(NonZero::new(5).expect("Invalid number"))
+        .with_minimum_segment_length(NonZero::new(2).expect("Invalid number"))
+        .with_segment_cost_function(SegmentCostFunction::L2);
+
+    let path = pelt
+        .penalty_path(
+            common::load_signals_fixture(include_str!("../tests/signals-small.csv")).view(),
+            &[0.1, 1.0, 10.0, 100.0, 1000.0, 10000.0],
+        )
+        .expect("Error predicting");
+    assert_eq!(path.segmentations.len(), 6);
+
+    // The elbow is at the true changepoints
+    assert_eq!(
+        path.elbow().map(|elbow| elbow.changepoints.clone()),
+        Some(vec![100, 200])
+    );
+}
+
+/// Ensure predicting many penalties at once is the same as predicting each.
+#[test]
+fn pelt_predict_many() {
+    let data = common::load_signals_fixture(include_str!("../tests/normal-10.csv"));
+
+    for segment_cost_function in [SegmentCostFunction::L1, SegmentCostFunction::L2] {
+        let pelt = Pelt::new()
+            .with_jump(NonZero::new(5).expect("Invalid number"))
+            .with_minimum_segment_length(NonZero::new(2).expect("Invalid number"))
+            .with_segment_cost_function(segment_cost_function);
+
+        let penalties = [3.0, 10.0, 30.0, 100.0];
+        let many = pelt
+            .predict_many(data.view(), &penalties)
+            .expect("Error predicting");
+        assert_eq!(many.len(), penalties.len());
+
+        for (penalty, changepoints) in penalties.into_iter().zip(many) {
+            assert_eq!(
+                pelt.predict(data.view(), penalty)
+                    .expect("Error predicting"),
+                changepoints,
+                "{segment_cost_function:?} {penalty}"
+            );
+        }
+    }
+}
+
+/// Ensure predicting a batch of signals is the same as predicting each.
+#[test]
+fn pelt_predict_batch() {
+    let small = common::load_signals_fixture(include_str!("../tests/signals-small.csv"));
+    let normal = common::load_signals_fixture(include_str!("../tests/normal-10.csv"));
+    let too_short = small.slice(ndarray::s![..1, ..]);
+
+    let pelt = Pelt::new()
+        .with_jump(NonZero::new(5).expect("Invalid number"))
+        .with_minimum_segment_length(NonZero::new(2).expect("Invalid number"));
+
+    let batch = pelt.predict_batch(&[small.view(), too_short, normal.view()], 10.0);
+    assert_eq!(batch.len(), 3);
+
+    let mut batch = batch.into_iter();
+    assert_eq!(
+        batch.next().and_then(Result::ok),
+        pelt.predict(small.view(), 10.0).ok()
+    );
+    assert!(
+        matches!(batch.next(), Some(Err(_))),
+        "A signal that's too short should fail on its own"
+    );
+    assert_eq!(
+        batch.next().and_then(Result::ok),
+        pelt.predict(normal.view(), 10.0).ok()
+    );
+}
+
+/// Ensure a fixed amount of changepoints is found.
+#[test]
+fn pelt_n_changepoints() {
+    let pelt = Pelt::new()
+        .with_jump(NonZero::new(5).expect("Invalid number"))
+        .with_minimum_segment_length(NonZero::new(2).expect("Invalid number"))
+        .with_segment_cost_function(SegmentCostFunction::L2);
+    let data = common::load_signals_fixture(include_str!("../tests/signals-small.csv"));
+
+    assert_eq!(
+        pelt.predict_n_changepoints(data.view(), 0)
+            .expect("Error predicting"),
+        vec![200]
+    );
+    assert_eq!(
+        pelt.predict_n_changepoints(data.view(), 1)
+            .expect("Error predicting"),
+        vec![100, 200]
+    );
+    assert_eq!(
+        pelt.predict_n_changepoints(data.view(), 3)
+            .expect("Error predicting")
+            .len(),
+        4
+    );
+
+    // Not enough points for this many changepoints
+    assert!(matches!(
+        pelt.predict_n_changepoints(data.view(), 100),
+        Err(Error::NotEnoughPoints)
+    ));
+}
+
+/// Ensure the maximum amount of changepoints is enforced.
+#[test]
+fn pelt_max_changepoints() {
+    let data = common::load_signals_fixture(include_str!("../tests/normal-10.csv"));
+
+    let pelt = Pelt::new()
+        .with_jump(NonZero::new(5).expect("Invalid number"))
+        .with_minimum_segment_length(NonZero::new(2).expect("Invalid number"))
+        .with_segment_cost_function(SegmentCostFunction::L2)
+        .with_max_changepoints(10);
+
+    // A low penalty results in too many changepoints
+    assert!(matches!(
+        pelt.predict(data.view(), 3.0),
+        Err(Error::TooManyChangepoints)
+    ));
+
+    // A higher penalty is fine
+    assert!(
+        pelt.predict(data.view(), 1000.0)
+            .expect("Error predicting")
+            .len()
+            <= 11
+    );
+}
+
+/// Ensure cross-validation selects a sensible penalty.
+#[test]
+fn pelt_select_penalty_cv() {
+    let data = common::load_signals_fixture(include_str!("../tests/signals-small.csv"));
+
+    for segment_cost_function in [SegmentCostFunction::L1, SegmentCostFunction::L2] {
+        let pelt = Pelt::new()
+            .with_jump(NonZero::new(5).expect("Invalid number"))
+            .with_minimum_segment_length(NonZero::new(2).expect("Invalid number"))
+            .with_segment_cost_function(segment_cost_function);
+
+        let penalty = pelt
+            .select_penalty_cv(data.view(), &[0.01, 10.0, 1_000_000.0])
+            .expect("Error selecting penalty");
+        assert_eq!(penalty, 10.0, "{segment_cost_function:?}");
+    }
+
+    // Needs penalties
+    assert!(matches!(
+        Pelt::new().select_penalty_cv(data.view(), &[]),
+        Err(Error::InvalidPenaltyRange)
+    ));
+}
+
+/// Ensure segment cost functions can be compared.
+#[test]
+fn pelt_compare_models() {
+    let data = common::load_signals_fixture(include_str!("../tests/signals-small.csv"));
+
+    let comparisons = Pelt::new()
+        .compare_models(
+            data.view(),
+            &[
+                SegmentCostFunction::L1,
+                SegmentCostFunction::L2,
+                SegmentCostFunction::Quantile(0.9),
+            ],
+            InformationCriterion::Bic,
+        )
+        .expect("Error predicting");
+    assert_eq!(comparisons.len(), 3);
+
+    // The signal has normal noise
+    let best = comparisons
+        .iter()
+        .min_by(|left, right| left.bic.total_cmp(&right.bic))
+        .expect("No comparisons");
+    assert_eq!(best.segment_cost_function, SegmentCostFunction::L2);
+    assert_eq!(best.changepoints, vec![100, 200]);
+}
+
+/// Ensure the bottom-up algorithm finds the changepoints.
+#[test]
+fn bottom_up_small() {
+    let bottom_up = BottomUp::new()
+        .with_jump(NonZero::new(5).expect("Invalid number"))
+        .with_minimum_segment_length(NonZero::new(2).expect("Invalid number"))
+        .with_segment_cost_function(SegmentCostFunction::L2);
+
+    // Test prediction
+    assert_eq!(
+        bottom_up
+            .predict(
+                common::load_signals_fixture(include_str!("../tests/signals-small.csv")).view(),
+                20.0
+            )
+            .expect("Error predicting"),
+        vec![100, 200]
+    );
+}
+
+/// Ensure the sliding window detector is correct on a small signal.
+#[test]
+fn window_small() {
+    let window = Window::new()
+        .with_width(NonZero::new(40).expect("Invalid number"))
+        .with_jump(NonZero::new(5).expect("Invalid number"))
+        .with_segment_cost_function(SegmentCostFunction::L2);
+
+    // Test prediction
+    assert_eq!(
+        window
+            .predict(
+                common::load_signals_fixture(include_str!("../tests/signals-small.csv")).view(),
+                20.0
+            )
+            .expect("Error predicting"),
+        vec![100, 200]
+    );
+}
+
+/// Ensure wild binary segmentation is correct on a small signal.
+#[test]
+fn wbs_small() {
+    let wbs = Wbs::new()
+        .with_jump(NonZero::new(5).expect("Invalid number"))
+        .with_minimum_segment_length(NonZero::new(2).expect("Invalid number"))
+        .with_segment_cost_function(SegmentCostFunction::L2)
+        .with_seed(42);
+
+    // Test prediction
+    assert_eq!(
+        wbs.predict(
+            common::load_signals_fixture(include_str!("../tests/signals-small.csv")).view(),
+            20.0
+        )
+        .expect("Error predicting"),
+        vec![100, 200]
+    );
+}
+
+/// Ensure functional pruning finds the same optimal segmentation as PELT.
+#[test]
+fn fpop_small() {
+    let signal = common::load_signals_fixture(include_str!("../tests/signals-small.csv"));
+    let column = signal.column(0);
+
+    // Test prediction
+    let changepoints = Fpop::new().predict(column, 20.0).expect("Error predicting");
+    assert_eq!(changepoints, vec![100, 200]);
+
+    // Same as PELT without subsampling
+    let pelt = Pelt::new()
+        .with_jump(NonZero::new(1).expect("Invalid number"))
+        .with_minimum_segment_length(NonZero::new(1).expect("Invalid number"))
+        .with_segment_cost_function(SegmentCostFunction::L2);
+    for penalty in [0.5, 2.0, 20.0] {
+        assert_eq!(
+            Fpop::new()
+                .predict(column, penalty)
+                .expect("Error predicting"),
+            pelt.predict(column, penalty).expect("Error predicting")
+        );
+    }
+}
+
+/// Ensure kernel changepoint detection is correct on a small signal.
+#[test]
+fn kernel_small() {
+    let signal = common::load_signals_fixture(include_str!("../tests/signals-small.csv"));
+
+    // Test prediction
+    for (kernel, penalty) in [(Kernel::Linear, 20.0), (Kernel::Rbf(0.1), 5.0)] {
+        let kernel_cpd = KernelCpd::new()
+            .with_kernel(kernel)
+            .with_jump(NonZero::new(5).expect("Invalid number"));
+
+        assert_eq!(
+            kernel_cpd
+                .predict(signal.view(), penalty)
+                .expect("Error predicting"),
+            vec![100, 200]
+        );
+    }
+
+    // Invalid bandwidth
+    assert!(matches!(
+        KernelCpd::new()
+            .with_kernel(Kernel::Rbf(-1.0))
+            .predict(signal.view(), 5.0),
+        Err(Error::InvalidKernelBandwidth)
+    ));
+}
+
+/// Ensure the online Bayesian detector finds the changepoints.
+#[test]
+fn bocpd_small() {
+    let signal = common::load_signals_fixture(include_str!("../tests/signals-small.csv"));
+    let column = signal.column(0);
+
+    // Use the scale of the signal as prior
+    let mean = column.mean().expect("Empty signal");
+    let bocpd = Bocpd::new().with_prior(NormalGamma {
+        mean,
+        mean_weight: 0.01,
+        shape: 1.0,
+        rate: column.var(0.0),
+    });
+
+    // Test prediction
+    let probabilities = bocpd.predict(column).expect("Error predicting");
+    assert_eq!(probabilities.len(), column.len());
+
+    // At the end the current run most likely started around the changepoint
+    let run_length = probabilities[column.len() - 1]
+        .iter()
+        .enumerate()
+        .max_by(|(_, left), (_, right)| left.total_cmp(right))
+        .map(|(run_length, _)| run_length)
+        .expect("Empty probabilities");
+    assert!((95..=105).contains(&run_length), "Changepoint not found");
+}
+
+/// Ensure the cumulative sum detector is correct on a small signal.
+#[test]
+fn cusum_small() {
+    let cusum = Cusum::new().with_threshold(8.0).with_drift(0.5);
+
+    // Test prediction
+    let changepoints = cusum
+        .predict(common::load_signals_fixture(include_str!("../tests/signals-small.csv")).view())
+        .expect("Error predicting");
+    assert_eq!(changepoints, vec![100, 200]);
+
+    // Invalid threshold
+    assert!(matches!(
+        Cusum::new().with_threshold(0.0).predict(&[1.0, 2.0]),
+        Err(Error::InvalidThreshold)
+    ));
+}
+
+/// Ensure every detector rejects signals that are empty or too short to segment.
+#[test]
+fn detectors_short_signals() {
+    for signal in [&[][..], &[1.0][..]] {
+        let results = [
+            Pelt::new().predict(signal, 1.0),
+            Fpop::new().predict(signal, 1.0),
+            Cusum::new().predict(signal),
+            KernelCpd::new().predict(signal, 1.0),
+            EDivisive::new().predict(signal),
+            Wbs::new().predict(signal, 1.0),
+            Window::new().predict(signal, 1.0),
+            BottomUp::new().predict(signal, 1.0),
+            OnlinePelt::new(Pelt::new(), 1.0).push(signal),
+        ];
+        for (detector, result) in results.into_iter().enumerate() {
+            assert!(
+                matches!(result, Err(Error::NotEnoughPoints)),
+                "{detector} {signal:?} {result:?}"
+            );
+        }
+    }
+    assert!(matches!(
+        Bocpd::new().predict(&[]),
+        Err(Error::NotEnoughPoints)
+    ));
+
+    // Samples pushed before there are enough are kept
+    let mut online = OnlinePelt::new(Pelt::new(), 1.0);
+    assert!(online.push(&[1.0]).is_err());
+    assert_eq!(online.push(&[1.0]).expect("Error predicting"), vec![2]);
+}
+
+/// Ensure every detector rejects NaN and infinite values at the first row containing one.
+#[test]
+fn detectors_non_finite() {
+    for value in [f64::NAN, f64::INFINITY] {
+        let mut signal = vec![1.0; 40];
+        signal[20] = value;
+
+        let mut results = vec![
+            Fpop::new().predict(&signal, 1.0),
+            Cusum::new().predict(&signal),
+            KernelCpd::new().predict(&signal, 1.0),
+            EDivisive::new().predict(&signal),
+            Wbs::new().predict(&signal, 1.0),
+            Window::new().predict(&signal, 1.0),
+            BottomUp::new().predict(&signal, 1.0),
+        ];
+        // Only NaN values are handled by the NaN policy
+        if value.is_nan() {
+            results.push(Pelt::new().predict(&signal, 1.0));
+            results.push(OnlinePelt::new(Pelt::new(), 1.0).push(&signal));
+        }
+        for (detector, result) in results.into_iter().enumerate() {
+            assert!(
+                matches!(result, Err(Error::InvalidInput { index: 20 })),
+                "{detector} {value} {result:?}"
+            );
+        }
+        assert!(matches!(
+            Bocpd::new().predict(&signal),
+            Err(Error::InvalidInput { index: 20 })
+        ));
+    }
+}
+
+/// Ensure the energy statistic detector is correct on a small signal.
+#[test]
+fn edivisive_small() {
+    let edivisive = EDivisive::new().with_seed(42);
+
+    // Test prediction
+    assert_eq!(
+        edivisive
+            .predict(
+                common::load_signals_fixture(include_str!("../tests/signals-small.csv")).view()
+            )
+            .expect("Error predicting"),
+        vec![100, 200]
+    );
+}
+
+/// Ensure pushing batches gives the same result as the whole signal.
+#[test]
+fn online_pelt_small() {
+    let signal = common::load_signals_fixture(include_str!("../tests/signals-small.csv"));
+    let samples = signal.column(0).to_vec();
+
+    let pelt = Pelt::new()
+        .with_jump(NonZero::new(5).expect("Invalid number"))
+        .with_minimum_segment_length(NonZero::new(2).expect("Invalid number"))
+        .with_segment_cost_function(SegmentCostFunction::L1);
+
+    // Test prediction
+    let mut online = OnlinePelt::new(pelt.clone(), 10.0);
+    let mut changepoints = Vec::new();
+    for batch in samples.chunks(60) {
+        changepoints = online.push(batch).expect("Error predicting");
+    }
+    assert_eq!(
+        changepoints,
+        pelt.predict(&samples, 10.0).expect("Error predicting")
+    );
+}
+
+/// Ensure processing in chunks finds the same changepoints.
+#[test]
+fn pelt_chunked() {
+    let data = common::load_signals_fixture(include_str!("../tests/normal-10.csv"));
+
+    let pelt = Pelt::new()
+        .with_jump(NonZero::new(5).expect("Invalid number"))
+        .with_minimum_segment_length(NonZero::new(2).expect("Invalid number"))
+        .with_segment_cost_function(SegmentCostFunction::L1);
+
+    // Chunks of 250 rows
+    let chunks = data
+        .axis_chunks_iter(ndarray::Axis(0), 250)
+        .map(|chunk| chunk.to_owned());
+
+    // Test prediction
+    assert_eq!(
+        pelt.predict_chunked(chunks, 3.0, 100)
+            .expect("Error predicting"),
+        pelt.predict(data.view(), 3.0).expect("Error predicting")
+    );
+}
+
+/// Ensure the parts between gaps are segmented separately.
+#[test]
+fn pelt_gaps() {
+    let data = common::load_signals_fixture(include_str!("../tests/normal-10.csv"));
+
+    let pelt = Pelt::new()
+        .with_jump(NonZero::new(5).expect("Invalid number"))
+        .with_minimum_segment_length(NonZero::new(2).expect("Invalid number"))
+        .with_segment_cost_function(SegmentCostFunction::L1);
+
+    // Segment each part by itself
+    let mut expected = Vec::new();
+    for (start, end) in [(0, 400), (400, 600), (650, 1000)] {
+        expected.extend(
+            pelt.predict(data.slice(ndarray::s![start..end, ..]), 3.0)
+                .expect("Error predicting")
+                .into_iter()
+                .map(|changepoint| changepoint + start),
+        );
+    }
+    expected.insert(expected.len() - 1, 650);
+    expected.sort_unstable();
+
+    // Test prediction
+    assert_eq!(
+        pelt.predict_with_gaps(data.view(), 3.0, &[400..400, 600..650])
+            .expect("Error predicting"),
+        expected
+    );
+}
+
+/// Ensure masked rows are left out, even when they are NaN.
+#[test]
+fn pelt_masked() {
+    let pelt = Pelt::new()
+        .with_jump(NonZero::new(5).expect("Invalid number"))
+        .with_minimum_segment_length(NonZero::new(2).expect("Invalid number"))
+        .with_segment_cost_function(SegmentCostFunction::L1);
+
+    let mut signal = common::load_signals_fixture(include_str!("../tests/signals-small.csv"));
+    let mask = (0..signal.nrows())
+        .map(|row| !(20..30).contains(&row))
+        .collect::<Vec<_>>();
+    signal.slice_mut(ndarray::s![20..30, ..]).fill(f64::NAN);
+
+    assert_eq!(
+        pelt.predict_masked(signal.view(), &mask, 10.0)
+            .expect("Error predicting"),
+        vec![100, 200]
+    );
+}
+
+/// Ensure the segments match the predicted changepoints.
+#[test]
+fn pelt_segment() {
+    let signal = common::load_signals_fixture(include_str!("../tests/signals-small.csv"));
+    let pelt = Pelt::new().with_segment_cost_function(SegmentCostFunction::L2);
+
+    let segmentation = pelt.segment(signal.view(), 10.0).expect("Error segmenting");
+    assert_eq!(
+        segmentation.changepoints,
+        pelt.predict(signal.view(), 10.0).expect("Error predicting")
+    );
+    assert_eq!(segmentation.segments.len(), segmentation.changepoints.len());
+    let loss = segmentation
+        .segments
+        .iter()
+        .map(|segment| segment.cost)
+        .sum::<f64>();
+    let penalties = 10.0 * segmentation.segments.len() as f64;
+    assert!((segmentation.loss - loss).abs() < 1e-9, "{segmentation:?}");
+    assert!(
+        (segmentation.objective - loss - penalties).abs() < 1e-9,
+        "{segmentation:?}"
+    );
+    for (segment, end) in segmentation.segments.iter().zip(&segmentation.changepoints) {
+        assert_eq!(segment.end, *end);
+        assert_eq!(segment.mean.len(), signal.ncols());
+        // The mean is the location of the L2 cost
+        assert_eq!(segment.location, segment.mean);
+    }
+}
+
+/// Ensure the statistics of given changepoints match the segmentation.
+#[test]
+fn pelt_segment_statistics() {
+    let signal = common::load_signals_fixture(include_str!("../tests/signals-small.csv"));
+    let pelt = Pelt::new();
+
+    let segmentation = pelt.segment(signal.view(), 10.0).expect("Error segmenting");
+    assert_eq!(
+        pelt.segment_statistics(signal.view(), &segmentation.changepoints)
+            .expect("Error calculating statistics"),
+        segmentation.segments
+    );
+
+    // The end of the signal is missing
+    assert!(matches!(
+        pelt.segment_statistics(signal.view(), &[10]),
+        Err(Error::InvalidChangepoints)
+    ));
+}
+
+/// Ensure the optimal segmentation scores better than a hand-made one.
+#[test]
+fn pelt_score() {
+    let signal = common::load_signals_fixture(include_str!("../tests/signals-small.csv"));
+    let pelt = Pelt::new().with_jump(NonZero::<usize>::MIN);
+
+    let segmentation = pelt.segment(signal.view(), 10.0).expect("Error segmenting");
+    let optimal = pelt
+        .score(signal.view(), &segmentation.changepoints, 10.0)
+        .expect("Error scoring");
+    assert!((optimal - segmentation.objective).abs() < 1e-9, "{optimal}");
+
+    let single_segment = pelt
+        .score(signal.view(), &[signal.nrows()], 10.0)
+        .expect("Error scoring");
+    assert!(single_segment >= optimal, "{single_segment} < {optimal}");
+}
+
+/// Ensure the changepoints of the whole signal are found in most runs on subsamples.
+#[test]
+fn pelt_changepoint_frequency() {
+    let signal = common::load_signals_fixture(include_str!("../tests/signals-small.csv"));
+    let pelt = Pelt::new();
+
+    let changepoints = pelt.predict(signal.view(), 10.0).expect("Error predicting");
+    let frequency = pelt
+        .changepoint_frequency(
+            signal.view(),
+            10.0,
+            &Subsampling::new().with_runs(NonZero::new(10).expect("Invalid number")),
+        )
+        .expect("Error subsampling");
+    assert_eq!(frequency.len(), signal.nrows());
+    assert!(
+        changepoints[..changepoints.len() - 1]
+            .iter()
+            .all(|changepoint| frequency[*changepoint] >= 0.5),
+        "{frequency:?}"
+    );
+    assert!(matches!(
+        pelt.changepoint_frequency(signal.view(), 10.0, &Subsampling::new().with_fraction(1.5)),
+        Err(Error::InvalidFraction)
+    ));
+}
+
+/// Ensure the consensus of several jumps is close to the exact segmentation.
+#[test]
+fn pelt_predict_consensus() {
+    let signal = common::load_signals_fixture(include_str!("../tests/signals-small.csv"));
+    let pelt = Pelt::new();
+
+    let exact = pelt
+        .clone()
+        .with_jump(NonZero::<usize>::MIN)
+        .predict(signal.view(), 10.0)
+        .expect("Error predicting");
+    let consensus = pelt
+        .predict_consensus(signal.view(), 10.0, &JumpConsensus::new())
+        .expect("Error predicting");
+    assert!(
+        consensus.iter().all(|(changepoint, agreement)| {
+            *agreement >= 0.5 && exact.iter().any(|exact| exact.abs_diff(*changepoint) <= 10)
+        }),
+        "{consensus:?} {exact:?}"
+    );
+}
+
+/// Ensure calibrating on a segmentation recovers its penalty.
+#[test]
+fn pelt_calibrate_penalty() {
+    let signal = common::load_signals_fixture(include_str!("../tests/signals-small.csv"));
+    let pelt = Pelt::new();
+
+    let truth = pelt.predict(signal.view(), 10.0).expect("Error predicting");
+    let calibration = pelt
+        .calibrate_penalty(
+            &[(signal.view(), truth.as_slice())],
+            &[0.1, 10.0, 10_000.0],
+            &[],
+            5,
+        )
+        .expect("Error calibrating");
+    assert!(
+        (calibration.penalty - 10.0).abs() < f64::EPSILON,
+        "{calibration:?}"
+    );
+    assert_eq!(calibration.minimum_segment_length, 2);
+    assert!(
+        (calibration.f1 - 1.0).abs() < f64::EPSILON,
+        "{calibration:?}"
+    );
+
+    assert!(matches!(
+        pelt.calibrate_penalty::<f64>(&[], &[10.0], &[], 5),
+        Err(Error::MissingLabels)
+    ));
+}
+
+/// Ensure the suggestions are sorted by uncertainty and contain their changepoint.
+#[test]
+fn pelt_suggest_annotations() {
+    let signal = common::load_signals_fixture(include_str!("../tests/signals-small.csv"));
+    let pelt = Pelt::new();
+
+    let suggestions = pelt
+        .suggest_annotations(signal.view(), 10.0, 3)
+        .expect("Error suggesting");
+    assert!(suggestions.len() <= 3);
+    assert!(
+        suggestions
+            .iter()
+            .zip(suggestions.iter().skip(1))
+            .all(|(before, after)| before.uncertainty >= after.uncertainty),
+        "{suggestions:?}"
+    );
+    assert!(
+        suggestions.iter().all(|suggestion| {
+            suggestion.region.contains(&suggestion.changepoint)
+                && (0.0..=1.0).contains(&suggestion.uncertainty)
+        }),
+        "{suggestions:?}"
+    );
+}
+
+/// Ensure known changepoints are kept and the other changepoints are still found.
+#[test]
+fn pelt_predict_with_forced() {
+    let signal = common::load_signals_fixture(include_str!("../tests/signals-small.csv"));
+    let pelt = Pelt::new();
+
+    let changepoints = pelt.predict(signal.view(), 10.0).expect("Error predicting");
+    assert_eq!(
+        pelt.predict_with_forced(signal.view(), 10.0, &changepoints)
+            .expect("Error predicting"),
+        changepoints
+    );
+
+    let forced = pelt
+        .predict_with_forced(signal.view(), 10.0, &[13])
+        .expect("Error predicting");
+    assert!(forced.contains(&13), "{forced:?}");
+    assert_eq!(forced.last(), changepoints.last());
+
+    assert!(matches!(
+        pelt.predict_with_forced(signal.view(), 10.0, &[20, 10]),
+        Err(Error::UnsortedChangepoints { position: 1 })
+    ));
+}
+
+/// Ensure no changepoint is placed inside a forbidden range.
+#[test]
+fn pelt_forbidden_ranges() {
+    let signal = common::load_signals_fixture(include_str!("../tests/signals-small.csv"));
+    let pelt = Pelt::new();
+
+    let changepoints = pelt.predict(signal.view(), 10.0).expect("Error predicting");
+    let forbidden = (changepoints[0] - 10)..(changepoints[0] + 10);
+    let allowed = pelt
+        .with_forbidden_ranges(std::slice::from_ref(&forbidden))
+        .predict(signal.view(), 10.0)
+        .expect("Error predicting");
+    assert!(
+        allowed
+            .iter()
+            .all(|changepoint| *changepoint <= forbidden.start || *changepoint >= forbidden.end),
+        "{allowed:?}"
+    );
+    assert_eq!(allowed.last(), changepoints.last());
+}
+
+/// Ensure uniform penalty weights don't change the segmentation and large ones remove changepoints.
+#[test]
+fn pelt_weighted_penalty() {
+    let signal = common::load_signals_fixture(include_str!("../tests/signals-small.csv"));
+    let pelt = Pelt::new();
+
+    let changepoints = pelt.predict(signal.view(), 10.0).expect("Error predicting");
+    let uniform = WeightedPenalty::new(10.0, vec![1.0; signal.nrows()]).expect("Invalid weights");
+    assert_eq!(
+        pelt.predict(signal.view(), uniform)
+            .expect("Error predicting"),
+        changepoints
+    );
+
+    // Changepoints are very unlikely in the first half
+    let mut weights = vec![1.0; signal.nrows()];
+    weights[..signal.nrows() / 2].fill(1e6);
+    let prior = WeightedPenalty::new(10.0, weights).expect("Invalid weights");
+    let biased = pelt
+        .predict(signal.view(), prior)
+        .expect("Error predicting");
+    assert!(
+        biased[..biased.len() - 1]
+            .iter()
+            .all(|changepoint| *changepoint >= signal.nrows() / 2),
+        "{biased:?}"
+    );
+}
+
+/// Ensure the strongest changepoints are the ones left at a higher penalty.
+#[test]
+fn pelt_strongest_changepoints() {
+    let signal = common::load_signals_fixture(include_str!("../tests/signals-small.csv"));
+    let pelt = Pelt::new().with_jump(NonZero::<usize>::MIN);
+
+    let changepoints = pelt.predict(signal.view(), 1.0).expect("Error predicting");
+    let ranking = pelt
+        .rank_changepoints(signal.view(), &changepoints)
+        .expect("Error ranking");
+    assert_eq!(ranking.len(), changepoints.len() - 1);
+
+    let strongest = pelt
+        .strongest_changepoints(signal.view(), &changepoints, 2)
+        .expect("Error ranking");
+    assert_eq!(strongest.len(), 3);
+    assert_eq!(strongest.last(), changepoints.last());
+    assert!(strongest.is_sorted(), "{strongest:?}");
+    assert!(
+        strongest
+            .iter()
+            .all(|changepoint| changepoints.contains(changepoint)),
+        "{strongest:?}"
+    );
+}
+
+/// Ensure the significance test keeps a subset of the candidates with low p-values.
+#[test]
+fn pelt_predict_significant() {
+    let signal = common::load_signals_fixture(include_str!("../tests/signals-small.csv"));
+    let pelt = Pelt::new();
+    let test = SignificanceTest::new().with_permutations(99);
+
+    let candidates = pelt.predict(signal.view(), 1.0).expect("Error predicting");
+    let significant = pelt
+        .predict_significant(signal.view(), 1.0, &test)
+        .expect("Error predicting");
+    assert!(significant.len() <= candidates.len(), "{significant:?}");
+    assert!(
+        significant
+            .iter()
+            .all(|changepoint| candidates.contains(changepoint)),
+        "{significant:?}"
+    );
+
+    let p_values = pelt
+        .p_values(signal.view(), &significant, &test)
+        .expect("Error testing");
+    assert_eq!(p_values.len(), significant.len() - 1);
+    assert!(
+        p_values.iter().all(|p_value| *p_value <= 0.05),
+        "{p_values:?}"
+    );
+}
+
+/// Ensure the changepoints follow the convention of the R `changepoint` package and can use inclusive indices.
+#[test]
+fn pelt_changepoint_convention() {
+    let signal = common::load_signals_fixture(include_str!("../tests/signals-small.csv"));
+    let pelt = Pelt::new();
+    let changepoints = pelt.predict(signal.view(), 10.0).expect("Error predicting");
+
+    let r_changepoint = pelt.clone().with_keep_signal_length(false);
+    assert_eq!(
+        r_changepoint
+            .predict(signal.view(), 10.0)
+            .expect("Error predicting"),
+        changepoints[..changepoints.len() - 1]
+    );
+
+    // The variants of predict use the same convention
+    let weights = vec![1.0; signal.nrows()];
+    assert_eq!(
+        r_changepoint
+            .predict_weighted(signal.view(), &weights, 10.0)
+            .expect("Error predicting"),
+        changepoints[..changepoints.len() - 1]
+    );
+    assert_eq!(
+        r_changepoint
+            .predict_with_gaps(signal.view(), 10.0, &[])
+            .expect("Error predicting"),
+        changepoints[..changepoints.len() - 1]
+    );
+    assert_eq!(
+        r_changepoint
+            .predict_many(signal.view(), &[10.0])
+            .expect("Error predicting"),
+        [&changepoints[..changepoints.len() - 1]]
+    );
+
+    let inclusive = pelt
+        .with_keep_initial_zero(true)
+        .with_changepoint_convention(ChangepointConvention::Inclusive);
+    let inclusive_changepoints = inclusive
+        .predict(signal.view(), 10.0)
+        .expect("Error predicting");
+    assert_eq!(inclusive_changepoints[0], 0);
+    assert!(
+        inclusive_changepoints[1..]
+            .iter()
+            .zip(&changepoints)
+            .all(|(inclusive, exclusive)| inclusive + 1 == *exclusive),
+        "{inclusive_changepoints:?}"
+    );
+}
+
+/// Ensure weights of one match the unweighted segmentation.
+#[test]
+fn pelt_weighted() {
+    let signal = common::load_signals_fixture(include_str!("../tests/signals-small.csv"));
+    let weights = vec![1.0; signal.nrows()];
+
+    for segment_cost_function in [
+        SegmentCostFunction::L1,
+        SegmentCostFunction::L2,
+        SegmentCostFunction::Quantile(0.5),
+    ] {
+        let pelt = Pelt::new()
+            .with_jump(NonZero::new(5).expect("Invalid number"))
+            .with_minimum_segment_length(NonZero::new(2).expect("Invalid number"))
+            .with_segment_cost_function(segment_cost_function);
+
+        assert_eq!(
+            pelt.predict_weighted(signal.view(), &weights, 10.0)
+                .expect("Error predicting"),
+            pelt.predict(signal.view(), 10.0).expect("Error predicting"),
+            "{segment_cost_function:?}"
+        );
+    }
+}
+
+/// Ensure regular sampling matches the segmentation by sample count.
+#[test]
+fn pelt_irregular() {
+    let signal = common::load_signals_fixture(include_str!("../tests/signals-small.csv"));
+    let times = (0..signal.nrows())
+        .map(|row| row as f64 * 0.5)
+        .collect::<Vec<_>>();
+
+    let pelt = Pelt::new()
+        .with_jump(NonZero::new(5).expect("Invalid number"))
+        .with_minimum_segment_length(NonZero::new(2).expect("Invalid number"))
+        .with_segment_cost_function(SegmentCostFunction::L2);
+
+    // Every sample has the same weight, so only the scale of the losses changes
+    assert_eq!(
+        pelt.predict_irregular(signal.view(), 5.0, &times, 1.0)
+            .expect("Error predicting"),
+        pelt.predict(signal.view(), 10.0).expect("Error predicting")
+    );
+}
+
+/// Ensure a factor of one matches the direct segmentation.
+#[test]
+fn pelt_multiscale() {
+    let signal = common::load_signals_fixture(include_str!("../tests/signals-small.csv"));
+    let pelt = Pelt::new()
+        .with_jump(NonZero::<usize>::MIN)
+        .with_minimum_segment_length(NonZero::new(2).expect("Invalid number"))
+        .with_segment_cost_function(SegmentCostFunction::L2);
+
+    assert_eq!(
+        pelt.predict_multiscale(
+            signal.view(),
+            10.0,
+            &Multiscale::new().with_factor(NonZero::<usize>::MIN)
+        )
+        .expect("Error predicting"),
+        pelt.predict(signal.view(), 10.0).expect("Error predicting")
+    );
+}
+
+/// Ensure refining a coarse segmentation doesn't increase the loss.
+#[test]
+fn pelt_refine() {
+    let signal = common::load_signals_fixture(include_str!("../tests/signals-small.csv"));
+    let pelt = Pelt::new()
+        .with_jump(NonZero::new(10).expect("Invalid number"))
+        .with_minimum_segment_length(NonZero::new(2).expect("Invalid number"))
+        .with_segment_cost_function(SegmentCostFunction::L2);
+
+    let coarse = pelt.predict(signal.view(), 10.0).expect("Error predicting");
+    let refined = pelt
+        .refine(signal.view(), &coarse, 10)
+        .expect("Error refining");
+    assert_eq!(refined.len(), coarse.len());
+    assert!(
+        pelt.score(signal.view(), &refined, 10.0)
+            .expect("Error scoring")
+            <= pelt
+                .score(signal.view(), &coarse, 10.0)
+                .expect("Error scoring")
+    );
+}
+
+/// Ensure merging keeps every changepoint without a minimum gain and none with an infinite one.
+#[test]
+fn pelt_merge_weak_changepoints() {
+    let signal = common::load_signals_fixture(include_str!("../tests/signals-small.csv"));
+    let pelt = Pelt::new()
+        .with_jump(NonZero::new(5).expect("Invalid number"))
+        .with_minimum_segment_length(NonZero::new(2).expect("Invalid number"))
+        .with_segment_cost_function(SegmentCostFunction::L2);
+
+    let changepoints = pelt.predict(signal.view(), 1.0).expect("Error predicting");
+    assert_eq!(
+        pelt.merge_weak_changepoints(signal.view(), &changepoints, 0.0)
+            .expect("Error merging"),
+        changepoints
+    );
+    assert_eq!(
+        pelt.merge_weak_changepoints(signal.view(), &changepoints, f64::INFINITY)
+            .expect("Error merging"),
+        vec![signal.nrows()]
+    );
+
+    // Merged changepoints are a subset of the original ones
+    let merged = pelt
+        .merge_weak_changepoints(signal.view(), &changepoints, 50.0)
+        .expect("Error merging");
+    assert!(
+        merged
+            .iter()
+            .all(|changepoint| changepoints.contains(changepoint))
+    );
+}
+
+/// Ensure a zero weight leaves a column out and equal weights only scale the penalty.
+#[test]
+fn pelt_column_weights() {
+    let signal = common::load_signals_fixture(include_str!("../tests/normal-10.csv"));
+
+    for segment_cost_function in [
+        SegmentCostFunction::L1,
+        SegmentCostFunction::L2,
+        SegmentCostFunction::Quantile(0.3),
+    ] {
+        let pelt = Pelt::new()
+            .with_jump(NonZero::new(5).expect("Invalid number"))
+            .with_minimum_segment_length(NonZero::new(2).expect("Invalid number"))
+            .with_segment_cost_function(segment_cost_function);
+
+        let mut column_weights = vec![0.0; signal.ncols()];
+        column_weights[0] = 1.0;
+        assert_eq!(
+            pelt.clone()
+                .with_column_weights(&column_weights)
+                .predict(signal.view(), 10.0)
+                .expect("Error predicting"),
+            pelt.predict(signal.column(0), 10.0)
+                .expect("Error predicting"),
+            "{segment_cost_function:?}"
+        );
+
+        assert_eq!(
+            pelt.clone()
+                .with_column_weights(&vec![2.0; signal.ncols()])
+                .predict(signal.view(), 20.0)
+                .expect("Error predicting"),
+            pelt.predict(signal.view(), 10.0).expect("Error predicting"),
+            "{segment_cost_function:?}"
+        );
+    }
+
+    assert!(matches!(
+        Pelt::new()
+            .with_column_weights(&[1.0])
+            .predict(signal.view(), 10.0),
+        Err(Error::InvalidColumnWeights)
+    ));
+}
+
+/// Ensure the same cost function for every column matches the segment cost function, and mixing them changes the result.
+#[test]
+fn pelt_column_cost_functions() {
+    let signal = common::load_signals_fixture(include_str!("../tests/normal-10.csv"));
+    let pelt = Pelt::new()
+        .with_jump(NonZero::new(5).expect("Invalid number"))
+        .with_minimum_segment_length(NonZero::new(2).expect("Invalid number"))
+        .with_segment_cost_function(SegmentCostFunction::L2);
+
+    let l2 = pelt.predict(signal.view(), 3.0).expect("Error predicting");
+    assert_eq!(
+        pelt.clone()
+            .with_column_cost_functions(&[SegmentCostFunction::L2, SegmentCostFunction::L2])
+            .predict(signal.view(), 3.0)
+            .expect("Error predicting"),
+        l2
+    );
+    assert_ne!(
+        pelt.clone()
+            .with_column_cost_functions(&[SegmentCostFunction::L2, SegmentCostFunction::L1])
+            .predict(signal.view(), 3.0)
+            .expect("Error predicting"),
+        l2
+    );
+
+    // A single column uses its own cost function
+    assert_eq!(
+        pelt.clone()
+            .with_column_cost_functions(&[SegmentCostFunction::L1])
+            .predict(signal.column(0), 3.0)
+            .expect("Error predicting"),
+        pelt.clone()
+            .with_segment_cost_function(SegmentCostFunction::L1)
+            .predict(signal.column(0), 3.0)
+            .expect("Error predicting")
+    );
+
+    assert!(matches!(
+        pelt.clone()
+            .with_column_cost_functions(&[SegmentCostFunction::L2])
+            .predict(signal.view(), 3.0),
+        Err(Error::ColumnMismatch)
+    ));
+    assert!(matches!(
+        pelt.with_column_cost_functions(&[
+            SegmentCostFunction::L2,
+            SegmentCostFunction::Quantile(2.0)
+        ])
+        .predict(signal.view(), 3.0),
+        Err(Error::InvalidQuantile)
+    ));
+}